@@ -1,7 +1,7 @@
 use std::process::Command;
 fn main() {
     let tag = Command::new("git")
-        .args(&["describe", "--tags", "--abbrev=0"])
+        .args(["describe", "--tags", "--abbrev=0"])
         .output()
         .ok()
         .and_then(|o| String::from_utf8(o.stdout).ok())
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use colored::*;
-use fishpi_rust::FishPi;
+use fishpi_rust::{FishPi, LeaderboardKind};
 use std::sync::Arc;
 
 use crate::commands::{CommandContext, CommandRegistry};
@@ -18,6 +18,9 @@ pub struct App {
 impl App {
     pub fn new() -> Self {
         let client = Arc::new(FishPi::new());
+        client
+            .redpacket
+            .set_gesture_stats_path(crate::utils::GESTURE_STATS_FILE);
         let auth_service = AuthService::new(client.clone());
         let input_handler = CrosstermInputHandler::new();
         let command_registry = CommandRegistry::new();
@@ -217,6 +220,25 @@ impl App {
                         "help" | "h" => {
                             self.show_help();
                         }
+                        cmd if cmd == ":rank" || cmd.starts_with(":rank ") => {
+                            let parts: Vec<&str> = cmd.split_whitespace().collect();
+                            let kind = match parts.get(1) {
+                                Some(kind) => match LeaderboardKind::from_str(kind) {
+                                    Some(kind) => kind,
+                                    None => {
+                                        println!(
+                                            "{}",
+                                            "用法: :rank [points|consumption|checkin] [数量]"
+                                                .yellow()
+                                        );
+                                        continue;
+                                    }
+                                },
+                                None => LeaderboardKind::default(),
+                            };
+                            let limit = parts.get(2).and_then(|n| n.parse().ok()).unwrap_or(10);
+                            self.show_leaderboard(kind, limit).await;
+                        }
                         _ => {
                             // 普通模式下的命令处理
                             let parts: Vec<&str> = input.split_whitespace().collect();
@@ -244,11 +266,45 @@ impl App {
         Ok(())
     }
 
+    async fn show_leaderboard(&self, kind: LeaderboardKind, limit: usize) {
+        let result = self.client.user.leaderboard(kind, limit).await;
+        if !result.success {
+            println!(
+                "{}: {}",
+                "获取排行榜失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            );
+            return;
+        }
+
+        let entries = result.data.unwrap_or_default();
+        if entries.is_empty() {
+            println!("{}", "暂无排行数据".yellow());
+            return;
+        }
+
+        println!("{}", kind.display_name().yellow().bold());
+        for entry in entries {
+            let name = if entry.nickname.is_empty() {
+                entry.user_name.clone()
+            } else {
+                format!("{}({})", entry.nickname, entry.user_name)
+            };
+            println!(
+                "  {:>3}. {} {}",
+                entry.rank,
+                name.green(),
+                entry.value.to_string().cyan()
+            );
+        }
+    }
+
     fn show_help(&self) {
         println!("{}", "FishPi 客户端全局命令:".yellow());
         println!("  {}       - 进入聊天室", "cr / :cr".green());
         println!("  {}        - 进入私聊", "c / :c".green());
         println!("  {}      - 看帖", "a / :a".green());
+        println!("  {}     - 排行榜", ":rank".green());
         println!("  {}      - 显示帮助", "help".green());
         println!("  {}      - 退出程序", ":exit".green());
         println!("  {}     - 登出", ":logout".green());
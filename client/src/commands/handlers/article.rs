@@ -81,14 +81,14 @@ impl ArticleCommand {
                     continue;
                 } else if input.starts_with("r ") {
                     let parts: Vec<&str> = input.split_whitespace().collect();
-                    if parts.len() == 2 {
-                        if let Ok(idx) = parts[1].parse::<usize>() {
-                            if idx > 0 && idx <= articles.len() {
-                                let article = &articles[idx - 1];
-                                self.article_detail_loop(&article.o_id).await?;
-                            } else {
-                                println!("{}", "无效的序号".red());
-                            }
+                    if parts.len() == 2
+                        && let Ok(idx) = parts[1].parse::<usize>()
+                    {
+                        if idx > 0 && idx <= articles.len() {
+                            let article = &articles[idx - 1];
+                            self.article_detail_loop(&article.o_id).await?;
+                        } else {
+                            println!("{}", "无效的序号".red());
                         }
                     }
                 } else if input == "h" || input == "help" {
@@ -197,11 +197,11 @@ impl ArticleCommand {
                     "t" => {
                         match article_service.reward(article_id).await {
                             Ok(res) => {
-                                if let Some(data) = &res.data {
-                                    if let Some(content) = data.get("articleRewardContent") {
-                                        println!("{}", "打赏成功".green());
-                                        println!("{}", content.to_string().green());
-                                    }
+                                if let Some(data) = &res.data
+                                    && let Some(content) = data.get("articleRewardContent")
+                                {
+                                    println!("{}", "打赏成功".green());
+                                    println!("{}", content.to_string().green());
                                 }
                             }
                             Err(e) => println!("打赏失败: {}", e),
@@ -239,18 +239,18 @@ impl ArticleCommand {
                     cmd if cmd.starts_with("tc ") => {
                         // 感谢评论
                         let parts: Vec<&str> = cmd.split_whitespace().collect();
-                        if parts.len() == 2 {
-                            if let Ok(idx) = parts[1].parse::<usize>() {
-                                if idx > 0 && idx <= normal_comments.len() {
-                                    let comment_id = &normal_comments[idx - 1].o_id;
-                                    match article_service.thank_comment(comment_id).await {
-                                        Ok(res) if res.code == 0 => println!("{}", "感谢评论成功".green()),
-                                        Ok(res) => println!("感谢评论失败: {}", res.msg),
-                                        Err(e) => println!("感谢评论失败: {}", e),
-                                    }
-                                } else {
-                                    println!("{}", "无效的评论序号".red());
+                        if parts.len() == 2
+                            && let Ok(idx) = parts[1].parse::<usize>()
+                        {
+                            if idx > 0 && idx <= normal_comments.len() {
+                                let comment_id = &normal_comments[idx - 1].o_id;
+                                match article_service.thank_comment(comment_id).await {
+                                    Ok(res) if res.code == 0 => println!("{}", "感谢评论成功".green()),
+                                    Ok(res) => println!("感谢评论失败: {}", res.msg),
+                                    Err(e) => println!("感谢评论失败: {}", e),
                                 }
+                            } else {
+                                println!("{}", "无效的评论序号".red());
                             }
                         }
                     }
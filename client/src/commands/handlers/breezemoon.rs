@@ -8,6 +8,7 @@ use crossterm::{
     cursor, execute,
     terminal::{Clear, ClearType},
 };
+use fishpi_rust::BreezemoonList;
 
 pub struct BreezemoonCommand {
     context: CommandContext,
@@ -30,7 +31,10 @@ impl Command for BreezemoonCommand {
         r#"
         清风明月命令:
             :list [页码]      - 显示清风明月列表（可选页码，默认1）
+            :user <用户名> [页码] - 显示指定用户的清风明月
             :post <内容>      - 发布清风明月
+            :edit <ID> <内容> - 修改清风明月
+            :rm <ID>          - 删除清风明月
             :cls              - 清屏
             :q                - 退出清风明月模式
             :help             - 显示帮助
@@ -46,10 +50,22 @@ impl BreezemoonCommand {
                 name: ":list",
                 desc: "显示清风明月列表",
             },
+            CommandItem {
+                name: ":user",
+                desc: "显示指定用户的清风明月",
+            },
             CommandItem {
                 name: ":post",
                 desc: "发布清风明月",
             },
+            CommandItem {
+                name: ":edit",
+                desc: "修改清风明月",
+            },
+            CommandItem {
+                name: ":rm",
+                desc: "删除清风明月",
+            },
             CommandItem {
                 name: ":cls",
                 desc: "清屏",
@@ -69,6 +85,8 @@ impl BreezemoonCommand {
             "进入清风明月模式 (输入 :q 退出, :help 查看命令)".yellow()
         );
 
+        self.show_list(1).await;
+
         let prompt = format!("{}", "清风明月> ".green());
         loop {
             let input_opt = input_handler.start_input_loop(&prompt).await?;
@@ -114,31 +132,28 @@ impl BreezemoonCommand {
                     } else {
                         1
                     };
-                    let breezemoon_service = &self.context.client.breezemoon;
-                    println!("{} 第{}页...", "获取清风明月列表".cyan(), page);
-                    match breezemoon_service.list(page, 10).await {
-                        Ok(result) => {
-                            if result.count > 0 {
-                                println!("共 {} 条清风明月:", result.count.to_string().green());
-                                for (i, bm) in result.breezemoons.iter().rev().enumerate() {
-                                    println!(
-                                        "{}. {} - {}  {}",
-                                        (i + 1).to_string().yellow(),
-                                        bm.author_name.green(),
-                                        strip_html_tags(&bm.content),
-                                        bm.time_ago.blue()
-                                    );
-                                }
-                                if result.has_more {
-                                    println!("输入 :list <页码> 查看更多");
-                                }
-                            } else {
-                                println!("{}", "暂无清风明月".yellow());
-                            }
-                        }
-                        Err(e) => {
-                            println!("{}: {:?}", "获取清风明月失败".red(), e);
-                        }
+                    self.show_list(page).await;
+                }
+                cmd if cmd.starts_with(":user") => {
+                    let parts: Vec<&str> = cmd.split_whitespace().collect();
+                    let Some(user) = parts.get(1) else {
+                        println!("{}", "用法: :user <用户名> [页码]".yellow());
+                        continue;
+                    };
+                    let page = parts
+                        .get(2)
+                        .and_then(|p| p.parse::<i32>().ok())
+                        .unwrap_or(1);
+                    println!("{} {} 第{}页...", "获取清风明月".cyan(), user, page);
+                    match self
+                        .context
+                        .client
+                        .breezemoon
+                        .list_by_user(user, page, 10)
+                        .await
+                    {
+                        Ok(result) => Self::print_list(&result),
+                        Err(e) => println!("{}: {}", "获取清风明月失败".red(), e),
                     }
                 }
                 cmd if cmd.starts_with(":post ") => {
@@ -150,7 +165,35 @@ impl BreezemoonCommand {
                     let breezemoon_service = &self.context.client.breezemoon;
                     match breezemoon_service.post(content).await {
                         Ok(id) => println!("{}: {}", "发布成功，ID".green(), id),
-                        Err(e) => println!("{}: {:?}", "发布失败".red(), e),
+                        Err(e) => println!("{}: {}", "发布失败".red(), e),
+                    }
+                }
+                cmd if cmd.starts_with(":edit ") => {
+                    let mut parts = cmd[6..].trim().splitn(2, char::is_whitespace);
+                    let (Some(id), Some(content)) = (parts.next(), parts.next()) else {
+                        println!("{}", "用法: :edit <ID> <内容>".yellow());
+                        continue;
+                    };
+                    match self
+                        .context
+                        .client
+                        .breezemoon
+                        .update(id, content.trim())
+                        .await
+                    {
+                        Ok(_) => println!("{}", "修改成功".green()),
+                        Err(e) => println!("{}: {}", "修改失败".red(), e),
+                    }
+                }
+                cmd if cmd.starts_with(":rm ") => {
+                    let id = cmd[4..].trim();
+                    if id.is_empty() {
+                        println!("{}", "用法: :rm <ID>".yellow());
+                        continue;
+                    }
+                    match self.context.client.breezemoon.delete(id).await {
+                        Ok(()) => println!("{}", "删除成功".green()),
+                        Err(e) => println!("{}: {}", "删除失败".red(), e),
                     }
                 }
                 _ => {
@@ -161,4 +204,34 @@ impl BreezemoonCommand {
         }
         Ok(())
     }
+
+    async fn show_list(&self, page: i32) {
+        println!("{} 第{}页...", "获取清风明月列表".cyan(), page);
+        match self.context.client.breezemoon.list(page, 10).await {
+            Ok(result) => Self::print_list(&result),
+            Err(e) => println!("{}: {}", "获取清风明月失败".red(), e),
+        }
+    }
+
+    fn print_list(result: &BreezemoonList) {
+        if result.breezemoons.is_empty() {
+            println!("{}", "暂无清风明月".yellow());
+            return;
+        }
+
+        println!("共 {} 条清风明月:", result.count.to_string().green());
+        for (i, bm) in result.breezemoons.iter().rev().enumerate() {
+            println!(
+                "{}. {} - {}  {} {}",
+                (i + 1).to_string().yellow(),
+                bm.author_name.green(),
+                strip_html_tags(&bm.content),
+                bm.time_ago.blue(),
+                format!("[{}]", bm.id).bright_black()
+            );
+        }
+        if result.has_more {
+            println!("输入 :list <页码> 查看更多");
+        }
+    }
 }
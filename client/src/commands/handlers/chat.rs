@@ -282,10 +282,10 @@ impl ChatCommand {
                     println!("{}", "请输入要私聊的用户名:".cyan());
 
                     let mut input_handler = CrosstermInputHandler::new();
-                    if let Some(username) = input_handler.start_input_loop("用户名> ").await? {
-                        if !username.trim().is_empty() {
-                            self.start_chat_with_user(username.trim()).await?;
-                        }
+                    if let Some(username) = input_handler.start_input_loop("用户名> ").await?
+                        && !username.trim().is_empty()
+                    {
+                        self.start_chat_with_user(username.trim()).await?;
                     }
                 } else {
                     println!("{}", "联系人列表:".green().bold());
@@ -299,7 +299,7 @@ impl ChatCommand {
                         );
                     }
 
-                    println!("{}", "请输入用户名或编号:".cyan());
+                    println!("{}", "请输入用户名或编号 (:readall 全部已读):".cyan());
                     let mut input_handler = CrosstermInputHandler::new();
                     while let Some(input) = input_handler.start_input_loop("选择> ").await? {
                         let input = input.trim();
@@ -314,6 +314,10 @@ impl ChatCommand {
                                     println!("{}", "已退出联系人选择".yellow());
                                     return Ok(());
                                 }
+                                ":readall" => {
+                                    self.mark_all_read().await;
+                                    continue;
+                                }
                                 _ => {
                                     println!("{}", "未知命令".red());
                                     continue; // 不退出，继续输入
@@ -349,6 +353,19 @@ impl ChatCommand {
         Ok(())
     }
 
+    async fn mark_all_read(&self) {
+        let result = self.context.client.chat.mark_all_read().await;
+        if result.success {
+            println!("{}", "已全部标记为已读".green());
+        } else {
+            println!(
+                "{}: {}",
+                "标记已读失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            );
+        }
+    }
+
     async fn send_message(&self, username: &str, message: &str) {
         let result = self
             .context
@@ -6,7 +6,8 @@ use crate::{
     ui::{CommandCompleter, CommandItem, CrosstermInputHandler},
     utils::{
         filter_tail_content, format_quote_message, format_reply_message, format_timestamp_millis,
-        is_quote_message, strip_html_tags_chatroom,
+        is_quote_message, markdown_ansi_enabled, render_markdown_ansi, set_markdown_ansi,
+        strip_html_tags_chatroom,
     },
 };
 use anyhow::Result;
@@ -18,11 +19,20 @@ use crossterm::{
     cursor, execute,
     terminal::{Clear, ClearType},
 };
-use fishpi_rust::{ChatRoomDataContent, ChatRoomMessage, GestureType, RedPacketType};
+use fishpi_rust::utils::render_markdown_plain;
+use fishpi_rust::{
+    ChatRoomDataContent, ChatRoomMessage, ConnectionState, GestureType, RedPacketType, RpsOutcome,
+};
 use lru::LruCache;
 use std::borrow::Cow;
 use std::io::{self, Write};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 发送过于频繁时的最多尝试次数
+const SEND_ATTEMPTS: u32 = 3;
+/// 发送过于频繁时首次重试前的等待时间
+const SEND_RETRY_BACKOFF: Duration = Duration::from_secs(2);
 
 pub struct ChatroomCommand {
     context: CommandContext,
@@ -48,6 +58,7 @@ impl ChatroomCommand {
 impl Command for ChatroomCommand {
     async fn execute(&mut self, _args: &[&str]) -> Result<CommandResult> {
         self.register_message_handler().await?;
+        self.context.client.chatroom.set_dedup(true).await;
         let result = self.context.client.chatroom.connect().await;
         if !result.success {
             println!(
@@ -59,6 +70,7 @@ impl Command for ChatroomCommand {
         }
 
         println!("{}", "已连接聊天室".green());
+        self.show_room_notice().await;
         self.chatroom_loop().await?;
 
         Ok(CommandResult::Success)
@@ -68,19 +80,23 @@ impl Command for ChatroomCommand {
         r#"
         聊天室命令:
             :h [页码]      - 历史消息
+            :h @用户 [页码] - 指定用户的历史消息
             :u             - 在线用户
             :topic [内容]  - 话题
             :revoke <ID>   - 撤回
+            :rm last [数量] - 撤回自己最近的消息
             :bg <内容>     - 弹幕
             :mutes         - 禁言列表
             :raw <ID>      - 消息原文
             :r <ID> <内容> - 回复消息
             :cost          - 弹幕价格
+            :md [on|off]   - Markdown 渲染开关
             :cls           - 清屏
             :q             - 退出
             :rp            - 红包
             :bl            - 消息屏蔽/过滤
             :rw            - 查看自动猜拳分布
+            :node [节点]   - 查看节点列表/切换节点
         "#
     }
 }
@@ -138,6 +154,10 @@ impl ChatroomCommand {
                 name: ":cost",
                 desc: "查看弹幕价格",
             },
+            CommandItem {
+                name: ":md",
+                desc: "Markdown 渲染开关",
+            },
             CommandItem {
                 name: ":disconnect",
                 desc: "断开连接",
@@ -154,12 +174,29 @@ impl ChatroomCommand {
                 name: ":rw",
                 desc: "查看自动猜拳分布",
             },
+            CommandItem {
+                name: ":node",
+                desc: "节点列表/切换节点",
+            },
         ]);
 
         let prompt = format!("{}", "聊天室> ".green().bold());
         loop {
             match input_handler.start_input_loop(&prompt).await? {
                 Some(input) => {
+                    if self
+                        .context
+                        .client
+                        .chatroom
+                        .connection_state()
+                        .await
+                        .is_banned()
+                    {
+                        println!("{}", "你已被封禁，已退出聊天室".red());
+                        self.context.client.chatroom.disconnect().await;
+                        break;
+                    }
+
                     if input.is_empty() {
                         continue;
                     }
@@ -189,47 +226,62 @@ impl ChatroomCommand {
                             self.context.show_switch_help();
                         }
                         ":rw" => {
-                            let stats = crate::utils::GESTURE_STATS.lock().unwrap();
-                            let total: u64 = stats.iter().sum();
-                            if total == 0 {
-                                println!(
-                                    "石头: {}, 剪刀: {}, 布: {}，总数: 0",
-                                    stats[0], stats[1], stats[2]
-                                );
-                            } else {
-                                let rock_pct = stats[0] as f64 / total as f64 * 100.0;
-                                let scissors_pct = stats[1] as f64 / total as f64 * 100.0;
-                                let paper_pct = stats[2] as f64 / total as f64 * 100.0;
+                            let stats = self.context.client.redpacket.gesture_stats();
+                            let total = stats.total_played();
+                            for gesture in
+                                [GestureType::Rock, GestureType::Scissors, GestureType::Paper]
+                            {
+                                let record = stats.get(gesture);
+                                let pct = if total == 0 {
+                                    0.0
+                                } else {
+                                    record.played as f64 / total as f64 * 100.0
+                                };
                                 println!(
-                                    "{}: {:>5} ({:>6.2}%)\n{}: {:>5} ({:>6.2}%)\n{}: {:>7} ({:>6.2}%)\n{}: {}",
-                                    "石头".yellow(),
-                                    stats[0],
-                                    rock_pct,
-                                    "剪刀".yellow(),
-                                    stats[1],
-                                    scissors_pct,
-                                    "布".yellow(),
-                                    stats[2],
-                                    paper_pct,
-                                    "总数".magenta(),
-                                    total
+                                    "{}: {:>5} ({:>6.2}%) 胜 {} 负 {} 平 {} 积分 {}",
+                                    gesture.name().yellow(),
+                                    record.played,
+                                    pct,
+                                    record.wins,
+                                    record.losses,
+                                    record.draws,
+                                    record.points
                                 );
                             }
+                            println!(
+                                "{}: {} 净得积分: {}",
+                                "总数".magenta(),
+                                total,
+                                stats.net_points()
+                            );
                         }
                         cmd if cmd.starts_with(":history") || cmd.starts_with(":h") => {
                             let parts: Vec<&str> = cmd.split_whitespace().collect();
-                            let page = if parts.len() > 1 {
-                                parts[1].parse().unwrap_or(1)
-                            } else {
-                                1
-                            };
+                            let user = parts
+                                .get(1)
+                                .and_then(|p| p.strip_prefix('@'))
+                                .map(|u| u.to_string());
+                            let page_index = if user.is_some() { 2 } else { 1 };
+                            let page = parts
+                                .get(page_index)
+                                .and_then(|p| p.parse().ok())
+                                .unwrap_or(1);
                             println!("{}", "=".repeat(50).yellow());
-                            self.show_history(page).await;
+                            match user {
+                                Some(user) => self.show_user_history(&user, page).await,
+                                None => self.show_history(page).await,
+                            }
                             println!("{}", "=".repeat(50).yellow());
                         }
                         ":users" | ":u" => {
                             self.show_online_users().await;
                         }
+                        cmd if cmd == ":node" || cmd.starts_with(":node ") => {
+                            match cmd.split_whitespace().nth(1) {
+                                Some(node) => self.switch_node(node).await,
+                                None => self.show_nodes().await,
+                            }
+                        }
                         cmd if cmd.starts_with(":topic") => {
                             let parts: Vec<&str> = cmd.split_whitespace().collect();
                             if parts.len() > 1 {
@@ -267,16 +319,29 @@ impl ChatroomCommand {
                         }
                         cmd if cmd.starts_with(":rm") || cmd.starts_with(":remove") => {
                             let parts: Vec<&str> = cmd.split_whitespace().collect();
-                            if parts.len() > 1 {
+                            if parts.len() > 1 && parts[1] == "last" {
+                                let n = parts.get(2).and_then(|n| n.parse().ok()).unwrap_or(1);
+                                self.revoke_last_messages(n).await;
+                            } else if parts.len() > 1 {
                                 let oid = parts[1];
                                 self.revoke_message(oid).await;
                             } else {
-                                println!("{}", "用法: :rm <消息ID> 或 :remove <消息ID>".yellow());
+                                println!("{}", "用法: :rm <消息ID> 或 :rm last [数量]".yellow());
                             }
                         }
                         ":cost" => {
                             self.show_barrage_cost().await;
                         }
+                        cmd if cmd == ":md" || cmd.starts_with(":md ") => {
+                            let enabled = match cmd.split_whitespace().nth(1) {
+                                Some("on") => true,
+                                Some("off") => false,
+                                _ => !markdown_ansi_enabled(),
+                            };
+                            set_markdown_ansi(enabled);
+                            let state = if enabled { "开启" } else { "关闭" };
+                            println!("Markdown 渲染已{}", state.yellow());
+                        }
                         ":disconnect" | ":dc" => {
                             self.disconnect().await;
                             break;
@@ -299,12 +364,21 @@ impl ChatroomCommand {
                                 let reply_content = parts[2..].join(" ");
                                 let raw_content =
                                     self.context.client.chatroom.get_raw_message(oid).await?;
-                                let user_name = {
+                                let cached = {
                                     let mut cache = self.message_cache.lock().unwrap();
-                                    cache
-                                        .get(oid)
-                                        .map(|msg| msg.user_name.clone())
-                                        .unwrap_or_default()
+                                    cache.get(oid).map(|msg| msg.user_name.clone())
+                                };
+                                let user_name = match cached {
+                                    Some(name) => name,
+                                    None => self
+                                        .context
+                                        .client
+                                        .chatroom
+                                        .get_message(oid)
+                                        .await
+                                        .data
+                                        .map(|msg| msg.user_name)
+                                        .unwrap_or_default(),
                                 };
                                 let msg = format_reply_message(
                                     oid,
@@ -360,9 +434,9 @@ impl ChatroomCommand {
                             }
                             let should_block = {
                                 let cfg = filter_handler.config.lock().unwrap();
-                                cfg.should_block(&msg.user_name, msg.md_text())
+                                cfg.should_block(&msg)
                             };
-                            if should_block && !msg.is_redpacket() {
+                            if should_block {
                                 filter_handler.push_blocked_msg((*msg).clone());
                                 return;
                             }
@@ -418,18 +492,18 @@ impl ChatroomCommand {
                                 let music = msg.music().unwrap();
                                 println!(
                                     "\r{} {} {}: {}\n{} - {}",
-                                    msg.time.blue(),
+                                    msg.display_time().blue(),
                                     msg.all_name().green(),
                                     format!("[{}]", msg.oid).bright_black(),
                                     "🎵 音乐分享".magenta().bold(),
                                     music.title.magenta().bold(),
-                                    music.source.magenta().bold()
+                                    music.resolve_url().unwrap_or_default().magenta().bold()
                                 );
                             } else if msg.is_weather() {
                                 let weather = msg.weather().unwrap();
                                 println!(
                                     "\r{} {} {}: {} - {}",
-                                    msg.time.blue(),
+                                    msg.display_time().blue(),
                                     msg.all_name().green(),
                                     format!("[{}]", msg.oid).bright_black(),
                                     "🌤️ 天气消息".cyan().bold(),
@@ -441,42 +515,32 @@ impl ChatroomCommand {
                                     let formatted_content = format_quote_message(content);
                                     println!(
                                         "\r{} {} {}: {}",
-                                        msg.time.blue(),
+                                        msg.display_time().blue(),
                                         msg.all_name().green(),
                                         format!("[{}]", msg.oid).bright_black(),
                                         filter_tail_content(&formatted_content)
                                     );
                                 } else {
                                     let filtered_content = filter_tail_content(content);
+                                    let text = strip_html_tags_chatroom(&filtered_content);
+                                    let text = if markdown_ansi_enabled() {
+                                        render_markdown_ansi(&text)
+                                    } else {
+                                        render_markdown_plain(&text)
+                                    };
                                     println!(
                                         "\r{} {} {}: {}",
-                                        msg.time.blue(),
+                                        msg.display_time().blue(),
                                         msg.all_name().green(),
                                         format!("[{}]", msg.oid).bright_black(),
-                                        strip_html_tags_chatroom(&filtered_content)
+                                        text
                                     );
                                 }
                             }
                         }
                         ChatRoomDataContent::Barrager(barrager) => {
-                            let color_str = barrager.barrager_color.as_str();
-                            let (r, g, b) = if let Some(stripped) = color_str
-                                .strip_prefix("rgba(")
-                                .and_then(|s| s.strip_suffix(')'))
-                            {
-                                let parts: Vec<&str> = stripped.split(',').collect();
-                                if parts.len() >= 3 {
-                                    (
-                                        parts[0].trim().parse::<u8>().unwrap_or(255),
-                                        parts[1].trim().parse::<u8>().unwrap_or(255),
-                                        parts[2].trim().parse::<u8>().unwrap_or(255),
-                                    )
-                                } else {
-                                    (255, 255, 255)
-                                }
-                            } else {
-                                (255, 255, 255)
-                            };
+                            let color = barrager.color().unwrap_or_default();
+                            let (r, g, b) = (color.r, color.g, color.b);
                             println!(
                                 "\r[{}]{}: {}",
                                 "🎯 弹幕".yellow().bold(),
@@ -492,7 +556,7 @@ impl ChatroomCommand {
                             println!("\r{}: {}", "💬 话题变更".yellow().bold(), topic.yellow());
                         }
                         ChatRoomDataContent::RedPacketStatus(status) => {
-                            if status.got >= status.count {
+                            if status.is_finished() {
                                 redpacket_cache.lock().unwrap().remove(&status.oid);
                             }
                             println!(
@@ -510,24 +574,19 @@ impl ChatroomCommand {
 
                                 if is_sender || is_receiver {
                                     let result = client.redpacket.open(&status.oid).await;
-                                    if result.success {
-                                        if let Some(info) = result.data {
-                                            if let Some(gesture) = info.info.gesture {
-                                                if let Some(who) = info
-                                                    .who
-                                                    .iter()
-                                                    .find(|w| w.user_name == status.who_got)
-                                                {
-                                                    let display_gesture = if is_sender {
-                                                        gesture
-                                                    } else {
-                                                        Self::deduce_receiver_gesture(gesture, who.money)
-                                                    };
-                                                    
-                                                    Self::rps_result(display_gesture, who.money, is_sender);
-                                                }
-                                            }
-                                        }
+                                    if result.success
+                                        && let Some(info) = result.data
+                                        && let Some(gesture) = info.info.gesture
+                                        && let Some(outcome) =
+                                            info.outcome_for(&status.who_got, is_sender)
+                                    {
+                                        let display_gesture = if is_sender {
+                                            gesture
+                                        } else {
+                                            Self::deduce_receiver_gesture(gesture, outcome)
+                                        };
+
+                                        Self::rps_result(display_gesture, outcome);
                                     }
                                 }
                             }
@@ -539,6 +598,14 @@ impl ChatroomCommand {
                                 revoke.cyan().bold()
                             );
                         }
+                        ChatRoomDataContent::ConnectionState(ConnectionState::Banned {
+                            reason,
+                        }) => {
+                            println!("\r{}: {}", "你已被封禁".red().bold(), reason);
+                            println!("{}", "按回车退出聊天室".yellow());
+                        }
+                        ChatRoomDataContent::ConnectionState(_) => {}
+                        ChatRoomDataContent::OnlineChange { .. } => {}
                     }
 
                     io::stdout().flush().ok();
@@ -558,7 +625,7 @@ impl ChatroomCommand {
             .context
             .client
             .chatroom
-            .send(Cow::from(message), None)
+            .send_retrying(Cow::from(message), SEND_ATTEMPTS, SEND_RETRY_BACKOFF)
             .await;
         if !result.success {
             println!(
@@ -566,58 +633,17 @@ impl ChatroomCommand {
                 "发送失败".red(),
                 result.message.unwrap_or("未知错误".to_string())
             );
+            println!("{}: {}", "未发送的内容".yellow(), message);
         }
     }
 
     async fn show_history(&self, page: i32) {
         println!("获取聊天室历史消息 (第{}页)...", page);
-        let result = self.context.client.chatroom.get_history(page).await;
+        let result = self.context.client.chatroom.get_history(page, None).await;
 
         if result.success {
-            if let Some(response) = result.data {
-                if let Some(messages) = response.data {
-                    for msg in messages.iter().rev() {
-                        if msg.is_redpacket() {
-                            let redpacket = msg.redpacket().unwrap();
-                            println!(
-                                "{} {} {}: {} 红包 - {} 个, {} 积分",
-                                msg.time.blue(),
-                                msg.all_name().green(),
-                                format!("[{}]", msg.oid).bright_black(),
-                                RedPacketType::to_name(&redpacket.type_).red(),
-                                redpacket.count.to_string().yellow(),
-                                redpacket.money.to_string().yellow()
-                            );
-                        } else if msg.is_music() {
-                            let music = msg.music().unwrap();
-                            println!(
-                                "{} {} {}: 🎵 {} - {}",
-                                msg.time.blue(),
-                                msg.all_name().green(),
-                                format!("[{}]", msg.oid).bright_black(),
-                                music.title.magenta(),
-                                music.from.magenta()
-                            );
-                        } else if msg.is_weather() {
-                            let weather = msg.weather().unwrap();
-                            println!(
-                                "{} {} {}: 🌤️ {}",
-                                msg.time.blue(),
-                                msg.all_name().green(),
-                                format!("[{}]", msg.oid).bright_black(),
-                                weather.format_colored_weather()
-                            );
-                        } else {
-                            println!(
-                                "{} {} {}:{}",
-                                msg.time.blue().bold(),
-                                msg.all_name().green().bold(),
-                                format!("[{}]", msg.oid).bright_black(),
-                                strip_html_tags_chatroom(msg.content_text())
-                            );
-                        }
-                    }
-                }
+            for msg in result.data.unwrap_or_default().iter().rev() {
+                Self::print_history_message(msg);
             }
         } else {
             println!(
@@ -628,6 +654,69 @@ impl ChatroomCommand {
         }
     }
 
+    async fn show_user_history(&self, user: &str, page: i32) {
+        println!("获取 {} 的历史消息 (从第{}页开始)...", user.green(), page);
+        let result = self.context.client.chatroom.user_history(user, page).await;
+
+        if result.success {
+            let messages = result.data.unwrap_or_default();
+            if messages.is_empty() {
+                println!("{}", "没有找到该用户的消息".yellow());
+            }
+            for msg in messages.iter().rev() {
+                Self::print_history_message(msg);
+            }
+        } else {
+            println!(
+                "{}: {}",
+                "获取历史消息失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            );
+        }
+    }
+
+    fn print_history_message(msg: &ChatRoomMessage) {
+        if msg.is_redpacket() {
+            let redpacket = msg.redpacket().unwrap();
+            println!(
+                "{} {} {}: {} 红包 - {} 个, {} 积分",
+                msg.display_time().blue(),
+                msg.all_name().green(),
+                format!("[{}]", msg.oid).bright_black(),
+                RedPacketType::to_name(&redpacket.type_).red(),
+                redpacket.count.to_string().yellow(),
+                redpacket.money.to_string().yellow()
+            );
+        } else if msg.is_music() {
+            let music = msg.music().unwrap();
+            println!(
+                "{} {} {}: 🎵 {} - {}",
+                msg.display_time().blue(),
+                msg.all_name().green(),
+                format!("[{}]", msg.oid).bright_black(),
+                music.title.magenta(),
+                music.from.magenta()
+            );
+        } else if msg.is_weather() {
+            let weather = msg.weather().unwrap();
+            println!(
+                "{} {} {}: 🌤️ {}",
+                msg.display_time().blue(),
+                msg.all_name().green(),
+                format!("[{}]", msg.oid).bright_black(),
+                weather.format_colored_weather()
+            );
+        } else {
+            println!(
+                "{} {} {}:{}",
+                msg.display_time().blue().bold(),
+                msg.all_name().green().bold(),
+                format!("[{}]", msg.oid).bright_black(),
+                strip_html_tags_chatroom(msg.content_text())
+            );
+        }
+    }
+
     async fn show_online_users(&self) {
         let result = self.context.client.chatroom.get_online_users().await;
 
@@ -635,7 +724,22 @@ impl ChatroomCommand {
             if let Some(mut users) = result.data {
                 users.sort_by_key(|a| a.all_name());
                 for (i, user) in users.iter().enumerate() {
-                    println!("  {}. {}", i + 1, user.all_name().green());
+                    let metals: Vec<String> = user
+                        .metals()
+                        .into_iter()
+                        .filter(|m| m.enabled != Some(false))
+                        .map(|m| m.name)
+                        .collect();
+                    if metals.is_empty() {
+                        println!("  {}. {}", i + 1, user.all_name().green());
+                    } else {
+                        println!(
+                            "  {}. {} {}",
+                            i + 1,
+                            user.all_name().green(),
+                            format!("[{}]", metals.join(", ")).yellow()
+                        );
+                    }
                 }
             }
         } else {
@@ -647,6 +751,45 @@ impl ChatroomCommand {
         }
     }
 
+    async fn show_nodes(&self) {
+        let result = self.context.client.chatroom.nodes().await;
+        match result.data {
+            Some(info) => {
+                for node in info.avaliable.iter() {
+                    let mark = if node.node == info.recommend.node {
+                        " (推荐)".yellow().to_string()
+                    } else {
+                        String::new()
+                    };
+                    println!(
+                        "  {} {} 人在线{}",
+                        node.name.green(),
+                        node.online.to_string().cyan(),
+                        mark
+                    );
+                }
+            }
+            None => println!(
+                "{}: {}",
+                "获取节点列表失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            ),
+        }
+    }
+
+    async fn switch_node(&self, node: &str) {
+        let result = self.context.client.chatroom.connect_to(node).await;
+        if result.success {
+            println!("{}", "已切换聊天室节点".green());
+        } else {
+            println!(
+                "{}: {}",
+                "切换节点失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            );
+        }
+    }
+
     async fn show_current_topic(&self) {
         let result = self.context.client.chatroom.get_discussing().await;
 
@@ -665,6 +808,13 @@ impl ChatroomCommand {
         }
     }
 
+    async fn show_room_notice(&self) {
+        let result = self.context.client.chatroom.room_notice().await;
+        if let Some(Some(notice)) = result.data {
+            println!("{}: {}", "公告".cyan(), notice.yellow());
+        }
+    }
+
     async fn set_topic(&self, topic: &str) {
         println!("设置聊天室话题: {}", topic.yellow());
 
@@ -695,6 +845,24 @@ impl ChatroomCommand {
         }
     }
 
+    async fn revoke_last_messages(&self, n: usize) {
+        let result = self.context.client.chatroom.revoke_last(n).await;
+
+        if result.success {
+            let revoked = result.data.unwrap_or_default();
+            println!("{}", format!("已撤回 {} 条消息", revoked.len()).yellow());
+            if let Some(errors) = result.message {
+                println!("{}: {}", "部分消息撤回失败".red(), errors);
+            }
+        } else {
+            println!(
+                "{}: {}",
+                "撤回消息失败".red(),
+                result.message.unwrap_or("未知错误".to_string())
+            );
+        }
+    }
+
     async fn send_barrage(&self, content: &str, color: &str) {
         println!("发送弹幕: {} (颜色: {})", content, color);
 
@@ -791,46 +959,30 @@ impl ChatroomCommand {
     }
 
     /// 显示猜拳红包结果
-    fn rps_result(gesture: i32, money: i32, is_sender: bool) {
+    fn rps_result(gesture: i32, outcome: RpsOutcome) {
         let gesture_name = GestureType::from_i32(gesture)
             .map(|g| g.name())
-            .unwrap_or("未知");        
-        if is_sender {
-            match money {
-                m if m < 0 => println!(
-                    "\r  🎉 你出 {} 赢了 {} 积分!",
-                    gesture_name.yellow(),
-                    m.abs().to_string().cyan().bold()
-                ),
-                m if m > 0 => println!(
-                    "\r  💔 你出 {} 输了 {} 积分!",
-                    gesture_name.yellow(),
-                    m.to_string().cyan().bold()
-                ),
-                _ => println!("\r  🤝 你出 {} 平局!", gesture_name.yellow()),
-            }
-        } else {
-            match money {
-                m if m > 0 => println!(
-                    "\r  🎉 你出 {} 赢了 {} 积分!",
-                    gesture_name.yellow(),
-                    m.to_string().cyan().bold()
-                ),
-                m if m < 0 => println!(
-                    "\r  💔 你出 {} 输了 {} 积分!",
-                    gesture_name.yellow(),
-                    m.abs().to_string().cyan().bold()
-                ),
-                _ => println!("\r  🤝 你出 {} 平局!", gesture_name.yellow()),
-            }
+            .unwrap_or("未知");
+        match outcome {
+            RpsOutcome::Win(points) => println!(
+                "\r  🎉 你出 {} 赢了 {} 积分!",
+                gesture_name.yellow(),
+                points.to_string().cyan().bold()
+            ),
+            RpsOutcome::Lose(points) => println!(
+                "\r  💔 你出 {} 输了 {} 积分!",
+                gesture_name.yellow(),
+                points.to_string().cyan().bold()
+            ),
+            RpsOutcome::Draw => println!("\r  🤝 你出 {} 平局!", gesture_name.yellow()),
         }
     }
 
-    fn deduce_receiver_gesture(sender_gesture: i32, money: i32) -> i32 {
-        if money == 0 {
+    fn deduce_receiver_gesture(sender_gesture: i32, outcome: RpsOutcome) -> i32 {
+        if outcome == RpsOutcome::Draw {
             // 平局
             sender_gesture
-        } else if money > 0 {
+        } else if matches!(outcome, RpsOutcome::Win(_)) {
             // 接收者赢
             match sender_gesture {
                 0 => 2, // 发送者石头(0)，接收者布(2)赢
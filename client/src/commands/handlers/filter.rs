@@ -5,109 +5,43 @@ use crate::utils::{
 use anyhow::Result;
 use async_trait::async_trait;
 use colored::*;
-use fishpi_rust::ChatRoomMessage;
+use fishpi_rust::{ChatRoomMessage, MessageFilter};
 use lru::LruCache;
-use serde::{Deserialize, Serialize};
 use std::fs;
 use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
 
 const FILTER_CONFIG_FILE: &str = "filters.json";
 
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
-pub struct FilterConfig {
-    pub blocked_users: Vec<String>,
-    pub keywords: Vec<String>,
-    pub regexes: Vec<String>,
+/// 从文件加载过滤规则，文件不存在或无效时返回空规则
+fn load_filter() -> MessageFilter {
+    let Ok(data) = fs::read_to_string(FILTER_CONFIG_FILE) else {
+        return MessageFilter::default();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|err| {
+        eprintln!(
+            "警告: 过滤规则文件 {} 无效，已忽略: {}",
+            FILTER_CONFIG_FILE, err
+        );
+        MessageFilter::default()
+    })
 }
 
-impl FilterConfig {
-    pub fn load() -> Self {
-        if let Ok(data) = fs::read_to_string(FILTER_CONFIG_FILE) {
-            serde_json::from_str(&data).unwrap_or_default()
-        } else {
-            FilterConfig::default()
-        }
-    }
-
-    pub fn save(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = fs::write(FILTER_CONFIG_FILE, json);
-        }
-    }
-
-    pub fn add_user(&mut self, user: String) {
-        let user = user.to_lowercase();
-        if !self.blocked_users.iter().any(|u| u == &user) {
-            self.blocked_users.push(user);
-        }
-    }
-
-    pub fn add_keyword(&mut self, kw: String) {
-        if !self.keywords.contains(&kw) {
-            self.keywords.push(kw);
-        }
-    }
-
-    pub fn add_regex(&mut self, re: String) {
-        if !self.regexes.contains(&re) {
-            self.regexes.push(re);
-        }
-    }
-
-    pub fn remove_user(&mut self, user: &str) {
-        let user = user.to_lowercase();
-        self.blocked_users.retain(|u| u != &user);
-    }
-
-    pub fn remove_keyword(&mut self, kw: &str) {
-        self.keywords.retain(|k| k != kw);
-    }
-
-    pub fn remove_regex(&mut self, re: &str) -> bool {
-        if let Some(pos) = self.regexes.iter().position(|r| r == re) {
-            self.regexes.remove(pos);
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn remove_regex_by_index(&mut self, idx: usize) -> Option<String> {
-        if idx == 0 || idx > self.regexes.len() {
-            return None;
-        }
-        Some(self.regexes.remove(idx - 1))
-    }
-
-    pub fn should_block(&self, username: &str, content: &str) -> bool {
-        let username = username.to_lowercase();
-        if self.blocked_users.iter().any(|u| u == &username) {
-            return true;
-        }
-        if self.keywords.iter().any(|kw| content.starts_with(kw)) {
-            return true;
-        }
-        for re_str in &self.regexes {
-            if let Ok(re) = regex::Regex::new(re_str) {
-                if re.is_match(content) {
-                    return true;
-                }
-            }
-        }
-        false
+fn save_filter(filter: &MessageFilter) {
+    if let Ok(json) = serde_json::to_string_pretty(filter) {
+        let _ = fs::write(FILTER_CONFIG_FILE, json);
     }
 }
 
 #[derive(Clone)]
 pub struct FilterCommand {
-    pub config: Arc<Mutex<FilterConfig>>,
+    pub config: Arc<Mutex<MessageFilter>>,
     pub blocked_msgs: Arc<Mutex<LruCache<String, ChatRoomMessage>>>,
 }
 
 impl FilterCommand {
     pub fn new() -> Self {
-        let config = Arc::new(Mutex::new(FilterConfig::load()));
+        let config = Arc::new(Mutex::new(load_filter()));
         let blocked_msgs = Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(200).unwrap())));
         Self {
             config,
@@ -119,35 +53,39 @@ impl FilterCommand {
         let mut cfg = self.config.lock().unwrap();
         match args {
             ["user", user] => {
-                cfg.add_user(user.to_string());
+                cfg.block_user(user);
                 println!("{}", format!("已添加屏蔽用户：{}", user).green());
             }
             ["kw", kw] => {
-                cfg.add_keyword(kw.to_string());
+                cfg.block_keyword(kw);
                 println!("{}", format!("已添加屏蔽关键字：{}", kw).green());
             }
             ["re", ..] => {
                 let re = args[1..].join(" ");
-                cfg.add_regex(re.clone());
-                println!("{}", format!("已添加屏蔽正则：{}", re).green());
+                match cfg.block_regex(&re) {
+                    Ok(()) => println!("{}", format!("已添加屏蔽正则：{}", re).green()),
+                    Err(err) => println!("{}", err.to_string().red()),
+                }
             }
             ["rm", "user", user] => {
-                cfg.remove_user(user);
+                cfg.unblock_user(user);
                 println!("{}", format!("已移除屏蔽用户：{}", user).yellow());
             }
             ["rm", "kw", kw] => {
-                cfg.remove_keyword(kw);
+                cfg.unblock_keyword(kw);
                 println!("{}", format!("已移除屏蔽关键字：{}", kw).yellow());
             }
             ["rm", "re", ..] => {
                 let re = args[2..].join(" ");
                 if let Ok(idx) = re.parse::<usize>() {
-                    if let Some(removed) = cfg.remove_regex_by_index(idx) {
+                    if (1..=cfg.regexes.len()).contains(&idx) {
+                        let removed = cfg.regexes[idx - 1].clone();
+                        cfg.unblock_regex(&removed);
                         println!("{}", format!("已移除屏蔽正则：{}", removed).yellow());
                     } else {
                         println!("{}", "编号无效".red());
                     }
-                } else if cfg.remove_regex(&re) {
+                } else if cfg.unblock_regex(&re) {
                     println!("{}", format!("已移除屏蔽正则：{}", re).yellow());
                 } else {
                     println!("{}", "未找到该正则".red());
@@ -177,7 +115,7 @@ impl FilterCommand {
                 println!("{}", "无效的命令或参数，请使用 :bl help 查看帮助".red());
             }
         }
-        cfg.save();
+        save_filter(&cfg);
     }
 
     pub fn push_blocked_msg(&self, msg: ChatRoomMessage) {
@@ -1,6 +1,5 @@
 use crate::commands::{Command, CommandContext, CommandResult};
 use crate::ui::{CommandItem, CrosstermInputHandler};
-use crate::utils::strip_html_tags;
 use anyhow::Result;
 use async_trait::async_trait;
 use colored::*;
@@ -8,7 +7,7 @@ use crossterm::{
     cursor, execute,
     terminal::{Clear, ClearType},
 };
-use fishpi_rust::{NoticeAt, NoticeComment, NoticeFollow, NoticePoint, NoticeSystem, NoticeType};
+use fishpi_rust::{NoticeMsgType, NoticeType};
 
 pub struct NoticeCommand {
     context: CommandContext,
@@ -76,6 +75,18 @@ impl NoticeCommand {
         ]);
 
         println!("{}", "进入通知模式 (输入 :q 退出, :help 查看命令)".yellow());
+        self.context
+            .client
+            .notice
+            .add_listener(|notice_msg| {
+                if notice_msg.msg_type() == NoticeMsgType::RefreshNotification {
+                    match notice_msg.count {
+                        Some(count) => println!("\r{}", format!("你有{}条新通知", count).green()),
+                        None => println!("\r{}", "你有新通知".green()),
+                    }
+                }
+            })
+            .await;
         let prompt = format!("{}", "通知> ".green());
         loop {
             let input = input_handler.start_input_loop(&prompt).await?;
@@ -87,11 +98,11 @@ impl NoticeCommand {
                 continue;
             }
 
-            if input.starts_with(':') {
-                if let Some(command) = self.context.is_switch_command(&input) {
-                    self.context.switch_to_mode(command).await?;
-                    continue;
-                }
+            if input.starts_with(':')
+                && let Some(command) = self.context.is_switch_command(&input)
+            {
+                self.context.switch_to_mode(command).await?;
+                continue;
             }
 
             match input.as_str() {
@@ -138,88 +149,30 @@ impl NoticeCommand {
                     for (notice_type_str, type_name) in query_types {
                         if let Some(notice_type) = NoticeType::from_str(notice_type_str) {
                             println!("\n\n获取{}通知列表...", type_name.cyan());
-                            let result = notice_service.list(notice_type.as_str(), Some(1)).await;
+                            let result = notice_service
+                                .list_by_type(notice_type.as_str(), Some(1))
+                                .await;
                             if result.success {
                                 if let Some(notices) = result.data {
                                     println!("{}通知列表 ({}条):", type_name, notices.len());
                                     for (i, notice) in notices.iter().rev().enumerate() {
-                                        match notice_type {
-                                            NoticeType::Point => {
-                                                let point = NoticePoint::from(notice);
-                                                let status = if point.has_read {
-                                                    "已读".green()
-                                                } else {
-                                                    "未读".red().bold()
-                                                };
-                                                println!(
-                                                    "  {}. [{}] {} {}",
-                                                    i + 1,
-                                                    status,
-                                                    point.create_time.cyan(),
-                                                    strip_html_tags(&point.description)
-                                                );
-                                            }
-                                            NoticeType::Commented => {
-                                                let comment = NoticeComment::from(notice);
-                                                let status = if comment.has_read {
-                                                    "已读".green()
-                                                } else {
-                                                    "未读".red().bold()
-                                                };
-                                                println!(
-                                                    "  {}. [{}] {} {}",
-                                                    i + 1,
-                                                    status,
-                                                    comment.create_time.cyan(),
-                                                    strip_html_tags(&comment.content)
-                                                );
-                                            }
-                                            NoticeType::At => {
-                                                let at = NoticeAt::from(notice);
-                                                let status = if at.has_read {
-                                                    "已读".green()
-                                                } else {
-                                                    "未读".red().bold()
-                                                };
-                                                println!(
-                                                    "  {}. [{}] {} {}",
-                                                    i + 1,
-                                                    status,
-                                                    at.create_time.cyan(),
-                                                    strip_html_tags(&at.content)
-                                                );
-                                            }
-                                            NoticeType::Following => {
-                                                let follow = NoticeFollow::from(notice);
-                                                let status = if follow.has_read {
-                                                    "已读".green()
-                                                } else {
-                                                    "未读".red().bold()
-                                                };
-                                                println!(
-                                                    "  {}. [{}] {} {}",
-                                                    i + 1,
-                                                    status,
-                                                    follow.create_time.cyan(),
-                                                    follow.title.yellow()
-                                                );
-                                            }
-                                            NoticeType::System => {
-                                                let sys = NoticeSystem::from(notice);
-                                                let status = if sys.has_read {
+                                        match notice_type.to_display(notice) {
+                                            Some(item) => {
+                                                let status = if item.has_read() {
                                                     "已读".green()
                                                 } else {
                                                     "未读".red().bold()
                                                 };
                                                 println!(
-                                                    "  {}. [{}] {} {}",
+                                                    "  {}. [{}] {} {} {}",
                                                     i + 1,
                                                     status,
-                                                    sys.create_time.cyan(),
-                                                    sys.description.yellow()
+                                                    item.time().cyan(),
+                                                    item.headline().yellow(),
+                                                    item.body()
                                                 );
                                             }
-                                            _ => {
+                                            None => {
                                                 println!("  {}. [未知类型] {:?}", i + 1, notice);
                                             }
                                         }
@@ -248,7 +201,7 @@ impl NoticeCommand {
                     if let Some(notice_type) = NoticeType::from_str(notice_type_str) {
                         let notice_service = &self.context.client.notice;
                         println!("标记{}通知为已读...", notice_type_str.green());
-                        let result = notice_service.make_read(notice_type.as_str()).await;
+                        let result = notice_service.make_read(notice_type).await;
                         if result.success {
                             println!("{}", "通知已标记为已读".green());
                         } else {
@@ -265,7 +218,7 @@ impl NoticeCommand {
                 ":readall" => {
                     let notice_service = &self.context.client.notice;
                     println!("{}", "标记所有通知为已读...".cyan());
-                    let result = notice_service.read_all().await;
+                    let result = notice_service.mark_all_read().await;
                     if result.success {
                         println!("{}", "所有通知已标记为已读".green());
                     } else {
@@ -313,6 +266,7 @@ impl NoticeCommand {
                 }
             }
         }
+        self.context.client.notice.remove_all_listeners().await;
         Ok(())
     }
 }
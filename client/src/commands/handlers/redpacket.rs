@@ -135,7 +135,7 @@ impl RedpacketCommand {
         }
 
         if let Some(info) = &result.data {
-            if info.info.count == info.info.got {
+            if info.info.is_finished() {
                 println!("{}", "红包已领完".yellow());
                 println!(
                     "{}",
@@ -192,13 +192,6 @@ impl RedpacketCommand {
             }
         };
 
-        let count = if count < 1 { default_count } else { count };
-        let money = if money < default_money {
-            default_money
-        } else {
-            money
-        };
-
         let result = self
             .context
             .client
@@ -241,12 +234,6 @@ impl RedpacketCommand {
                 (count, money, msg)
             }
         };
-        let count = if count < 1 { default_count } else { count };
-        let money = if money < default_money {
-            default_money
-        } else {
-            money
-        };
 
         let result = self
             .context
@@ -302,12 +289,6 @@ impl RedpacketCommand {
             }
         };
 
-        let money = if money < default_money {
-            default_money
-        } else {
-            money
-        };
-
         let result = self
             .context
             .client
@@ -352,12 +333,6 @@ impl RedpacketCommand {
             }
         };
 
-        let count = if count < 1 { default_count } else { count };
-        let money = if money < default_money {
-            default_money
-        } else {
-            money
-        };
         let result = self
             .context
             .client
@@ -1,7 +1,6 @@
 use crate::commands::CommandContext;
 use colored::*;
-use fishpi_rust::api::ChatroomApi;
-use once_cell::sync::OnceCell;
+use once_cell::sync::{Lazy, OnceCell};
 use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
@@ -14,6 +13,15 @@ use std::io;
 
 pub static GLOBAL_COMMAND_CONTEXT: OnceCell<CommandContext> = OnceCell::new();
 
+/// 补全专用运行时，避免每次按键都新建
+static COMPLETION_RUNTIME: Lazy<tokio::runtime::Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .expect("创建补全运行时失败")
+});
+
 pub struct CommandItem {
     pub name: &'static str,
     pub desc: &'static str,
@@ -76,32 +84,31 @@ impl Completer for CommandCompleter {
         // @用户名补全
         if let Some(at_pos) = line[..pos].rfind('@') {
             let prefix = &line[at_pos + 1..pos];
-            if !prefix.is_empty() {
-                if let Some(ctx) = GLOBAL_COMMAND_CONTEXT.get() {
-                    let prefix_owned = prefix.to_string();
-                    let ctx_clone = ctx.clone();
-                    let candidates = std::thread::scope(|s| {
-                        let handle = s.spawn(move || {
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async move {
-                                let api = ChatroomApi::new(ctx_clone.client.api_client.clone());
-                                match api.autocomplete_username(&prefix_owned).await {
-                                    Ok(api_users) => api_users
-                                        .into_iter()
-                                        .map(|u| Pair {
-                                            display: format!("@{}", u.user_name.cyan()),
-                                            replacement: u.user_name,
-                                        })
-                                        .collect(),
-                                    Err(_) => vec![],
-                                }
-                            })
-                        });
-                        handle.join().unwrap_or_else(|_| vec![])
+            if !prefix.is_empty()
+                && let Some(ctx) = GLOBAL_COMMAND_CONTEXT.get()
+            {
+                let prefix_owned = prefix.to_string();
+                let ctx_clone = ctx.clone();
+                let candidates = std::thread::scope(|s| {
+                    let handle = s.spawn(move || {
+                        COMPLETION_RUNTIME.block_on(async move {
+                            let result =
+                                ctx_clone.client.chatroom.autocomplete(&prefix_owned).await;
+                            result
+                                .data
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|u| Pair {
+                                    display: format!("@{}", u.user_name.cyan()),
+                                    replacement: u.user_name,
+                                })
+                                .collect()
+                        })
                     });
+                    handle.join().unwrap_or_else(|_| vec![])
+                });
 
-                    return Ok((at_pos + 1, candidates));
-                }
+                return Ok((at_pos + 1, candidates));
             }
         }
 
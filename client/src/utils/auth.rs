@@ -23,20 +23,20 @@ impl AuthService {
         let expire = Duration::from_secs(300); // 5分钟
 
         // 检查缓存是否可用
-        if let Some((info, ts)) = &*cache {
-            if now.duration_since(*ts) < expire {
-                return Ok(info.clone());
-            }
+        if let Some((info, ts)) = &*cache
+            && now.duration_since(*ts) < expire
+        {
+            return Ok(info.clone());
         }
 
         // 缓存无效，重新获取
         let result = self.client.user.get_info().await;
-        if result.success && result.data.is_some() {
-            let user_info = result.data.unwrap();
-            if let Some(user_data) = user_info.data {
-                *cache = Some((user_data.clone(), now));
-                return Ok(user_data);
-            }
+        if result.success
+            && let Some(user_info) = result.data
+            && let Some(user_data) = user_info.data
+        {
+            *cache = Some((user_data.clone(), now));
+            return Ok(user_data);
         }
         Err(anyhow::anyhow!("获取用户信息失败"))
     }
@@ -66,18 +66,13 @@ impl AuthService {
 
     /// 尝试使用保存的token登录
     pub async fn try_login_with_saved_token(&self) -> Result<()> {
-        if let Ok(token) = std::fs::read_to_string("token.txt") {
-            let token = token.trim().to_string();
-            if !token.is_empty() {
-                self.client.set_token(Some(token)).await;
-
-                // 设置token后验证是否有效
-                if self.is_logged_in().await {
-                    return Ok(());
-                } else {
-                    // token已过期，清除无效token
-                    self.client.set_token(None).await;
-                }
+        if self.client.load_token_from("token.txt").await {
+            // 加载token后验证是否有效
+            if self.is_logged_in().await {
+                return Ok(());
+            } else {
+                // token已过期，清除无效token
+                self.client.set_token(None).await;
             }
         }
 
@@ -104,12 +99,7 @@ impl AuthService {
             .await;
 
         if response.success {
-            if let Some(token) = self.client.get_token().await {
-                if std::fs::write("token.txt", &token).is_err() {
-                    // 保存失败不影响登录成功
-                    eprintln!("警告: 无法保存token到文件");
-                }
-            }
+            // 已开启令牌持久化，登录成功时token会自动保存到文件
             Ok(())
         } else {
             Err(anyhow::anyhow!(
@@ -126,12 +116,16 @@ impl AuthService {
 
     /// 获取username
     pub async fn get_user_name(&self) -> Result<String> {
+        if let Some(user_name) = self.client.user.current_username() {
+            return Ok(user_name);
+        }
         self.get_user_info_cached().await.map(|info| info.user_name)
     }
 
     /// 登出
     pub async fn logout(&self) -> Result<()> {
-        self.client.set_token(None).await;
+        self.client.user.logout().await;
+        *self.user_info.lock().await = None;
 
         let _ = std::fs::remove_file("token.txt").is_ok();
 
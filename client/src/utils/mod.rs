@@ -6,63 +6,33 @@ use chrono::{Local, TimeZone};
 use colored::*;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-const GESTURE_STATS_FILE: &str = "gesture_stats.json";
-
-#[derive(Serialize, Deserialize)]
-struct GestureStats([u64; 3]);
-
-pub fn save_gesture_stats(stats: &[u64; 3]) {
-    let data = GestureStats(*stats);
-    if let Ok(json) = serde_json::to_string(&data) {
-        let _ = fs::write(GESTURE_STATS_FILE, json);
-    }
-}
-
-pub fn load_gesture_stats() -> [u64; 3] {
-    if Path::new(GESTURE_STATS_FILE).exists() {
-        if let Ok(json) = fs::read_to_string(GESTURE_STATS_FILE) {
-            if let Ok(data) = serde_json::from_str::<GestureStats>(&json) {
-                return data.0;
-            }
-        }
-    }
-    [0; 3]
-}
-
-pub static GESTURE_STATS: Lazy<Arc<Mutex<[u64; 3]>>> =
-    Lazy::new(|| Arc::new(Mutex::new(load_gesture_stats())));
+/// 猜拳统计文件
+pub const GESTURE_STATS_FILE: &str = "gesture_stats.json";
 
 //随机猜拳
 pub fn random_gesture() -> u8 {
     let rand_u32: u32 = rand::random();
     let rand_f64 = rand_u32 as f64 / 4294967296.0;
-    let gesture = (rand_f64 * 3.0).floor() as u8;
-    {
-        let mut stats = GESTURE_STATS.lock().unwrap();
-        if (gesture as usize) < 3 {
-            stats[gesture as usize] += 1;
-            save_gesture_stats(&stats);
-        }
-    }
-    gesture
+    (rand_f64 * 3.0).floor() as u8
 }
 
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static HTML_TAG_OR_EMPTY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLOCKQUOTE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<blockquote[^>]*>.*?</blockquote>").unwrap());
+
 pub fn strip_html_tags(html: &str) -> String {
-    let re = Regex::new(r"<[^>]+>").unwrap();
-    re.replace_all(html, "").to_string()
+    HTML_TAG_RE.replace_all(html, "").to_string()
 }
 
 pub fn strip_html_tags_chatroom(html: &str) -> String {
-    let blockquote_re = Regex::new(r"<blockquote[^>]*>.*?</blockquote>").unwrap();
-    let without_blockquote = blockquote_re.replace_all(html, "");
-
-    let re = Regex::new(r"<[^>]*>").unwrap();
-    re.replace_all(&without_blockquote, "").trim().to_string()
+    let without_blockquote = BLOCKQUOTE_RE.replace_all(html, "");
+    HTML_TAG_OR_EMPTY_RE
+        .replace_all(&without_blockquote, "")
+        .trim()
+        .to_string()
 }
 
 // 检查是否是引用消息
@@ -202,3 +172,114 @@ pub fn format_timestamp_millis(ts: i64) -> String {
         _ => "无效时间".to_string(),
     }
 }
+
+static MARKDOWN_ANSI: AtomicBool = AtomicBool::new(false);
+
+static MD_CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`\n]+)`").unwrap());
+/// 匹配 Markdown 链接或裸链接，其中的地址不参与粗体、斜体解析
+static MD_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\[([^\]\n]+)\]\(([^)\s]+)(?:\s+"[^"]*")?\)|https?://\S+"#).unwrap());
+static MD_BOLD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\*\*([^*\n]+)\*\*|__([^_\n]+)__").unwrap());
+static MD_ITALIC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\*([^*\s](?:[^*\n]*[^*\s])?)\*").unwrap());
+
+/// 是否启用 Markdown 终端渲染
+pub fn markdown_ansi_enabled() -> bool {
+    MARKDOWN_ANSI.load(Ordering::Relaxed)
+}
+
+/// 开关 Markdown 终端渲染
+pub fn set_markdown_ansi(enabled: bool) {
+    MARKDOWN_ANSI.store(enabled, Ordering::Relaxed);
+}
+
+/// 将常见 Markdown 语法（粗体、斜体、行内代码、链接）转换为 ANSI 样式
+///
+/// 行内代码中的内容原样保留，不再解析其他语法；无法识别的标记保持原样
+pub fn render_markdown_ansi(md: &str) -> String {
+    let mut result = String::with_capacity(md.len());
+    let mut last = 0;
+    for caps in MD_CODE_RE.captures_iter(md) {
+        let whole = caps.get(0).unwrap();
+        result.push_str(&render_markdown_inline(&md[last..whole.start()]));
+        result.push_str(&caps[1].yellow().on_black().to_string());
+        last = whole.end();
+    }
+    result.push_str(&render_markdown_inline(&md[last..]));
+    result
+}
+
+fn render_markdown_inline(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in MD_LINK_RE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        result.push_str(&render_markdown_emphasis(&text[last..whole.start()]));
+        match (caps.get(1), caps.get(2)) {
+            (Some(label), Some(url)) => result.push_str(&format!(
+                "{} {}",
+                label.as_str().blue().underline(),
+                format!("({})", url.as_str()).bright_black()
+            )),
+            _ => result.push_str(whole.as_str()),
+        }
+        last = whole.end();
+    }
+    result.push_str(&render_markdown_emphasis(&text[last..]));
+    result
+}
+
+fn render_markdown_emphasis(text: &str) -> String {
+    let text = MD_BOLD_RE.replace_all(text, |caps: &regex::Captures| {
+        let inner = caps.get(1).or_else(|| caps.get(2)).unwrap().as_str();
+        inner.bold().to_string()
+    });
+    MD_ITALIC_RE
+        .replace_all(&text, |caps: &regex::Captures| caps[1].italic().to_string())
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_html_tags_chatroom_removes_blockquotes_and_trims() {
+        let html = "  <p>回复</p><blockquote><p>被引用的内容</p></blockquote><p>正文</p>\n";
+        assert_eq!(strip_html_tags_chatroom(html), "回复正文");
+    }
+
+    #[test]
+    fn render_markdown_ansi_styles_bold_and_code() {
+        assert_eq!(render_markdown_ansi("__x__"), "x".bold().to_string());
+        assert_eq!(render_markdown_ansi("**x**"), "x".bold().to_string());
+        assert_eq!(
+            render_markdown_ansi("`a__b__`"),
+            "a__b__".yellow().on_black().to_string()
+        );
+    }
+
+    #[test]
+    fn render_markdown_ansi_keeps_urls_intact() {
+        assert_eq!(
+            render_markdown_ansi("[t](https://a.com/__x__/y)"),
+            format!(
+                "{} {}",
+                "t".blue().underline(),
+                "(https://a.com/__x__/y)".bright_black()
+            )
+        );
+        assert_eq!(
+            render_markdown_ansi("__b__ https://a.com/__x__/*y*"),
+            format!("{} https://a.com/__x__/*y*", "b".bold())
+        );
+    }
+
+    #[test]
+    fn is_quote_message_detects_quotes() {
+        assert!(is_quote_message("好的\n\n##### 引用 @alice"));
+        assert!(is_quote_message("  > 引用一行"));
+        assert!(!is_quote_message("a > b"));
+    }
+}
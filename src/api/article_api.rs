@@ -1,12 +1,16 @@
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use url::Url;
 
+use crate::api::CommentApi;
 use crate::api::client::ApiClient;
+use crate::error::FishPiError;
 use crate::models::article::{
-    ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticlePost, CommentPost,
-    ResponseResult,
+    ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticlePost, CommentAuthor,
+    CommentPost, ResponseResult, VoteStatus,
 };
+use crate::models::user::ApiEnvelope;
 
 /// 帖子API接口
 #[derive(Clone, Debug)]
@@ -20,6 +24,11 @@ impl ArticleApi {
         Self { client }
     }
 
+    /// 底层 HTTP 客户端
+    pub(crate) fn client(&self) -> &ApiClient {
+        &self.client
+    }
+
     /// 发布帖子
     ///
     /// - `data` 帖子信息
@@ -28,10 +37,10 @@ impl ArticleApi {
     pub async fn post_article(&self, data: &ArticlePost) -> Result<String> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result = self
@@ -39,10 +48,7 @@ impl ArticleApi {
             .post::<Value>("article", None, json_data)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["articleId"].as_str().unwrap_or("").to_string())
     }
@@ -56,23 +62,43 @@ impl ArticleApi {
     pub async fn update_article(&self, id: &str, data: &ArticlePost) -> Result<String> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let path = format!("article/{}", id);
         let result = self.client.post::<Value>(&path, None, json_data).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["articleId"].as_str().unwrap_or("").to_string())
     }
 
+    /// 删除帖子
+    ///
+    /// - `id` 帖子 Id
+    ///
+    /// 返回删除的帖子 Id；删除他人帖子等被服务端拒绝时返回 [`FishPiError::ApiError`]，携带服务端状态码
+    pub async fn remove_article(&self, id: &str) -> Result<String> {
+        let url = format!("article/{}/remove", id);
+
+        let mut json_data = json!({});
+
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
+        }
+
+        let result = self.client.post::<Value>(&url, None, json_data).await?;
+
+        ApiEnvelope::peek(&result)?.check("删除帖子失败")?;
+
+        Ok(id.to_string())
+    }
+
     /// 查询帖子列表
     ///
     /// - `params` 帖子列表查询参数
@@ -116,20 +142,57 @@ impl ArticleApi {
 
         let result = self.client.get::<Value>(&url, Some(query_params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         match ArticleList::from_json(&result["data"]) {
             Ok(article_list) => Ok(article_list),
             Err(e) => {
-                println!("解析文章列表失败: {}", e);
+                log::warn!("解析文章列表失败: {}", e);
                 Err(anyhow!("解析文章列表失败: {}", e))
             }
         }
     }
 
+    /// 搜索帖子
+    ///
+    /// - `keyword` 关键词，支持中文与空格
+    /// - `page` 页码
+    /// - `size` 每页数量
+    ///
+    /// 返回帖子列表，关键词为空时直接返回空列表，不发起请求
+    pub async fn search_articles(
+        &self,
+        keyword: &str,
+        page: i32,
+        size: i32,
+    ) -> Result<ArticleList> {
+        let keyword = keyword.trim();
+        if keyword.is_empty() {
+            return Ok(ArticleList::default());
+        }
+
+        let mut query_params = HashMap::new();
+        query_params.insert(
+            "key".to_string(),
+            url::form_urlencoded::byte_serialize(keyword.as_bytes()).collect::<String>(),
+        );
+        query_params.insert("p".to_string(), page.to_string());
+        query_params.insert("size".to_string(), size.to_string());
+
+        if let Some(token) = self.client.get_token().await {
+            query_params.insert("apiKey".to_string(), token);
+        }
+
+        let result = self
+            .client
+            .get::<Value>("api/search/articles", Some(query_params))
+            .await?;
+
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
+
+        ArticleList::from_json(&result["data"]).map_err(|e| anyhow!("解析搜索结果失败: {}", e))
+    }
+
     /// 查询帖子列表 (兼容旧方法)
     ///
     /// - `type_` 查询类型，来自 ArticleListType
@@ -250,10 +313,7 @@ impl ArticleApi {
 
         let result = self.client.get::<Value>(&url, Some(params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         match serde_json::from_value::<ArticleList>(result["data"].clone()) {
             Ok(article_list) => Ok(article_list),
@@ -276,15 +336,12 @@ impl ArticleApi {
         }
 
         let result = self.client.get::<Value>(&url, Some(params)).await?;
-        if result["code"] != 0 {
-            let msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         match ArticleDetail::from_json(&result["data"]["article"]) {
             Ok(article) => Ok(article),
             Err(e) => {
-                println!("解析文章详情失败: {}", e);
+                log::warn!("解析文章详情失败: {}", e);
                 Err(anyhow!("解析文章详情失败: {}", e))
             }
         }
@@ -297,6 +354,16 @@ impl ArticleApi {
     ///
     /// 返回点赞结果，true 为点赞，false 为点踩
     pub async fn vote_article(&self, id: &str, like: bool) -> Result<bool> {
+        Ok(self.vote_article_status(id, like).await? == VoteStatus::Up)
+    }
+
+    /// 点赞/点踩帖子，返回操作后的投票状态
+    ///
+    /// - `id` 帖子id
+    /// - `like` true 为点赞，false 为点踩
+    ///
+    /// 重复投同一票会取消投票，此时返回 [`VoteStatus::Normal`]
+    pub async fn vote_article_status(&self, id: &str, like: bool) -> Result<VoteStatus> {
         let vote_type = if like { "up" } else { "down" };
         let url = format!("vote/{}/article", vote_type);
 
@@ -304,20 +371,22 @@ impl ArticleApi {
             "dataId": id
         });
 
-        if let Some(token) = self.client.get_token().await {
-            if let Value::Object(ref mut map) = json_data {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Some(token) = self.client.get_token().await
+            && let Value::Object(ref mut map) = json_data
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result = self.client.post::<Value>(&url, None, json_data).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
-        Ok(result["type"] == 0)
+        // 服务端 type 为操作后的投票类型：0 点赞，1 点踩，-1 已取消
+        Ok(match result["type"].as_i64() {
+            Some(0) => VoteStatus::Up,
+            Some(1) => VoteStatus::Down,
+            _ => VoteStatus::Normal,
+        })
     }
 
     /// 感谢帖子
@@ -333,10 +402,10 @@ impl ArticleApi {
 
         let mut json_data = json!({});
 
-        if let Some(token) = self.client.get_token().await {
-            if let Value::Object(ref mut map) = json_data {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Some(token) = self.client.get_token().await
+            && let Value::Object(ref mut map) = json_data
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: ResponseResult = self.client.post(&url, Some(params), json_data).await?;
@@ -354,10 +423,10 @@ impl ArticleApi {
             "followingId": id
         });
 
-        if let Some(token) = self.client.get_token().await {
-            if let Value::Object(ref mut map) = json_data {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Some(token) = self.client.get_token().await
+            && let Value::Object(ref mut map) = json_data
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: ResponseResult = self.client.post("follow/article", None, json_data).await?;
@@ -375,10 +444,10 @@ impl ArticleApi {
             "followingId": id
         });
 
-        if let Some(token) = self.client.get_token().await {
-            if let Value::Object(ref mut map) = json_data {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Some(token) = self.client.get_token().await
+            && let Value::Object(ref mut map) = json_data
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: ResponseResult = self
@@ -402,10 +471,10 @@ impl ArticleApi {
 
         let mut json_data = json!({});
 
-        if let Some(token) = self.client.get_token().await {
-            if let Value::Object(ref mut map) = json_data {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Some(token) = self.client.get_token().await
+            && let Value::Object(ref mut map) = json_data
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: ResponseResult = self.client.post(&url, Some(params), json_data).await?;
@@ -435,40 +504,34 @@ impl ArticleApi {
         }
     }
 
-    // /// 添加帖子监听器（WebSocket）
-    // ///
-    // /// - `id` 帖子id
-    // /// - `article_type` 帖子类型
-    // /// - `on_message` 消息回调函数
-    // /// - `on_error` 错误回调函数
-    // /// - `on_close` 关闭回调函数
-    // ///
-    // /// 返回连接结果
-    // pub async fn add_article_listener(
-    //     &self,
-    //     id: &str,
-    //     article_type: i32,
-    //     on_message: impl Fn(Value) + Send + 'static,
-    //     on_error: Option<impl Fn(String) + Send + 'static>,
-    //     on_close: Option<impl Fn() + Send + 'static>,
-    // ) -> Result<()> {
-    //     let mut params = HashMap::new();
-    //     if let Some(token) = self.client.get_token().await {
-    //         params.insert("apiKey".to_string(), token);
-    //     }
-    //     params.insert("articleId".to_string(), id.to_string());
-    //     params.insert("articleType".to_string(), article_type.to_string());
-
-    //     self.client
-    //         .connect_websocket(
-    //             "/article-channel",
-    //             Some(params),
-    //             on_message,
-    //             on_error,
-    //             on_close,
-    //         )
-    //         .await
-    // }
+    /// 获取帖子频道 WebSocket 地址
+    ///
+    /// - `id` 帖子id
+    /// - `article_type` 帖子类型
+    pub async fn article_channel_url(&self, id: &str, article_type: i32) -> Result<Url> {
+        let base_url = self.client.base_url();
+        let host = base_url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/');
+        let protocol = if base_url.starts_with("https") {
+            "wss"
+        } else {
+            "ws"
+        };
+
+        let token = self.client.get_token().await;
+        let mut url = Url::parse(&format!("{}://{}/article-channel", protocol, host))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("articleId", id);
+            query.append_pair("articleType", &article_type.to_string());
+            if let Some(token) = token {
+                query.append_pair("apiKey", &token);
+            }
+        }
+        Ok(url)
+    }
 
     /// 发布评论
     ///
@@ -478,10 +541,10 @@ impl ArticleApi {
     pub async fn post_comment(&self, comment: &CommentPost) -> Result<String> {
         let mut json_data = serde_json::to_value(comment)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result = self
@@ -489,10 +552,7 @@ impl ArticleApi {
             .post::<Value>("comment", None, json_data)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["cmtId"].as_str().unwrap_or("").to_string())
     }
@@ -515,10 +575,7 @@ impl ArticleApi {
 
         let result = self.client.get::<Value>(&url, Some(params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["data"].clone())
     }
@@ -532,19 +589,16 @@ impl ArticleApi {
     pub async fn update_comment(&self, comment_id: &str, comment: &CommentPost) -> Result<String> {
         let mut json_data = serde_json::to_value(comment)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let url = format!("comment/{}", comment_id);
         let result = self.client.put::<Value>(&url, None, json_data).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["html"].as_str().unwrap_or("").to_string())
     }
@@ -563,18 +617,15 @@ impl ArticleApi {
             "dataId": comment_id
         });
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result = self.client.post::<Value>(&url, None, json_data).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["type"] == -1)
     }
@@ -589,10 +640,10 @@ impl ArticleApi {
             "commentId": comment_id
         });
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: ResponseResult = self.client.post("comment/thank", None, json_data).await?;
@@ -600,6 +651,58 @@ impl ArticleApi {
         Ok(result)
     }
 
+    /// 打赏评论
+    ///
+    /// - `comment_id` 评论ID
+    ///
+    /// 返回执行结果，服务端拒绝时返回错误，同 [`CommentApi::reward`]
+    pub async fn reward_comment(&self, comment_id: &str) -> Result<ResponseResult> {
+        CommentApi::new(self.client.clone())
+            .reward(comment_id)
+            .await
+    }
+
+    /// 获取感谢过评论的用户
+    ///
+    /// - `comment_id` 评论ID
+    ///
+    /// 返回感谢者列表，同 [`CommentApi::thankers`]
+    pub async fn comment_thankers(&self, comment_id: &str) -> Result<Vec<CommentAuthor>> {
+        CommentApi::new(self.client.clone())
+            .thankers(comment_id)
+            .await
+    }
+
+    /// 采纳问答帖的回答
+    ///
+    /// - `comment_id` 评论ID
+    ///
+    /// 返回执行结果；仅帖子作者可采纳，否则返回服务端的拒绝信息
+    pub async fn accept_answer(&self, comment_id: &str) -> Result<ResponseResult> {
+        let mut json_data = json!({
+            "commentId": comment_id
+        });
+
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
+        }
+
+        let result: ResponseResult = self.client.post("comment/accept", None, json_data).await?;
+
+        if result.code != 0 {
+            let msg = if result.msg.is_empty() {
+                "采纳回答失败"
+            } else {
+                result.msg.as_str()
+            };
+            return Err(FishPiError::from_code(result.code, msg).into());
+        }
+
+        Ok(result)
+    }
+
     /// 删除评论
     ///
     /// - `comment_id` 评论ID
@@ -610,18 +713,15 @@ impl ArticleApi {
 
         let mut json_data = json!({});
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result = self.client.post::<Value>(&url, None, json_data).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(comment_id.to_string())
     }
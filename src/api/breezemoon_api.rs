@@ -4,6 +4,7 @@ use std::collections::HashMap;
 
 use crate::api::client::ApiClient;
 use crate::models::breezemoon::{Breezemoon, BreezemoonList, BreezemoonPost, BreezemoonResponse};
+use crate::models::user::ApiEnvelope;
 
 /// 清风明月API接口
 #[derive(Clone, Debug)]
@@ -35,10 +36,7 @@ impl BreezemoonApi {
         }
         let result = self.client.get::<Value>(url, Some(params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         // 检查API响应结构
         if result["breezemoons"].is_array() {
@@ -89,10 +87,7 @@ impl BreezemoonApi {
 
         let result = self.client.get::<Value>(&url, Some(params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         let breezemoon_list: BreezemoonList = serde_json::from_value(result["data"].clone())
             .map_err(|e| anyhow!("解析用户清风明月列表数据失败: {}", e))?;
@@ -108,19 +103,14 @@ impl BreezemoonApi {
     pub async fn post_breezemoon(&self, data: &BreezemoonPost) -> Result<String> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: BreezemoonResponse = self.client.post("breezemoon", None, json_data).await?;
-
-        if result.code != 0 {
-            return Err(anyhow!("发布清风明月失败"));
-        }
-
-        Ok(result.data.id)
+        result.into_id("发布清风明月")
     }
 
     /// 更新清风明月
@@ -132,20 +122,15 @@ impl BreezemoonApi {
     pub async fn update_breezemoon(&self, id: &str, data: &BreezemoonPost) -> Result<String> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let path = format!("breezemoon/{}", id);
         let result: BreezemoonResponse = self.client.post(&path, None, json_data).await?;
-
-        if result.code != 0 {
-            return Err(anyhow!("更新清风明月失败"));
-        }
-
-        Ok(result.data.id)
+        result.into_id("更新清风明月")
     }
 
     /// 删除清风明月
@@ -166,10 +151,7 @@ impl BreezemoonApi {
             .delete::<Value>(&path, Some(params), None)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(())
     }
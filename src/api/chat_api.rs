@@ -1,4 +1,5 @@
 use crate::api::client::ApiClient;
+use crate::error::not_authenticated;
 use anyhow::Result;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -14,12 +15,17 @@ impl ChatApi {
         Self { client }
     }
 
+    /// 底层 HTTP 客户端
+    pub(crate) fn client(&self) -> &ApiClient {
+        &self.client
+    }
+
     /// 获取有私聊用户列表第一条消息
     pub async fn get_list(&self) -> Result<Value> {
         let url = "chat/get-list";
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法获取私聊列表"));
+            return Err(not_authenticated("未登录，无法获取私聊列表"));
         }
 
         let mut params = HashMap::new();
@@ -40,7 +46,7 @@ impl ChatApi {
         let url = "chat/get-message";
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法获取私聊消息"));
+            return Err(not_authenticated("未登录，无法获取私聊消息"));
         }
 
         let mut params = HashMap::new();
@@ -62,7 +68,7 @@ impl ChatApi {
         let url = "chat/mark-as-read";
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法标记消息已读"));
+            return Err(not_authenticated("未登录，无法标记消息已读"));
         }
 
         let mut params = HashMap::new();
@@ -80,7 +86,7 @@ impl ChatApi {
         let url = "chat/has-unread";
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法获取未读消息"));
+            return Err(not_authenticated("未登录，无法获取未读消息"));
         }
 
         let mut params = HashMap::new();
@@ -99,7 +105,7 @@ impl ChatApi {
         let url = "chat/revoke";
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法撤回消息"));
+            return Err(not_authenticated("未登录，无法撤回消息"));
         }
 
         let mut params = HashMap::new();
@@ -118,7 +124,7 @@ impl ChatApi {
     pub async fn get_websocket_url(&self, user: Option<&str>) -> Result<String> {
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，无法获取WebSocket URL"));
+            return Err(not_authenticated("未登录，无法获取WebSocket URL"));
         }
 
         if let Some(token_value) = token {
@@ -132,7 +138,7 @@ impl ChatApi {
                 Ok(ws_url)
             }
         } else {
-            Err(anyhow::anyhow!("未登录，无法获取WebSocket URL"))
+            Err(not_authenticated("未登录，无法获取WebSocket URL"))
         }
     }
 }
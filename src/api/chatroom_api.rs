@@ -1,14 +1,21 @@
 use crate::api::client::ApiClient;
+use crate::error::{FishPiError, not_authenticated};
 use crate::models::chatroom::{
-    AutoCompleteUsername, BarrageCost, ChatRoomMessage, ChatRoomNode, ChatRoomNodeInfo,
-    ChatRoomQueryMode, ChatSource, MuteItem,
+    AutoCompleteUsername, BarrageCost, ChatContentType, ChatRoomMessage, ChatRoomNode,
+    ChatRoomNodeInfo, ChatRoomQueryMode, ChatSource, MuteItem,
 };
-use crate::models::user::ApiResponse;
+use crate::models::user::{ApiEnvelope, ApiResponse};
 use anyhow::{Result, anyhow};
+use lru::LruCache;
 use regex::Regex;
 use serde::Deserialize;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
 /// 聊天室节点信息
 #[derive(Debug, Deserialize)]
@@ -30,16 +37,37 @@ pub struct NodeResponse {
     pub avaliable: Option<Vec<NodeInfo>>,
 }
 
+/// 用户名补全缓存容量
+const USERNAME_CACHE_CAPACITY: usize = 128;
+
+/// 用户名补全缓存有效期
+const USERNAME_CACHE_TTL: Duration = Duration::from_secs(60);
+
+type UsernameCache = LruCache<String, (Instant, Vec<AutoCompleteUsername>)>;
+
 /// 聊天室API接口
 #[derive(Clone, Debug)]
 pub struct ChatroomApi {
     client: ApiClient,
+    username_cache: Arc<std::sync::Mutex<UsernameCache>>,
+    username_inflight: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
 }
 
 impl ChatroomApi {
     /// 创建新的聊天室API实例
     pub fn new(client: ApiClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            username_cache: Arc::new(std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(USERNAME_CACHE_CAPACITY).unwrap(),
+            ))),
+            username_inflight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 底层 HTTP 客户端
+    pub(crate) fn client(&self) -> &ApiClient {
+        &self.client
     }
 
     /// 检查登录状态并返回token
@@ -47,7 +75,7 @@ impl ChatroomApi {
         let token = self.client.get_token().await;
         if token.is_none() {
             log::debug!("{}: 未登录", operation);
-            return Err(anyhow!("未登录"));
+            return Err(not_authenticated("未登录"));
         }
         Ok(token)
     }
@@ -66,10 +94,10 @@ impl ChatroomApi {
 
     /// 构建带token的请求体
     fn build_request_body(&self, mut body: Value, token: Option<String>) -> Value {
-        if let Some(token_value) = token {
-            if let Value::Object(ref mut map) = body {
-                map.insert("apiKey".into(), token_value.into());
-            }
+        if let Some(token_value) = token
+            && let Value::Object(ref mut map) = body
+        {
+            map.insert("apiKey".into(), token_value.into());
         }
         body
     }
@@ -85,6 +113,15 @@ impl ChatroomApi {
         page: i32,
         content_type: &str,
     ) -> Result<ApiResponse<Vec<ChatRoomMessage>>> {
+        self.get_history_as(page, content_type).await
+    }
+
+    /// 获取历史消息，按 `M` 反序列化消息，可用于延迟解析特殊消息内容
+    pub(crate) async fn get_history_as<M: DeserializeOwned>(
+        &self,
+        page: i32,
+        content_type: &str,
+    ) -> Result<ApiResponse<Vec<M>>> {
         log::debug!("获取聊天室历史消息: 页码={}, 类型={}", page, content_type);
 
         let token = self.check_token("获取聊天室历史消息").await?;
@@ -95,7 +132,7 @@ impl ChatroomApi {
         let params = self.build_params(params, token);
 
         self.client
-            .get::<ApiResponse<Vec<ChatRoomMessage>>>("/chat-room/more", Some(params))
+            .get::<ApiResponse<Vec<M>>>("/chat-room/more", Some(params))
             .await
     }
 
@@ -114,6 +151,17 @@ impl ChatroomApi {
         size: i32,
         content_type: &str,
     ) -> Result<ApiResponse<Vec<ChatRoomMessage>>> {
+        self.get_messages_as(oid, mode, size, content_type).await
+    }
+
+    /// 获取聊天室消息，按 `M` 反序列化消息，可用于延迟解析特殊消息内容
+    pub(crate) async fn get_messages_as<M: DeserializeOwned>(
+        &self,
+        oid: &str,
+        mode: ChatRoomQueryMode,
+        size: i32,
+        content_type: &str,
+    ) -> Result<ApiResponse<Vec<M>>> {
         log::debug!(
             "获取聊天室消息: ID={}, 模式={:?}, 数量={}, 类型={}",
             oid,
@@ -133,12 +181,39 @@ impl ChatroomApi {
 
         let response = self
             .client
-            .get::<ApiResponse<Vec<ChatRoomMessage>>>("/chat-room/getMessage", Some(params))
+            .get::<ApiResponse<Vec<M>>>("/chat-room/getMessage", Some(params))
             .await?;
 
         Ok(response)
     }
 
+    /// 获取单条聊天室消息
+    ///
+    /// - `oid` 消息ID
+    ///
+    /// 返回完整消息，包含作者、时间、勋章与特殊消息内容（反序列化时已解析）
+    pub async fn get_message(&self, oid: &str) -> Result<ChatRoomMessage> {
+        let oid = oid.trim();
+        if oid.is_empty() {
+            return Err(FishPiError::Validation("消息ID不能为空".to_string()).into());
+        }
+
+        let response = self
+            .get_messages(oid, ChatRoomQueryMode::Context, 1, "html")
+            .await?;
+        if response.code != 0 {
+            let msg = response.msg.as_deref().unwrap_or("获取聊天室消息失败");
+            return Err(FishPiError::from_code(response.code, msg).into());
+        }
+
+        response
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .find(|msg| msg.oid == oid)
+            .ok_or_else(|| anyhow!("消息不存在: {}", oid))
+    }
+
     /// 发送聊天室消息
     ///
     /// - `content` 消息内容
@@ -150,7 +225,39 @@ impl ChatroomApi {
         content: &str,
         client: Option<ChatSource>,
     ) -> Result<ApiResponse<()>> {
-        log::debug!("发送聊天室消息: {}", content);
+        let response = self.send_message_value(content, client).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// 发送聊天室消息，返回原始响应
+    ///
+    /// - `content` 消息内容
+    /// - `client` 客户端来源
+    ///
+    /// 返回原始 JSON 响应，可从中解析慢速模式等附加信息
+    pub async fn send_message_value(
+        &self,
+        content: &str,
+        client: Option<ChatSource>,
+    ) -> Result<Value> {
+        self.send_message_value_with(content, ChatContentType::MARKDOWN, client)
+            .await
+    }
+
+    /// 以指定内容类型发送聊天室消息，返回原始响应
+    ///
+    /// - `content` 消息内容
+    /// - `content_type` 内容类型，见 [`ChatContentType`]
+    /// - `client` 客户端来源
+    ///
+    /// Markdown 消息的请求体与 [`Self::send_message_value`] 一致，仅 HTML 消息附带 `contentType`
+    pub async fn send_message_value_with(
+        &self,
+        content: &str,
+        content_type: &str,
+        client: Option<ChatSource>,
+    ) -> Result<Value> {
+        log::debug!("发送聊天室消息({}): {}", content_type, content);
 
         let token = self.check_token("发送聊天室消息").await?;
         let client_str = match client {
@@ -158,15 +265,18 @@ impl ChatroomApi {
             None => ChatSource::default().to_string(),
         };
 
-        let request_body = json!({
+        let mut request_body = json!({
             "content": content,
             "client": client_str,
         });
+        if content_type != ChatContentType::MARKDOWN {
+            request_body["contentType"] = json!(content_type);
+        }
         let request_body = self.build_request_body(request_body, token);
 
         match self
             .client
-            .post::<ApiResponse<()>>("/chat-room/send", None, request_body.clone())
+            .post::<Value>("/chat-room/send", None, request_body.clone())
             .await
         {
             Ok(response) => Ok(response),
@@ -178,7 +288,7 @@ impl ChatroomApi {
                     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
                     // 重试一次
                     self.client
-                        .post::<ApiResponse<()>>("/chat-room/send", None, request_body)
+                        .post::<Value>("/chat-room/send", None, request_body)
                         .await
                 } else {
                     Err(e)
@@ -196,16 +306,21 @@ impl ChatroomApi {
         let token = self.check_token("撤回聊天室消息").await?;
         let request_body = self.build_request_body(json!({}), token);
 
-        let response = self
+        let envelope = self
             .client
-            .delete::<ApiResponse<()>>(
+            .delete::<ApiEnvelope<IgnoredAny>>(
                 &format!("/chat-room/revoke/{}", oid),
                 None,
                 Some(request_body),
             )
             .await?;
 
-        Ok(response)
+        // 撤回被拒绝时 HTTP 状态仍为 200，失败原因在 `code`/`result` 与 `msg` 中
+        Ok(ApiResponse {
+            code: envelope.status(),
+            msg: envelope.msg,
+            data: None,
+        })
     }
 
     /// 发送弹幕
@@ -265,14 +380,14 @@ impl ChatroomApi {
             .get::<serde_json::Value>("/chat-room/si-guo-list", None)
             .await?;
 
-        if let Some(data) = response.get("data") {
-            if let Some(array) = data.as_array() {
-                let mutes: Vec<MuteItem> = array
-                    .iter()
-                    .filter_map(|v| serde_json::from_value::<MuteItem>(v.clone()).ok())
-                    .collect();
-                return Ok(mutes);
-            }
+        if let Some(data) = response.get("data")
+            && let Some(array) = data.as_array()
+        {
+            let mutes: Vec<MuteItem> = array
+                .iter()
+                .filter_map(|v| serde_json::from_value::<MuteItem>(v.clone()).ok())
+                .collect();
+            return Ok(mutes);
         }
 
         Ok(Vec::new())
@@ -293,10 +408,10 @@ impl ChatroomApi {
             .await?;
 
         let re = Regex::new(r"^(.*?)\r\n\n").unwrap();
-        if let Some(caps) = re.captures(&response) {
-            if let Some(matched) = caps.get(1) {
-                return Ok(matched.as_str().to_string());
-            }
+        if let Some(caps) = re.captures(&response)
+            && let Some(matched) = caps.get(1)
+        {
+            return Ok(matched.as_str().to_string());
         }
 
         Ok(response)
@@ -326,7 +441,15 @@ impl ChatroomApi {
     /// 获取聊天室节点信息
     ///
     /// 返回节点信息
+    #[deprecated(note = "请使用 get_nodes")]
     pub async fn get_node_info(&self) -> Result<ChatRoomNodeInfo> {
+        self.get_nodes().await
+    }
+
+    /// 获取聊天室节点列表
+    ///
+    /// 返回推荐节点与全部可用节点
+    pub async fn get_nodes(&self) -> Result<ChatRoomNodeInfo> {
         let token = self.check_token("获取聊天室节点信息").await?;
         let params = self.build_params(HashMap::new(), token);
 
@@ -381,6 +504,50 @@ impl ChatroomApi {
         })
     }
 
+    /// 获取聊天室置顶公告
+    ///
+    /// 返回原始 JSON 响应
+    pub async fn get_room_notice(&self) -> Result<Value> {
+        let token = self.check_token("获取聊天室公告").await?;
+        let params = self.build_params(HashMap::new(), token);
+
+        self.client
+            .get::<Value>("/chat-room/notice", Some(params))
+            .await
+    }
+
+    /// 设置聊天室置顶公告（需管理员权限）
+    ///
+    /// - `content` 公告内容，为空则清除公告
+    ///
+    /// 返回设置结果
+    pub async fn set_room_notice(&self, content: &str) -> Result<ApiResponse<()>> {
+        log::debug!("设置聊天室公告: {}", content);
+
+        let token = self.check_token("设置聊天室公告").await?;
+        let request_body = self.build_request_body(json!({ "content": content }), token);
+
+        self.client
+            .post::<ApiResponse<()>>("/chat-room/notice", None, request_body)
+            .await
+    }
+
+    /// 获取当前登录用户名
+    ///
+    /// 返回用户名
+    pub async fn get_current_user_name(&self) -> Result<String> {
+        let token = self.check_token("获取当前用户").await?;
+        let params = self.build_params(HashMap::new(), token);
+        let response = self.client.get::<Value>("/api/user", Some(params)).await?;
+
+        response
+            .get("data")
+            .and_then(|data| data.get("userName"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("获取当前用户失败"))
+    }
+
     /// 用户名补全接口
     ///
     /// 返回用户名 Vec<AutoCompleteUsername>
@@ -398,4 +565,57 @@ impl ChatroomApi {
         }
         Ok(response.data.unwrap_or_default())
     }
+
+    /// 带缓存的用户名补全
+    ///
+    /// 结果按前缀缓存一段时间，同一前缀同时只会有一个请求在途，
+    /// 其余调用等待该请求完成后直接读取缓存
+    pub async fn autocomplete_username_cached(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<AutoCompleteUsername>> {
+        let key = prefix.to_lowercase();
+        if let Some(users) = self.cached_usernames(&key) {
+            return Ok(users);
+        }
+
+        let slot = {
+            let mut inflight = self.username_inflight.lock().await;
+            inflight.entry(key.clone()).or_default().clone()
+        };
+        let result = {
+            let _guard = slot.lock().await;
+            match self.cached_usernames(&key) {
+                Some(users) => Ok(users),
+                None => self.autocomplete_username(prefix).await.inspect(|users| {
+                    if let Ok(mut cache) = self.username_cache.lock() {
+                        cache.put(key.clone(), (Instant::now(), users.clone()));
+                    }
+                }),
+            }
+        };
+
+        let mut inflight = self.username_inflight.lock().await;
+        if inflight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &slot) && Arc::strong_count(&slot) <= 2)
+        {
+            inflight.remove(&key);
+        }
+
+        result
+    }
+
+    /// 读取未过期的用户名补全缓存
+    fn cached_usernames(&self, key: &str) -> Option<Vec<AutoCompleteUsername>> {
+        let mut cache = self.username_cache.lock().ok()?;
+        match cache.get(key) {
+            Some((at, users)) if at.elapsed() < USERNAME_CACHE_TTL => Some(users.clone()),
+            Some(_) => {
+                cache.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
 }
@@ -1,34 +1,353 @@
+use crate::api::metrics::Metrics;
+use crate::error::FishPiError;
+use crate::models::upload::{MAX_UPLOAD_SIZE, UploadResponse, guess_mime, sanitize_file_name};
+use crate::models::user::{ApiEnvelope, Response};
 use anyhow::Result;
-use futures::StreamExt;
+use futures::{SinkExt, StreamExt};
 use log;
 use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue, USER_AGENT};
 use reqwest::{Client, ClientBuilder, Response as ReqwestResponse};
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Message};
 use url::Url;
 
 // 常量定义
 const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; WOW64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/69.0.3497.100 Safari/537.36";
 const DEFAULT_TIMEOUT: u64 = 30;
+const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+const DEFAULT_RETRY_BACKOFF: u64 = 500; // 毫秒
 const DEFAULT_BASE_URL: &str = "https://fishpi.cn";
-const WEBSOCKET_CLEANUP_DELAY: u64 = 100; // 毫秒
+/// 主动断开时等待关闭握手完成的最长时间
+pub(crate) const WEBSOCKET_CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_RECONNECT_MAX_ATTEMPTS: i32 = 10;
+const DEFAULT_RECONNECT_BASE_DELAY: u64 = 1000; // 毫秒
+const DEFAULT_RECONNECT_MAX_DELAY: u64 = 30000; // 毫秒
+const DEFAULT_RECONNECT_JITTER: u64 = 500; // 毫秒
 
-// 定义一个全局静态变量来存储WebSocket任务句柄
+// 定义一个全局静态变量来存储WebSocket任务句柄，按连接编号分组
 lazy_static::lazy_static! {
-    static ref WEBSOCKET_TASKS: Arc<Mutex<Vec<JoinHandle<()>>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref WEBSOCKET_TASKS: Arc<Mutex<HashMap<u64, Vec<JoinHandle<()>>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// 下一个WebSocket连接的编号
+static NEXT_WEBSOCKET_ID: AtomicU64 = AtomicU64::new(0);
+
+/// WebSocket 消息发送通道
+pub type WebSocketSender = futures::channel::mpsc::UnboundedSender<Message>;
+
+/// WebSocket 连接句柄
+///
+/// 通过 [`WebSocketConnection::sender`] 向服务器推送帧，
+/// [`WebSocketConnection::shutdown`] 仅关闭本连接，不影响其他服务的连接
+#[derive(Debug, Clone)]
+pub struct WebSocketConnection {
+    id: u64,
+    sender: WebSocketSender,
+}
+
+impl WebSocketConnection {
+    /// 消息发送通道
+    pub fn sender(&self) -> &WebSocketSender {
+        &self.sender
+    }
+
+    /// 发送关闭帧并等待本连接的任务结束，超时后强制终止
+    pub async fn shutdown(&self, timeout: Duration) {
+        let _ = self.sender.unbounded_send(Message::Close(None));
+        let Some(mut tasks) = WEBSOCKET_TASKS.lock().await.remove(&self.id) else {
+            return;
+        };
+
+        let all_done = futures::future::join_all(tasks.iter_mut());
+        if tokio::time::timeout(timeout, all_done).await.is_err() {
+            log::debug!("等待WebSocket关闭超时，强制终止连接任务");
+            for task in tasks.iter() {
+                task.abort();
+            }
+        }
+    }
+}
+
+/// 已建立的 WebSocket 连接
+pub(crate) type WebSocketStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>;
+
+/// 令牌失效时的重新登录回调，返回新令牌，无法登录时返回 `None`
+pub type ReauthHook = Arc<
+    dyn Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// 限流等待时长上限，防止计算溢出
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(3600);
+
+/// 服务端表示未登录或令牌失效的错误信息关键字
+const AUTH_FAILURE_KEYWORDS: [&str; 3] = ["请先登录", "未登录", "登录身份验证失败"];
+
+/// 重新登录回调槽位
+#[derive(Clone, Default)]
+struct ReauthSlot {
+    hook: Arc<Mutex<Option<ReauthHook>>>,
+    /// 正在重新登录，避免回调内的请求再次触发重新登录
+    running: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl std::fmt::Debug for ReauthSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReauthSlot")
+            .field("hook", &"<function callback>")
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+/// 封禁/踢出类关闭原因中的中文关键字，按子串匹配
+const BANNED_CLOSE_KEYWORDS: [&str; 3] = ["封禁", "踢出", "被踢"];
+
+/// 封禁/踢出类关闭原因中的英文单词，按整词匹配，避免 `bandwidth` 之类误判
+const BANNED_CLOSE_WORDS: [&str; 9] = [
+    "ban", "banned", "bans", "kick", "kicked", "kicks", "block", "blocked", "blocks",
+];
+
+/// WebSocket 关闭码 1008（违反策略）
+const POLICY_VIOLATION_CLOSE_CODE: u16 = 1008;
+
+/// WebSocket 连接状态
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// 已连接
+    Connected,
+    /// 未连接或因网络等原因断开，可重连
+    #[default]
+    Disconnected,
+    /// 被服务端封禁或踢出，不应再自动重连
+    Banned { reason: String },
+}
+
+impl ConnectionState {
+    /// 根据 WebSocket 关闭帧判断断开原因
+    ///
+    /// 关闭码为 1008（违反策略）、关闭原因包含封禁/踢出中文关键字或英文整词时视为被封禁，
+    /// 其余情况视为普通断开
+    pub fn from_close_frame(frame: Option<&CloseFrame<'_>>) -> Self {
+        let Some(frame) = frame else {
+            return ConnectionState::Disconnected;
+        };
+
+        let reason = frame.reason.trim();
+        let lower = reason.to_lowercase();
+        let is_policy = u16::from(frame.code) == POLICY_VIOLATION_CLOSE_CODE;
+        let has_keyword = BANNED_CLOSE_KEYWORDS.iter().any(|k| lower.contains(k));
+        let has_word = lower
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| BANNED_CLOSE_WORDS.contains(&word));
+        if is_policy || has_keyword || has_word {
+            return ConnectionState::Banned {
+                reason: reason.to_string(),
+            };
+        }
+        ConnectionState::Disconnected
+    }
+
+    /// 是否被封禁
+    pub fn is_banned(&self) -> bool {
+        matches!(self, ConnectionState::Banned { .. })
+    }
+}
+
+/// WebSocket 重连配置，聊天室与私聊共用
+#[derive(Clone, Debug)]
+pub struct ReconnectConfig {
+    /// 最大重连次数
+    pub max_attempts: i32,
+    /// 首次重连等待时间
+    pub base_delay: Duration,
+    /// 重连等待时间上限
+    pub max_delay: Duration,
+    /// 随机抖动上限，避免多个连接同时重连
+    pub jitter: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RECONNECT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_RECONNECT_BASE_DELAY),
+            max_delay: Duration::from_millis(DEFAULT_RECONNECT_MAX_DELAY),
+            jitter: Duration::from_millis(DEFAULT_RECONNECT_JITTER),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// 是否还能继续重连
+    ///
+    /// - `attempts` 已重试次数
+    pub fn can_retry(&self, attempts: i32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// 计算第 `attempts` 次重连前的等待时间（指数退避 + 随机抖动）
+    pub fn backoff_delay(&self, attempts: i32) -> Duration {
+        let exp = attempts.clamp(0, 16) as u32;
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(exp))
+            .min(self.max_delay);
+
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            delay
+        } else {
+            delay + Duration::from_millis(rand::random_range(0..=jitter_ms))
+        }
+    }
+}
+
+/// 服务器连通性检查结果
+#[derive(Clone, Debug, Default)]
+pub struct ServerStatus {
+    /// 是否可访问
+    pub reachable: bool,
+    /// 请求耗时（毫秒）
+    pub latency_ms: u64,
+    /// 服务端版本，服务端未在响应头 `X-Version` 中提供时为 `None`
+    pub version: Option<String>,
+}
+
+/// 令牌桶限流器
+///
+/// 令牌按 `rate` 个/秒匀速补充，最多累积 `burst` 个；
+/// 令牌不足时预留令牌并返回需要等待的时长，保证请求按到达顺序放行
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// 创建限流器
+    ///
+    /// - `per_sec` 每秒补充的令牌数，必须为有限正数
+    /// - `burst` 令牌桶容量，即允许的最大突发请求数
+    pub fn new(per_sec: f64, burst: u32) -> Result<Self, FishPiError> {
+        if !per_sec.is_finite() || per_sec <= 0.0 {
+            return Err(FishPiError::Validation(format!(
+                "限流速率必须为正数: {}",
+                per_sec
+            )));
+        }
+        let burst = f64::from(burst.max(1));
+        Ok(Self {
+            rate: per_sec,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        })
+    }
+
+    /// 取出一个令牌，返回获取令牌前需要等待的时长
+    pub fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(-self.tokens / self.rate).unwrap_or(MAX_RATE_LIMIT_WAIT)
+        }
+    }
+}
+
+/// HTTP 客户端配置
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// 服务器地址
+    pub base_url: String,
+    /// 建立连接的超时时间
+    pub connect_timeout: Duration,
+    /// 单次请求的总超时时间
+    pub request_timeout: Duration,
+    /// 幂等请求在连接失败或服务端 5xx 时的最大重试次数，默认不重试
+    pub max_retries: u32,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub retry_backoff_ms: u64,
+    /// 请求携带的 User-Agent
+    pub user_agent: String,
+    /// 代理地址，如 `http://127.0.0.1:7890`，为 None 时读取 `HTTPS_PROXY` 等环境变量
+    pub proxy: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+            request_timeout: Duration::from_secs(DEFAULT_TIMEOUT),
+            max_retries: 0,
+            retry_backoff_ms: DEFAULT_RETRY_BACKOFF,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// 计算第 `attempt` 次重试前的等待时间
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.retry_backoff_ms
+                .saturating_mul(2u64.saturating_pow(attempt.min(16))),
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ApiClient {
     client: Client,
-    base_url: String,
+    /// 服务器地址，在所有克隆间共享，便于运行时切换
+    base_url: Arc<std::sync::RwLock<String>>,
     token: Arc<Mutex<Option<String>>>,
+    rate_limiter: Arc<Mutex<Option<RateLimiter>>>,
+    config: ClientConfig,
+    token_path: Arc<Mutex<Option<PathBuf>>>,
+    reauth: ReauthSlot,
+    /// 令牌版本，每次令牌变更时递增，用于判断依赖令牌的缓存是否失效
+    token_generation: Arc<AtomicU64>,
+    /// 请求与 WebSocket 计数，在所有克隆间共享
+    metrics: Arc<Metrics>,
+    /// WebSocket 连接使用的代理，与 HTTP 客户端的代理保持一致
+    websocket_proxy: WebSocketProxy,
+    /// HTTP 客户端是否由 [`ApiClient::from_reqwest`] 从外部传入
+    external_client: bool,
+}
+
+/// WebSocket 连接使用的代理
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum WebSocketProxy {
+    /// 按协议读取 `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY` 环境变量，并遵循 `NO_PROXY`
+    Environment,
+    /// 固定使用该代理
+    Fixed(String),
+    /// 直接连接
+    Direct,
 }
 
 impl Default for ApiClient {
@@ -39,44 +358,403 @@ impl Default for ApiClient {
 
 impl ApiClient {
     pub fn new() -> Self {
-        Self::with_config(DEFAULT_BASE_URL, DEFAULT_TIMEOUT)
+        Self::with_config(ClientConfig::default())
+    }
+
+    /// 使用自定义配置创建客户端
+    ///
+    /// - `config` 超时、重试与代理配置，代理地址无效时忽略并使用环境变量中的代理
+    ///
+    /// 需要在代理地址无效时得到错误，请使用 [`ApiClient::try_with_config`]
+    pub fn with_config(config: ClientConfig) -> Self {
+        match Self::try_with_config(config.clone()) {
+            Ok(client) => client,
+            Err(err) => {
+                log::warn!("代理配置无效，已忽略: {}", err);
+                Self::try_with_config(ClientConfig {
+                    proxy: None,
+                    ..config
+                })
+                .expect("Failed to build HTTP client")
+            }
+        }
+    }
+
+    /// 使用自定义配置创建客户端
+    ///
+    /// - `config` 超时、重试与代理配置
+    ///
+    /// 代理地址无效时返回 `FishPiError::Validation`
+    pub fn try_with_config(config: ClientConfig) -> Result<Self, FishPiError> {
+        let client = Self::build_http_client(&config)?;
+        let websocket_proxy = match &config.proxy {
+            Some(proxy) => WebSocketProxy::Fixed(proxy.clone()),
+            None => WebSocketProxy::Environment,
+        };
+
+        Ok(Self {
+            client,
+            base_url: Arc::new(std::sync::RwLock::new(config.base_url.clone())),
+            token: Arc::new(Mutex::new(None)),
+            rate_limiter: Arc::new(Mutex::new(None)),
+            config,
+            token_path: Arc::new(Mutex::new(None)),
+            reauth: ReauthSlot::default(),
+            token_generation: Arc::new(AtomicU64::new(0)),
+            metrics: Arc::new(Metrics::default()),
+            websocket_proxy,
+            external_client: false,
+        })
+    }
+
+    /// 使用外部构建的 reqwest 客户端创建实例
+    ///
+    /// - `client` 已配置代理、TLS 根证书或连接池的客户端，可在多个实例间共享
+    /// - `base_url` 服务器地址，WebSocket 地址同样由其推导
+    ///
+    /// 超时、代理等均以传入客户端的配置为准。WebSocket 连接不经过 reqwest，
+    /// 无法得知传入客户端的代理，默认与 reqwest 一样读取环境变量中的代理，
+    /// 传入客户端的代理配置不同时需通过 [`ApiClient::with_websocket_proxy`] 指定
+    pub fn from_reqwest(client: Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: Arc::new(std::sync::RwLock::new(base_url.to_string())),
+            token: Arc::new(Mutex::new(None)),
+            rate_limiter: Arc::new(Mutex::new(None)),
+            config: ClientConfig {
+                base_url: base_url.to_string(),
+                ..ClientConfig::default()
+            },
+            token_path: Arc::new(Mutex::new(None)),
+            reauth: ReauthSlot::default(),
+            token_generation: Arc::new(AtomicU64::new(0)),
+            metrics: Arc::new(Metrics::default()),
+            websocket_proxy: WebSocketProxy::Environment,
+            external_client: true,
+        }
     }
 
-    pub fn with_config(base_url: &str, timeout: u64) -> Self {
-        unsafe {
-            std::env::set_var("NO_PROXY", "*");
-            std::env::set_var("no_proxy", "*");
+    /// 设置初始令牌，用于构建阶段，不写入令牌文件
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = Arc::new(Mutex::new(token));
+        self.token_generation.fetch_add(1, Ordering::SeqCst);
+        self
+    }
+
+    /// 当前令牌版本
+    ///
+    /// 每次设置、加载或清除令牌时递增，可用于判断按令牌缓存的数据是否仍然有效
+    pub fn token_generation(&self) -> u64 {
+        self.token_generation.load(Ordering::SeqCst)
+    }
+
+    /// 请求与 WebSocket 计数器，由所有克隆共享
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    /// 设置代理，覆盖环境变量中的代理设置
+    ///
+    /// - `proxy` 代理地址，如 `http://127.0.0.1:7890` 或 `socks5://127.0.0.1:1080`
+    ///
+    /// HTTP 请求与 WebSocket 连接都会经过该代理，会按当前配置重新构建 HTTP 客户端。
+    /// WebSocket 仅支持不带认证的 `http://` 代理，其他代理在建立连接时返回错误。
+    ///
+    /// 代理地址无效，或客户端由 [`ApiClient::from_reqwest`] 传入时返回 `FishPiError::Validation`，
+    /// 后者应在传入前自行配置代理，并通过 [`ApiClient::with_websocket_proxy`] 指定 WebSocket 代理
+    pub fn with_proxy(mut self, proxy: &str) -> Result<Self, FishPiError> {
+        if self.external_client {
+            return Err(FishPiError::Validation(
+                "外部传入的 HTTP 客户端需自行配置代理".to_string(),
+            ));
         }
+        self.config.proxy = Some(proxy.to_string());
+        self.client = Self::build_http_client(&self.config)?;
+        self.websocket_proxy = WebSocketProxy::Fixed(proxy.to_string());
+        Ok(self)
+    }
 
+    /// 指定 WebSocket 连接使用的代理，不影响 HTTP 客户端
+    ///
+    /// - `proxy` 代理地址，为 None 时直接连接
+    ///
+    /// 用于与 [`ApiClient::from_reqwest`] 传入客户端的代理保持一致，代理地址无效时返回 `FishPiError::Validation`
+    pub fn with_websocket_proxy(mut self, proxy: Option<&str>) -> Result<Self, FishPiError> {
+        self.websocket_proxy = match proxy {
+            Some(proxy) => {
+                Url::parse(proxy).map_err(|err| {
+                    FishPiError::Validation(format!("代理地址 {} 无效: {}", proxy, err))
+                })?;
+                WebSocketProxy::Fixed(proxy.to_string())
+            }
+            None => WebSocketProxy::Direct,
+        };
+        Ok(self)
+    }
+
+    /// 按配置构建 HTTP 客户端，未指定代理时由 reqwest 读取环境变量中的代理
+    fn build_http_client(config: &ClientConfig) -> Result<Client, FishPiError> {
         let mut default_headers = HeaderMap::new();
-        default_headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_USER_AGENT));
+        default_headers.insert(USER_AGENT, Self::user_agent_header(&config.user_agent));
 
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(timeout))
+        let mut builder = ClientBuilder::new()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
             .default_headers(default_headers)
-            .no_proxy()
             .pool_idle_timeout(Duration::from_secs(30))
             .pool_max_idle_per_host(5)
             .tcp_keepalive(Duration::from_secs(15))
-            .tcp_nodelay(true)
+            .tcp_nodelay(true);
+        if let Some(proxy) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy).map_err(|err| {
+                FishPiError::Validation(format!("代理地址 {} 无效: {}", proxy, err))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+
+        builder
             .build()
-            .expect("Failed to build HTTP client");
+            .map_err(|err| FishPiError::Network(format!("创建 HTTP 客户端失败: {}", err)))
+    }
 
-        Self {
-            client,
-            base_url: base_url.to_string(),
-            token: Arc::new(Mutex::new(None)),
+    /// WebSocket 连接使用的代理
+    ///
+    /// 与 HTTP 客户端一致：指定了代理时固定使用该代理，否则按协议读取
+    /// `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`，目标地址命中 `NO_PROXY` 时不使用代理
+    fn websocket_proxy(&self, target: &Url) -> Option<String> {
+        match &self.websocket_proxy {
+            WebSocketProxy::Fixed(proxy) => return Some(proxy.clone()),
+            WebSocketProxy::Direct => return None,
+            WebSocketProxy::Environment => {}
+        }
+
+        let env = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| std::env::var(key).ok().filter(|v| !v.trim().is_empty()))
+        };
+        if let Some(no_proxy) = env(&["NO_PROXY", "no_proxy"])
+            && no_proxy_matches(&no_proxy, target)
+        {
+            return None;
+        }
+
+        if target.scheme() == "wss" {
+            env(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"])
+        } else {
+            env(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"])
+        }
+    }
+
+    /// 建立 WebSocket 连接，配置或环境变量中有代理时经由代理建立隧道
+    ///
+    /// - `url` WebSocket 地址
+    pub(crate) async fn open_websocket(&self, url: Url) -> Result<WebSocketStream> {
+        let Some(proxy) = self.websocket_proxy(&url) else {
+            let (ws_stream, _) = connect_async(url).await?;
+            return Ok(ws_stream);
+        };
+
+        let stream = tokio::time::timeout(
+            self.config.connect_timeout,
+            Self::connect_tunnel(&proxy, &url),
+        )
+        .await
+        .map_err(|_| FishPiError::Network(format!("连接代理 {} 超时", proxy)))??;
+        let (ws_stream, _) = tokio_tungstenite::client_async_tls(url, stream).await?;
+        Ok(ws_stream)
+    }
+
+    /// 通过 HTTP 代理的 CONNECT 方法建立到目标地址的 TCP 隧道
+    async fn connect_tunnel(proxy: &str, target: &Url) -> Result<TcpStream> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let proxy_url = Url::parse(proxy)
+            .map_err(|err| FishPiError::Validation(format!("代理地址 {} 无效: {}", proxy, err)))?;
+        if proxy_url.scheme() != "http" {
+            return Err(FishPiError::Validation(format!(
+                "WebSocket 仅支持 http:// 代理，当前代理为 {}",
+                proxy
+            ))
+            .into());
+        }
+        if !proxy_url.username().is_empty() || proxy_url.password().is_some() {
+            return Err(
+                FishPiError::Validation("WebSocket 暂不支持带认证信息的代理".to_string()).into(),
+            );
+        }
+
+        let proxy_host = match proxy_url.host() {
+            Some(url::Host::Ipv6(addr)) => addr.to_string(),
+            Some(host) => host.to_string(),
+            None => {
+                return Err(
+                    FishPiError::Validation(format!("代理地址 {} 缺少主机名", proxy)).into(),
+                );
+            }
+        };
+        let proxy_port = proxy_url.port_or_known_default().unwrap_or(80);
+        let authority = connect_authority(target).ok_or_else(|| {
+            FishPiError::Validation(format!("WebSocket 地址 {} 缺少主机名", target))
+        })?;
+
+        let mut stream = TcpStream::connect((proxy_host.as_str(), proxy_port))
+            .await
+            .map_err(|err| FishPiError::Network(format!("无法连接代理 {}: {}", proxy, err)))?;
+        stream
+            .write_all(format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", authority).as_bytes())
+            .await?;
+
+        // 逐字节读取响应头，避免读走隧道建立后的数据
+        let mut head = Vec::new();
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            if stream.read(&mut byte).await? == 0 || head.len() > 8192 {
+                return Err(FishPiError::Network(format!("代理 {} 响应无效", proxy)).into());
+            }
+            head.push(byte[0]);
         }
+
+        let head = String::from_utf8_lossy(&head);
+        let status_line = head.lines().next().unwrap_or_default();
+        if status_line.split_whitespace().nth(1) != Some("200") {
+            return Err(FishPiError::Network(format!(
+                "代理 {} 拒绝建立隧道: {}",
+                proxy, status_line
+            ))
+            .into());
+        }
+        Ok(stream)
     }
 
+    /// User-Agent 请求头，配置值非法时使用默认值
+    fn user_agent_header(user_agent: &str) -> HeaderValue {
+        HeaderValue::from_str(user_agent)
+            .unwrap_or_else(|_| HeaderValue::from_static(DEFAULT_USER_AGENT))
+    }
+
+    /// 使用新的服务器地址，返回的实例不再与原实例共享地址
     pub fn with_base_url(mut self, base_url: &str) -> Self {
-        self.base_url = base_url.to_string();
+        self.base_url = Arc::new(std::sync::RwLock::new(base_url.to_string()));
+        self.config.base_url = base_url.to_string();
         self
     }
 
+    /// 切换服务器地址，对共享该地址的所有克隆生效
+    ///
+    /// 此后 [`ApiClient::base_url`] 与 [`ApiClient::config`] 均返回新地址。
+    /// 已建立的 WebSocket 连接不受影响，需重新连接后才会使用新地址
+    pub fn set_base_url(&self, base_url: &str) {
+        if let Ok(mut current) = self.base_url.write() {
+            *current = base_url.to_string();
+        }
+    }
+
+    /// 当前客户端配置
+    ///
+    /// 服务器地址可在运行时通过 [`ApiClient::set_base_url`] 切换，返回的 `base_url` 为当前生效的地址
+    pub fn config(&self) -> ClientConfig {
+        ClientConfig {
+            base_url: self.base_url(),
+            ..self.config.clone()
+        }
+    }
+
     pub async fn set_token(&self, token: Option<String>) {
+        if let Some(path) = self.token_path.lock().await.as_ref()
+            && let Err(err) = Self::store_token_file(path, token.as_deref())
+        {
+            log::warn!("保存令牌到 {} 失败: {}", path.display(), err);
+        }
         let mut current_token = self.token.lock().await;
         *current_token = token;
+        self.token_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 开启令牌持久化
+    ///
+    /// - `path` 令牌文件路径
+    ///
+    /// 立即从文件加载令牌（文件存在且有效时），此后每次 `set_token` 都会同步写入该文件，
+    /// 清除令牌时删除文件。返回是否加载到了令牌
+    pub async fn enable_token_persistence(&self, path: PathBuf) -> bool {
+        let loaded = Self::load_token_file(&path);
+        *self.token_path.lock().await = Some(path);
+        match loaded {
+            Some(token) => {
+                *self.token.lock().await = Some(token);
+                self.token_generation.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 读取令牌文件，文件不存在或内容无效时返回 `None`
+    fn load_token_file(path: &Path) -> Option<String> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let token = content.trim();
+        if token.is_empty() || token.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            log::warn!("令牌文件 {} 内容无效，已忽略", path.display());
+            return None;
+        }
+        Some(token.to_string())
+    }
+
+    /// 写入令牌文件，Unix 下权限为 0600
+    fn store_token_file(path: &Path, token: Option<&str>) -> std::io::Result<()> {
+        let Some(token) = token else {
+            return match std::fs::remove_file(path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        };
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        // mode 仅对新建文件生效，已存在的文件需在写入令牌前收紧权限
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.set_len(0)?;
+        std::io::Write::write_all(&mut file, token.as_bytes())
+    }
+
+    /// 设置全局请求限流，所有共享该客户端的服务都会受限
+    ///
+    /// - `per_sec` 每秒允许的请求数，必须为有限正数
+    /// - `burst` 允许的最大突发请求数
+    ///
+    /// 速率无效时返回 [`FishPiError::Validation`]，原有限流设置保持不变
+    pub async fn set_rate_limit(&self, per_sec: f64, burst: u32) -> Result<(), FishPiError> {
+        let limiter = RateLimiter::new(per_sec, burst)?;
+        *self.rate_limiter.lock().await = Some(limiter);
+        Ok(())
+    }
+
+    /// 取消全局请求限流
+    pub async fn clear_rate_limit(&self) {
+        let mut limiter = self.rate_limiter.lock().await;
+        *limiter = None;
+    }
+
+    /// 等待限流令牌，未设置限流时立即返回
+    async fn acquire_rate_limit(&self) {
+        let wait = match self.rate_limiter.lock().await.as_mut() {
+            Some(limiter) => limiter.reserve(),
+            None => return,
+        };
+        if !wait.is_zero() {
+            log::debug!("触发请求限流，等待 {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
     }
 
     pub async fn get_token(&self) -> Option<String> {
@@ -88,13 +766,20 @@ impl ApiClient {
         &self.client
     }
 
-    pub fn base_url(&self) -> &str {
-        &self.base_url
+    /// 当前服务器地址
+    ///
+    /// 地址在所有克隆间共享，可通过 [`ApiClient::set_base_url`] 在运行时切换，
+    /// 因此返回当前地址的副本（`String`），不再像早期版本那样返回 `&str`
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .read()
+            .map(|url| url.clone())
+            .unwrap_or_else(|_| self.config.base_url.clone())
     }
 
     pub async fn build_url(&self, path: &str) -> String {
         let path = path.trim_start_matches('/');
-        format!("{}/{}", self.base_url, path)
+        format!("{}/{}", self.base_url(), path)
     }
 
     pub async fn build_url_with_token(&self, path: &str) -> String {
@@ -103,15 +788,49 @@ impl ApiClient {
 
         if let Some(token) = token {
             if path.contains('?') {
-                format!("{}/{}&apiKey={}", self.base_url, path, token)
+                format!("{}/{}&apiKey={}", self.base_url(), path, token)
             } else {
-                format!("{}/{}?apiKey={}", self.base_url, path, token)
+                format!("{}/{}?apiKey={}", self.base_url(), path, token)
             }
         } else {
-            format!("{}/{}", self.base_url, path)
+            format!("{}/{}", self.base_url(), path)
         }
     }
 
+    /// 设置令牌失效时的重新登录回调
+    ///
+    /// 请求因令牌失效失败时调用一次该回调，取得新令牌后重试原请求；
+    /// 回调返回 `None` 或重试仍失败时返回 [`FishPiError::NotAuthenticated`]
+    pub async fn set_reauth<F, Fut>(&self, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Option<String>> + Send + 'static,
+    {
+        let hook: ReauthHook = Arc::new(move || Box::pin(hook()));
+        *self.reauth.hook.lock().await = Some(hook);
+    }
+
+    /// 清除重新登录回调
+    pub async fn clear_reauth(&self) {
+        *self.reauth.hook.lock().await = None;
+    }
+
+    /// 调用重新登录回调，成功时保存并返回新令牌
+    async fn try_reauth(&self) -> Option<String> {
+        let hook = self.reauth.hook.lock().await.clone()?;
+        if self.reauth.running.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        log::debug!("令牌失效，尝试重新登录");
+        // 在独立任务中执行回调，避免回调的 future 影响请求 future 的 Sync 约束
+        let token = tokio::spawn(hook()).await.ok().flatten();
+        self.reauth.running.store(false, Ordering::SeqCst);
+
+        let token = token.filter(|t| !t.is_empty())?;
+        self.set_token(Some(token.clone())).await;
+        Some(token)
+    }
+
     // 添加通用请求方法
     async fn request<T: DeserializeOwned>(
         &self,
@@ -120,6 +839,79 @@ impl ApiClient {
         params: Option<HashMap<String, String>>,
         data: Option<Value>,
     ) -> Result<T> {
+        self.metrics.record_request();
+        let params = &params;
+        let data = &data;
+        let result = self
+            .with_reauth(move |token| {
+                let (params, data) = match token {
+                    Some(token) => Self::replace_api_key(params.clone(), data.clone(), &token),
+                    None => (params.clone(), data.clone()),
+                };
+                self.request_once(method.clone(), path, params, data)
+            })
+            .await;
+        if result.is_err() {
+            self.metrics.record_request_error();
+        }
+        result
+    }
+
+    /// 发起请求，令牌失效时调用重新登录回调并用新令牌重试一次
+    ///
+    /// - `send` 发起一次请求，首次调用参数为 `None`，重试时为新令牌
+    ///
+    /// 无法重新登录或重试仍未通过认证时清除令牌
+    async fn with_reauth<T, F, Fut>(&self, send: F) -> Result<T>
+    where
+        F: Fn(Option<String>) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        match send(None).await {
+            Err(err) if Self::is_not_authenticated(&err) => match self.try_reauth().await {
+                Some(token) => {
+                    let retried = send(Some(token)).await;
+                    if retried.as_ref().is_err_and(Self::is_not_authenticated) {
+                        self.invalidate_token().await;
+                    }
+                    retried
+                }
+                None => {
+                    self.invalidate_token().await;
+                    Err(err)
+                }
+            },
+            result => result,
+        }
+    }
+
+    fn is_not_authenticated(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<FishPiError>() == Some(&FishPiError::NotAuthenticated)
+    }
+
+    /// 将调用方放入查询参数或请求体中的 `apiKey` 替换为新令牌，用于重新登录后重试
+    fn replace_api_key(
+        mut params: Option<HashMap<String, String>>,
+        mut data: Option<Value>,
+        token: &str,
+    ) -> (Option<HashMap<String, String>>, Option<Value>) {
+        if let Some(key) = params.as_mut().and_then(|p| p.get_mut("apiKey")) {
+            *key = token.to_string();
+        }
+        if let Some(key) = data.as_mut().and_then(|d| d.get_mut("apiKey")) {
+            *key = Value::String(token.to_string());
+        }
+        (params, data)
+    }
+
+    async fn request_once<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+        data: Option<Value>,
+    ) -> Result<T> {
+        self.acquire_rate_limit().await;
         let mut url = self.build_url(path).await;
 
         if let Some(params) = params {
@@ -127,19 +919,73 @@ impl ApiClient {
         }
 
         let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_USER_AGENT));
+        headers.insert(USER_AGENT, Self::user_agent_header(&self.config.user_agent));
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
+        // POST 用于发消息、发红包等非幂等操作，不能重试
+        let idempotent = method != reqwest::Method::POST;
         let mut request = self.client.request(method, &url).headers(headers);
 
         if let Some(json_data) = data {
             request = request.json(&json_data);
         }
 
-        let response = request.send().await?;
+        let response = self.send_with_retry(request, idempotent).await?;
         self.process_response(response).await
     }
 
+    /// 发送请求，幂等请求在连接失败或服务端 5xx 时按配置退避重试
+    async fn send_with_retry(
+        &self,
+        request: reqwest::RequestBuilder,
+        idempotent: bool,
+    ) -> Result<ReqwestResponse> {
+        let max_retries = if idempotent {
+            self.config.max_retries
+        } else {
+            0
+        };
+
+        let mut attempt = 0;
+        loop {
+            let Some(current) = request.try_clone() else {
+                return request.send().await.map_err(Self::map_send_error);
+            };
+
+            let retryable = match current.send().await {
+                Ok(response) if response.status().is_server_error() && attempt < max_retries => {
+                    format!("状态码 {}", response.status())
+                }
+                Ok(response) => return Ok(response),
+                Err(err) if err.is_connect() && attempt < max_retries => err.to_string(),
+                Err(err) => return Err(Self::map_send_error(err)),
+            };
+
+            let delay = self.config.retry_delay(attempt);
+            attempt += 1;
+            self.metrics.record_retry();
+            log::debug!(
+                "请求失败（{}），{:?} 后进行第 {} 次重试",
+                retryable,
+                delay,
+                attempt
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// 将请求错误转换为可读的错误信息
+    fn map_send_error(err: reqwest::Error) -> anyhow::Error {
+        let url = err.url().map(|u| u.path().to_string()).unwrap_or_default();
+        if err.is_timeout() {
+            FishPiError::Network(format!("请求超时: {}", url)).into()
+        } else if err.is_connect() {
+            FishPiError::Network(format!("无法连接服务器: {}", url)).into()
+        } else {
+            anyhow::Error::new(err)
+        }
+    }
+
     // 使用通用请求方法重写 HTTP 方法
     pub async fn get<T: DeserializeOwned>(
         &self,
@@ -159,6 +1005,37 @@ impl ApiClient {
             .await
     }
 
+    /// 发送 GET 请求并解析通用响应信封
+    ///
+    /// 同时兼容 `code` 与 `result` 两种成功约定，请求失败或状态码非 0 时返回错误响应
+    pub async fn get_parsed<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+    ) -> Response<T> {
+        Self::into_response(self.get::<ApiEnvelope<T>>(path, params).await)
+    }
+
+    /// 发送 POST 请求并解析通用响应信封
+    ///
+    /// 同时兼容 `code` 与 `result` 两种成功约定，请求失败或状态码非 0 时返回错误响应
+    pub async fn post_parsed<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+        data: Value,
+    ) -> Response<T> {
+        Self::into_response(self.post::<ApiEnvelope<T>>(path, params, data).await)
+    }
+
+    fn into_response<T>(result: Result<ApiEnvelope<T>>) -> Response<T> {
+        match result {
+            Ok(envelope) => envelope.into(),
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
     pub async fn put<T: DeserializeOwned>(
         &self,
         path: &str,
@@ -179,12 +1056,61 @@ impl ApiClient {
             .await
     }
 
+    /// 发送任意 GET 请求，返回原始 JSON
+    ///
+    /// - `path` 接口路径，如 `user/liveness`
+    /// - `params` 查询参数
+    ///
+    /// 用于调用尚未封装的接口。已登录时自动附加 `apiKey` 参数，调用方无需重复添加；
+    /// `params` 中已有 `apiKey` 时保留调用方的值
+    pub async fn raw_get(
+        &self,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+    ) -> Result<Value> {
+        let mut params = params.unwrap_or_default();
+        if let Some(token) = self.get_token().await {
+            params.entry("apiKey".to_string()).or_insert(token);
+        }
+        let params = (!params.is_empty()).then_some(params);
+        self.get::<Value>(path, params).await
+    }
+
+    /// 发送任意 POST 请求，返回原始 JSON
+    ///
+    /// - `path` 接口路径
+    /// - `params` 查询参数
+    /// - `body` 请求体
+    ///
+    /// 用于调用尚未封装的接口。已登录时自动附加 `apiKey`：请求体为对象时写入请求体，
+    /// 否则写入查询参数，调用方无需重复添加；已有 `apiKey` 时保留调用方的值
+    pub async fn raw_post(
+        &self,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+        mut body: Value,
+    ) -> Result<Value> {
+        let mut params = params.unwrap_or_default();
+        if let Some(token) = self.get_token().await {
+            match body {
+                Value::Object(ref mut map) => {
+                    map.entry("apiKey").or_insert_with(|| token.into());
+                }
+                _ => {
+                    params.entry("apiKey".to_string()).or_insert(token);
+                }
+            }
+        }
+        let params = (!params.is_empty()).then_some(params);
+        self.post::<Value>(path, params, body).await
+    }
+
     /// 关闭所有WebSocket连接
     pub async fn close_websocket_connections(&self) -> Result<()> {
         let mut tasks = WEBSOCKET_TASKS.lock().await;
         let mut completed = 0;
 
-        for task in tasks.iter() {
+        for task in tasks.values().flatten() {
             task.abort();
             completed += 1;
         }
@@ -194,12 +1120,34 @@ impl ApiClient {
         Ok(())
     }
 
+    /// 等待所有WebSocket连接任务结束，超时后强制终止
+    ///
+    /// 调用前应已通过发送通道发出关闭帧，以便连接完成关闭握手
+    pub async fn shutdown_websocket_connections(&self, timeout: Duration) -> Result<()> {
+        let mut tasks: Vec<JoinHandle<()>> = std::mem::take(&mut *WEBSOCKET_TASKS.lock().await)
+            .into_values()
+            .flatten()
+            .collect();
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let all_done = futures::future::join_all(tasks.iter_mut());
+        if tokio::time::timeout(timeout, all_done).await.is_err() {
+            log::debug!("等待WebSocket关闭超时，强制终止连接任务");
+            for task in tasks.iter() {
+                task.abort();
+            }
+        }
+        Ok(())
+    }
+
     /// 创建WebSocket URL
     fn create_websocket_url(&self, url: &str) -> String {
         if url.starts_with("ws") || url.starts_with("wss") {
             url.to_string()
         } else {
-            let protocol = if self.base_url.starts_with("https") {
+            let protocol = if self.base_url().starts_with("https") {
                 "wss"
             } else {
                 "ws"
@@ -207,24 +1155,27 @@ impl ApiClient {
             format!(
                 "{}://{}/{}",
                 protocol,
-                self.base_url.replace("https://", "").replace("http://", ""),
+                self.base_url()
+                    .replace("https://", "")
+                    .replace("http://", ""),
                 url
             )
         }
     }
 
-    /// 处理WebSocket消息
+    /// 处理 WebSocket 消息，收到关闭帧时返回断开状态
     async fn handle_websocket_message(
         msg: Message,
+        metrics: &Metrics,
         on_message: impl Fn(Value) + Send + Sync + Clone,
         on_error: Option<impl Fn(String) + Send + Sync + Clone>,
-        on_close: Option<impl Fn() + Send + Sync + Clone>,
-    ) {
+    ) -> Option<ConnectionState> {
         match msg {
             Message::Text(text) => {
                 if text == "heartbeat" || text == "pong" {
                     log::debug!("收到WebSocket心跳消息: {}", text);
                 } else {
+                    metrics.record_message();
                     match serde_json::from_str::<Value>(&text) {
                         Ok(value) => on_message(value),
                         Err(e) => {
@@ -235,47 +1186,64 @@ impl ApiClient {
                     }
                 }
             }
-            Message::Close(_) => {
-                if let Some(on_close) = on_close {
-                    on_close();
-                }
+            Message::Close(frame) => {
+                return Some(ConnectionState::from_close_frame(frame.as_ref()));
             }
             _ => {}
         }
+        None
     }
 
+    /// 建立WebSocket连接
+    ///
+    /// 返回连接句柄，可通过其发送通道向服务器推送帧；
+    /// 连接断开时以断开状态调用 `on_close`，可据此区分封禁与普通断开。
+    /// 不影响已建立的其他连接，需要替换旧连接时由调用方通过 [`WebSocketConnection::shutdown`] 关闭
     pub async fn connect_websocket(
         &self,
         url: &str,
         params: Option<HashMap<String, String>>,
         on_message: impl Fn(Value) + Send + Sync + Clone + 'static,
         on_error: Option<impl Fn(String) + Send + Sync + Clone + 'static>,
-        on_close: Option<impl Fn() + Send + Sync + Clone + 'static>,
-    ) -> Result<()> {
-        let _ = self.close_websocket_connections().await;
-        tokio::time::sleep(Duration::from_millis(WEBSOCKET_CLEANUP_DELAY)).await;
-
+        on_close: Option<impl Fn(ConnectionState) + Send + Sync + Clone + 'static>,
+    ) -> Result<WebSocketConnection> {
         let mut full_url = self.create_websocket_url(url);
         if let Some(params) = params {
             full_url = Self::add_params_to_url(&full_url, params);
         }
 
         let url = Url::parse(&full_url)?;
-        let (ws_stream, _) = connect_async(url).await?;
-        let (_, read) = ws_stream.split();
+        let ws_stream = self.open_websocket(url).await?;
+        let (mut write, read) = ws_stream.split();
 
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded::<Message>();
+        let write_handle = tokio::spawn(async move {
+            while let Some(message) = receiver.next().await {
+                let closing = matches!(message, Message::Close(_));
+                if write.send(message).await.is_err() || closing {
+                    break;
+                }
+            }
+        });
+
+        let metrics = self.metrics.clone();
         let task_handle = tokio::spawn(async move {
             let mut read = read;
+            let mut state = ConnectionState::Disconnected;
             while let Some(msg_result) = read.next().await {
                 match msg_result {
                     Ok(msg) => {
-                        Self::handle_websocket_message(
+                        if let Some(closed) = Self::handle_websocket_message(
                             msg,
+                            &metrics,
                             on_message.clone(),
                             on_error.clone(),
-                            on_close.clone(),
                         )
-                        .await;
+                        .await
+                        {
+                            state = closed;
+                            break;
+                        }
                     }
                     Err(e) => {
                         if let Some(on_error) = on_error {
@@ -287,33 +1255,70 @@ impl ApiClient {
             }
 
             if let Some(on_close) = on_close {
-                on_close();
+                on_close(state);
             }
         });
 
+        let id = NEXT_WEBSOCKET_ID.fetch_add(1, Ordering::Relaxed);
         {
             let mut tasks = WEBSOCKET_TASKS.lock().await;
-            tasks.push(task_handle);
+            tasks.retain(|_, handles| handles.iter().any(|h| !h.is_finished()));
+            tasks.insert(id, vec![task_handle, write_handle]);
         }
 
-        Ok(())
+        Ok(WebSocketConnection { id, sender })
     }
 
     async fn process_response<T: DeserializeOwned>(&self, response: ReqwestResponse) -> Result<T> {
         let status = response.status();
         let text = response.text().await?;
 
-        if !status.is_success() {
-            return Err(anyhow::anyhow!(
-                "HTTP请求失败: 状态码 {}, 响应: {}",
-                status,
-                text
-            ));
+        // 令牌仅在重新登录失败后清除，见 `request`
+        if status.as_u16() == 401 || Self::is_auth_failure(&text) {
+            return Err(FishPiError::NotAuthenticated.into());
+        }
+
+        match status.as_u16() {
+            429 => return Err(FishPiError::RateLimited.into()),
+            _ if !status.is_success() => {
+                return Err(anyhow::anyhow!(
+                    "HTTP请求失败: 状态码 {}, 响应: {}",
+                    status,
+                    text
+                ));
+            }
+            _ => {}
         }
 
         match serde_json::from_str::<T>(&text) {
             Ok(data) => Ok(data),
-            Err(e) => Err(anyhow::anyhow!("解析响应失败: {}, 原始响应: {}", e, text)),
+            Err(e) => Err(FishPiError::Parse(format!("{}, 原始响应: {}", e, text)).into()),
+        }
+    }
+
+    /// 响应是否表示未登录或令牌失效
+    fn is_auth_failure(text: &str) -> bool {
+        if !text.contains("401") && !AUTH_FAILURE_KEYWORDS.iter().any(|k| text.contains(k)) {
+            return false;
+        }
+        let Ok(envelope) = serde_json::from_str::<ApiEnvelope<IgnoredAny>>(text) else {
+            return false;
+        };
+        let code = envelope.code.or(envelope.result);
+        if code == Some(401) {
+            return true;
+        }
+        code.is_some_and(|code| code != 0)
+            && envelope
+                .msg
+                .is_some_and(|msg| AUTH_FAILURE_KEYWORDS.iter().any(|k| msg.contains(k)))
+    }
+
+    /// 清除失效的令牌，使 `is_logged_in` 等如实反映登录状态
+    async fn invalidate_token(&self) {
+        if self.get_token().await.is_some() {
+            log::warn!("令牌已失效，清除本地令牌");
+            self.set_token(None).await;
         }
     }
 
@@ -333,26 +1338,136 @@ impl ApiClient {
         result
     }
 
+    /// 检查服务器连通性
+    ///
+    /// 对服务器根路径发送 `HEAD` 请求并记录耗时，不下载页面内容，同样受限流控制；
+    /// 网络错误时返回 `Err`，收到非成功状态码时 `reachable` 为 `false`
+    pub async fn ping(&self) -> Result<ServerStatus> {
+        self.acquire_rate_limit().await;
+        let url = self.build_url("/").await;
+        let started = Instant::now();
+        let response = self
+            .client
+            .head(&url)
+            .header(USER_AGENT, Self::user_agent_header(&self.config.user_agent))
+            .send()
+            .await?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let reachable = response.status().is_success() || response.status().is_redirection();
+        let version = response
+            .headers()
+            .get("x-version")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        Ok(ServerStatus {
+            reachable,
+            latency_ms,
+            version,
+        })
+    }
+
+    /// 上传文件
+    ///
+    /// - `files` 文件名与文件内容
+    ///
+    /// 按传入顺序返回文件地址；单个文件超过 `MAX_UPLOAD_SIZE` 时不发起请求直接返回错误，
+    /// 服务端拒绝（如文件类型不允许）时错误信息为服务端返回的原因；
+    /// 令牌失效时与其他请求一样经重新登录回调重试，见 [`ApiClient::set_reauth`]
+    pub async fn upload(&self, files: Vec<(String, Vec<u8>)>) -> Result<Vec<String>> {
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some((name, data)) = files.iter().find(|(_, data)| data.len() > MAX_UPLOAD_SIZE) {
+            return Err(FishPiError::Validation(format!(
+                "文件 {} 大小 {} 字节，超过上限 {} 字节",
+                name,
+                data.len(),
+                MAX_UPLOAD_SIZE
+            ))
+            .into());
+        }
+
+        let boundary = format!("----fishpi{:016x}", rand::random::<u64>());
+        let files: Vec<(String, Vec<u8>)> = files
+            .into_iter()
+            .map(|(name, data)| (sanitize_file_name(&name), data))
+            .collect();
+        let names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+        let body = Self::build_multipart(&boundary, files);
+
+        let boundary = &boundary;
+        let body = &body;
+        let result = self
+            .with_reauth(move |_| self.upload_once(boundary, body.clone()))
+            .await?;
+        result.into_urls(&names)
+    }
+
+    /// 发送一次上传请求，令牌取当前保存的令牌
+    async fn upload_once(&self, boundary: &str, body: Vec<u8>) -> Result<UploadResponse> {
+        self.acquire_rate_limit().await;
+        let mut url = self.build_url("upload").await;
+        if let Some(token) = self.get_token().await {
+            let mut params = HashMap::new();
+            params.insert("apiKey".to_string(), token);
+            url = Self::add_params_to_url(&url, params);
+        }
+
+        let request = self
+            .client
+            .post(&url)
+            .header(USER_AGENT, Self::user_agent_header(&self.config.user_agent))
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body);
+        let response = self.send_with_retry(request, false).await?;
+        self.process_response(response).await
+    }
+
+    /// 构造 `file[]` 字段的 multipart 请求体，文件名需已经过 [`sanitize_file_name`] 清理
+    ///
+    /// reqwest 的 `multipart` 特性依赖 `mime_guess`，而本项目的依赖中没有该 crate，
+    /// 因此这里手工拼接请求体，MIME 类型由 [`guess_mime`] 推断
+    fn build_multipart(boundary: &str, files: Vec<(String, Vec<u8>)>) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, data) in files {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"file[]\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    boundary,
+                    name,
+                    guess_mime(&name)
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(&data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+        body
+    }
+
     pub async fn get_html(
         &self,
         path: &str,
         params: Option<HashMap<String, String>>,
     ) -> Result<String> {
+        self.acquire_rate_limit().await;
         let mut url = self.build_url(path).await;
 
         if let Some(params) = params {
             url = Self::add_params_to_url(&url, params);
         }
 
-        let response = self
-            .client
-            .get(&url)
-            .header(
-                "User-Agent",
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
-            )
-            .send()
-            .await?;
+        let request = self.client.get(&url).header(
+            "User-Agent",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+        );
+        let response = self.send_with_retry(request, true).await?;
 
         let status = response.status();
         let text = response.text().await?;
@@ -368,3 +1483,514 @@ impl ApiClient {
         Ok(text)
     }
 }
+
+/// CONNECT 请求中的目标地址，IPv6 地址加方括号
+fn connect_authority(target: &Url) -> Option<String> {
+    let port = target.port_or_known_default().unwrap_or(443);
+    match target.host()? {
+        url::Host::Ipv6(addr) => Some(format!("[{}]:{}", addr, port)),
+        host => Some(format!("{}:{}", host, port)),
+    }
+}
+
+/// 目标地址是否命中 `NO_PROXY`
+///
+/// 条目为 `*`、主机名（匹配自身及子域名，可带前导 `.`）或 IP，
+/// 带端口（如 `example.com:8080`、`[::1]:8080`）时仅匹配该端口
+fn no_proxy_matches(no_proxy: &str, target: &Url) -> bool {
+    let Some(host) = target.host() else {
+        return false;
+    };
+    let host = match host {
+        url::Host::Ipv6(addr) => addr.to_string(),
+        host => host.to_string().to_ascii_lowercase(),
+    };
+    let port = target.port_or_known_default();
+
+    no_proxy.split(',').map(str::trim).any(|entry| {
+        if entry == "*" {
+            return true;
+        }
+        let (entry_host, entry_port) = split_host_port(entry);
+        if entry_port.is_some() && entry_port != port {
+            return false;
+        }
+        let entry_host = entry_host.trim_start_matches('.').to_ascii_lowercase();
+        !entry_host.is_empty()
+            && (host == entry_host || host.ends_with(&format!(".{}", entry_host)))
+    })
+}
+
+/// 拆分 `NO_PROXY` 条目中的主机与端口，兼容 `[::1]:8080` 与不带方括号的 IPv6 地址
+fn split_host_port(entry: &str) -> (&str, Option<u16>) {
+    if let Some(rest) = entry.strip_prefix('[') {
+        return match rest.split_once(']') {
+            Some((host, tail)) => (host, tail.strip_prefix(':').and_then(|p| p.parse().ok())),
+            None => (rest, None),
+        };
+    }
+    match entry.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => (entry, None),
+        },
+        _ => (entry, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockResponse, MockServer};
+
+    fn mock_client(server: &MockServer) -> ApiClient {
+        crate::test_util::mock_client(server).with_token(Some("old".to_string()))
+    }
+
+    #[test]
+    fn rate_limiter_rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                RateLimiter::new(rate, 1),
+                Err(FishPiError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rate_limiter_waits_after_burst() {
+        let mut limiter = RateLimiter::new(2.0, 2).unwrap();
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+
+        let first = limiter.reserve();
+        let second = limiter.reserve();
+        assert!(first > Duration::from_millis(400) && first <= Duration::from_millis(500));
+        assert!(second > Duration::from_millis(900) && second <= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn set_rate_limit_keeps_previous_limiter_on_error() {
+        let client = ApiClient::new();
+        client.set_rate_limit(5.0, 1).await.unwrap();
+        assert!(client.set_rate_limit(0.0, 1).await.is_err());
+        assert!(client.rate_limiter.lock().await.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_file_permissions_tightened_on_rewrite() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("fishpi-token-{}", std::process::id()));
+        std::fs::write(&path, "old-token-longer").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        ApiClient::store_token_file(&path, Some("new")).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mode, 0o600);
+        assert_eq!(content, "new");
+    }
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        let config = ClientConfig {
+            retry_backoff_ms: 100,
+            ..ClientConfig::default()
+        };
+        assert_eq!(config.retry_delay(0), Duration::from_millis(100));
+        assert_eq!(config.retry_delay(1), Duration::from_millis(200));
+        assert_eq!(config.retry_delay(3), Duration::from_millis(800));
+        assert_eq!(config.retry_delay(u32::MAX), config.retry_delay(16));
+    }
+
+    #[tokio::test]
+    async fn idempotent_requests_retry_up_to_max_retries() {
+        let server = MockServer::start(vec![
+            (503, "{}"),
+            (503, "{}"),
+            (503, "{}"),
+            (200, r#"{"code":0}"#),
+        ])
+        .await;
+        let mut client = mock_client(&server);
+        client.config.max_retries = 2;
+        client.config.retry_backoff_ms = 1;
+
+        assert!(client.get::<Value>("api/test", None).await.is_err());
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(client.metrics().snapshot().request_retries, 2);
+    }
+
+    #[tokio::test]
+    async fn post_requests_are_not_retried() {
+        let server = MockServer::start(vec![(503, "{}"), (200, r#"{"code":0}"#)]).await;
+        let mut client = mock_client(&server);
+        client.config.max_retries = 2;
+        client.config.retry_backoff_ms = 1;
+
+        assert!(
+            client
+                .post::<Value>("api/test", None, serde_json::json!({}))
+                .await
+                .is_err()
+        );
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reauth_retries_with_new_token_in_params() {
+        let server = MockServer::start(vec![
+            (401, r#"{"code":401,"msg":"未登录"}"#),
+            (200, r#"{"code":0,"data":"ok"}"#),
+        ])
+        .await;
+        let client = mock_client(&server);
+        client
+            .set_reauth(|| async { Some("new".to_string()) })
+            .await;
+
+        let mut params = HashMap::new();
+        params.insert("apiKey".to_string(), "old".to_string());
+        let value: Value = client.get("api/test", Some(params)).await.unwrap();
+
+        assert_eq!(value["data"], "ok");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("apiKey=old"));
+        assert!(requests[1].contains("apiKey=new"));
+        assert_eq!(client.get_token().await.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn reauth_retries_with_new_token_in_body() {
+        let server = MockServer::start(vec![
+            (401, r#"{"code":401,"msg":"未登录"}"#),
+            (200, r#"{"code":0}"#),
+        ])
+        .await;
+        let client = mock_client(&server);
+        client
+            .set_reauth(|| async { Some("new".to_string()) })
+            .await;
+
+        let body = serde_json::json!({ "apiKey": "old", "content": "hi" });
+        let _: Value = client.post("api/test", None, body).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(r#""apiKey":"new""#));
+    }
+
+    #[tokio::test]
+    async fn token_cleared_only_after_reauth_fails() {
+        let server = MockServer::start(vec![(401, r#"{"code":401,"msg":"未登录"}"#)]).await;
+        let client = mock_client(&server);
+        let seen = Arc::new(std::sync::Mutex::new(None));
+        let (probe, seen_in_hook) = (client.clone(), seen.clone());
+        client
+            .set_reauth(move || {
+                let (probe, seen) = (probe.clone(), seen_in_hook.clone());
+                async move {
+                    *seen.lock().unwrap() = probe.get_token().await;
+                    None
+                }
+            })
+            .await;
+
+        let err = client.get::<Value>("api/test", None).await.unwrap_err();
+
+        assert!(ApiClient::is_not_authenticated(&err));
+        assert_eq!(seen.lock().unwrap().as_deref(), Some("old"));
+        assert_eq!(client.get_token().await, None);
+    }
+
+    #[test]
+    fn set_base_url_updates_config_for_all_clones() {
+        let client = ApiClient::new();
+        let clone = client.clone();
+
+        client.set_base_url("https://example.com");
+
+        assert_eq!(clone.base_url(), "https://example.com");
+        assert_eq!(clone.config().base_url, "https://example.com");
+        let detached = clone.with_base_url("https://other.example.com");
+        client.set_base_url("https://fishpi.cn");
+        assert_eq!(detached.config().base_url, "https://other.example.com");
+    }
+
+    const BOGUS_PROXY: &str = "http://[not-a-host";
+
+    #[test]
+    fn invalid_proxy_is_a_validation_error() {
+        let config = ClientConfig {
+            proxy: Some(BOGUS_PROXY.to_string()),
+            ..ClientConfig::default()
+        };
+        assert!(matches!(
+            ApiClient::try_with_config(config.clone()),
+            Err(FishPiError::Validation(_))
+        ));
+
+        // with_config 忽略无效代理时，WebSocket 也应回到环境变量中的代理
+        let client = ApiClient::with_config(config);
+        assert_eq!(client.websocket_proxy, WebSocketProxy::Environment);
+    }
+
+    #[test]
+    fn with_proxy_keeps_external_client() {
+        let client = ApiClient::from_reqwest(Client::new(), "http://localhost");
+        assert!(matches!(
+            client.clone().with_proxy("http://127.0.0.1:7890"),
+            Err(FishPiError::Validation(_))
+        ));
+
+        let client = client
+            .with_websocket_proxy(Some("http://127.0.0.1:7890"))
+            .unwrap();
+        let target = Url::parse("wss://fishpi.cn/chat-room-channel").unwrap();
+        assert_eq!(
+            client.websocket_proxy(&target).as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+        let client = client.with_websocket_proxy(None).unwrap();
+        assert_eq!(client.websocket_proxy(&target), None);
+    }
+
+    #[test]
+    fn with_proxy_applies_to_websocket() {
+        let client = ApiClient::new()
+            .with_proxy("http://127.0.0.1:7890")
+            .unwrap();
+        let target = Url::parse("wss://fishpi.cn/chat-room-channel").unwrap();
+        assert_eq!(
+            client.websocket_proxy(&target).as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+        assert!(matches!(
+            ApiClient::new().with_proxy(BOGUS_PROXY),
+            Err(FishPiError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn connect_authority_brackets_ipv6() {
+        let target = Url::parse("wss://[::1]/chat").unwrap();
+        assert_eq!(connect_authority(&target).as_deref(), Some("[::1]:443"));
+        let target = Url::parse("ws://fishpi.cn:8080/chat").unwrap();
+        assert_eq!(
+            connect_authority(&target).as_deref(),
+            Some("fishpi.cn:8080")
+        );
+    }
+
+    #[test]
+    fn no_proxy_honours_ports() {
+        let target = Url::parse("wss://ws.fishpi.cn/chat").unwrap();
+        assert!(no_proxy_matches("fishpi.cn", &target));
+        assert!(no_proxy_matches(".fishpi.cn:443", &target));
+        assert!(!no_proxy_matches("fishpi.cn:8080", &target));
+        assert!(!no_proxy_matches("pi.cn", &target));
+        assert!(no_proxy_matches("*", &target));
+
+        let target = Url::parse("ws://[::1]:8080/chat").unwrap();
+        assert!(no_proxy_matches("[::1]:8080", &target));
+        assert!(no_proxy_matches("::1", &target));
+        assert!(!no_proxy_matches("[::1]:9090", &target));
+    }
+
+    #[tokio::test]
+    async fn connect_tunnel_sends_bracketed_ipv6_authority() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut byte = [0u8; 1];
+            while !request.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).await.unwrap();
+                request.push(byte[0]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let target = Url::parse("ws://[::1]:8080/chat").unwrap();
+        ApiClient::connect_tunnel(&proxy, &target).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT [::1]:8080 HTTP/1.1\r\nHost: [::1]:8080\r\n"));
+    }
+
+    fn close_state(code: u16, reason: &str) -> ConnectionState {
+        let frame = CloseFrame {
+            code: code.into(),
+            reason: reason.to_string().into(),
+        };
+        ConnectionState::from_close_frame(Some(&frame))
+    }
+
+    #[test]
+    fn close_frame_matches_ban_words_only() {
+        assert!(close_state(1000, "You are banned").is_banned());
+        assert!(close_state(1000, "kick: duplicate login").is_banned());
+        assert!(close_state(1000, "你已被封禁").is_banned());
+        assert!(close_state(1008, "").is_banned());
+        assert!(!close_state(1000, "bandwidth exceeded").is_banned());
+        assert!(!close_state(1001, "server blockchain maintenance").is_banned());
+        assert_eq!(
+            ConnectionState::from_close_frame(None),
+            ConnectionState::Disconnected
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_looks_up_sanitized_file_name() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":0,"data":{"errFiles":[],"succMap":{"a_b.png":"https://file/a.png"}}}"#,
+        )])
+        .await;
+
+        let urls = mock_client(&server)
+            .upload(vec![("a\"b.png".to_string(), vec![1, 2, 3])])
+            .await
+            .unwrap();
+
+        assert_eq!(urls, ["https://file/a.png"]);
+        assert!(server.requests()[0].contains("filename=\"a_b.png\""));
+    }
+
+    #[tokio::test]
+    async fn upload_retries_with_new_token_after_reauth() {
+        let server = MockServer::start(vec![
+            (401, r#"{"code":401,"msg":"未登录"}"#),
+            (
+                200,
+                r#"{"code":0,"data":{"errFiles":[],"succMap":{"a.png":"https://file/a.png"}}}"#,
+            ),
+        ])
+        .await;
+        let client = mock_client(&server);
+        client
+            .set_reauth(|| async { Some("new".to_string()) })
+            .await;
+
+        let urls = client
+            .upload(vec![("a.png".to_string(), vec![1, 2, 3])])
+            .await
+            .unwrap();
+
+        assert_eq!(urls, ["https://file/a.png"]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("apiKey=old"));
+        assert!(requests[1].contains("apiKey=new"));
+        assert!(requests[1].contains("filename=\"a.png\""));
+        assert_eq!(client.get_token().await.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn ping_sends_head_through_rate_limiter() {
+        let delay = Duration::from_millis(200);
+        let server = MockServer::start_with(vec![
+            MockResponse::new(200, "")
+                .header("x-version", "2.0.1")
+                .delay(delay),
+        ])
+        .await;
+        let client = mock_client(&server);
+        client.set_rate_limit(1.0, 1).await.unwrap();
+
+        let status = client.ping().await.unwrap();
+
+        assert!(status.reachable);
+        assert!(status.latency_ms >= delay.as_millis() as u64);
+        assert_eq!(status.version.as_deref(), Some("2.0.1"));
+        assert!(server.requests()[0].starts_with("HEAD / "));
+        let wait = client.rate_limiter.lock().await.as_mut().unwrap().reserve();
+        assert!(wait > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn parsed_requests_accept_both_envelope_conventions() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":0,"data":1}"#),
+            (200, r#"{"result":0,"data":2}"#),
+            (200, r#"{"result":-1,"msg":"积分不足"}"#),
+        ])
+        .await;
+        let client = mock_client(&server);
+
+        let by_code = client.get_parsed::<i32>("a", None).await;
+        let by_result = client
+            .post_parsed::<i32>("b", None, serde_json::json!({}))
+            .await;
+        let failed = client.get_parsed::<i32>("c", None).await;
+
+        assert_eq!(by_code.data, Some(1));
+        assert_eq!(by_result.data, Some(2));
+        assert!(!failed.success);
+        assert_eq!(
+            failed.error,
+            Some(FishPiError::ApiError {
+                code: -1,
+                msg: "积分不足".to_string()
+            })
+        );
+    }
+
+    /// 已关闭端口上的代理地址，经由它的连接会立即失败
+    async fn refused_proxy() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn injected_client_with_bogus_proxy_fails_fast() {
+        let server = MockServer::start(vec![(200, r#"{"code":0}"#)]).await;
+        let ws_server = crate::test_util::MockWebSocketServer::start().await;
+        let proxy = refused_proxy().await;
+        let client = Client::builder()
+            .proxy(reqwest::Proxy::all(&proxy).unwrap())
+            .build()
+            .unwrap();
+        let client = ApiClient::from_reqwest(client, &server.url)
+            .with_websocket_proxy(Some(&proxy))
+            .unwrap();
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.get::<Value>("api/ping", None),
+        )
+        .await
+        .expect("请求应立即失败");
+        assert!(result.is_err());
+        assert!(server.requests().is_empty());
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.connect_websocket(
+                &ws_server.url,
+                None,
+                |_| {},
+                None::<fn(String)>,
+                None::<fn(ConnectionState)>,
+            ),
+        )
+        .await
+        .expect("连接应立即失败");
+        assert!(result.is_err());
+        assert!(ws_server.paths().is_empty());
+    }
+}
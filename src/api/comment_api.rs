@@ -1,7 +1,10 @@
 use crate::api::client::ApiClient;
-use crate::models::article::{CommentPost, ResponseResult};
-use anyhow::{Result, anyhow};
+use crate::error::FishPiError;
+use crate::models::article::{CommentAuthor, CommentPost, ResponseResult};
+use crate::models::user::ApiEnvelope;
+use anyhow::Result;
 use serde_json::{Value, json};
+use std::collections::HashMap;
 
 /// 评论API
 #[derive(Clone, Debug)]
@@ -23,25 +26,22 @@ impl CommentApi {
     pub async fn send(&self, data: &CommentPost) -> Result<ResponseResult> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: Value = self.client.post("comment", None, json_data).await?;
 
-        let response = ResponseResult {
-            code: result["code"].as_i64().unwrap_or(0) as i32,
-            msg: result["msg"].as_str().unwrap_or("").to_string(),
-            data: None,
-        };
-
-        if response.code != 0 {
-            return Err(anyhow!(response.msg.clone()));
-        }
+        let envelope = ApiEnvelope::peek(&result)?;
+        envelope.check("未知错误")?;
 
-        Ok(response)
+        Ok(ResponseResult {
+            code: envelope.status(),
+            msg: envelope.msg.unwrap_or_default(),
+            data: None,
+        })
     }
 
     /// 更新评论
@@ -53,10 +53,10 @@ impl CommentApi {
     pub async fn update(&self, id: &str, data: &CommentPost) -> Result<String> {
         let mut json_data = serde_json::to_value(data)?;
 
-        if let Value::Object(ref mut map) = json_data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = json_data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: Value = self
@@ -64,10 +64,7 @@ impl CommentApi {
             .put(&format!("comment/{}", id), None, json_data)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["commentContent"].as_str().unwrap_or("").to_string())
     }
@@ -83,10 +80,10 @@ impl CommentApi {
             "dataId": id
         });
 
-        if let Value::Object(ref mut map) = data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let vote_type = if like { "up" } else { "down" };
@@ -95,10 +92,7 @@ impl CommentApi {
             .post(&format!("vote/{}/comment", vote_type), None, data)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["type"].as_i64().unwrap_or(0) == 0)
     }
@@ -113,27 +107,89 @@ impl CommentApi {
             "commentId": id
         });
 
-        if let Value::Object(ref mut map) = data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: Value = self.client.post("comment/thank", None, data).await?;
 
-        let response = ResponseResult {
-            code: result["code"].as_i64().unwrap_or(0) as i32,
-            msg: result["msg"].as_str().unwrap_or("").to_string(),
+        let envelope = ApiEnvelope::peek(&result)?;
+        envelope.check("未知错误")?;
+
+        Ok(ResponseResult {
+            code: envelope.status(),
+            msg: envelope.msg.unwrap_or_default(),
             data: None,
-        };
+        })
+    }
+
+    /// 评论打赏
+    ///
+    /// - `id` 评论 Id
+    ///
+    /// 返回执行结果，服务端拒绝（如积分不足、打赏自己的评论）时返回错误
+    pub async fn reward(&self, id: &str) -> Result<ResponseResult> {
+        let mut data = json!({
+            "commentId": id
+        });
+
+        if let Value::Object(ref mut map) = data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
+        }
+
+        let response: ResponseResult = self.client.post("comment/reward", None, data).await?;
 
         if response.code != 0 {
-            return Err(anyhow!(response.msg.clone()));
+            return Err(FishPiError::from_code(response.code, &response.msg).into());
         }
 
         Ok(response)
     }
 
+    /// 获取感谢过评论的用户
+    ///
+    /// - `id` 评论 Id
+    ///
+    /// 返回感谢者列表，无法解析的条目记录警告后跳过
+    pub async fn thankers(&self, id: &str) -> Result<Vec<CommentAuthor>> {
+        let mut params = HashMap::new();
+        params.insert("commentId".to_string(), id.to_string());
+        if let Some(token) = self.client.get_token().await {
+            params.insert("apiKey".to_string(), token);
+        }
+
+        let result = self
+            .client
+            .get::<Value>("api/comment/thanks", Some(params))
+            .await?;
+
+        ApiEnvelope::peek(&result)?.check("获取评论感谢者失败")?;
+
+        let users = match result.get("data") {
+            Some(Value::Array(users)) => users.clone(),
+            Some(data) => data
+                .get("users")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        Ok(users
+            .into_iter()
+            .filter_map(|user| match serde_json::from_value(user.clone()) {
+                Ok(user) => Some(user),
+                Err(err) => {
+                    log::warn!("评论 {} 的感谢者解析失败: {}，原始数据: {}", id, err, user);
+                    None
+                }
+            })
+            .collect())
+    }
+
     /// 删除评论
     ///
     /// - `id` 评论 Id
@@ -142,10 +198,10 @@ impl CommentApi {
     pub async fn remove(&self, id: &str) -> Result<String> {
         let mut data = json!({});
 
-        if let Value::Object(ref mut map) = data {
-            if let Some(token) = self.client.get_token().await {
-                map.insert("apiKey".into(), token.into());
-            }
+        if let Value::Object(ref mut map) = data
+            && let Some(token) = self.client.get_token().await
+        {
+            map.insert("apiKey".into(), token.into());
         }
 
         let result: Value = self
@@ -153,10 +209,7 @@ impl CommentApi {
             .post(&format!("comment/{}/remove", id), None, data)
             .await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         Ok(result["commentId"].as_str().unwrap_or("").to_string())
     }
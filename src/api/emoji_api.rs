@@ -3,7 +3,12 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 use crate::api::client::ApiClient;
+use crate::error::not_authenticated;
 use crate::models::emoji::EmojiList;
+use crate::models::user::ApiEnvelope;
+
+/// 云端保存用户自定义表情使用的游戏ID
+const USER_EMOJI_GAME_ID: &str = "emojis";
 
 /// 表情API接口
 #[derive(Clone, Debug)]
@@ -30,14 +35,94 @@ impl EmojiApi {
 
         let result: Value = self.client.get(url, Some(params)).await?;
 
-        if result["code"] != 0 {
-            let error_msg = result["msg"].as_str().unwrap_or("未知错误").to_string();
-            return Err(anyhow!(error_msg));
-        }
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
 
         let emoji_list: EmojiList = serde_json::from_value(result["data"].clone())
             .map_err(|e| anyhow!("解析表情包列表数据失败: {}", e))?;
 
         Ok(emoji_list)
     }
+
+    /// 获取当前用户的表情短码映射
+    ///
+    /// 返回短码到图片地址的映射，兼容对象与单键对象数组两种返回形式
+    pub async fn get_user_emotions(&self) -> Result<HashMap<String, String>> {
+        let token = self
+            .client
+            .get_token()
+            .await
+            .ok_or_else(|| not_authenticated("获取表情短码失败: 未登录"))?;
+        let mut params = HashMap::new();
+        params.insert("apiKey".to_string(), token);
+
+        let result: Value = self.client.get("users/emotions", Some(params)).await?;
+
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
+
+        let entries: Vec<&serde_json::Map<String, Value>> = match &result["data"] {
+            Value::Object(map) => vec![map],
+            Value::Array(items) => items.iter().filter_map(Value::as_object).collect(),
+            Value::Null => Vec::new(),
+            other => return Err(anyhow!("解析表情短码数据失败: {}", other)),
+        };
+        Ok(entries
+            .into_iter()
+            .flatten()
+            .filter_map(|(code, url)| Some((code.clone(), url.as_str()?.to_string())))
+            .collect())
+    }
+
+    /// 获取用户自定义表情
+    ///
+    /// 返回表情URL列表
+    pub async fn get_user_emojis(&self) -> Result<Vec<String>> {
+        let token = self
+            .client
+            .get_token()
+            .await
+            .ok_or_else(|| not_authenticated("获取自定义表情失败: 未登录"))?;
+        let data = serde_json::json!({
+            "gameId": USER_EMOJI_GAME_ID,
+            "apiKey": token,
+        });
+
+        let result: Value = self.client.post("api/cloud/get", None, data).await?;
+
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
+
+        // 云端数据可能以 JSON 字符串形式保存
+        let emojis = match &result["data"] {
+            Value::String(raw) if raw.trim().is_empty() => Vec::new(),
+            Value::String(raw) => {
+                serde_json::from_str(raw).map_err(|e| anyhow!("解析自定义表情数据失败: {}", e))?
+            }
+            Value::Null => Vec::new(),
+            other => serde_json::from_value(other.clone())
+                .map_err(|e| anyhow!("解析自定义表情数据失败: {}", e))?,
+        };
+
+        Ok(emojis)
+    }
+
+    /// 同步用户自定义表情，覆盖云端保存的列表
+    ///
+    /// - `emojis` 表情URL列表
+    pub async fn sync_user_emojis(&self, emojis: &[String]) -> Result<()> {
+        let token = self
+            .client
+            .get_token()
+            .await
+            .ok_or_else(|| not_authenticated("同步自定义表情失败: 未登录"))?;
+        let data = serde_json::json!({
+            "gameId": USER_EMOJI_GAME_ID,
+            "data": emojis,
+            "apiKey": token,
+        });
+
+        let result: Value = self.client.post("api/cloud/sync", None, data).await?;
+
+        ApiEnvelope::peek(&result)?.check("未知错误")?;
+
+        Ok(())
+    }
 }
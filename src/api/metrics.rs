@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 请求与 WebSocket 事件计数器
+///
+/// 由同一 `ApiClient` 创建的所有服务共享，只做计数，可定期读取 [`Metrics::snapshot`] 输出日志
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests_total: AtomicU64,
+    request_errors: AtomicU64,
+    request_retries: AtomicU64,
+    ws_reconnects: AtomicU64,
+    messages_received: AtomicU64,
+}
+
+/// 某一时刻的计数快照
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// 发起的 HTTP 请求数
+    pub requests_total: u64,
+    /// 失败的 HTTP 请求数，含业务层判定的未登录等错误
+    pub request_errors: u64,
+    /// 幂等请求的重试次数
+    pub request_retries: u64,
+    /// WebSocket 自动重连次数
+    pub ws_reconnects: u64,
+    /// 从 WebSocket 收到的消息数，不含心跳
+    pub messages_received: u64,
+}
+
+impl Metrics {
+    /// 读取当前计数
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests_total: self.requests_total.load(Ordering::Relaxed),
+            request_errors: self.request_errors.load(Ordering::Relaxed),
+            request_retries: self.request_retries.load(Ordering::Relaxed),
+            ws_reconnects: self.ws_reconnects.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn record_request(&self) {
+        self.requests_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_request_error(&self) {
+        self.request_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.request_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_reconnect(&self) {
+        self.ws_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_message(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+}
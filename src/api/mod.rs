@@ -5,6 +5,7 @@ pub mod chatroom_api;
 pub mod client;
 pub mod comment_api;
 pub mod emoji_api;
+pub mod metrics;
 pub mod notice_api;
 pub mod redpacket_api;
 pub mod user_api;
@@ -13,9 +14,13 @@ pub use article_api::ArticleApi;
 pub use breezemoon_api::BreezemoonApi;
 pub use chat_api::ChatApi;
 pub use chatroom_api::ChatroomApi;
-pub use client::ApiClient;
+pub use client::{
+    ApiClient, ClientConfig, ConnectionState, RateLimiter, ReconnectConfig, ServerStatus,
+    WebSocketConnection, WebSocketSender,
+};
 pub use comment_api::CommentApi;
 pub use emoji_api::EmojiApi;
+pub use metrics::{Metrics, MetricsSnapshot};
 pub use notice_api::NoticeApi;
 pub use redpacket_api::RedpacketApi;
 pub use user_api::UserApi;
@@ -1,9 +1,12 @@
 use crate::api::client::ApiClient;
+use crate::error::not_authenticated;
 use crate::models::notice::{
     NoticeAt, NoticeComment, NoticeCount, NoticeFollow, NoticeItem, NoticePoint, NoticeSystem,
 };
+use crate::models::user::{ApiEnvelope, ApiResponse};
 use anyhow::{Result, anyhow};
-use serde_json::Value;
+use serde::de::IgnoredAny;
+use serde_json::{Value, json};
 use std::collections::HashMap;
 
 /// 通知 API 接口
@@ -18,12 +21,17 @@ impl NoticeApi {
         Self { client }
     }
 
+    /// 底层 HTTP 客户端
+    pub(crate) fn client(&self) -> &ApiClient {
+        &self.client
+    }
+
     /// 检查登录状态并返回token
     async fn check_token(&self, operation: &str) -> Result<Option<String>> {
         let token = self.client.get_token().await;
         if token.is_none() {
             log::debug!("{}: 未登录", operation);
-            return Err(anyhow!("未登录"));
+            return Err(not_authenticated("未登录"));
         }
         Ok(token)
     }
@@ -70,14 +78,8 @@ impl NoticeApi {
             .get::<Value>("api/getNotifications", Some(params))
             .await?;
 
-        if let Some(code) = response.get("code") {
-            if code.as_i64() != Some(0) {
-                let msg = response
-                    .get("msg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("未知错误");
-                return Err(anyhow!("获取通知列表失败: {}", msg));
-            }
+        if let Err(err) = ApiEnvelope::peek(&response)?.check("未知错误") {
+            return Err(anyhow::Error::new(err).context("获取通知列表失败"));
         }
 
         if let Some(data) = response.get("data") {
@@ -152,6 +154,40 @@ impl NoticeApi {
             .await
     }
 
+    /// 按 oId 批量标记通知为已读
+    ///
+    /// * `ids` - 通知 oId 列表
+    pub async fn make_read_ids(&self, ids: &[&str]) -> Result<ApiResponse<()>> {
+        self.post_make_read(json!({ "oIds": ids }), "批量标记通知为已读")
+            .await
+    }
+
+    /// 按 oId 标记单条通知为已读
+    ///
+    /// * `id` - 通知 oId
+    pub async fn make_read_id(&self, id: &str) -> Result<ApiResponse<()>> {
+        self.post_make_read(json!({ "oId": id }), "标记通知为已读")
+            .await
+    }
+
+    async fn post_make_read(&self, mut body: Value, operation: &str) -> Result<ApiResponse<()>> {
+        let token = self.check_token(operation).await?;
+        if let (Some(token_value), Value::Object(map)) = (token, &mut body) {
+            map.insert("apiKey".into(), token_value.into());
+        }
+
+        let envelope = self
+            .client
+            .post::<ApiEnvelope<IgnoredAny>>("notifications/make-read", None, body)
+            .await?;
+
+        if !envelope.is_success() {
+            let msg = envelope.msg.as_deref().unwrap_or("标记通知为已读失败");
+            return Ok(ApiResponse::error(envelope.status(), msg));
+        }
+        Ok(ApiResponse::success(()))
+    }
+
     /// 标记所有通知为已读
     pub async fn read_all(&self) -> Result<Value> {
         let token = self.check_token("标记所有通知为已读").await?;
@@ -168,7 +204,7 @@ impl NoticeApi {
         if let Some(token_value) = token {
             Ok(format!("user-channel?apiKey={}", token_value))
         } else {
-            Err(anyhow!("未登录，无法获取 WebSocket URL"))
+            Err(not_authenticated("未登录，无法获取 WebSocket URL"))
         }
     }
 }
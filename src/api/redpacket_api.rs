@@ -1,6 +1,7 @@
 use crate::api::client::ApiClient;
+use crate::error::not_authenticated;
 use crate::models::redpacket::{RedPacketInfo, RedPacketMessage};
-use crate::models::user::ApiResponse;
+use crate::models::user::{ApiEnvelope, ApiResponse};
 use anyhow::Result;
 use serde_json::{Value, json};
 
@@ -27,7 +28,7 @@ impl RedpacketApi {
     pub async fn open_redpacket(&self, oid: &str, gesture: Option<i32>) -> Result<RedPacketInfo> {
         let token = self.client.get_token().await;
         if token.is_none() {
-            return Err(anyhow::anyhow!("未登录，请先登录"));
+            return Err(not_authenticated("未登录，请先登录"));
         }
 
         let mut request_data = json!({
@@ -35,10 +36,10 @@ impl RedpacketApi {
             "apiKey": token.unwrap(),
         });
 
-        if let Some(gesture_value) = gesture {
-            if let Value::Object(ref mut map) = request_data {
-                map.insert("gesture".into(), gesture_value.into());
-            }
+        if let Some(gesture_value) = gesture
+            && let Value::Object(ref mut map) = request_data
+        {
+            map.insert("gesture".into(), gesture_value.into());
         }
 
         self.client
@@ -52,8 +53,11 @@ impl RedpacketApi {
     /// * `redpacket` - 红包消息对象
     ///
     /// # 返回
-    /// * `ApiResponse<()>` - API响应
-    pub async fn send_redpacket(&self, redpacket: &RedPacketMessage) -> Result<ApiResponse<()>> {
+    /// * `ApiResponse<String>` - API响应，接口返回了消息 oid 时 `data` 为该 oid
+    pub async fn send_redpacket(
+        &self,
+        redpacket: &RedPacketMessage,
+    ) -> Result<ApiResponse<String>> {
         let token = self.client.get_token().await;
         if token.is_none() {
             return Ok(ApiResponse::error(401, "未登录，请先登录"));
@@ -70,6 +74,29 @@ impl RedpacketApi {
         });
 
         // 发送请求
-        self.client.post("chat-room/send", None, request_data).await
+        let response = self
+            .client
+            .post::<Value>("chat-room/send", None, request_data)
+            .await?;
+
+        let envelope = ApiEnvelope::peek(&response)?;
+        if !envelope.is_success() {
+            let msg = envelope.msg.as_deref().unwrap_or("发送红包失败");
+            return Ok(ApiResponse::error(envelope.status(), msg));
+        }
+
+        let oid = response
+            .get("oId")
+            .or_else(|| response.get("data").and_then(|data| data.get("oId")))
+            .and_then(|oid| match oid {
+                Value::String(oid) if !oid.is_empty() => Some(oid.clone()),
+                Value::Number(oid) => Some(oid.to_string()),
+                _ => None,
+            });
+        Ok(ApiResponse {
+            code: 0,
+            msg: None,
+            data: oid,
+        })
     }
 }
@@ -1,5 +1,9 @@
 use crate::api::client::ApiClient;
-use crate::models::user::{ApiResponse, LoginResponse, UserInfo};
+use crate::error::{FishPiError, not_authenticated};
+use crate::models::user::{
+    Activity, ApiEnvelope, ApiResponse, LeaderboardEntry, LeaderboardKind, LoginResponse,
+    PointRecord, UserInfo, parse_liveness,
+};
 use anyhow::Result;
 use serde::Deserialize;
 use serde_json::{Value, json};
@@ -15,12 +19,46 @@ impl UserApi {
         Self { client }
     }
 
+    /// 底层 HTTP 客户端
+    pub(crate) fn client(&self) -> &ApiClient {
+        &self.client
+    }
+
+    /// 登录
+    ///
+    /// - `username` 用户名或邮箱
+    /// - `password` 密码的 MD5
+    /// - `mfa_code` 二次验证码，未开启时为空
+    /// - `captcha` 验证码，服务端要求时填写
     pub async fn login(
         &self,
         username: &str,
         password: &str,
         mfa_code: &str,
     ) -> Result<LoginResponse> {
+        let request_body = Self::login_body(username, password, mfa_code)?;
+        self.post_login(request_body).await
+    }
+
+    /// 携带验证码登录
+    ///
+    /// - `captcha_token` 上次登录失败时服务端返回的 `needCaptcha`
+    /// - `captcha` 用户输入的验证码
+    pub async fn login_with_captcha(
+        &self,
+        username: &str,
+        password: &str,
+        mfa_code: &str,
+        captcha_token: &str,
+        captcha: &str,
+    ) -> Result<LoginResponse> {
+        let mut request_body = Self::login_body(username, password, mfa_code)?;
+        request_body["needCaptcha"] = captcha_token.into();
+        request_body["captcha"] = captcha.into();
+        self.post_login(request_body).await
+    }
+
+    fn login_body(username: &str, password: &str, mfa_code: &str) -> Result<Value> {
         if username.is_empty() {
             return Err(anyhow::anyhow!("用户名不能为空"));
         }
@@ -29,21 +67,24 @@ impl UserApi {
             return Err(anyhow::anyhow!("密码不能为空"));
         }
 
-        let request_body = json!({
+        Ok(json!({
             "nameOrEmail": username,
             "userPassword": password,
             "mfaCode": mfa_code
-        });
+        }))
+    }
 
+    async fn post_login(&self, request_body: Value) -> Result<LoginResponse> {
         let response = self
             .client
             .post::<LoginResponse>("/api/getKey", None, request_body)
             .await?;
 
-        if response.code == 0 && response.key.is_some() {
-            if let Some(token) = &response.key {
-                self.client.set_token(Some(token.clone())).await;
-            }
+        if response.code == 0
+            && response.key.is_some()
+            && let Some(token) = &response.key
+        {
+            self.client.set_token(Some(token.clone())).await;
         }
 
         Ok(response)
@@ -92,15 +133,29 @@ impl UserApi {
             params.insert("apiKey".to_string(), token_value);
         }
 
-        #[derive(Deserialize)]
-        struct LivenessResponse {
-            liveness: f64,
+        self.client
+            .get::<Value>("/user/liveness", Some(params))
+            .await
+            .and_then(|r| Ok(parse_liveness(&r)?))
+    }
+
+    /// 每日签到
+    ///
+    /// 返回原始响应，`code` 非 0 表示签到失败（如今日已签到）
+    pub async fn checkin(&self) -> Result<Value> {
+        let token = self.client.get_token().await;
+        if token.is_none() {
+            return Err(not_authenticated("未登录，无法签到"));
+        }
+
+        let mut params = HashMap::new();
+        if let Some(token_value) = token {
+            params.insert("apiKey".to_string(), token_value);
         }
 
         self.client
-            .get::<LivenessResponse>("/user/liveness", Some(params))
+            .get::<Value>("/activity/daily-checkin-api", Some(params))
             .await
-            .map(|r| r.liveness)
     }
 
     pub async fn is_check_in(&self) -> Result<bool> {
@@ -188,10 +243,10 @@ impl UserApi {
             "memo": memo,
         });
 
-        if let Some(token_value) = token {
-            if let Value::Object(ref mut map) = request_body {
-                map.insert("apiKey".into(), token_value.into());
-            }
+        if let Some(token_value) = token
+            && let Value::Object(ref mut map) = request_body
+        {
+            map.insert("apiKey".into(), token_value.into());
         }
 
         self.client
@@ -199,6 +254,10 @@ impl UserApi {
             .await
     }
 
+    /// 关注或取消关注用户
+    ///
+    /// * `user_oid` - 对方用户的 oId，不是用户名
+    /// * `follow` - true 为关注，false 为取消关注
     pub async fn follow(&self, user_oid: &str, follow: bool) -> Result<ApiResponse<()>> {
         let token = self.client.get_token().await;
         if token.is_none() {
@@ -207,17 +266,246 @@ impl UserApi {
 
         let mut request_body = json!({
             "followingId": user_oid,
-            "following": follow,
         });
 
-        if let Some(token_value) = token {
-            if let Value::Object(ref mut map) = request_body {
-                map.insert("apiKey".into(), token_value.into());
-            }
+        if let Some(token_value) = token
+            && let Value::Object(ref mut map) = request_body
+        {
+            map.insert("apiKey".into(), token_value.into());
         }
 
+        let path = if follow {
+            "/follow/user"
+        } else {
+            "/unfollow/user"
+        };
         self.client
-            .post::<ApiResponse<()>>("/follow/user", None, request_body)
+            .post::<ApiResponse<()>>(path, None, request_body)
+            .await
+    }
+
+    pub async fn get_points_log(&self, page: i32) -> Result<ApiResponse<Vec<PointRecord>>> {
+        let token = self.client.get_token().await;
+        if token.is_none() {
+            return Ok(ApiResponse::error(401, "未登录"));
+        }
+
+        let mut params = HashMap::new();
+        if let Some(token_value) = token {
+            params.insert("apiKey".to_string(), token_value);
+        }
+        params.insert("p".to_string(), page.to_string());
+
+        let envelope = self
+            .client
+            .get::<ApiEnvelope<Value>>("/api/user/points", Some(params))
+            .await?;
+
+        Ok(envelope
+            .map_data(|data| {
+                data.as_array()
+                    .or_else(|| data.get("points")?.as_array())
+                    .map(|arr| arr.iter().map(PointRecord::from).collect())
+                    .unwrap_or_default()
+            })
+            .into())
+    }
+
+    pub async fn get_user(&self, username: &str) -> Result<ApiResponse<UserInfo>> {
+        let token = self.client.get_token().await;
+
+        let mut params = HashMap::new();
+        if let Some(token_value) = token {
+            params.insert("apiKey".to_string(), token_value);
+        }
+
+        let response = self
+            .client
+            .get::<Value>(&format!("/user/{}", username), Some(params))
+            .await?;
+
+        let envelope = ApiEnvelope::peek(&response)?;
+        if !envelope.is_success() {
+            let msg = envelope.msg.as_deref().unwrap_or("获取用户信息失败");
+            return Ok(ApiResponse::error(envelope.status(), msg));
+        }
+
+        let data = response.get("data").unwrap_or(&response);
+        let user = serde_json::from_value::<UserInfo>(data.clone())?;
+        Ok(ApiResponse::success(user))
+    }
+
+    /// 获取用户关注的人
+    ///
+    /// * `username` - 用户名
+    /// * `page` - 页码
+    /// * `size` - 每页数量
+    pub async fn get_following(
+        &self,
+        username: &str,
+        page: i32,
+        size: i32,
+    ) -> Result<ApiResponse<Vec<UserInfo>>> {
+        self.get_follow_list(username, "following", page, size)
+            .await
+    }
+
+    /// 获取用户的粉丝
+    ///
+    /// * `username` - 用户名
+    /// * `page` - 页码
+    /// * `size` - 每页数量
+    pub async fn get_followers(
+        &self,
+        username: &str,
+        page: i32,
+        size: i32,
+    ) -> Result<ApiResponse<Vec<UserInfo>>> {
+        self.get_follow_list(username, "followers", page, size)
             .await
     }
+
+    /// 获取关注关系列表，`kind` 为 `following` 或 `followers`
+    async fn get_follow_list(
+        &self,
+        username: &str,
+        kind: &str,
+        page: i32,
+        size: i32,
+    ) -> Result<ApiResponse<Vec<UserInfo>>> {
+        let token = self.client.get_token().await;
+        if token.is_none() {
+            return Ok(ApiResponse::error(401, "未登录"));
+        }
+
+        let mut params = HashMap::new();
+        if let Some(token_value) = token {
+            params.insert("apiKey".to_string(), token_value);
+        }
+        params.insert("p".to_string(), page.to_string());
+        params.insert("size".to_string(), size.to_string());
+
+        let envelope = self
+            .client
+            .get::<ApiEnvelope<Vec<Value>>>(
+                &format!("/api/user/{}/{}", username, kind),
+                Some(params),
+            )
+            .await?;
+
+        Ok(envelope
+            .map_data(|data| {
+                data.into_iter()
+                    .filter_map(|v| serde_json::from_value::<UserInfo>(v).ok())
+                    .collect()
+            })
+            .into())
+    }
+
+    pub async fn get_activities(&self) -> Result<ApiResponse<Vec<Activity>>> {
+        let token = self.client.get_token().await;
+        if token.is_none() {
+            return Ok(ApiResponse::error(401, "未登录"));
+        }
+
+        let mut params = HashMap::new();
+        if let Some(token_value) = token {
+            params.insert("apiKey".to_string(), token_value);
+        }
+
+        let envelope = self
+            .client
+            .get::<ApiEnvelope<Vec<Value>>>("/api/activities", Some(params))
+            .await?;
+
+        Ok(envelope
+            .map_data(|data| data.iter().map(Activity::from).collect())
+            .into())
+    }
+
+    pub async fn get_leaderboard(
+        &self,
+        kind: LeaderboardKind,
+    ) -> Result<ApiResponse<Vec<LeaderboardEntry>>> {
+        let mut params = HashMap::new();
+        if let Some(token_value) = self.client.get_token().await {
+            params.insert("apiKey".to_string(), token_value);
+        }
+
+        let envelope = self
+            .client
+            .get::<ApiEnvelope<Vec<Value>>>(&format!("/api/top/{}", kind.path()), Some(params))
+            .await?;
+
+        Ok(envelope
+            .map_data(|data| {
+                data.iter()
+                    .enumerate()
+                    .map(|(i, entry)| LeaderboardEntry::parse(kind, i as u32 + 1, entry))
+                    .collect()
+            })
+            .into())
+    }
+
+    /// 查询用户名是否可注册
+    ///
+    /// * `name` - 用户名
+    ///
+    /// 服务端返回 `data` 为 true 表示可用
+    pub async fn check_username(&self, name: &str) -> Result<ApiResponse<bool>> {
+        let mut params = HashMap::new();
+        params.insert("userName".to_string(), name.to_string());
+
+        let envelope = self
+            .client
+            .get::<ApiEnvelope<Value>>("/api/user/check-name", Some(params))
+            .await?;
+
+        if !envelope.is_success() {
+            return Ok(envelope.map_data(|_| false).into());
+        }
+        let available = envelope
+            .data
+            .as_ref()
+            .and_then(|data| data.as_bool().or_else(|| data.get("available")?.as_bool()));
+        match available {
+            Some(available) => Ok(envelope.map_data(|_| available).into()),
+            None => Err(
+                FishPiError::Parse(format!("无法解析用户名查询结果: {:?}", envelope.data)).into(),
+            ),
+        }
+    }
+
+    pub async fn claim_activity(&self, id: &str) -> Result<ApiResponse<i32>> {
+        let token = self.client.get_token().await;
+        if token.is_none() {
+            return Ok(ApiResponse::error(401, "未登录"));
+        }
+
+        let mut request_body = json!({});
+        if let Some(token_value) = token
+            && let Value::Object(ref mut map) = request_body
+        {
+            map.insert("apiKey".into(), token_value.into());
+        }
+
+        let response = self
+            .client
+            .post::<Value>(&format!("/api/activity/{}/claim", id), None, request_body)
+            .await?;
+
+        let envelope = ApiEnvelope::peek(&response)?;
+        if !envelope.is_success() {
+            let msg = envelope.msg.as_deref().unwrap_or("领取活动奖励失败");
+            return Ok(ApiResponse::error(envelope.status(), msg));
+        }
+
+        let sum = response
+            .get("sum")
+            .or_else(|| response.get("data"))
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0) as i32;
+
+        Ok(ApiResponse::success(sum))
+    }
 }
@@ -0,0 +1,176 @@
+use thiserror::Error;
+
+/// FishPi 错误类型
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum FishPiError {
+    /// 参数校验失败，未发起网络请求
+    #[error("参数校验失败: {0}")]
+    Validation(String),
+    /// 未登录或令牌失效
+    #[error("未登录")]
+    NotAuthenticated,
+    /// 请求过于频繁，被服务端限流
+    #[error("请求过于频繁")]
+    RateLimited,
+    /// 网络错误，如连接失败、超时
+    #[error("网络错误: {0}")]
+    Network(String),
+    /// 服务端返回了非 0 状态码
+    #[error("{msg}")]
+    ApiError { code: i32, msg: String },
+    /// 响应解析失败
+    #[error("解析响应失败: {0}")]
+    Parse(String),
+}
+
+/// 服务端限流（发送冷却中）时返回的状态码
+pub const RATE_LIMITED_CODE: i32 = 429;
+
+/// 服务端的通用错误码，具体原因只体现在 `msg` 中
+pub const GENERIC_ERROR_CODE: i32 = -1;
+
+/// 服务端以通用错误码返回限流时，提示中的关键字
+const RATE_LIMIT_HINTS: &[&str] = &["频繁", "太快", "过快", "冷却"];
+
+impl FishPiError {
+    /// 根据服务端状态码构造错误
+    ///
+    /// - `code` 响应中的 `code` 或 `result` 字段
+    /// - `msg` 响应中的错误信息
+    pub fn from_code(code: i32, msg: &str) -> Self {
+        match code {
+            401 => FishPiError::NotAuthenticated,
+            RATE_LIMITED_CODE => FishPiError::RateLimited,
+            _ => FishPiError::ApiError {
+                code,
+                msg: msg.to_string(),
+            },
+        }
+    }
+
+    /// 是否为发送过于频繁导致的失败，稍后重试通常可以成功
+    ///
+    /// 依据冷却状态码 [`RATE_LIMITED_CODE`] 判断（已转换为 [`FishPiError::RateLimited`]）。
+    /// 部分接口在冷却时仅返回通用错误码 [`GENERIC_ERROR_CODE`]，此时才退回到按“发送太频繁”类提示识别，
+    /// 其他错误码一律不视为限流
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            FishPiError::RateLimited => true,
+            FishPiError::ApiError { code, msg } => {
+                *code == RATE_LIMITED_CODE
+                    || (*code == GENERIC_ERROR_CODE
+                        && RATE_LIMIT_HINTS.iter().any(|hint| msg.contains(hint)))
+            }
+            _ => false,
+        }
+    }
+
+    /// 从 API 层的 `anyhow` 错误中识别错误类型
+    ///
+    /// 优先取错误链中的 `FishPiError`，其次按底层错误类型判断，其余视为通用错误码的 `ApiError`
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        if let Some(known) = err.downcast_ref::<FishPiError>() {
+            return known.clone();
+        }
+        if let Some(req) = err.downcast_ref::<reqwest::Error>() {
+            if req.is_decode() {
+                return FishPiError::Parse(req.to_string());
+            }
+            return FishPiError::Network(req.to_string());
+        }
+        if err.downcast_ref::<serde_json::Error>().is_some() {
+            return FishPiError::Parse(err.to_string());
+        }
+
+        FishPiError::ApiError {
+            code: GENERIC_ERROR_CODE,
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<std::convert::Infallible> for FishPiError {
+    /// 使不会失败的转换（如 `TryFrom<T> for T`）可用于要求 `Error: Into<FishPiError>` 的参数
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// 构造未登录错误
+///
+/// - `msg` 错误信息，说明无法执行的操作
+///
+/// 错误可通过 [`FishPiError::from_anyhow`] 识别为 [`FishPiError::NotAuthenticated`]
+pub(crate) fn not_authenticated(msg: &str) -> anyhow::Error {
+    anyhow::Error::new(FishPiError::NotAuthenticated).context(msg.to_string())
+}
+
+/// 校验发送内容，拒绝空内容或仅包含空白字符的内容
+///
+/// - `content` 待发送内容
+pub fn ensure_content(content: &str) -> Result<(), FishPiError> {
+    if content.trim().is_empty() {
+        return Err(FishPiError::Validation("content empty".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limited_keys_on_cooldown_code() {
+        assert!(FishPiError::from_code(RATE_LIMITED_CODE, "稍后再试").is_rate_limited());
+        assert!(
+            FishPiError::ApiError {
+                code: RATE_LIMITED_CODE,
+                msg: String::new(),
+            }
+            .is_rate_limited()
+        );
+    }
+
+    #[test]
+    fn rate_limited_falls_back_to_message_only_for_generic_code() {
+        let generic = FishPiError::from_code(GENERIC_ERROR_CODE, "发送太频繁，请稍后再试");
+        assert!(generic.is_rate_limited());
+
+        let permanent = FishPiError::from_code(403, "禁言中，请勿频繁尝试");
+        assert!(!permanent.is_rate_limited());
+        assert!(!FishPiError::NotAuthenticated.is_rate_limited());
+    }
+
+    #[test]
+    fn from_anyhow_uses_error_type_not_message() {
+        let err = not_authenticated("未登录，无法签到");
+        assert_eq!(
+            FishPiError::from_anyhow(&err),
+            FishPiError::NotAuthenticated
+        );
+        assert_eq!(err.to_string(), "未登录，无法签到");
+
+        let err = anyhow::anyhow!("用户名包含未登录字样");
+        assert_eq!(
+            FishPiError::from_anyhow(&err),
+            FishPiError::ApiError {
+                code: GENERIC_ERROR_CODE,
+                msg: "用户名包含未登录字样".to_string()
+            }
+        );
+
+        let err = anyhow::Error::new(FishPiError::RateLimited).context("发送失败");
+        assert_eq!(FishPiError::from_anyhow(&err), FishPiError::RateLimited);
+    }
+
+    #[test]
+    fn ensure_content_rejects_blank_content() {
+        for content in ["", "  \n"] {
+            assert_eq!(
+                ensure_content(content),
+                Err(FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert_eq!(ensure_content(" hi "), Ok(()));
+    }
+}
@@ -60,52 +60,77 @@ let client = FishPi::with_base_url("https://your-fishpi-server.com");
 */
 
 pub mod api;
+pub mod error;
 pub mod models;
 pub mod services;
+pub mod utils;
+
+#[cfg(test)]
+mod test_util;
 
 // 导出常用类型到顶层命名空间
 pub use models::chatroom::{
-    AutoCompleteUsername, BarrageCost, BarragerMsg, ChatContentType, ChatRoomData,
-    ChatRoomDataContent, ChatRoomMessage, ChatRoomMessageType, ChatRoomNode, ChatRoomNodeInfo,
-    ChatRoomQueryMode, ChatRoomUser, ChatSource, MusicMsg, MuteItem, SpecialMessageContent,
-    WeatherMsg, WeatherMsgData, WebSocketMessage,
+    AutoCompleteUsername, BarrageColor, BarrageCost, BarragerMsg, Capabilities, ChatContentType,
+    ChatMessageKind, ChatRoomCommand, ChatRoomData, ChatRoomDataContent, ChatRoomMessage,
+    ChatRoomMessageType, ChatRoomNode, ChatRoomNodeInfo, ChatRoomQueryMode, ChatRoomUser,
+    ChatSource, ClientType, DisplayNameMode, LazyChatRoomMessage, LogFormat, MusicMsg, MuteItem,
+    SentMessage, SpecialMessageContent, WeatherMsg, WeatherMsgData, WebSocketMessage,
 };
 
 pub use models::chat::{
-    ChatData, ChatDataContent, ChatMessage, ChatMessageType, ChatNotice, ChatRevoke, WebsocketInfo,
+    ChatData, ChatDataContent, ChatMessage, ChatMessageType, ChatNotice, ChatRevoke, ChatUnread,
+    WebsocketInfo,
 };
 
+pub use models::filter::MessageFilter;
+
 pub use models::redpacket::{
-    GestureType, RedPacketBase, RedPacketGot, RedPacketInfo, RedPacketMessage, RedPacketStatusMsg,
-    RedPacketType,
+    GestureRecord, GestureStats, GestureType, RedPacketBase, RedPacketGot, RedPacketInfo,
+    RedPacketMessage, RedPacketStatusMsg, RedPacketType, RpsOutcome,
 };
 
-pub use models::user::{Response, UserInfo};
+pub use models::user::{
+    Activity, ActivityClaimFailure, ApiEnvelope, CheckinResult, LeaderboardEntry, LeaderboardKind,
+    LoginChallenge, LoginFailure, LoginResponse, Metal, PointRecord, Relationship, Reputation,
+    Response, UserInfo,
+};
 
 pub use models::notice::{
-    NoticeAt, NoticeComment, NoticeCount, NoticeFollow, NoticeMsg, NoticeMsgType, NoticePoint,
-    NoticeSystem, NoticeType, NoticeWebsocketInfo,
+    NoticeAt, NoticeComment, NoticeCount, NoticeDisplay, NoticeFollow, NoticeMsg, NoticeMsgType,
+    NoticePoint, NoticeSystem, NoticeType, NoticeWebsocketInfo,
 };
 
 pub use models::article::{
-    ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticlePost, ArticleTag,
-    CommentPost, ResponseResult,
+    ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticleNotify, ArticlePost,
+    ArticleTag, CommentPost, HotRange, ResponseResult, VoteStatus,
 };
 
+pub use models::comment::CommentNode;
+
 pub use models::breezemoon::{Breezemoon, BreezemoonList, BreezemoonPost, BreezemoonResponse};
 
 pub use models::emoji::{Emoji, EmojiCategory, EmojiList};
 
 pub use services::{
-    ArticleService, BreezemoonService, ChatService, ChatroomService, CommentService, EmojiService,
-    NoticeService, RedpacketService, UserService,
+    ArticleService, BreezemoonService, ChatService, ChatroomService, CheckinSchedule,
+    CommentService, ConnectionChangeHandler, EmojiService, NoticeService, RedpacketService,
+    UnknownMessageHandler, UserService,
 };
 
+pub use api::{
+    ClientConfig, ConnectionState, Metrics, MetricsSnapshot, ReconnectConfig, ServerStatus,
+};
+pub use error::FishPiError;
+
 use api::client::ApiClient;
 use api::{
     ArticleApi, BreezemoonApi, ChatApi, ChatroomApi, CommentApi, EmojiApi, NoticeApi, RedpacketApi,
     UserApi,
 };
+use services::UnknownHandlerSlot;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// FishPi API 客户端主类
 #[derive(Debug, Clone)]
@@ -130,8 +155,29 @@ impl Default for FishPi {
 
 impl FishPi {
     /// 创建一个新的 FishPi 客户端实例
+    ///
+    /// 等价于不做任何设置的 [`FishPi::builder`]
     pub fn new() -> Self {
-        let api_client = ApiClient::new();
+        Self::builder()
+            .build()
+            .expect("Failed to build HTTP client")
+    }
+
+    /// 创建客户端构建器，可一次性设置服务器地址、令牌、超时与 User-Agent
+    pub fn builder() -> FishPiBuilder {
+        FishPiBuilder::default()
+    }
+
+    /// 使用外部构建的 reqwest 客户端创建 FishPi 客户端
+    ///
+    /// - `client` 已配置代理、TLS 根证书或连接池的客户端
+    /// - `base_url` 服务器地址
+    pub fn with_client(client: reqwest::Client, base_url: &str) -> Self {
+        Self::from_api_client(ApiClient::from_reqwest(client, base_url))
+    }
+
+    fn from_api_client(api_client: ApiClient) -> Self {
+        let unknown_handler: UnknownHandlerSlot = Arc::new(tokio::sync::Mutex::new(None));
 
         let user_api = UserApi::new(api_client.clone());
         let chatroom_api = ChatroomApi::new(api_client.clone());
@@ -144,11 +190,16 @@ impl FishPi {
         let emoji_api = EmojiApi::new(api_client.clone());
 
         let user_service = UserService::new(user_api);
-        let chatroom_service = ChatroomService::new(chatroom_api);
-        let redpacket_service = RedpacketService::new(redpacket_api);
-        let chat_service = ChatService::new(chat_api);
-        let notice_service = NoticeService::new(notice_api);
-        let article_service = ArticleService::new(article_api);
+        let chatroom_service = ChatroomService::new(chatroom_api)
+            .with_user(user_service.clone())
+            .with_unknown_handler(unknown_handler.clone());
+        let redpacket_service = RedpacketService::new(redpacket_api)
+            .with_user(user_service.clone())
+            .with_chatroom(chatroom_service.clone());
+        let chat_service = ChatService::new(chat_api).with_unknown_handler(unknown_handler.clone());
+        let notice_service =
+            NoticeService::new(notice_api).with_unknown_handler(unknown_handler.clone());
+        let article_service = ArticleService::new(article_api).with_user(user_service.clone());
         let comment_service = CommentService::new(comment_api);
         let breezemoon_service = BreezemoonService::new(breezemoon_api);
         let emoji_service = EmojiService::new(emoji_api);
@@ -168,35 +219,51 @@ impl FishPi {
     }
 
     /// 使用自定义的基础 URL 创建 FishPi 客户端
+    ///
+    /// 等价于只设置 `base_url` 的 [`FishPi::builder`]
     pub fn with_base_url(base_url: &str) -> Self {
-        let mut client = Self::new();
-        client.set_base_url(base_url);
-        client
+        Self::builder()
+            .base_url(base_url)
+            .build()
+            .expect("Failed to build HTTP client")
     }
 
     /// 设置 API 服务器的基础 URL
-    pub fn set_base_url(&mut self, base_url: &str) {
-        self.api_client = self.api_client.clone().with_base_url(base_url);
-
-        let user_api = UserApi::new(self.api_client.clone());
-        let chatroom_api = ChatroomApi::new(self.api_client.clone());
-        let redpacket_api = RedpacketApi::new(self.api_client.clone());
-        let chat_api = ChatApi::new(self.api_client.clone());
-        let notice_api = NoticeApi::new(self.api_client.clone());
-        let article_api = ArticleApi::new(self.api_client.clone());
-        let comment_api = CommentApi::new(self.api_client.clone());
-        let breezemoon_api = BreezemoonApi::new(self.api_client.clone());
-        let emoji_api = EmojiApi::new(self.api_client.clone());
-
-        self.user = UserService::new(user_api);
-        self.chatroom = ChatroomService::new(chatroom_api);
-        self.redpacket = RedpacketService::new(redpacket_api);
-        self.chat = ChatService::new(chat_api);
-        self.notice = NoticeService::new(notice_api);
-        self.article = ArticleService::new(article_api);
-        self.comment = CommentService::new(comment_api);
-        self.breezemoon = BreezemoonService::new(breezemoon_api);
-        self.emoji = EmojiService::new(emoji_api);
+    ///
+    /// 所有服务共享同一个 `ApiClient`，切换后立即对后续请求生效，已注册的监听器与配置保持不变。
+    /// 地址未变化时直接返回；聊天室、私聊、通知或帖子实时连接仍在线时返回错误，
+    /// 需先断开连接，避免旧连接继续指向原服务器
+    pub async fn set_base_url(&self, base_url: &str) -> Result<(), FishPiError> {
+        let base_url = base_url.trim().trim_end_matches('/');
+        if base_url.is_empty() {
+            return Err(FishPiError::Validation("服务器地址不能为空".to_string()));
+        }
+        if self.api_client.base_url() == base_url {
+            return Ok(());
+        }
+        if self.has_live_connections().await {
+            return Err(FishPiError::Validation(
+                "存在活动的 WebSocket 连接，请先断开后再切换服务器地址".to_string(),
+            ));
+        }
+
+        self.api_client.set_base_url(base_url);
+        Ok(())
+    }
+
+    /// 是否存在活动的 WebSocket 连接
+    pub async fn has_live_connections(&self) -> bool {
+        self.chatroom.is_connected().await
+            || self.chat.has_live_connections().await
+            || self.notice.is_connected().await
+            || self.article.has_live_connections().await
+    }
+
+    /// 请求与 WebSocket 计数快照
+    ///
+    /// 包括请求总数、失败数、重试数、自动重连次数与收到的消息数，计数自客户端创建起累计
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.api_client.metrics().snapshot()
     }
 
     /// 获取当前认证令牌
@@ -205,12 +272,180 @@ impl FishPi {
     }
 
     /// 设置认证令牌
+    ///
+    /// 令牌变更会清除缓存的当前用户信息
     pub async fn set_token(&self, token: Option<String>) {
+        self.user.clear_current_user();
         self.api_client.set_token(token).await;
     }
 
+    /// 从文件加载令牌并开启令牌持久化
+    ///
+    /// - `path` 令牌文件路径，不存在或内容无效时不加载
+    ///
+    /// 之后登录、`set_token` 与退出登录都会同步更新该文件。返回是否加载到了令牌
+    pub async fn load_token_from(&self, path: impl Into<PathBuf>) -> bool {
+        self.user.clear_current_user();
+        self.api_client.enable_token_persistence(path.into()).await
+    }
+
+    /// 设置全局请求限流
+    ///
+    /// - `per_sec` 每秒允许的请求数，必须为有限正数
+    /// - `burst` 允许的最大突发请求数
+    pub async fn set_rate_limit(&self, per_sec: f64, burst: u32) -> Result<(), FishPiError> {
+        self.api_client.set_rate_limit(per_sec, burst).await
+    }
+
+    /// 设置未识别消息回调
+    ///
+    /// 聊天室、私聊、通知的 WebSocket 收到无法识别的消息时，以消息类型与原始 JSON 调用该回调。
+    /// 主要用于调试，便于记录服务端新增的消息格式。
+    pub async fn on_unknown<F>(&self, handler: F)
+    where
+        F: Fn(&str, serde_json::Value) + Send + Sync + 'static,
+    {
+        let mut slot = self.chatroom.unknown_handler.lock().await;
+        *slot = Some(Arc::new(handler));
+    }
+
+    /// 检查服务器是否可访问
+    ///
+    /// 用于连接前确认服务器状态，或排查错误配置的 `base_url`
+    pub async fn ping(&self) -> Response<ServerStatus> {
+        match self.api_client.ping().await {
+            Ok(status) if status.reachable => Response::success(status),
+            Ok(status) => Response {
+                success: false,
+                message: Some(format!("服务器响应异常: {}", self.api_client.base_url())),
+                data: Some(status),
+                error: None,
+            },
+            Err(err) => Response::error(&format!(
+                "无法连接服务器 {}: {}",
+                self.api_client.base_url(),
+                err
+            ))
+            .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
     /// 检查是否已登录
     pub async fn is_logged_in(&self) -> bool {
         self.api_client.get_token().await.is_some()
     }
 }
+
+/// FishPi 客户端构建器
+///
+/// ```no_run
+/// use fishpi_rust::FishPi;
+/// use std::time::Duration;
+///
+/// let client = FishPi::builder()
+///     .base_url("https://fishpi.cn")
+///     .token("your-api-key")
+///     .timeout(Duration::from_secs(10))
+///     .build()?;
+/// # Ok::<(), fishpi_rust::FishPiError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FishPiBuilder {
+    config: ClientConfig,
+    token: Option<String>,
+}
+
+impl FishPiBuilder {
+    /// 设置服务器地址
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.config.base_url = base_url.to_string();
+        self
+    }
+
+    /// 设置初始令牌
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// 设置单次请求的总超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    /// 设置请求携带的 User-Agent
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// 设置代理，覆盖 `HTTPS_PROXY` 等环境变量
+    ///
+    /// - `proxy` 代理地址，如 `http://127.0.0.1:7890`；地址无效时 `build` 返回错误
+    ///
+    /// HTTP 请求与 WebSocket 连接都会经过该代理，WebSocket 仅支持不带认证的 `http://` 代理
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.config.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// 构建客户端
+    ///
+    /// 代理地址无效时返回 `FishPiError::Validation`
+    pub fn build(self) -> Result<FishPi, FishPiError> {
+        let api_client = ApiClient::try_with_config(self.config)?.with_token(self.token);
+        Ok(FishPi::from_api_client(api_client))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockResponse, MockServer, client_for};
+
+    fn mock_fishpi(server: &MockServer) -> FishPi {
+        FishPi::from_api_client(client_for(&server.url))
+    }
+
+    #[tokio::test]
+    async fn ping_reports_latency_version_and_unreachable_status() {
+        let delay = Duration::from_millis(200);
+        let server = MockServer::start_with(vec![
+            MockResponse::new(200, "")
+                .header("x-version", "2.0.1")
+                .delay(delay),
+            MockResponse::new(503, ""),
+        ])
+        .await;
+        let client = mock_fishpi(&server);
+
+        let response = client.ping().await;
+        assert!(response.success);
+        let status = response.data.unwrap();
+        assert!(status.reachable);
+        assert!(status.latency_ms >= delay.as_millis() as u64);
+        assert_eq!(status.version.as_deref(), Some("2.0.1"));
+
+        let response = client.ping().await;
+        assert!(!response.success);
+        let status = response.data.unwrap();
+        assert!(!status.reachable);
+        assert_eq!(status.version, None);
+    }
+
+    #[test]
+    fn constructors_match_default_builder() {
+        let client = FishPi::new();
+        let built = FishPi::builder().build().unwrap();
+        assert_eq!(client.api_client.base_url(), built.api_client.base_url());
+        assert_eq!(
+            client.api_client.config().user_agent,
+            built.api_client.config().user_agent
+        );
+
+        let client = FishPi::with_base_url("https://example.com");
+        assert_eq!(client.api_client.base_url(), "https://example.com");
+        assert_eq!(client.api_client.config().base_url, "https://example.com");
+    }
+}
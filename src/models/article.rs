@@ -1118,7 +1118,7 @@ impl ArticleDetail {
             Ok(val) => Ok(val),
             Err(e) => {
                 let path_str = track.path().to_string();
-                println!("反序列化失败路径: {}", path_str);
+                log::debug!("反序列化失败路径: {}", path_str);
                 Err(serde::de::Error::custom(format!(
                     "路径: {}, 错误: {}",
                     path_str, e
@@ -1126,6 +1126,14 @@ impl ArticleDetail {
             }
         }
     }
+
+    /// 评论统计：评论数与最后评论者，无评论时返回 `(0, "")`
+    pub fn comment_stats(&self) -> (i32, String) {
+        if self.comment_cnt <= 0 {
+            return (0, String::new());
+        }
+        (self.comment_cnt, self.latest_cmter_name.clone())
+    }
 }
 
 impl Default for ArticleDetail {
@@ -1234,27 +1242,25 @@ impl ArticleList {
         let mut article_list = ArticleList::default();
 
         // 解析分页信息
-        if let Some(pagination) = data.get("pagination") {
-            if let Ok(p) = serde_json::from_value::<Pagination>(pagination.clone()) {
-                article_list.pagination = p;
-            }
+        if let Some(pagination) = data.get("pagination")
+            && let Ok(p) = serde_json::from_value::<Pagination>(pagination.clone())
+        {
+            article_list.pagination = p;
         }
 
         // 解析tag信息
-        if let Some(tag_data) = data.get("tag") {
-            if let Ok(t) = serde_json::from_value::<ArticleTag>(tag_data.clone()) {
-                article_list.tag = Some(t);
-            }
+        if let Some(tag_data) = data.get("tag")
+            && let Ok(t) = serde_json::from_value::<ArticleTag>(tag_data.clone())
+        {
+            article_list.tag = Some(t);
         }
 
         // 解析文章列表
         if let Some(Value::Array(arr)) = data.get("articles") {
             for article in arr.iter() {
-                if let Err(e) = ArticleDetail::from_json(article) {
-                    println!("ArticleDetail 反序列化失败: {e}");
-                }
-                if let Ok(detail) = ArticleDetail::from_json(article) {
-                    article_list.list.push(detail);
+                match ArticleDetail::from_json(article) {
+                    Ok(detail) => article_list.list.push(detail),
+                    Err(e) => log::warn!("ArticleDetail 反序列化失败: {e}"),
                 }
             }
         }
@@ -1263,6 +1269,53 @@ impl ArticleList {
     }
 }
 
+/// 帖子频道（`article-channel`）推送的事件
+#[derive(Debug, Clone)]
+pub enum ArticleNotify {
+    /// 新评论
+    NewComment(Box<ArticleComment>),
+    /// 在线浏览人数变化，`delta` 为 `1` 或 `-1`
+    ViewCountChange { article_id: String, delta: i32 },
+    /// 新的关注者
+    NewWatcher { user_name: String },
+    /// 未识别的消息
+    Unknown(Value),
+}
+
+impl From<&Value> for ArticleNotify {
+    fn from(data: &Value) -> Self {
+        let type_ = data
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        match type_ {
+            "comment" => match serde_json::from_value::<ArticleComment>(data.clone()) {
+                Ok(comment) => ArticleNotify::NewComment(Box::new(comment)),
+                Err(_) => ArticleNotify::Unknown(data.clone()),
+            },
+            "articleHeat" => ArticleNotify::ViewCountChange {
+                article_id: data
+                    .get("articleId")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                delta: match data.get("operation").and_then(|v| v.as_str()) {
+                    Some("-") => -1,
+                    _ => 1,
+                },
+            },
+            "watch" => ArticleNotify::NewWatcher {
+                user_name: data
+                    .get("userName")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            _ => ArticleNotify::Unknown(data.clone()),
+        }
+    }
+}
+
 /// 帖子列表查询类型
 pub struct ArticleListType;
 
@@ -1304,6 +1357,69 @@ impl ArticleListType {
     }
 }
 
+/// 热门帖子时间范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HotRange {
+    /// 今日
+    #[default]
+    Day,
+
+    /// 本周
+    Week,
+
+    /// 本月
+    Month,
+}
+
+impl HotRange {
+    /// 时间范围跨度
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            HotRange::Day => chrono::Duration::days(1),
+            HotRange::Week => chrono::Duration::weeks(1),
+            HotRange::Month => chrono::Duration::days(30),
+        }
+    }
+
+    /// 判断帖子是否在时间范围内
+    ///
+    /// - `article` 帖子详情
+    /// - `now` 当前时间
+    ///
+    /// 优先解析 `create_time`，解析失败时使用 `oId` 中的毫秒时间戳
+    pub fn contains(&self, article: &ArticleDetail, now: chrono::DateTime<chrono::Local>) -> bool {
+        match article_create_time(article) {
+            Some(time) => time <= now && now - time <= self.duration(),
+            None => false,
+        }
+    }
+}
+
+/// 解析帖子创建时间
+fn article_create_time(article: &ArticleDetail) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+
+    if let Ok(time) =
+        chrono::NaiveDateTime::parse_from_str(article.create_time.trim(), "%Y-%m-%d %H:%M:%S")
+        && let Some(time) = chrono::Local.from_local_datetime(&time).single()
+    {
+        return Some(time);
+    }
+
+    let millis = article.o_id.parse::<i64>().ok()?;
+    chrono::Local.timestamp_millis_opt(millis).single()
+}
+
+impl ArticleList {
+    /// 按时间范围过滤帖子
+    ///
+    /// - `range` 时间范围
+    /// - `now` 当前时间
+    pub fn retain_in_range(&mut self, range: HotRange, now: chrono::DateTime<chrono::Local>) {
+        self.list.retain(|article| range.contains(article, now));
+    }
+}
+
 /// 帖子列表请求参数
 #[derive(Debug, Clone)]
 pub struct ArticleListParams {
@@ -1493,3 +1609,31 @@ where
         _ => Ok(false),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detail(data: Value) -> ArticleDetail {
+        ArticleDetail::from_json(&data).unwrap()
+    }
+
+    #[test]
+    fn comment_stats_is_empty_without_comments() {
+        assert_eq!(ArticleDetail::default().comment_stats(), (0, String::new()));
+        let stale = detail(serde_json::json!({
+            "articleCommentCount": 0,
+            "articleLatestCmterName": "alice",
+        }));
+        assert_eq!(stale.comment_stats(), (0, String::new()));
+    }
+
+    #[test]
+    fn comment_stats_reports_count_and_latest_commenter() {
+        let article = detail(serde_json::json!({
+            "articleCommentCount": 12,
+            "articleLatestCmterName": "alice",
+        }));
+        assert_eq!(article.comment_stats(), (12, "alice".to_string()));
+    }
+}
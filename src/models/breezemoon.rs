@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::FishPiError;
+
 /// 清风明月列表返回
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BreezemoonList {
@@ -15,7 +17,7 @@ pub struct BreezemoonList {
 }
 
 /// 清风明月返回
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Breezemoon {
     /// 作者头像
     #[serde(rename = "breezemoonAuthorThumbnailURL48")]
@@ -59,6 +61,67 @@ pub struct BreezemoonPost {
 pub struct BreezemoonResponse {
     /// 状态码，0为成功
     pub code: i32,
-    /// 返回数据
+    /// 错误信息，如内容长度不足
+    #[serde(default)]
+    pub msg: Option<String>,
+    /// 返回数据，失败时为默认值
+    #[serde(default)]
     pub data: Breezemoon,
 }
+
+impl BreezemoonResponse {
+    /// 转为清风明月ID，失败时以服务端错误信息构造错误
+    ///
+    /// - `action` 操作名称，服务端未返回错误信息时使用
+    ///
+    /// 成功但响应中没有清风明月ID时返回 [`FishPiError::Parse`]
+    pub fn into_id(self, action: &str) -> anyhow::Result<String> {
+        if self.code != 0 {
+            let msg = self
+                .msg
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| format!("{}失败", action));
+            return Err(anyhow::anyhow!(msg));
+        }
+        if self.data.id.is_empty() {
+            return Err(FishPiError::Parse(format!("{}: 响应缺少清风明月ID", action)).into());
+        }
+        Ok(self.data.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: &str) -> BreezemoonResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn into_id_returns_server_id() {
+        let response = response(
+            r#"{"code":0,"data":{"oId":"123","breezemoonAuthorThumbnailURL48":"","breezemoonAuthorName":"me","breezemoonContent":"hi"}}"#,
+        );
+        assert_eq!(response.into_id("发布清风明月").unwrap(), "123");
+    }
+
+    #[test]
+    fn into_id_rejects_missing_id() {
+        let err = response(r#"{"code":0}"#)
+            .into_id("发布清风明月")
+            .unwrap_err();
+        assert!(matches!(
+            FishPiError::from_anyhow(&err),
+            FishPiError::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn into_id_reports_server_message() {
+        let err = response(r#"{"code":-1,"msg":"内容过短"}"#)
+            .into_id("发布清风明月")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "内容过短");
+    }
+}
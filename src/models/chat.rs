@@ -1,5 +1,36 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+
+static REPLY_TO_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"##### 引用[^\n]*\[↩\]\(#chat([0-9A-Za-z]+)").unwrap());
+
+/// 按引用约定拼接回复内容
+///
+/// 服务端私聊没有原生回复字段，沿用聊天室的 Markdown 引用格式，
+/// 在消息末尾附加指向原消息的锚点
+///
+/// - `reply_to` 被回复消息ID
+/// - `content` 回复内容
+pub fn format_chat_reply(reply_to: &str, content: &str) -> String {
+    format!(
+        "{}\n\n##### 引用 [↩](#chat{} \"跳转至原消息\")",
+        content, reply_to
+    )
+}
+
+/// 从消息内容中解析被回复的消息ID
+///
+/// 回复标记附加在消息末尾，内容本身带有引用时取最后一处
+pub fn parse_chat_reply_to(content: &str) -> Option<String> {
+    REPLY_TO_RE
+        .captures_iter(content)
+        .last()
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
 
 /// 私聊消息类型
 pub struct ChatMessageType;
@@ -48,10 +79,24 @@ pub struct ChatData {
     /// 接收者用户名
     #[serde(rename = "receiverUserName")]
     pub receiver_user_name: String,
+    /// 被回复的消息ID
+    #[serde(rename = "replyTo", default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
 }
 
 impl From<&Value> for ChatData {
     fn from(data: &Value) -> Self {
+        let markdown = data
+            .get("markdown")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let reply_to = data
+            .get("replyTo")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| parse_chat_reply_to(&markdown));
+
         Self {
             to_id: data
                 .get("toId")
@@ -73,11 +118,7 @@ impl From<&Value> for ChatData {
                 .and_then(|v| v.as_str())
                 .unwrap_or_default()
                 .to_string(),
-            markdown: data
-                .get("markdown")
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string(),
+            markdown,
             receiver_avatar: data
                 .get("receiverAvatar")
                 .and_then(|v| v.as_str())
@@ -114,6 +155,7 @@ impl From<&Value> for ChatData {
                 .and_then(|v| v.as_str())
                 .unwrap_or_default()
                 .to_string(),
+            reply_to,
         }
     }
 }
@@ -128,6 +170,45 @@ impl ChatData {
     }
 }
 
+/// 未读私聊消息
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChatUnread {
+    /// 未读消息总数
+    pub count: u32,
+    /// 未读消息预览
+    pub messages: Vec<ChatData>,
+}
+
+impl From<&Value> for ChatUnread {
+    /// 从 `chat/has-unread` 的完整响应解析，缺少 `count` 时取预览条数
+    fn from(data: &Value) -> Self {
+        let messages: Vec<ChatData> = data
+            .get("data")
+            .and_then(|v| v.as_array())
+            .map(|list| list.iter().map(ChatData::from).collect())
+            .unwrap_or_default();
+        let count = data
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .map(|count| count as u32)
+            .unwrap_or(messages.len() as u32);
+        Self { count, messages }
+    }
+}
+
+impl ChatUnread {
+    /// 按发送者统计未读消息数
+    pub fn counts_by_sender(&self) -> HashMap<String, u32> {
+        let mut counts = HashMap::new();
+        for message in &self.messages {
+            if !message.sender_user_name.is_empty() {
+                *counts.entry(message.sender_user_name.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+}
+
 /// 私聊通知
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChatNotice {
@@ -285,6 +366,7 @@ impl Default for ChatData {
             sender_user_name: String::new(),
             content: String::new(),
             receiver_user_name: String::new(),
+            reply_to: None,
         }
     }
 }
@@ -335,3 +417,28 @@ impl MessageInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_reply_round_trips() {
+        let content = format_chat_reply("1700000000123", "好的");
+        assert!(content.starts_with("好的\n\n"));
+        assert_eq!(
+            parse_chat_reply_to(&content).as_deref(),
+            Some("1700000000123")
+        );
+        assert_eq!(parse_chat_reply_to("好的"), None);
+    }
+
+    #[test]
+    fn chat_reply_to_uses_trailing_marker_when_content_quotes_a_reply() {
+        let quoted = format_chat_reply("old1", "原消息");
+        let content = format_chat_reply("new2", &quoted);
+
+        assert_eq!(parse_chat_reply_to(&content).as_deref(), Some("new2"));
+        assert_eq!(parse_chat_reply_to(&quoted).as_deref(), Some("old1"));
+    }
+}
@@ -1,7 +1,14 @@
+use crate::api::client::ConnectionState;
+use crate::error::FishPiError;
 use crate::models::redpacket::{RedPacketMessage, RedPacketStatusMsg};
+use crate::models::user::{MetalList, parse_metals};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
+use std::collections::HashSet;
 use std::fmt;
+use std::time::SystemTime;
 
 // 客户端类型常量
 pub struct ClientType;
@@ -34,6 +41,11 @@ pub struct ChatContentType;
 impl ChatContentType {
     pub const MARKDOWN: &'static str = "md";
     pub const HTML: &'static str = "html";
+
+    /// 是否为支持的内容类型
+    pub fn is_valid(content_type: &str) -> bool {
+        matches!(content_type, Self::MARKDOWN | Self::HTML)
+    }
 }
 
 // 查询模式
@@ -68,6 +80,73 @@ impl ChatRoomMessageType {
     pub const CUSTOM: &'static str = "customMessage";
     pub const WEATHER: &'static str = "weather";
     pub const MUSIC: &'static str = "music";
+    /// 在线用户变化，需通过 `ChatroomService::set_online_diff` 开启
+    pub const ONLINE_CHANGE: &'static str = "onlineChange";
+}
+
+/// 从聊天室接口响应中解析慢速模式间隔（秒）
+///
+/// 读取 `slowMode`/`slowModeInterval` 字段（顶层或 `data` 内），
+/// 服务端未返回该字段时为 `None`，不从提示信息中猜测
+pub fn parse_slow_mode(response: &Value) -> Option<u64> {
+    const KEYS: [&str; 2] = ["slowMode", "slowModeInterval"];
+
+    let value = KEYS.iter().find_map(|key| {
+        response
+            .get(*key)
+            .or_else(|| response.get("data").and_then(|d| d.get(*key)))
+    })?;
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse::<u64>().ok(),
+        _ => None,
+    }
+}
+
+/// 从聊天室公告接口响应中解析置顶公告
+///
+/// `data` 可以是字符串，或包含 `content`/`notice` 字段的对象；
+/// 缺失或内容为空时返回 `None`
+pub fn parse_room_notice(response: &Value) -> Option<String> {
+    let data = response.get("data")?;
+    let notice = match data {
+        Value::String(s) => s.as_str(),
+        Value::Object(_) => data
+            .get("content")
+            .or_else(|| data.get("notice"))
+            .and_then(|v| v.as_str())?,
+        _ => return None,
+    };
+    let notice = notice.trim();
+    (!notice.is_empty()).then(|| notice.to_string())
+}
+
+/// 用户显示名称模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayNameMode {
+    /// 昵称(用户名)，无昵称时仅显示用户名
+    #[default]
+    NicknameWithUser,
+    /// 仅昵称，无昵称时显示用户名
+    NicknameOnly,
+    /// 仅用户名
+    UsernameOnly,
+}
+
+impl DisplayNameMode {
+    /// 根据用户名与昵称生成显示名称
+    ///
+    /// 仅由空白组成的昵称视为无昵称，其余昵称按原样显示
+    pub fn format(&self, user_name: &str, nickname: Option<&str>) -> String {
+        let nickname = nickname.filter(|n| !n.trim().is_empty());
+        match (self, nickname) {
+            (DisplayNameMode::NicknameWithUser, Some(nickname)) => {
+                format!("{}({})", nickname, user_name)
+            }
+            (DisplayNameMode::NicknameOnly, Some(nickname)) => nickname.to_string(),
+            _ => user_name.to_string(),
+        }
+    }
 }
 
 // 特殊消息内容枚举
@@ -80,6 +159,42 @@ pub enum SpecialMessageContent {
     None,
 }
 
+/// 聊天室消息种类
+///
+/// 由特殊消息内容与消息类型推导，见 [`ChatRoomMessage::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChatMessageKind {
+    /// 普通文本消息
+    Normal,
+    /// 红包
+    RedPacket,
+    /// 天气
+    Weather,
+    /// 音乐
+    Music,
+    /// 弹幕
+    Barrage,
+    /// 自定义消息
+    Custom,
+    /// 撤回
+    Revoke,
+}
+
+impl ChatMessageKind {
+    /// 对应的 [`ChatRoomMessageType`] 类型字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatMessageKind::Normal => ChatRoomMessageType::MSG,
+            ChatMessageKind::RedPacket => ChatRoomMessageType::RED_PACKET,
+            ChatMessageKind::Weather => ChatRoomMessageType::WEATHER,
+            ChatMessageKind::Music => ChatRoomMessageType::MUSIC,
+            ChatMessageKind::Barrage => ChatRoomMessageType::BARRAGER,
+            ChatMessageKind::Custom => ChatRoomMessageType::CUSTOM,
+            ChatMessageKind::Revoke => ChatRoomMessageType::REVOKE,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ChatRoomMessage {
     pub oid: String,
@@ -94,9 +209,62 @@ pub struct ChatRoomMessage {
     pub md: Option<String>,
     pub client: Option<String>,
     pub special_content: SpecialMessageContent,
+    /// 客户端收到消息的时间（毫秒时间戳），仅 WebSocket 推送的消息有值
+    pub received_at: Option<i64>,
+    /// 延迟解析模式下的特殊消息内容缓存，为 None 表示已在反序列化时解析
+    lazy_special: Option<OnceCell<SpecialMessageContent>>,
+}
+
+/// 延迟解析特殊消息内容的聊天室消息
+///
+/// 反序列化时不解析特殊消息内容（红包、天气、音乐），首次通过 `special()` 等方法访问时才解析；
+/// 此时 `special_content` 字段与 `message_type` 不会被自动填充。
+/// 直接反序列化 [`ChatRoomMessage`] 时总是立即解析
+#[derive(Debug, Clone)]
+pub struct LazyChatRoomMessage(pub ChatRoomMessage);
+
+impl<'de> Deserialize<'de> for LazyChatRoomMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = RawChatRoomMessage::deserialize(deserializer)?;
+        Ok(Self(raw.into_message(true)))
+    }
+}
+
+/// 解析以 JSON 字符串形式保存的 `sysMetal`
+fn metals_from_raw(raw: Option<&str>) -> MetalList {
+    raw.and_then(|s| serde_json::from_str::<Value>(s).ok())
+        .map(|v| parse_metals(&v))
+        .unwrap_or_default()
 }
 
 impl ChatRoomMessage {
+    /// 解析用户徽章，`sysMetal` 缺失或格式错误时返回空列表
+    pub fn metals(&self) -> MetalList {
+        metals_from_raw(self.sys_metal.as_deref())
+    }
+
+    /// 获取用于展示的时间
+    ///
+    /// `time` 为空或无法解析时退回到客户端接收时间；没有接收时间时原样返回 `time`
+    pub fn display_time(&self) -> String {
+        let time = self.time.trim();
+        if NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").is_ok() {
+            return time.to_string();
+        }
+        self.received_at
+            .and_then(|ts| Local.timestamp_millis_opt(ts).single())
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| time.to_string())
+    }
+
+    /// 记录客户端接收时间
+    pub fn mark_received(&mut self) {
+        self.received_at = Some(Local::now().timestamp_millis());
+    }
+
     // 获取 Markdown 内容，如果为空则返回空字符串
     pub fn md_text(&self) -> &str {
         self.md.as_deref().unwrap_or("")
@@ -109,30 +277,74 @@ impl ChatRoomMessage {
 
     // 获取用户全名（昵称+用户名）
     pub fn all_name(&self) -> String {
-        match &self.user_nickname {
-            Some(nickname) if !nickname.is_empty() => format!("{}({})", nickname, self.user_name),
-            _ => self.user_name.clone(),
+        self.display_name(DisplayNameMode::default())
+    }
+
+    /// 按指定模式获取显示名称
+    pub fn display_name(&self, mode: DisplayNameMode) -> String {
+        mode.format(&self.user_name, self.user_nickname.as_deref())
+    }
+
+    /// 获取特殊消息内容，延迟解析模式下首次访问时解析
+    pub fn special(&self) -> &SpecialMessageContent {
+        match &self.lazy_special {
+            Some(cell) => cell.get_or_init(|| {
+                let mut parsed = ChatRoomMessage {
+                    content: self.content.clone(),
+                    md: self.md.clone(),
+                    ..Default::default()
+                };
+                parsed.parse_special_content();
+                parsed.special_content
+            }),
+            None => &self.special_content,
+        }
+    }
+
+    /// 特殊消息内容是否已解析
+    pub fn is_special_parsed(&self) -> bool {
+        self.lazy_special
+            .as_ref()
+            .is_none_or(|cell| cell.get().is_some())
+    }
+
+    /// 消息种类
+    ///
+    /// 优先按特殊消息内容判断，延迟解析模式下会触发解析；
+    /// 不是特殊消息时按 `message_type` 判断，未知类型视为普通消息
+    pub fn kind(&self) -> ChatMessageKind {
+        match self.special() {
+            SpecialMessageContent::RedPacket(_) => return ChatMessageKind::RedPacket,
+            SpecialMessageContent::Weather(_) => return ChatMessageKind::Weather,
+            SpecialMessageContent::Music(_) => return ChatMessageKind::Music,
+            SpecialMessageContent::None => {}
+        }
+        match self.message_type.as_deref() {
+            Some(ChatRoomMessageType::BARRAGER) => ChatMessageKind::Barrage,
+            Some(ChatRoomMessageType::CUSTOM) => ChatMessageKind::Custom,
+            Some(ChatRoomMessageType::REVOKE) => ChatMessageKind::Revoke,
+            _ => ChatMessageKind::Normal,
         }
     }
 
     // 判断是否为红包消息
     pub fn is_redpacket(&self) -> bool {
-        matches!(self.special_content, SpecialMessageContent::RedPacket(_))
+        matches!(self.special(), SpecialMessageContent::RedPacket(_))
     }
 
     // 判断是否为天气消息
     pub fn is_weather(&self) -> bool {
-        matches!(self.special_content, SpecialMessageContent::Weather(_))
+        matches!(self.special(), SpecialMessageContent::Weather(_))
     }
 
     // 判断是否为音乐消息
     pub fn is_music(&self) -> bool {
-        matches!(self.special_content, SpecialMessageContent::Music(_))
+        matches!(self.special(), SpecialMessageContent::Music(_))
     }
 
     // 获取红包消息内容
     pub fn redpacket(&self) -> Option<&RedPacketMessage> {
-        match &self.special_content {
+        match self.special() {
             SpecialMessageContent::RedPacket(redpacket) => Some(redpacket),
             _ => None,
         }
@@ -140,7 +352,7 @@ impl ChatRoomMessage {
 
     // 获取天气消息内容
     pub fn weather(&self) -> Option<&WeatherMsg> {
-        match &self.special_content {
+        match self.special() {
             SpecialMessageContent::Weather(weather) => Some(weather),
             _ => None,
         }
@@ -148,7 +360,7 @@ impl ChatRoomMessage {
 
     // 获取音乐消息内容
     pub fn music(&self) -> Option<&MusicMsg> {
-        match &self.special_content {
+        match self.special() {
             SpecialMessageContent::Music(music) => Some(music),
             _ => None,
         }
@@ -156,15 +368,14 @@ impl ChatRoomMessage {
 
     pub fn parse_special_content(&mut self) {
         // 先检查md字段是否包含天气消息
-        if let Some(md_content) = &self.md {
-            if md_content.contains("\"msgType\":\"weather\"") {
-                if let Ok(md_json) = serde_json::from_str::<serde_json::Value>(md_content) {
-                    let weather = WeatherMsg::from(&md_json);
-                    self.special_content = SpecialMessageContent::Weather(weather);
-                    self.message_type = Some(ChatRoomMessageType::WEATHER.to_string());
-                    return;
-                }
-            }
+        if let Some(md_content) = &self.md
+            && md_content.contains("\"msgType\":\"weather\"")
+            && let Ok(md_json) = serde_json::from_str::<serde_json::Value>(md_content)
+        {
+            let weather = WeatherMsg::from(&md_json);
+            self.special_content = SpecialMessageContent::Weather(weather);
+            self.message_type = Some(ChatRoomMessageType::WEATHER.to_string());
+            return;
         }
 
         // 尝试将内容解析为JSON
@@ -273,51 +484,63 @@ impl<'de> Deserialize<'de> for ChatRoomMessage {
     where
         D: serde::Deserializer<'de>,
     {
-        #[derive(serde::Deserialize)]
-        struct ChatRoomMessageTemp {
-            #[serde(rename = "oId")]
-            oid: String,
-            #[serde(rename = "userOId")]
-            user_oid: i64,
-            #[serde(rename = "userName")]
-            user_name: String,
-            #[serde(rename = "userAvatarURL")]
-            user_avatar_url: String,
-            #[serde(rename = "userNickname")]
-            user_nickname: Option<String>,
-            #[serde(rename = "sysMetal")]
-            sys_metal: Option<String>,
-            content: String,
-            #[serde(rename = "time")]
-            time: String,
-            #[serde(rename = "type", default)]
-            message_type: Option<String>,
-            #[serde(default)]
-            md: Option<String>,
-            #[serde(default)]
-            client: Option<String>,
-        }
+        let raw = RawChatRoomMessage::deserialize(deserializer)?;
+        Ok(raw.into_message(false))
+    }
+}
 
-        let temp = ChatRoomMessageTemp::deserialize(deserializer)?;
+/// 聊天室消息的原始字段
+#[derive(serde::Deserialize)]
+struct RawChatRoomMessage {
+    #[serde(rename = "oId")]
+    oid: String,
+    #[serde(rename = "userOId")]
+    user_oid: i64,
+    #[serde(rename = "userName")]
+    user_name: String,
+    #[serde(rename = "userAvatarURL")]
+    user_avatar_url: String,
+    #[serde(rename = "userNickname")]
+    user_nickname: Option<String>,
+    #[serde(rename = "sysMetal")]
+    sys_metal: Option<String>,
+    content: String,
+    #[serde(rename = "time", default)]
+    time: String,
+    #[serde(rename = "type", default)]
+    message_type: Option<String>,
+    #[serde(default)]
+    md: Option<String>,
+    #[serde(default)]
+    client: Option<String>,
+}
 
+impl RawChatRoomMessage {
+    /// 转换为消息，`lazy` 为 true 时延迟解析特殊消息内容
+    fn into_message(self, lazy: bool) -> ChatRoomMessage {
         let mut message = ChatRoomMessage {
-            oid: temp.oid,
-            user_oid: temp.user_oid,
-            user_name: temp.user_name,
-            user_avatar_url: temp.user_avatar_url,
-            user_nickname: temp.user_nickname,
-            sys_metal: temp.sys_metal,
-            content: temp.content,
-            time: temp.time,
-            message_type: temp.message_type,
-            md: temp.md,
-            client: temp.client,
+            oid: self.oid,
+            user_oid: self.user_oid,
+            user_name: self.user_name,
+            user_avatar_url: self.user_avatar_url,
+            user_nickname: self.user_nickname,
+            sys_metal: self.sys_metal,
+            content: self.content,
+            time: self.time,
+            message_type: self.message_type,
+            md: self.md,
+            client: self.client,
             special_content: SpecialMessageContent::None,
+            received_at: None,
+            lazy_special: None,
         };
 
-        message.parse_special_content();
-
-        Ok(message)
+        if lazy {
+            message.lazy_special = Some(OnceCell::new());
+        } else {
+            message.parse_special_content();
+        }
+        message
     }
 }
 
@@ -336,6 +559,8 @@ impl Default for ChatRoomMessage {
             md: None,
             client: None,
             special_content: SpecialMessageContent::None,
+            received_at: None,
+            lazy_special: None,
         }
     }
 }
@@ -354,20 +579,47 @@ pub struct ChatRoomUser {
     pub sys_metal: Option<String>,
     #[serde(rename = "homePage", default)]
     pub home_page: Option<String>,
-    #[serde(rename = "userAvatarURL20", default)]
+    #[serde(rename = "userAvatarURL20", alias = "avatarURL20", default)]
     pub user_avatar_url_20: Option<String>,
-    #[serde(rename = "userAvatarURL48", default)]
+    #[serde(rename = "userAvatarURL48", alias = "avatarURL48", default)]
     pub user_avatar_url_48: Option<String>,
-    #[serde(rename = "userAvatarURL210", default)]
+    #[serde(rename = "userAvatarURL210", alias = "avatarURL210", default)]
     pub user_avatar_url_210: Option<String>,
 }
 
+/// 比较前后两次在线列表，按用户名返回新加入与离开的用户
+pub fn diff_online_users(
+    previous: &[ChatRoomUser],
+    current: &[ChatRoomUser],
+) -> (Vec<ChatRoomUser>, Vec<ChatRoomUser>) {
+    let previous_names: HashSet<&str> = previous.iter().map(|u| u.user_name.as_str()).collect();
+    let current_names: HashSet<&str> = current.iter().map(|u| u.user_name.as_str()).collect();
+    let joined = current
+        .iter()
+        .filter(|u| !previous_names.contains(u.user_name.as_str()))
+        .cloned()
+        .collect();
+    let left = previous
+        .iter()
+        .filter(|u| !current_names.contains(u.user_name.as_str()))
+        .cloned()
+        .collect();
+    (joined, left)
+}
+
 impl ChatRoomUser {
+    /// 解析用户徽章，`sysMetal` 缺失或格式错误时返回空列表
+    pub fn metals(&self) -> MetalList {
+        metals_from_raw(self.sys_metal.as_deref())
+    }
+
     pub fn all_name(&self) -> String {
-        match &self.user_nickname {
-            Some(nickname) if !nickname.is_empty() => format!("{}({})", nickname, self.user_name),
-            _ => self.user_name.clone(),
-        }
+        self.display_name(DisplayNameMode::default())
+    }
+
+    /// 按指定模式获取显示名称
+    pub fn display_name(&self, mode: DisplayNameMode) -> String {
+        mode.format(&self.user_name, self.user_nickname.as_deref())
     }
 }
 
@@ -472,11 +724,11 @@ pub enum WebSocketMessage {
         who_give: String,
         #[serde(rename = "whoGot")]
         who_got: String,
-        #[serde(rename = "userAvatarURL20", default)]
+        #[serde(rename = "userAvatarURL20", alias = "avatarURL20", default)]
         avatar_url_20: Option<String>,
-        #[serde(rename = "userAvatarURL48", default)]
+        #[serde(rename = "userAvatarURL48", alias = "avatarURL48", default)]
         avatar_url_48: Option<String>,
-        #[serde(rename = "userAvatarURL210", default)]
+        #[serde(rename = "userAvatarURL210", alias = "avatarURL210", default)]
         avatar_url_210: Option<String>,
     },
     #[serde(rename = "heartbeat")]
@@ -487,12 +739,26 @@ pub enum WebSocketMessage {
     SimpleHeartbeat,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 消息来源，显示在聊天室消息旁的客户端标识
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatSource {
     pub client: String,
     pub version: String,
 }
 
+impl ChatSource {
+    /// 创建客户端来源
+    ///
+    /// - `client` 客户端名称，可使用 [`ClientType`] 中的常量或自定义名称
+    /// - `version` 客户端版本
+    pub fn new(client: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            client: client.into(),
+            version: version.into(),
+        }
+    }
+}
+
 impl Default for ChatSource {
     fn default() -> Self {
         Self {
@@ -508,6 +774,128 @@ impl fmt::Display for ChatSource {
     }
 }
 
+/// 聊天室消息发送结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentMessage {
+    /// 新消息的 oid，接口未返回且未等到回显时为 `None`
+    pub oid: Option<String>,
+    /// 实际发出请求的时间
+    pub sent_at: SystemTime,
+}
+
+/// 弹幕颜色
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarrageColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 不透明度，范围 0.0 ~ 1.0
+    pub a: f32,
+}
+
+impl Default for BarrageColor {
+    fn default() -> Self {
+        Self::from_rgba(255, 255, 255, 1.0)
+    }
+}
+
+impl BarrageColor {
+    /// 由 RGBA 分量创建颜色，不透明度超出范围时截断到 0.0 ~ 1.0
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: f32) -> Self {
+        Self {
+            r,
+            g,
+            b,
+            a: a.clamp(0.0, 1.0),
+        }
+    }
+
+    /// 解析十六进制颜色，支持 `#RGB`、`#RRGGBB` 与 `#RRGGBBAA`，`#` 可省略
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        match hex.len() {
+            3 => {
+                let mut rgb = hex.chars().map(|c| c.to_digit(16).map(|v| (v * 17) as u8));
+                Some(Self::from_rgba(
+                    rgb.next()??,
+                    rgb.next()??,
+                    rgb.next()??,
+                    1.0,
+                ))
+            }
+            6 => Some(Self::from_rgba(channel(0)?, channel(2)?, channel(4)?, 1.0)),
+            8 => Some(Self::from_rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)? as f32 / 255.0,
+            )),
+            _ => None,
+        }
+    }
+
+    /// 解析颜色字符串，支持十六进制与 `rgb(...)`、`rgba(...)` 格式
+    pub fn parse(color: &str) -> Option<Self> {
+        let color = color.trim();
+        let inner = color
+            .strip_prefix("rgba(")
+            .or_else(|| color.strip_prefix("rgb("))
+            .and_then(|s| s.strip_suffix(')'));
+        let Some(inner) = inner else {
+            return Self::from_hex(color);
+        };
+
+        let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+        if parts.len() < 3 {
+            return None;
+        }
+        let a = match parts.get(3) {
+            Some(a) => a.parse::<f32>().ok()?,
+            None => 1.0,
+        };
+        Some(Self::from_rgba(
+            parts[0].parse().ok()?,
+            parts[1].parse().ok()?,
+            parts[2].parse().ok()?,
+            a,
+        ))
+    }
+}
+
+impl fmt::Display for BarrageColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+impl std::str::FromStr for BarrageColor {
+    type Err = FishPiError;
+
+    /// 无法解析时返回 [`FishPiError::Validation`]
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        Self::parse(color)
+            .ok_or_else(|| FishPiError::Validation(format!("无法解析弹幕颜色: {}", color)))
+    }
+}
+
+impl TryFrom<&str> for BarrageColor {
+    type Error = FishPiError;
+
+    fn try_from(color: &str) -> Result<Self, Self::Error> {
+        color.parse()
+    }
+}
+
+impl From<(u8, u8, u8)> for BarrageColor {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::from_rgba(r, g, b, 1.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BarragerMsg {
     #[serde(rename = "userName")]
@@ -530,10 +918,17 @@ pub struct BarragerMsg {
 
 impl BarragerMsg {
     pub fn all_name(&self) -> String {
-        match &self.user_nickname {
-            Some(nickname) if !nickname.is_empty() => format!("{}({})", nickname, self.user_name),
-            _ => self.user_name.clone(),
-        }
+        self.display_name(DisplayNameMode::default())
+    }
+
+    /// 按指定模式获取显示名称
+    pub fn display_name(&self, mode: DisplayNameMode) -> String {
+        mode.format(&self.user_name, self.user_nickname.as_deref())
+    }
+
+    /// 解析弹幕颜色，格式无法识别时返回 `None`
+    pub fn color(&self) -> Option<BarrageColor> {
+        BarrageColor::parse(&self.barrager_color)
     }
 }
 
@@ -809,47 +1204,61 @@ impl WeatherMsg {
         result
     }
 
+    /// 解析多日天气数据
+    ///
+    /// 各字段按逗号分割并忽略空元素（如尾部多余的逗号），字段数量不一致时按最短的字段截断并记录警告；
+    /// 需要感知截断时使用 [`WeatherMsg::data_checked`]
     pub fn data(&self) -> Vec<WeatherMsgData> {
-        if self.dates.is_empty()
-            || self.codes.is_empty()
-            || self.min_temps.is_empty()
-            || self.max_temps.is_empty()
-        {
-            return Vec::new();
+        let fields = self.split_fields();
+        let lens = fields.each_ref().map(|field| field.len());
+        let min_len = lens.iter().copied().min().unwrap_or(0);
+        if lens.iter().any(|&len| len != min_len) {
+            log::warn!(
+                "天气数据字段数量不一致(日期{}、天气{}、最低温{}、最高温{})，按 {} 天截断",
+                lens[0],
+                lens[1],
+                lens[2],
+                lens[3],
+                min_len
+            );
         }
+        Self::zip_fields(&fields, min_len)
+    }
 
-        // 安全地分割字符串，处理可能的尾部空字符串
-        let dates: Vec<&str> = self.dates.split(',').collect();
-        let codes: Vec<&str> = self.codes.split(',').collect();
-        let min_temps: Vec<&str> = self.min_temps.split(',').collect();
-        let max_temps: Vec<&str> = self.max_temps.split(',').collect();
-
-        // 计算最小长度，避免索引越界
-        let min_len = dates
-            .len()
-            .min(codes.len())
-            .min(min_temps.len())
-            .min(max_temps.len());
-
-        if min_len == 0 {
-            return Vec::new();
+    /// 解析多日天气数据，字段数量不一致时返回错误而不是截断
+    pub fn data_checked(&self) -> Result<Vec<WeatherMsgData>, FishPiError> {
+        let fields = self.split_fields();
+        let lens = fields.each_ref().map(|field| field.len());
+        if lens.iter().any(|&len| len != lens[0]) {
+            return Err(FishPiError::Parse(format!(
+                "天气数据字段数量不一致: 日期{}、天气{}、最低温{}、最高温{}",
+                lens[0], lens[1], lens[2], lens[3]
+            )));
         }
+        Ok(Self::zip_fields(&fields, lens[0]))
+    }
 
-        let mut result = Vec::with_capacity(min_len);
-        for i in 0..min_len {
-            // 安全地解析温度值
-            let min_temp = min_temps[i].trim().parse::<f64>().unwrap_or(0.0);
-
-            let max_temp = max_temps[i].trim().parse::<f64>().unwrap_or(0.0);
+    /// 按逗号分割日期、天气代码、最低温、最高温，忽略空元素
+    fn split_fields(&self) -> [Vec<&str>; 4] {
+        [&self.dates, &self.codes, &self.min_temps, &self.max_temps].map(|field| {
+            field
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+    }
 
-            result.push(WeatherMsgData {
-                date: dates[i].trim().to_string(),
-                code: codes[i].trim().to_string(),
-                min: min_temp,
-                max: max_temp,
-            });
-        }
-        result
+    fn zip_fields(fields: &[Vec<&str>; 4], len: usize) -> Vec<WeatherMsgData> {
+        let [dates, codes, min_temps, max_temps] = fields;
+        (0..len)
+            .map(|i| WeatherMsgData {
+                date: dates[i].to_string(),
+                code: codes[i].to_string(),
+                min: min_temps[i].parse::<f64>().unwrap_or(0.0),
+                max: max_temps[i].parse::<f64>().unwrap_or(0.0),
+            })
+            .collect()
     }
 }
 
@@ -932,6 +1341,9 @@ impl fmt::Display for WeatherMsg {
     }
 }
 
+/// 网易云音乐外链播放地址
+const NETEASE_OUTER_URL: &str = "https://music.163.com/song/media/outer/url";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MusicMsg {
     #[serde(rename = "type")]
@@ -956,6 +1368,69 @@ impl Default for MusicMsg {
     }
 }
 
+impl MusicMsg {
+    /// 创建音乐消息
+    ///
+    /// - `title` 歌曲名
+    /// - `source` 音频地址
+    /// - `cover_url` 封面地址
+    /// - `from` 来源说明
+    pub fn new(title: &str, source: &str, cover_url: &str, from: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            source: source.to_string(),
+            cover_url: cover_url.to_string(),
+            from: from.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 校验必填字段，歌曲名与音频地址不能为空
+    pub fn validate(&self) -> Result<(), FishPiError> {
+        if self.title.trim().is_empty() {
+            return Err(FishPiError::Validation("music title empty".to_string()));
+        }
+        if self.source.trim().is_empty() {
+            return Err(FishPiError::Validation("music source empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 解析可播放的音频地址
+    ///
+    /// 来源为网易云音乐且 `source` 为纯数字歌曲 id 时，拼接网易云外链播放地址；
+    /// 其余情况原样返回 `source`，为空时返回 `None`。不发起网络请求
+    pub fn resolve_url(&self) -> Option<String> {
+        let source = self.source.trim();
+        if source.is_empty() {
+            return None;
+        }
+        if self.is_netease() && source.chars().all(|c| c.is_ascii_digit()) {
+            return Some(format!("{}?id={}.mp3", NETEASE_OUTER_URL, source));
+        }
+        Some(source.to_string())
+    }
+
+    /// 来源是否为网易云音乐
+    fn is_netease(&self) -> bool {
+        let from = self.from.to_lowercase();
+        ["netease", "163", "网易"].iter().any(|k| from.contains(k))
+    }
+
+    /// 序列化为聊天室消息内容（内联 JSON）
+    pub fn to_content(&self) -> String {
+        json!({
+            "msgType": "music",
+            "type": "music",
+            "title": self.title,
+            "source": self.source,
+            "coverURL": self.cover_url,
+            "from": self.from,
+        })
+        .to_string()
+    }
+}
+
 impl From<&Value> for MusicMsg {
     fn from(data: &Value) -> Self {
         Self {
@@ -1017,6 +1492,69 @@ pub struct AutoCompleteUsername {
     pub uservatar_url48: String,
 }
 
+impl From<&ChatRoomUser> for AutoCompleteUsername {
+    fn from(user: &ChatRoomUser) -> Self {
+        Self {
+            username_lowercase: user.user_name.to_lowercase(),
+            useravatar_url: user.user_avatar_url.clone(),
+            uservatar_url20: user.user_avatar_url_20.clone().unwrap_or_default(),
+            user_name: user.user_name.clone(),
+            uservatar_url210: user.user_avatar_url_210.clone().unwrap_or_default(),
+            uservatar_url48: user.user_avatar_url_48.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// 聊天室内联指令
+pub struct ChatRoomCommand;
+
+impl ChatRoomCommand {
+    pub const SET_DISCUSS: &'static str = "setdiscuss";
+    pub const RED_PACKET: &'static str = "redpacket";
+    pub const BARRAGER: &'static str = "barrager";
+    pub const WEATHER: &'static str = "weather";
+    pub const MUSIC: &'static str = "music";
+    pub const REVOKE: &'static str = "revoke";
+
+    pub fn values() -> Vec<&'static str> {
+        vec![
+            Self::SET_DISCUSS,
+            Self::RED_PACKET,
+            Self::BARRAGER,
+            Self::WEATHER,
+            Self::MUSIC,
+            Self::REVOKE,
+        ]
+    }
+}
+
+/// 聊天室服务端能力
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Capabilities {
+    /// 支持的内联指令，取值来自 ChatRoomCommand
+    pub commands: Vec<String>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            commands: ChatRoomCommand::values()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+impl Capabilities {
+    /// 是否支持指定指令
+    pub fn supports(&self, command: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(command))
+    }
+}
+
 // 聊天室数据类型
 #[derive(Debug, Clone)]
 pub struct ChatRoomData {
@@ -1033,4 +1571,343 @@ pub enum ChatRoomDataContent {
     RedPacketStatus(RedPacketStatusMsg),
     Barrager(BarragerMsg),
     Custom(String),
+    /// 连接状态变化，目前仅在被封禁/踢出时推送
+    ConnectionState(ConnectionState),
+    /// 相比上一次在线列表新加入与离开的用户
+    OnlineChange {
+        joined: Vec<ChatRoomUser>,
+        left: Vec<ChatRoomUser>,
+    },
+}
+
+/// 聊天室日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// 每行一个 JSON 对象
+    #[default]
+    JsonLines,
+    /// 便于阅读的纯文本
+    Plain,
+}
+
+impl ChatRoomData {
+    /// 转为 JSON，用于日志归档
+    pub fn to_json(&self) -> Value {
+        let data = match &self.data {
+            ChatRoomDataContent::OnlineUsers(users, count, discussing) => json!({
+                "users": users,
+                "onlineChatCnt": count,
+                "discussing": discussing,
+            }),
+            ChatRoomDataContent::Discuss(topic) => json!(topic),
+            ChatRoomDataContent::Revoke(oid) => json!(oid),
+            ChatRoomDataContent::Message(msg) => json!(msg),
+            ChatRoomDataContent::RedPacketStatus(status) => json!(status),
+            ChatRoomDataContent::Barrager(barrager) => json!(barrager),
+            ChatRoomDataContent::Custom(custom) => json!(custom),
+            ChatRoomDataContent::ConnectionState(state) => match state {
+                ConnectionState::Connected => json!({ "state": "connected" }),
+                ConnectionState::Disconnected => json!({ "state": "disconnected" }),
+                ConnectionState::Banned { reason } => {
+                    json!({ "state": "banned", "reason": reason })
+                }
+            },
+            ChatRoomDataContent::OnlineChange { joined, left } => json!({
+                "joined": joined,
+                "left": left,
+            }),
+        };
+        json!({ "type": self.type_, "data": data })
+    }
+
+    /// 格式化为单行日志，不含换行符
+    ///
+    /// - `format` 输出格式
+    pub fn to_log_line(&self, format: LogFormat) -> String {
+        if format == LogFormat::JsonLines {
+            return self.to_json().to_string();
+        }
+
+        let text = match &self.data {
+            ChatRoomDataContent::OnlineUsers(users, count, _) => {
+                format!("在线人数: {}", count.unwrap_or(users.len() as i32))
+            }
+            ChatRoomDataContent::Discuss(topic) => format!("话题变更: {}", topic),
+            ChatRoomDataContent::Revoke(oid) => format!("撤回消息: {}", oid),
+            ChatRoomDataContent::Message(msg) => format!(
+                "{} {}: {}",
+                msg.display_time(),
+                msg.user_name,
+                msg.md.as_deref().unwrap_or(&msg.content)
+            ),
+            ChatRoomDataContent::RedPacketStatus(status) => format!(
+                "{} 领取了 {} 的红包 ({}/{})",
+                status.who_got, status.who_give, status.got, status.count
+            ),
+            ChatRoomDataContent::Barrager(barrager) => {
+                format!("弹幕 {}: {}", barrager.user_name, barrager.barrager_content)
+            }
+            ChatRoomDataContent::Custom(custom) => custom.clone(),
+            ChatRoomDataContent::ConnectionState(state) => match state {
+                ConnectionState::Connected => "已连接".to_string(),
+                ConnectionState::Disconnected => "已断开".to_string(),
+                ConnectionState::Banned { reason } => format!("已被封禁: {}", reason),
+            },
+            ChatRoomDataContent::OnlineChange { joined, left } => {
+                let names = |users: &[ChatRoomUser]| {
+                    users
+                        .iter()
+                        .map(|u| u.user_name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                format!("加入: [{}] 离开: [{}]", names(joined), names(left))
+            }
+        };
+        format!("[{}] {}", self.type_, text.replace('\n', " "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_name_covers_every_mode_and_type() {
+        use DisplayNameMode::*;
+        let cases = [
+            (NicknameWithUser, Some(" 鱼 "), " 鱼 (fish)"),
+            (NicknameWithUser, Some(""), "fish"),
+            (NicknameWithUser, Some("  "), "fish"),
+            (NicknameWithUser, None, "fish"),
+            (NicknameOnly, Some(" 鱼 "), " 鱼 "),
+            (NicknameOnly, Some(""), "fish"),
+            (NicknameOnly, Some("  "), "fish"),
+            (NicknameOnly, None, "fish"),
+            (UsernameOnly, Some(" 鱼 "), "fish"),
+            (UsernameOnly, Some(""), "fish"),
+            (UsernameOnly, Some("  "), "fish"),
+            (UsernameOnly, None, "fish"),
+        ];
+
+        for (mode, nickname, expected) in cases {
+            let user = ChatRoomUser {
+                user_name: "fish".to_string(),
+                user_nickname: nickname.map(str::to_string),
+                ..Default::default()
+            };
+            let barrager = BarragerMsg {
+                user_name: "fish".to_string(),
+                user_nickname: nickname.map(str::to_string),
+                ..Default::default()
+            };
+            let message: ChatRoomMessage = serde_json::from_value(serde_json::json!({
+                "oId": "1",
+                "userOId": 1,
+                "userName": "fish",
+                "userNickname": nickname,
+                "userAvatarURL": "",
+                "content": "hi",
+                "time": "2024-01-01 00:00:00",
+            }))
+            .unwrap();
+
+            assert_eq!(
+                mode.format("fish", nickname),
+                expected,
+                "{:?} {:?}",
+                mode,
+                nickname
+            );
+            assert_eq!(user.display_name(mode), expected);
+            assert_eq!(barrager.display_name(mode), expected);
+            assert_eq!(message.display_name(mode), expected);
+        }
+    }
+
+    #[test]
+    fn parse_room_notice_handles_present_empty_and_absent() {
+        let cases = [
+            (serde_json::json!({"data": "公告"}), Some("公告")),
+            (
+                serde_json::json!({"data": {"content": " 公告 "}}),
+                Some("公告"),
+            ),
+            (
+                serde_json::json!({"data": {"notice": "公告"}}),
+                Some("公告"),
+            ),
+            (serde_json::json!({"data": "  "}), None),
+            (serde_json::json!({"data": {"content": ""}}), None),
+            (serde_json::json!({"data": null}), None),
+            (serde_json::json!({"code": 0}), None),
+        ];
+        for (response, expected) in cases {
+            assert_eq!(
+                parse_room_notice(&response).as_deref(),
+                expected,
+                "{}",
+                response
+            );
+        }
+    }
+
+    #[test]
+    fn parse_slow_mode_reads_structured_field_only() {
+        let top = serde_json::json!({"code": 0, "slowMode": 5});
+        let nested = serde_json::json!({"code": 0, "data": {"slowModeInterval": "8"}});
+        let text_only = serde_json::json!({"code": -1, "msg": "慢速模式中，请 30 秒后再试"});
+
+        assert_eq!(parse_slow_mode(&top), Some(5));
+        assert_eq!(parse_slow_mode(&nested), Some(8));
+        assert_eq!(parse_slow_mode(&text_only), None);
+    }
+
+    fn weather(dates: &str, codes: &str, min: &str, max: &str) -> WeatherMsg {
+        WeatherMsg {
+            dates: dates.to_string(),
+            codes: codes.to_string(),
+            min_temps: min.to_string(),
+            max_temps: max.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weather_data_ignores_trailing_commas() {
+        let msg = weather("d1,d2,d3,", "CLEAR_DAY,RAIN,CLOUDY,", "1,2,3,", "4,5,6");
+        let days = msg.data();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[2].date, "d3");
+        assert_eq!(days[2].code, "CLOUDY");
+        assert_eq!(days[2].max, 6.0);
+        assert_eq!(msg.data_checked().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn weather_data_checked_rejects_mismatched_counts() {
+        let msg = weather("d1,d2,d3", "CLEAR_DAY,RAIN", "1,2,3", "4,5,6");
+        assert_eq!(msg.data().len(), 2);
+        assert!(matches!(msg.data_checked(), Err(FishPiError::Parse(_))));
+    }
+
+    fn music(source: &str, from: &str) -> MusicMsg {
+        MusicMsg {
+            source: source.to_string(),
+            from: from.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn music_resolves_netease_song_id() {
+        assert_eq!(
+            music("1234567", "netease").resolve_url().as_deref(),
+            Some("https://music.163.com/song/media/outer/url?id=1234567.mp3")
+        );
+    }
+
+    #[test]
+    fn music_keeps_full_urls() {
+        let url = "https://example.com/song.mp3";
+        assert_eq!(music(url, "netease").resolve_url().as_deref(), Some(url));
+        assert_eq!(
+            music("1234567", "").resolve_url().as_deref(),
+            Some("1234567")
+        );
+        assert_eq!(music("  ", "netease").resolve_url(), None);
+    }
+
+    const MUSIC_MESSAGE: &str = r#"{"oId":"1","userOId":2,"userName":"alice","userAvatarURL":"","content":"{\"msgType\":\"music\",\"type\":\"music\",\"title\":\"歌\",\"source\":\"1234\",\"coverURL\":\"\",\"from\":\"netease\"}"}"#;
+
+    #[test]
+    fn chat_message_parses_special_content_eagerly() {
+        let message: ChatRoomMessage = serde_json::from_str(MUSIC_MESSAGE).unwrap();
+        assert!(message.is_special_parsed());
+        assert!(matches!(
+            message.special_content,
+            SpecialMessageContent::Music(_)
+        ));
+        assert_eq!(
+            message.message_type.as_deref(),
+            Some(ChatRoomMessageType::MUSIC)
+        );
+    }
+
+    #[test]
+    fn lazy_chat_message_parses_special_content_on_first_access() {
+        let LazyChatRoomMessage(message) = serde_json::from_str(MUSIC_MESSAGE).unwrap();
+        assert!(!message.is_special_parsed());
+        assert!(matches!(message.special(), SpecialMessageContent::Music(_)));
+        assert!(message.is_special_parsed());
+        assert_eq!(message.kind(), ChatMessageKind::Music);
+    }
+
+    #[test]
+    fn barrage_color_from_str_rejects_invalid_input() {
+        assert_eq!(
+            "#ff0000".parse::<BarrageColor>().unwrap(),
+            BarrageColor::from_rgba(255, 0, 0, 1.0)
+        );
+        assert_eq!(
+            BarrageColor::try_from("rgba(1, 2, 3, 0.5)").unwrap(),
+            BarrageColor::from_rgba(1, 2, 3, 0.5)
+        );
+        for color in ["red", "#12", "rgb(1, 2)"] {
+            assert!(matches!(
+                color.parse::<BarrageColor>(),
+                Err(FishPiError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn music_msg_round_trips_through_content() {
+        let music = MusicMsg::new(
+            "晴天 \"live\"",
+            "186016",
+            "https://p1.music.126.net/cover.jpg",
+            "网易云音乐",
+        );
+
+        let value: Value = serde_json::from_str(&music.to_content()).unwrap();
+        let parsed = MusicMsg::from(&value);
+
+        assert_eq!(parsed.msg_type, "music");
+        assert_eq!(parsed.title, music.title);
+        assert_eq!(parsed.source, music.source);
+        assert_eq!(parsed.cover_url, music.cover_url);
+        assert_eq!(parsed.from, music.from);
+        assert_eq!(parsed.resolve_url(), music.resolve_url());
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn display_time_falls_back_to_receipt_time() {
+        let received = Local.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap();
+        let message = |time: &str, received_at: Option<i64>| {
+            let mut message: ChatRoomMessage = serde_json::from_value(json!({
+                "oId": "1",
+                "userOId": 1,
+                "userName": "alice",
+                "userAvatarURL": "",
+                "content": "hi",
+                "time": time,
+            }))
+            .unwrap();
+            message.received_at = received_at;
+            message
+        };
+        let at = Some(received.timestamp_millis());
+
+        assert_eq!(
+            message("2024-01-01 12:00:00", at).display_time(),
+            "2024-01-01 12:00:00"
+        );
+        for time in ["", "  ", "昨天", "2024-13-40 99:00:00"] {
+            assert_eq!(message(time, at).display_time(), "2024-05-06 07:08:09");
+        }
+        assert_eq!(message("", None).display_time(), "");
+        assert_eq!(message(" 昨天 ", None).display_time(), "昨天");
+    }
 }
@@ -1,5 +1,6 @@
 use crate::models::article::ArticleComment;
 use serde_json::Value;
+use std::collections::HashMap;
 
 /// 从评论数据中解析普通评论和精选评论
 pub fn parse_comment_data(comments_data: &Value) -> (Vec<ArticleComment>, Vec<ArticleComment>) {
@@ -7,23 +8,23 @@ pub fn parse_comment_data(comments_data: &Value) -> (Vec<ArticleComment>, Vec<Ar
     let mut nice_comments = Vec::new();
 
     // 解析普通评论
-    if let Some(article_comments) = comments_data.get("articleComments") {
-        if let Some(comments_array) = article_comments.as_array() {
-            for comment_value in comments_array.iter() {
-                if let Ok(comment) = ArticleComment::from_json(comment_value) {
-                    comments.push(comment);
-                }
+    if let Some(article_comments) = comments_data.get("articleComments")
+        && let Some(comments_array) = article_comments.as_array()
+    {
+        for comment_value in comments_array.iter() {
+            if let Ok(comment) = ArticleComment::from_json(comment_value) {
+                comments.push(comment);
             }
         }
     }
 
     // 解析精选评论
-    if let Some(article_nice_comments) = comments_data.get("articleNiceComments") {
-        if let Some(comments_array) = article_nice_comments.as_array() {
-            for comment_value in comments_array.iter() {
-                if let Ok(comment) = ArticleComment::from_json(comment_value) {
-                    nice_comments.push(comment);
-                }
+    if let Some(article_nice_comments) = comments_data.get("articleNiceComments")
+        && let Some(comments_array) = article_nice_comments.as_array()
+    {
+        for comment_value in comments_array.iter() {
+            if let Ok(comment) = ArticleComment::from_json(comment_value) {
+                nice_comments.push(comment);
             }
         }
     }
@@ -34,3 +35,83 @@ pub fn parse_comment_data(comments_data: &Value) -> (Vec<ArticleComment>, Vec<Ar
 
     (comments, nice_comments)
 }
+
+/// 评论树节点
+#[derive(Debug, Clone)]
+pub struct CommentNode {
+    /// 评论
+    pub comment: ArticleComment,
+    /// 回复该评论的子评论，按原顺序排列
+    pub children: Vec<CommentNode>,
+}
+
+/// 按 `reply_id` 将评论组织为回复树
+///
+/// 顶层评论与每层回复都保持原顺序。`reply_id` 指向不存在的评论、指向自身或形成循环时，
+/// 该评论按顶层评论处理
+pub fn build_thread(comments: Vec<ArticleComment>) -> Vec<CommentNode> {
+    let mut index = HashMap::new();
+    for (i, comment) in comments.iter().enumerate() {
+        index.entry(comment.o_id.clone()).or_insert(i);
+    }
+
+    let mut parents: Vec<Option<usize>> = comments
+        .iter()
+        .enumerate()
+        .map(|(i, comment)| {
+            index
+                .get(&comment.reply_id)
+                .copied()
+                .filter(|&parent| !comment.reply_id.is_empty() && parent != i)
+        })
+        .collect();
+
+    // 断开循环引用：沿父链向上查找，回到当前路径上的节点时将其提升为顶层
+    let mut resolved = vec![false; comments.len()];
+    for start in 0..comments.len() {
+        let mut path = Vec::new();
+        let mut current = start;
+        while !resolved[current] {
+            if path.contains(&current) {
+                parents[current] = None;
+                break;
+            }
+            path.push(current);
+            match parents[current] {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        for node in path {
+            resolved[node] = true;
+        }
+    }
+
+    let mut children = vec![Vec::new(); comments.len()];
+    let mut roots = Vec::new();
+    for (i, parent) in parents.iter().enumerate() {
+        match parent {
+            Some(parent) => children[*parent].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    fn build(
+        i: usize,
+        slots: &mut Vec<Option<ArticleComment>>,
+        children: &[Vec<usize>],
+    ) -> Option<CommentNode> {
+        let comment = slots[i].take()?;
+        let children = children[i]
+            .iter()
+            .filter_map(|&child| build(child, slots, children))
+            .collect();
+        Some(CommentNode { comment, children })
+    }
+
+    let mut slots: Vec<Option<ArticleComment>> = comments.into_iter().map(Some).collect();
+    roots
+        .into_iter()
+        .filter_map(|root| build(root, &mut slots, &children))
+        .collect()
+}
@@ -0,0 +1,206 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FishPiError;
+use crate::models::chatroom::ChatRoomMessage;
+
+/// 聊天室消息过滤规则
+///
+/// 可序列化保存，通过 `ChatroomService::set_filter` 启用后，命中规则的消息不会推送给监听器。
+/// 正则表达式在添加或反序列化时编译，直接修改 `regexes` 后需调用 [`MessageFilter::compile`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawMessageFilter")]
+pub struct MessageFilter {
+    /// 屏蔽的用户名，统一保存为小写
+    #[serde(default)]
+    pub blocked_users: Vec<String>,
+    /// 屏蔽的消息前缀
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// 屏蔽的正则表达式
+    #[serde(default)]
+    pub regexes: Vec<String>,
+    /// 已编译的正则表达式，与 `regexes` 一一对应
+    #[serde(skip)]
+    compiled: Vec<Regex>,
+}
+
+/// 反序列化时的原始规则
+#[derive(Deserialize)]
+struct RawMessageFilter {
+    #[serde(default)]
+    blocked_users: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    regexes: Vec<String>,
+}
+
+impl TryFrom<RawMessageFilter> for MessageFilter {
+    type Error = FishPiError;
+
+    fn try_from(raw: RawMessageFilter) -> Result<Self, Self::Error> {
+        let mut filter = Self {
+            blocked_users: raw.blocked_users,
+            keywords: raw.keywords,
+            regexes: raw.regexes,
+            compiled: Vec::new(),
+        };
+        filter.compile()?;
+        Ok(filter)
+    }
+}
+
+impl PartialEq for MessageFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocked_users == other.blocked_users
+            && self.keywords == other.keywords
+            && self.regexes == other.regexes
+    }
+}
+
+/// 编译正则表达式，无效时返回 [`FishPiError::Validation`]
+fn compile_regex(pattern: &str) -> Result<Regex, FishPiError> {
+    Regex::new(pattern)
+        .map_err(|err| FishPiError::Validation(format!("无效的正则表达式 {}: {}", pattern, err)))
+}
+
+impl MessageFilter {
+    /// 创建空的过滤规则
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 屏蔽用户，用户名不区分大小写
+    pub fn block_user(&mut self, user: &str) {
+        let user = user.to_lowercase();
+        if !self.blocked_users.contains(&user) {
+            self.blocked_users.push(user);
+        }
+    }
+
+    /// 取消屏蔽用户
+    pub fn unblock_user(&mut self, user: &str) {
+        let user = user.to_lowercase();
+        self.blocked_users.retain(|u| u != &user);
+    }
+
+    /// 屏蔽以指定关键字开头的消息
+    pub fn block_keyword(&mut self, keyword: &str) {
+        if !self.keywords.iter().any(|k| k == keyword) {
+            self.keywords.push(keyword.to_string());
+        }
+    }
+
+    /// 取消屏蔽关键字
+    pub fn unblock_keyword(&mut self, keyword: &str) {
+        self.keywords.retain(|k| k != keyword);
+    }
+
+    /// 屏蔽匹配正则表达式的消息
+    ///
+    /// - `pattern` 正则表达式，无效时返回 [`FishPiError::Validation`]
+    pub fn block_regex(&mut self, pattern: &str) -> Result<(), FishPiError> {
+        let regex = compile_regex(pattern)?;
+        if !self.regexes.iter().any(|r| r == pattern) {
+            self.regexes.push(pattern.to_string());
+            self.compiled.push(regex);
+        }
+        Ok(())
+    }
+
+    /// 取消屏蔽正则表达式，返回是否存在该规则
+    pub fn unblock_regex(&mut self, pattern: &str) -> bool {
+        let len = self.regexes.len();
+        self.regexes.retain(|r| r != pattern);
+        self.compiled.retain(|re| re.as_str() != pattern);
+        self.regexes.len() != len
+    }
+
+    /// 重新编译 `regexes` 中的全部正则表达式
+    ///
+    /// 存在无效表达式时返回 [`FishPiError::Validation`]，已编译的规则保持不变
+    pub fn compile(&mut self) -> Result<(), FishPiError> {
+        self.compiled = self
+            .regexes
+            .iter()
+            .map(|pattern| compile_regex(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.blocked_users.is_empty() && self.keywords.is_empty() && self.regexes.is_empty()
+    }
+
+    /// 用户名与消息内容是否命中规则
+    ///
+    /// - `user_name` 发送者用户名
+    /// - `content` 消息的 Markdown 内容
+    pub fn matches(&self, user_name: &str, content: &str) -> bool {
+        let user_name = user_name.to_lowercase();
+        if self.blocked_users.contains(&user_name) {
+            return true;
+        }
+        if self
+            .keywords
+            .iter()
+            .any(|kw| content.starts_with(kw.as_str()))
+        {
+            return true;
+        }
+        self.compiled.iter().any(|re| re.is_match(content))
+    }
+
+    /// 聊天室消息是否应被屏蔽
+    ///
+    /// 红包消息始终放行，避免错过红包
+    pub fn should_block(&self, message: &ChatRoomMessage) -> bool {
+        !message.is_redpacket() && self.matches(&message.user_name, message.md_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regexes_are_compiled_when_added() {
+        let mut filter = MessageFilter::new();
+        filter.block_regex(r"^\d+$").unwrap();
+        assert!(filter.matches("alice", "123"));
+        assert!(!filter.matches("alice", "abc"));
+
+        assert!(filter.unblock_regex(r"^\d+$"));
+        assert!(!filter.matches("alice", "123"));
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected_when_added() {
+        let mut filter = MessageFilter::new();
+        assert!(matches!(
+            filter.block_regex("("),
+            Err(FishPiError::Validation(_))
+        ));
+        assert!(filter.regexes.is_empty());
+    }
+
+    #[test]
+    fn deserialized_filter_compiles_regexes() {
+        let filter: MessageFilter =
+            serde_json::from_str(r#"{"regexes":["^广告"],"blockedUsers":[]}"#).unwrap();
+        assert!(filter.matches("alice", "广告：买鱼"));
+        assert!(serde_json::from_str::<MessageFilter>(r#"{"regexes":["("]}"#).is_err());
+    }
+
+    #[test]
+    fn compile_reports_patterns_edited_in_place() {
+        let mut filter = MessageFilter::new();
+        filter.regexes.push("[".to_string());
+        assert!(matches!(filter.compile(), Err(FishPiError::Validation(_))));
+        filter.regexes = vec!["x".to_string()];
+        filter.compile().unwrap();
+        assert!(filter.matches("alice", "x"));
+    }
+}
@@ -4,6 +4,7 @@ pub mod chat;
 pub mod chatroom;
 pub mod comment;
 pub mod emoji;
+pub mod filter;
 pub mod notice;
 pub mod redpacket;
 pub mod upload;
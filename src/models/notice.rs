@@ -1,6 +1,29 @@
+use crate::utils::strip_html_tags;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// 通知统一展示接口，用于将不同类型的通知渲染为一致的信息流条目
+pub trait NoticeDisplay {
+    /// 标题，概括通知内容
+    fn headline(&self) -> String;
+    /// 正文，已去除 HTML 标签
+    fn body(&self) -> String;
+    /// 通知时间
+    fn time(&self) -> &str;
+    /// 是否已读
+    fn has_read(&self) -> bool;
+
+    /// 单行展示：`时间 标题: 正文`
+    fn display_line(&self) -> String {
+        let body = self.body();
+        if body.is_empty() {
+            format!("{} {}", self.time(), self.headline())
+        } else {
+            format!("{} {}: {}", self.time(), self.headline(), body)
+        }
+    }
+}
+
 /// 通知类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum NoticeType {
@@ -35,6 +58,7 @@ impl NoticeType {
     }
 
     /// 从字符串转换为枚举
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "point" => Some(NoticeType::Point),
@@ -48,6 +72,18 @@ impl NoticeType {
         }
     }
 
+    /// 按通知类型解析单条通知为统一展示对象，不支持的类型返回 `None`
+    pub fn to_display(&self, data: &Value) -> Option<Box<dyn NoticeDisplay>> {
+        match self {
+            NoticeType::Point => Some(Box::new(NoticePoint::from(data))),
+            NoticeType::Commented | NoticeType::Reply => Some(Box::new(NoticeComment::from(data))),
+            NoticeType::At => Some(Box::new(NoticeAt::from(data))),
+            NoticeType::Following => Some(Box::new(NoticeFollow::from(data))),
+            NoticeType::System => Some(Box::new(NoticeSystem::from(data))),
+            NoticeType::Broadcast => None,
+        }
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             NoticeType::Point => "积分",
@@ -547,6 +583,7 @@ impl NoticeMsgType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "refreshNotification" => NoticeMsgType::RefreshNotification,
@@ -631,6 +668,11 @@ impl NoticeMsg {
         Some(Self::from(data))
     }
 
+    /// 通知消息类型
+    pub fn msg_type(&self) -> NoticeMsgType {
+        NoticeMsgType::from_str(&self.command)
+    }
+
     pub fn to_json(&self) -> Value {
         serde_json::to_value(self).unwrap_or(Value::Null)
     }
@@ -736,3 +778,196 @@ impl NoticeItem for NoticeSystem {
         NoticeType::System.as_str()
     }
 }
+
+impl NoticeDisplay for NoticePoint {
+    fn headline(&self) -> String {
+        "积分变动".to_string()
+    }
+
+    fn body(&self) -> String {
+        strip_html_tags(&self.description)
+    }
+
+    fn time(&self) -> &str {
+        &self.create_time
+    }
+
+    fn has_read(&self) -> bool {
+        self.has_read
+    }
+}
+
+impl NoticeDisplay for NoticeComment {
+    fn headline(&self) -> String {
+        format!("{} 评论了《{}》", self.author, self.title)
+    }
+
+    fn body(&self) -> String {
+        strip_html_tags(&self.content)
+    }
+
+    fn time(&self) -> &str {
+        &self.create_time
+    }
+
+    fn has_read(&self) -> bool {
+        self.has_read
+    }
+}
+
+impl NoticeDisplay for NoticeAt {
+    fn headline(&self) -> String {
+        format!("{} 提到了你", self.user_name)
+    }
+
+    fn body(&self) -> String {
+        if self.deleted {
+            return "内容已删除".to_string();
+        }
+        strip_html_tags(&self.content)
+    }
+
+    fn time(&self) -> &str {
+        &self.create_time
+    }
+
+    fn has_read(&self) -> bool {
+        self.has_read
+    }
+}
+
+impl NoticeDisplay for NoticeFollow {
+    fn headline(&self) -> String {
+        if self.is_comment {
+            format!("{} 评论了关注的帖子", self.author)
+        } else {
+            format!("{} 发布了新帖", self.author)
+        }
+    }
+
+    fn body(&self) -> String {
+        self.title.clone()
+    }
+
+    fn time(&self) -> &str {
+        &self.create_time
+    }
+
+    fn has_read(&self) -> bool {
+        self.has_read
+    }
+}
+
+impl NoticeDisplay for NoticeSystem {
+    fn headline(&self) -> String {
+        "系统通知".to_string()
+    }
+
+    fn body(&self) -> String {
+        strip_html_tags(&self.description)
+    }
+
+    fn time(&self) -> &str {
+        &self.create_time
+    }
+
+    fn has_read(&self) -> bool {
+        self.has_read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn to_display_headline_for_each_notice_type() {
+        let cases = [
+            (
+                NoticeType::Point,
+                json!({"description": "<p>收到 10 积分</p>", "hasRead": true, "createTime": "t"}),
+                "积分变动",
+                "收到 10 积分",
+            ),
+            (
+                NoticeType::Commented,
+                json!({"commentAuthorName": "alice", "commentArticleTitle": "标题", "commentContent": "<b>好</b>", "commentCreateTime": "t"}),
+                "alice 评论了《标题》",
+                "好",
+            ),
+            (
+                NoticeType::Reply,
+                json!({"commentAuthorName": "bob", "commentArticleTitle": "标题", "commentContent": "回复", "commentCreateTime": "t"}),
+                "bob 评论了《标题》",
+                "回复",
+            ),
+            (
+                NoticeType::At,
+                json!({"userName": "carol", "content": "@me 你好", "createTime": "t"}),
+                "carol 提到了你",
+                "@me 你好",
+            ),
+            (
+                NoticeType::At,
+                json!({"userName": "carol", "content": "已删", "deleted": true, "createTime": "t"}),
+                "carol 提到了你",
+                "内容已删除",
+            ),
+            (
+                NoticeType::Following,
+                json!({"authorName": "dave", "articleTitle": "新帖", "isComment": false, "createTime": "t"}),
+                "dave 发布了新帖",
+                "新帖",
+            ),
+            (
+                NoticeType::Following,
+                json!({"authorName": "dave", "articleTitle": "旧帖", "isComment": true, "createTime": "t"}),
+                "dave 评论了关注的帖子",
+                "旧帖",
+            ),
+            (
+                NoticeType::System,
+                json!({"description": "维护公告", "createTime": "t"}),
+                "系统通知",
+                "维护公告",
+            ),
+        ];
+
+        for (notice_type, data, headline, body) in cases {
+            let display = notice_type.to_display(&data).unwrap();
+            assert_eq!(display.headline(), headline, "{:?}", notice_type);
+            assert_eq!(display.body(), body, "{:?}", notice_type);
+            assert_eq!(display.time(), "t");
+            assert_eq!(display.display_line(), format!("t {}: {}", headline, body));
+        }
+        assert!(NoticeType::Broadcast.to_display(&json!({})).is_none());
+    }
+
+    #[test]
+    fn to_display_tolerates_malformed_data() {
+        let types = [
+            NoticeType::Point,
+            NoticeType::Commented,
+            NoticeType::Reply,
+            NoticeType::At,
+            NoticeType::Following,
+            NoticeType::System,
+        ];
+        let malformed = [
+            json!(null),
+            json!("oops"),
+            json!([1, 2]),
+            json!({"userName": 1, "createTime": 2, "hasRead": "yes", "deleted": "no"}),
+        ];
+
+        for notice_type in types {
+            for data in &malformed {
+                let display = notice_type.to_display(data).unwrap();
+                assert!(!display.headline().is_empty(), "{:?}", notice_type);
+                assert_eq!(display.time(), "");
+                assert!(!display.has_read());
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::FishPiError;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -108,16 +109,37 @@ impl Default for RedPacketMessage {
     }
 }
 
+impl RedPacketMessage {
+    /// 红包基本信息，用于发送前校验
+    pub fn base(&self) -> RedPacketBase {
+        RedPacketBase {
+            count: self.count,
+            gesture: self.gesture,
+            got: self.got,
+            msg: self.msg.clone(),
+            user_name: self.sender_name.clone(),
+            money: self.money,
+            type_: self.type_.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// 接收者列表，`recivers` 字段无法解析时为空
+    pub fn receiver_list(&self) -> Vec<String> {
+        serde_json::from_str(&self.receivers).unwrap_or_default()
+    }
+}
+
 impl From<&Value> for RedPacketMessage {
     fn from(data: &Value) -> Self {
         // 解析who字段，如果解析失败就使用空数组
         let who = if let Some(who_array) = data.get("who").and_then(|v| v.as_array()) {
             let mut result = Vec::new();
             for item in who_array {
-                if item.is_object() {
-                    if let Ok(got_item) = serde_json::from_value::<RedPacketGot>(item.clone()) {
-                        result.push(got_item);
-                    }
+                if item.is_object()
+                    && let Ok(got_item) = serde_json::from_value::<RedPacketGot>(item.clone())
+                {
+                    result.push(got_item);
                 }
             }
             result
@@ -183,6 +205,160 @@ pub struct RedPacketGot {
     pub time: String,
 }
 
+impl RedPacketGot {
+    /// 猜拳红包的胜负结果
+    ///
+    /// `money` 是领取者的得失，发送者视角需要取反
+    ///
+    /// - `is_sender` - 是否以发送者视角查看
+    pub fn outcome(&self, is_sender: bool) -> RpsOutcome {
+        let money = if is_sender { -self.money } else { self.money };
+        match money {
+            m if m > 0 => RpsOutcome::Win(m),
+            m if m < 0 => RpsOutcome::Lose(-m),
+            _ => RpsOutcome::Draw,
+        }
+    }
+}
+
+/// 猜拳红包结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpsOutcome {
+    /// 赢得的积分
+    Win(i32),
+    /// 输掉的积分
+    Lose(i32),
+    /// 平局
+    Draw,
+}
+
+/// 单个手势的猜拳红包统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct GestureRecord {
+    /// 出拳次数
+    #[serde(default)]
+    pub played: u64,
+    /// 赢的次数
+    #[serde(default)]
+    pub wins: u64,
+    /// 输的次数
+    #[serde(default)]
+    pub losses: u64,
+    /// 平局次数
+    #[serde(default)]
+    pub draws: u64,
+    /// 净得积分，输时为负
+    #[serde(default)]
+    pub points: i64,
+}
+
+/// 猜拳红包统计，按手势分别记录
+///
+/// 兼容旧版本 `[石头, 剪刀, 布]` 形式的出拳次数数组，读取时计入各手势的出拳次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(from = "GestureStatsRepr")]
+pub struct GestureStats {
+    #[serde(default)]
+    pub rock: GestureRecord,
+    #[serde(default)]
+    pub scissors: GestureRecord,
+    #[serde(default)]
+    pub paper: GestureRecord,
+}
+
+impl GestureStats {
+    /// 指定手势的统计
+    pub fn get(&self, gesture: GestureType) -> &GestureRecord {
+        match gesture {
+            GestureType::Rock => &self.rock,
+            GestureType::Scissors => &self.scissors,
+            GestureType::Paper => &self.paper,
+        }
+    }
+
+    fn get_mut(&mut self, gesture: GestureType) -> &mut GestureRecord {
+        match gesture {
+            GestureType::Rock => &mut self.rock,
+            GestureType::Scissors => &mut self.scissors,
+            GestureType::Paper => &mut self.paper,
+        }
+    }
+
+    /// 记录一次出拳
+    ///
+    /// - `gesture` 出的手势
+    /// - `outcome` 胜负结果，无法确定时为 `None`，仅计入出拳次数
+    pub fn record(&mut self, gesture: GestureType, outcome: Option<RpsOutcome>) {
+        let record = self.get_mut(gesture);
+        record.played += 1;
+        match outcome {
+            Some(RpsOutcome::Win(points)) => {
+                record.wins += 1;
+                record.points += points as i64;
+            }
+            Some(RpsOutcome::Lose(points)) => {
+                record.losses += 1;
+                record.points -= points as i64;
+            }
+            Some(RpsOutcome::Draw) => record.draws += 1,
+            None => {}
+        }
+    }
+
+    /// 总出拳次数
+    pub fn total_played(&self) -> u64 {
+        self.rock.played + self.scissors.played + self.paper.played
+    }
+
+    /// 所有手势的净得积分
+    pub fn net_points(&self) -> i64 {
+        self.rock.points + self.scissors.points + self.paper.points
+    }
+}
+
+/// 猜拳统计的存储格式
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GestureStatsRepr {
+    /// 旧版本按 [`GestureType`] 顺序保存的出拳次数
+    Legacy([u64; 3]),
+    Current {
+        #[serde(default)]
+        rock: GestureRecord,
+        #[serde(default)]
+        scissors: GestureRecord,
+        #[serde(default)]
+        paper: GestureRecord,
+    },
+}
+
+impl From<GestureStatsRepr> for GestureStats {
+    fn from(repr: GestureStatsRepr) -> Self {
+        match repr {
+            GestureStatsRepr::Legacy([rock, scissors, paper]) => {
+                let played = |played| GestureRecord {
+                    played,
+                    ..Default::default()
+                };
+                Self {
+                    rock: played(rock),
+                    scissors: played(scissors),
+                    paper: played(paper),
+                }
+            }
+            GestureStatsRepr::Current {
+                rock,
+                scissors,
+                paper,
+            } => Self {
+                rock,
+                scissors,
+                paper,
+            },
+        }
+    }
+}
+
 /// 红包基本信息
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RedPacketBase {
@@ -204,6 +380,47 @@ pub struct RedPacketBase {
     /// 用户头像
     #[serde(rename = "userAvatarURL", default)]
     pub avatar_url: String,
+    /// 积分总额，打开红包的响应中不返回
+    #[serde(default)]
+    pub money: i32,
+    /// 红包类型，取值见 `RedPacketType`，打开红包的响应中不返回
+    #[serde(rename = "type", default)]
+    pub type_: String,
+}
+
+impl RedPacketBase {
+    /// 红包是否已领完
+    pub fn is_finished(&self) -> bool {
+        self.got >= self.count
+    }
+
+    /// 剩余可领取的个数
+    pub fn remaining(&self) -> i32 {
+        (self.count - self.got).max(0)
+    }
+
+    /// 发送前校验红包参数
+    ///
+    /// - `receivers` 接收者列表，仅专属红包需要
+    ///
+    /// 数量至少为 1，积分不能少于数量，专属红包必须指定接收者
+    pub fn validate(&self, receivers: &[String]) -> Result<(), FishPiError> {
+        if self.type_ == RedPacketType::SPECIFY && receivers.iter().all(|r| r.trim().is_empty()) {
+            return Err(FishPiError::Validation(
+                "专属红包至少需要一个接收者".to_string(),
+            ));
+        }
+        if self.count < 1 {
+            return Err(FishPiError::Validation("红包数量至少为 1".to_string()));
+        }
+        if self.money < self.count {
+            return Err(FishPiError::Validation(format!(
+                "积分({})不能少于红包数量({})",
+                self.money, self.count
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// 红包信息（打开红包后返回）
@@ -220,6 +437,19 @@ pub struct RedPacketInfo {
     pub who: Vec<RedPacketGot>,
 }
 
+impl RedPacketInfo {
+    /// 指定领取者这一局的猜拳结果，未找到其领取记录时返回 `None`
+    ///
+    /// - `user` - 领取者用户名
+    /// - `is_sender` - 是否以发送者视角查看
+    pub fn outcome_for(&self, user: &str, is_sender: bool) -> Option<RpsOutcome> {
+        self.who
+            .iter()
+            .find(|got| got.user_name == user)
+            .map(|got| got.outcome(is_sender))
+    }
+}
+
 /// 红包状态消息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedPacketStatusMsg {
@@ -233,11 +463,11 @@ pub struct RedPacketStatusMsg {
     pub who_give: String,
     #[serde(rename = "whoGot")]
     pub who_got: String,
-    #[serde(rename = "userAvatarURL20", default)]
+    #[serde(rename = "userAvatarURL20", alias = "avatarURL20", default)]
     pub avatar_url_20: Option<String>,
-    #[serde(rename = "userAvatarURL48", default)]
+    #[serde(rename = "userAvatarURL48", alias = "avatarURL48", default)]
     pub avatar_url_48: Option<String>,
-    #[serde(rename = "userAvatarURL210", default)]
+    #[serde(rename = "userAvatarURL210", alias = "avatarURL210", default)]
     pub avatar_url_210: Option<String>,
 }
 
@@ -261,18 +491,160 @@ impl From<&Value> for RedPacketStatusMsg {
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_string(),
-            avatar_url_20: data
-                .get("userAvatarURL20")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            avatar_url_48: data
-                .get("userAvatarURL48")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            avatar_url_210: data
-                .get("userAvatarURL210")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            avatar_url_20: avatar_field(data, "20"),
+            avatar_url_48: avatar_field(data, "48"),
+            avatar_url_210: avatar_field(data, "210"),
+        }
+    }
+}
+
+/// 读取指定尺寸的头像地址，兼容 `userAvatarURL*` 与 `avatarURL*` 两种字段名，空字符串视为缺失
+fn avatar_field(data: &Value, size: &str) -> Option<String> {
+    [
+        format!("userAvatarURL{}", size),
+        format!("avatarURL{}", size),
+    ]
+    .iter()
+    .filter_map(|key| data.get(key).and_then(|v| v.as_str()))
+    .find(|url| !url.is_empty())
+    .map(|url| url.to_string())
+}
+
+impl RedPacketStatusMsg {
+    /// 红包是否已领完，见 [`RedPacketBase::is_finished`]
+    pub fn is_finished(&self) -> bool {
+        self.progress().is_finished()
+    }
+
+    /// 剩余可领取的个数，见 [`RedPacketBase::remaining`]
+    pub fn remaining(&self) -> i32 {
+        self.progress().remaining()
+    }
+
+    /// 仅含领取进度的基本信息
+    fn progress(&self) -> RedPacketBase {
+        RedPacketBase {
+            count: self.count,
+            got: self.got,
+            ..Default::default()
+        }
+    }
+
+    /// 领取者头像，优先取较大尺寸
+    pub fn avatar_url(&self) -> Option<&str> {
+        [
+            &self.avatar_url_210,
+            &self.avatar_url_48,
+            &self.avatar_url_20,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .find(|url| !url.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_with(user: &str, money: i32) -> RedPacketInfo {
+        RedPacketInfo {
+            who: vec![RedPacketGot {
+                user_name: user.to_string(),
+                money,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sender_wins_when_receiver_loses() {
+        let info = info_with("bob", -32);
+        assert_eq!(info.outcome_for("bob", true), Some(RpsOutcome::Win(32)));
+    }
+
+    #[test]
+    fn sender_loses_when_receiver_wins() {
+        let info = info_with("bob", 32);
+        assert_eq!(info.outcome_for("bob", true), Some(RpsOutcome::Lose(32)));
+    }
+
+    #[test]
+    fn receiver_wins_with_positive_money() {
+        let info = info_with("bob", 32);
+        assert_eq!(info.outcome_for("bob", false), Some(RpsOutcome::Win(32)));
+    }
+
+    #[test]
+    fn receiver_loses_with_negative_money() {
+        let info = info_with("bob", -32);
+        assert_eq!(info.outcome_for("bob", false), Some(RpsOutcome::Lose(32)));
+    }
+
+    #[test]
+    fn zero_money_is_a_draw_for_both_sides() {
+        let info = info_with("bob", 0);
+        assert_eq!(info.outcome_for("bob", false), Some(RpsOutcome::Draw));
+        assert_eq!(info.outcome_for("bob", true), Some(RpsOutcome::Draw));
+    }
+
+    #[test]
+    fn unknown_user_has_no_outcome() {
+        let info = info_with("bob", 32);
+        assert_eq!(info.outcome_for("alice", false), None);
+    }
+
+    #[test]
+    fn base_validate_checks_count_money_and_receivers() {
+        let base = |type_: &str, count, money| RedPacketBase {
+            type_: type_.to_string(),
+            count,
+            money,
+            ..Default::default()
+        };
+        let receivers = ["alice".to_string()];
+
+        assert!(base(RedPacketType::RANDOM, 2, 32).validate(&[]).is_ok());
+        assert!(
+            base(RedPacketType::SPECIFY, 1, 32)
+                .validate(&receivers)
+                .is_ok()
+        );
+        for (packet, receivers) in [
+            (base(RedPacketType::RANDOM, 0, 32), &[][..]),
+            (base(RedPacketType::AVERAGE, 5, 4), &[][..]),
+            (base(RedPacketType::SPECIFY, 1, 32), &[" ".to_string()][..]),
+        ] {
+            assert!(matches!(
+                packet.validate(receivers),
+                Err(FishPiError::Validation(_))
+            ));
+        }
+
+        let message = RedPacketMessage {
+            type_: RedPacketType::SPECIFY.to_string(),
+            count: 1,
+            money: 32,
+            receivers: r#"["alice"]"#.to_string(),
+            ..Default::default()
+        };
+        assert!(message.base().validate(&message.receiver_list()).is_ok());
+    }
+
+    #[test]
+    fn status_progress_matches_base() {
+        for (count, got) in [(3, 1), (3, 3), (3, 5)] {
+            let base = RedPacketBase {
+                count,
+                got,
+                ..Default::default()
+            };
+            let status =
+                RedPacketStatusMsg::from(&serde_json::json!({ "count": count, "got": got }));
+            assert_eq!(status.is_finished(), base.is_finished());
+            assert_eq!(status.remaining(), base.remaining());
         }
     }
 }
@@ -22,3 +22,73 @@ pub struct UploadData {
     #[serde(rename = "succMap")]
     pub succ_map: HashMap<String, String>,
 }
+
+/// 单个上传文件的大小上限（字节）
+pub const MAX_UPLOAD_SIZE: usize = 10 * 1024 * 1024;
+
+/// 清理文件名中会破坏 multipart 头部的字符（引号与换行）
+///
+/// 服务端按清理后的文件名返回 `succMap`，查找地址时也要用清理后的名字
+pub fn sanitize_file_name(file_name: &str) -> String {
+    file_name.replace(['"', '\r', '\n'], "_")
+}
+
+/// 根据文件扩展名推断 MIME 类型，无法识别时为 `application/octet-stream`
+pub fn guess_mime(file_name: &str) -> &'static str {
+    let ext = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "txt" => "text/plain",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+impl UploadResponse {
+    /// 按上传顺序取出文件地址
+    ///
+    /// - `names` 上传的文件名
+    ///
+    /// 状态码非 0 或有文件上传失败时返回错误，错误信息包含服务端原因（如文件类型不允许）
+    pub fn into_urls(self, names: &[String]) -> anyhow::Result<Vec<String>> {
+        let msg = self.msg.filter(|m| !m.is_empty());
+        if self.code != 0 {
+            return Err(anyhow::anyhow!(
+                "上传失败: {}",
+                msg.unwrap_or_else(|| format!("状态码 {}", self.code))
+            ));
+        }
+
+        let data = self
+            .data
+            .ok_or_else(|| anyhow::anyhow!("上传失败: 响应缺少数据"))?;
+        if !data.err_files.is_empty() {
+            return Err(anyhow::anyhow!(
+                "上传失败: {}{}",
+                data.err_files.join(", "),
+                msg.map(|m| format!(" ({})", m)).unwrap_or_default()
+            ));
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                data.succ_map
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("上传失败: 未返回 {} 的地址", name))
+            })
+            .collect()
+    }
+}
@@ -1,4 +1,6 @@
-use serde::de::Deserializer;
+use crate::error::{FishPiError, GENERIC_ERROR_CODE};
+use crate::models::article::ArticleDetail;
+use serde::de::{Deserializer, IgnoredAny};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -81,6 +83,23 @@ impl Metal {
 
 pub type MetalList = Vec<Metal>;
 
+/// 解析勋章列表
+///
+/// 兼容 `sysMetal` 的几种形式：JSON 字符串、`{"list": [...]}` 对象与数组，无法解析的条目会被忽略
+pub fn parse_metals(value: &Value) -> MetalList {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => serde_json::from_str::<Value>(s)
+            .map(|v| parse_metals(&v))
+            .unwrap_or_default(),
+        Value::Object(map) => map.get("list").map(parse_metals).unwrap_or_default(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| serde_json::from_value::<Metal>(item.clone()).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
     #[serde(rename = "oId")]
@@ -172,6 +191,27 @@ impl UserInfo {
         }
     }
 
+    /// 已佩戴的勋章
+    pub fn metals(&self) -> MetalList {
+        parse_metals(&self.sys_metal)
+    }
+
+    /// 是否在线
+    pub fn is_online(&self) -> bool {
+        self.user_online_flag.unwrap_or(false)
+    }
+
+    /// 当前登录用户是否已关注该用户
+    ///
+    /// 根据 `canFollow` 判断：`no` 表示已关注，`yes` 表示未关注，其余情况（如本人、隐藏）无法确定
+    pub fn is_followed(&self) -> Option<bool> {
+        match self.can_follow.as_deref() {
+            Some("no") => Some(true),
+            Some("yes") => Some(false),
+            _ => None,
+        }
+    }
+
     pub fn all_name(&self) -> String {
         match &self.user_nickname {
             Some(nickname) if !nickname.is_empty() => format!("{}({})", nickname, self.user_name),
@@ -180,17 +220,455 @@ impl UserInfo {
     }
 }
 
+/// 用户关系
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Relationship {
+    /// 我是否关注了对方，无法确定时为 None
+    pub i_follow: Option<bool>,
+    /// 对方是否关注了我，无法确定时为 None
+    pub follows_me: Option<bool>,
+}
+
+impl Relationship {
+    /// 是否互相关注，任一方向无法确定时为 None
+    pub fn is_mutual(&self) -> Option<bool> {
+        match (self.i_follow, self.follows_me) {
+            (Some(a), Some(b)) => Some(a && b),
+            (Some(false), None) | (None, Some(false)) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// 用户获得的认可统计
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Reputation {
+    /// 收到的感谢数
+    pub thanks: i64,
+    /// 收到的赞同数
+    pub goods: i64,
+    /// 被收藏数
+    pub collects: i64,
+    /// 是否为估算值
+    ///
+    /// 由帖子列表累加得出时为 true：不含评论获得的感谢，且仅统计有限页数的帖子
+    pub approximate: bool,
+}
+
+impl Reputation {
+    /// 累加帖子的感谢、赞同、收藏数
+    pub fn add_articles(&mut self, articles: &[ArticleDetail]) {
+        for article in articles {
+            self.thanks += i64::from(article.thank_cnt);
+            self.goods += i64::from(article.good_cnt);
+            self.collects += i64::from(article.collect_cnt);
+        }
+    }
+}
+
+/// 社区活动
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Activity {
+    /// 活动 id
+    pub id: String,
+    /// 活动标题
+    pub title: String,
+    /// 活动描述
+    pub description: String,
+    /// 奖励积分
+    pub reward: i32,
+    /// 开始时间（毫秒时间戳）
+    pub start_time: i64,
+    /// 结束时间（毫秒时间戳）
+    pub end_time: i64,
+    /// 是否满足领取条件
+    pub eligible: bool,
+    /// 是否已领取
+    pub claimed: bool,
+}
+
+impl Activity {
+    /// 是否可以领取奖励
+    pub fn claimable(&self) -> bool {
+        self.eligible && !self.claimed
+    }
+}
+
+impl From<&Value> for Activity {
+    fn from(data: &Value) -> Self {
+        let str_field = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|k| data.get(*k))
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                })
+                .unwrap_or_default()
+        };
+        let bool_field = |key: &str| match data.get(key) {
+            Some(Value::Bool(b)) => *b,
+            Some(Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+            _ => false,
+        };
+
+        Self {
+            id: str_field(&["oId", "activityId", "id"]),
+            title: str_field(&["activityTitle", "title"]),
+            description: str_field(&["activityDescription", "description"]),
+            reward: parse_signed(data.get("activityReward").or(data.get("reward"))) as i32,
+            start_time: parse_signed(data.get("activityStartTime").or(data.get("startTime"))),
+            end_time: parse_signed(data.get("activityEndTime").or(data.get("endTime"))),
+            eligible: bool_field("eligible"),
+            claimed: bool_field("claimed"),
+        }
+    }
+}
+
+/// 签到结果
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CheckinResult {
+    /// 本次签到获得的积分
+    pub points: i64,
+    /// 当前连续签到天数
+    pub streak: i64,
+}
+
+impl From<&Value> for CheckinResult {
+    fn from(data: &Value) -> Self {
+        let data = data.get("data").filter(|d| d.is_object()).unwrap_or(data);
+        let field = |keys: &[&str]| parse_signed(keys.iter().find_map(|k| data.get(*k)));
+        Self {
+            points: field(&["sum", "points", "point"]),
+            streak: field(&["currentStreak", "streak", "checkinStreak"]),
+        }
+    }
+}
+
+/// 用户名最大长度
+pub const USERNAME_MAX_LEN: usize = 20;
+
+/// 校验用户名格式
+///
+/// - `name` 用户名，长度 1 到 20，仅允许字母、数字、下划线与连字符
+pub fn validate_username(name: &str) -> Result<(), FishPiError> {
+    if name.is_empty() {
+        return Err(FishPiError::Validation("用户名不能为空".to_string()));
+    }
+    if name.chars().count() > USERNAME_MAX_LEN {
+        return Err(FishPiError::Validation(format!(
+            "用户名长度不能超过 {} 个字符",
+            USERNAME_MAX_LEN
+        )));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(FishPiError::Validation(
+            "用户名只能包含字母、数字、下划线与连字符".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// 解析活跃度，兼容数字与字符串形式
+///
+/// 缺少 `liveness` 字段或无法解析为数字时返回 [`FishPiError::Parse`]，
+/// 避免把异常响应误报为 0 活跃度
+pub(crate) fn parse_liveness(data: &Value) -> Result<f64, FishPiError> {
+    let liveness = match data.get("liveness") {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().trim_end_matches('%').parse().ok(),
+        _ => None,
+    };
+    liveness.ok_or_else(|| FishPiError::Parse(format!("无法解析活跃度: {}", data)))
+}
+
+/// 排行榜类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum LeaderboardKind {
+    /// 积分（财富）榜
+    #[default]
+    Points,
+    /// 消费榜
+    Consumption,
+    /// 连续签到榜
+    CheckinStreak,
+}
+
+impl LeaderboardKind {
+    /// 排行榜接口路径
+    pub fn path(&self) -> &'static str {
+        match self {
+            LeaderboardKind::Points => "balance",
+            LeaderboardKind::Consumption => "consumption",
+            LeaderboardKind::CheckinStreak => "checkin",
+        }
+    }
+
+    /// 从字符串转换为枚举
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "points" | "balance" => Some(LeaderboardKind::Points),
+            "consumption" => Some(LeaderboardKind::Consumption),
+            "checkin" => Some(LeaderboardKind::CheckinStreak),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LeaderboardKind::Points => "财富榜",
+            LeaderboardKind::Consumption => "消费榜",
+            LeaderboardKind::CheckinStreak => "签到榜",
+        }
+    }
+
+    /// 各榜单数值所在字段
+    fn value_keys(&self) -> &'static [&'static str] {
+        match self {
+            LeaderboardKind::Points => &["userPoint", "value"],
+            LeaderboardKind::Consumption => &["userUsedPoint", "userPoint", "value"],
+            LeaderboardKind::CheckinStreak => &[
+                "userCurrentCheckinStreak",
+                "userLongestCheckinStreak",
+                "value",
+            ],
+        }
+    }
+}
+
+/// 排行榜条目
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    /// 名次，从 1 开始
+    pub rank: u32,
+    /// 用户名
+    pub user_name: String,
+    /// 昵称
+    pub nickname: String,
+    /// 榜单数值（积分、消费或连续签到天数）
+    pub value: i64,
+}
+
+impl LeaderboardEntry {
+    /// 解析排行榜条目
+    ///
+    /// - `kind` 榜单类型，决定数值字段
+    /// - `rank` 接口未返回名次时使用的默认名次
+    /// - `data` 条目 JSON
+    pub fn parse(kind: LeaderboardKind, rank: u32, data: &Value) -> Self {
+        let str_field = |key: &str| {
+            data.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        Self {
+            rank: data
+                .get("rank")
+                .and_then(|v| v.as_u64())
+                .map(|r| r as u32)
+                .unwrap_or(rank),
+            user_name: str_field("userName"),
+            nickname: str_field("userNickname"),
+            value: parse_signed(kind.value_keys().iter().find_map(|k| data.get(*k))),
+        }
+    }
+}
+
+/// 活动奖励领取失败原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityClaimFailure {
+    /// 不满足领取条件
+    NotEligible,
+    /// 已领取过
+    AlreadyClaimed,
+    /// 其他错误
+    Other(String),
+}
+
+impl ActivityClaimFailure {
+    /// 根据服务端返回的错误信息判断失败原因
+    pub fn from_msg(msg: &str) -> Self {
+        let lower = msg.to_lowercase();
+        if msg.contains("已领取") || lower.contains("already") {
+            Self::AlreadyClaimed
+        } else if msg.contains("不满足") || msg.contains("资格") || lower.contains("eligible")
+        {
+            Self::NotEligible
+        } else {
+            Self::Other(msg.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for ActivityClaimFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotEligible => write!(f, "不满足活动领取条件"),
+            Self::AlreadyClaimed => write!(f, "活动奖励已领取"),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// 积分流水记录
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PointRecord {
+    /// 记录时间（毫秒时间戳）
+    pub time: i64,
+    /// 积分变动，收入为正，支出为负
+    pub delta: i32,
+    /// 变动后余额
+    pub balance_after: i32,
+    /// 变动类型
+    pub kind: String,
+    /// 变动描述
+    pub description: String,
+}
+
+impl PointRecord {
+    /// 是否为收入
+    pub fn is_income(&self) -> bool {
+        self.delta > 0
+    }
+}
+
+/// 解析可能带符号的数值字段，兼容数字与 `"+10"`/`"-5"` 形式的字符串
+fn parse_signed(value: Option<&Value>) -> i64 {
+    match value {
+        Some(Value::Number(n)) => n
+            .as_i64()
+            .or_else(|| n.as_f64().map(|f| f as i64))
+            .unwrap_or(0),
+        Some(Value::String(s)) => {
+            let s = s.trim();
+            s.strip_prefix('+').unwrap_or(s).parse::<i64>().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+impl From<&Value> for PointRecord {
+    fn from(data: &Value) -> Self {
+        Self {
+            time: parse_signed(data.get("time")),
+            delta: parse_signed(data.get("sum")) as i32,
+            balance_after: parse_signed(data.get("balance")) as i32,
+            kind: data
+                .get("type")
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                })
+                .unwrap_or_default(),
+            description: data
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub code: i32,
     pub msg: Option<String>,
     #[serde(rename = "Key")]
     pub key: Option<String>,
+    /// 需要验证码时服务端返回的验证码标识
+    #[serde(
+        rename = "needCaptcha",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub need_captcha: Option<String>,
+}
+
+impl LoginResponse {
+    /// 是否登录成功
+    pub fn is_success(&self) -> bool {
+        self.code == 0 && self.key.is_some()
+    }
+
+    /// 识别登录失败原因，登录成功时返回 `None`
+    ///
+    /// 优先依据 `needCaptcha` 与状态码判断；服务端对其余失败统一返回通用错误码
+    /// [`GENERIC_ERROR_CODE`]，仅此时才退回到按提示文案识别
+    pub fn failure(&self) -> Option<LoginFailure> {
+        if self.is_success() {
+            return None;
+        }
+
+        let msg = self.msg.clone().unwrap_or_else(|| "登录失败".to_string());
+        if let Some(token) = self.need_captcha.as_deref().filter(|t| !t.is_empty()) {
+            return Some(LoginFailure::Challenge(LoginChallenge::Captcha {
+                token: token.to_string(),
+            }));
+        }
+        match self.code {
+            401 => Some(LoginFailure::WrongPassword(msg)),
+            GENERIC_ERROR_CODE => {
+                if ["两步验证", "一次性密码", "二次验证"]
+                    .iter()
+                    .any(|k| msg.contains(k))
+                {
+                    Some(LoginFailure::Challenge(LoginChallenge::Mfa))
+                } else if ["密码错误", "用户名或密码", "用户不存在"]
+                    .iter()
+                    .any(|k| msg.contains(k))
+                {
+                    Some(LoginFailure::WrongPassword(msg))
+                } else {
+                    Some(LoginFailure::Other(msg))
+                }
+            }
+            _ => Some(LoginFailure::Other(msg)),
+        }
+    }
 }
 
+/// 登录时服务端要求的额外验证
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginChallenge {
+    /// 需要验证码，将 `token` 与用户输入的验证码一起通过 `login_with_captcha` 重试
+    Captcha { token: String },
+    /// 需要二次验证码，通过 `login_with_mfa` 重试
+    Mfa,
+}
+
+/// 登录失败原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginFailure {
+    /// 需要额外验证
+    Challenge(LoginChallenge),
+    /// 用户名或密码错误
+    WrongPassword(String),
+    /// 其他错误
+    Other(String),
+}
+
+impl std::fmt::Display for LoginFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginFailure::Challenge(LoginChallenge::Captcha { .. }) => write!(f, "需要验证码"),
+            LoginFailure::Challenge(LoginChallenge::Mfa) => write!(f, "需要二次验证(MFA)"),
+            LoginFailure::WrongPassword(msg) => write!(f, "密码错误: {}", msg),
+            LoginFailure::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// 接口响应，反序列化时经由 [`ApiEnvelope`] 统一 `code`/`result` 两种状态码约定
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ApiEnvelope<T>")]
 pub struct ApiResponse<T> {
-    #[serde(default)]
     pub code: i32,
     pub msg: Option<String>,
     pub data: Option<T>,
@@ -212,6 +690,18 @@ impl<T> ApiResponse<T> {
             data: None,
         }
     }
+
+    /// 转换数据部分，保留响应码与提示信息
+    pub fn map_data<U, F>(self, f: F) -> ApiResponse<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        ApiResponse {
+            code: self.code,
+            msg: self.msg,
+            data: self.data.map(f),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -219,6 +709,9 @@ pub struct Response<T> {
     pub success: bool,
     pub message: Option<String>,
     pub data: Option<T>,
+    /// 失败原因，便于程序区分未登录、限流、网络错误等情况；`message` 保留可读描述
+    #[serde(skip)]
+    pub error: Option<FishPiError>,
 }
 
 impl<T> Response<T> {
@@ -228,6 +721,7 @@ impl<T> Response<T> {
             success: true,
             message: None,
             data: Some(data),
+            error: None,
         }
     }
 
@@ -237,9 +731,26 @@ impl<T> Response<T> {
             success: false,
             message: Some(message.to_string()),
             data: None,
+            error: None,
         }
     }
 
+    /// 根据错误类型创建错误响应，`message` 为错误的描述
+    pub fn from_error(error: FishPiError) -> Self {
+        Self {
+            success: false,
+            message: Some(error.to_string()),
+            data: None,
+            error: Some(error),
+        }
+    }
+
+    /// 附加错误类型，不改变 `message`
+    pub fn with_error(mut self, error: FishPiError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
     /// 映射响应数据
     pub fn map<U, F>(self, f: F) -> Response<U>
     where
@@ -251,12 +762,14 @@ impl<T> Response<T> {
                     success: true,
                     message: None,
                     data: Some(f(data)),
+                    error: None,
                 }
             } else {
                 Response {
                     success: true,
                     message: self.message,
                     data: None,
+                    error: None,
                 }
             }
         } else {
@@ -264,10 +777,35 @@ impl<T> Response<T> {
                 success: false,
                 message: self.message,
                 data: None,
+                error: self.error,
             }
         }
     }
 
+    /// 转为 `Result`，失败时返回错误类型，缺少错误类型时以 `message` 构造 `ApiError`
+    ///
+    /// 成功但没有数据时返回 `Parse` 错误；不返回数据的接口请使用 [`Self::into_optional_result`]
+    pub fn into_typed_result(self) -> Result<T, FishPiError> {
+        self.into_optional_result()?
+            .ok_or_else(|| FishPiError::Parse("响应缺少数据".to_string()))
+    }
+
+    /// 转为 `Result`，成功但没有数据时返回 `Ok(None)`，失败时同 [`Self::into_typed_result`]
+    pub fn into_optional_result(self) -> Result<Option<T>, FishPiError> {
+        if !self.success {
+            return Err(self.error.unwrap_or_else(|| FishPiError::ApiError {
+                code: GENERIC_ERROR_CODE,
+                msg: self.message.unwrap_or_default(),
+            }));
+        }
+        Ok(self.data)
+    }
+
+    /// 转为 `anyhow::Result`，便于使用 `?`
+    pub fn into_result(self) -> anyhow::Result<T> {
+        self.into_typed_result().map_err(anyhow::Error::new)
+    }
+
     /// 映射错误信息
     pub fn map_err<F>(self, f: F) -> Self
     where
@@ -279,6 +817,7 @@ impl<T> Response<T> {
                 success: false,
                 message: Some(f(msg)),
                 data: None,
+                error: self.error,
             }
         } else {
             self
@@ -286,6 +825,88 @@ impl<T> Response<T> {
     }
 }
 
+/// 服务端通用响应信封
+///
+/// 兼容 `{code, msg, data}` 与 `{result, msg, data}` 两种格式，`code`/`result` 为 0 表示成功，
+/// 两者均缺失时视为成功
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEnvelope<T> {
+    #[serde(default)]
+    pub code: Option<i32>,
+    #[serde(default)]
+    pub result: Option<i32>,
+    #[serde(default)]
+    pub msg: Option<String>,
+    #[serde(default = "Option::default")]
+    pub data: Option<T>,
+}
+
+impl<T> ApiEnvelope<T> {
+    /// 状态码，优先 `code`，其次 `result`
+    pub fn status(&self) -> i32 {
+        self.code.or(self.result).unwrap_or(0)
+    }
+
+    /// 是否成功
+    pub fn is_success(&self) -> bool {
+        self.status() == 0
+    }
+
+    /// 状态码非 0 时转换为对应错误，服务端未返回 `msg` 时使用 `default_msg`
+    pub fn check(&self, default_msg: &str) -> Result<(), FishPiError> {
+        if self.is_success() {
+            return Ok(());
+        }
+        let msg = self.msg.as_deref().unwrap_or(default_msg);
+        Err(FishPiError::from_code(self.status(), msg))
+    }
+
+    /// 映射响应数据
+    pub fn map_data<U, F>(self, f: F) -> ApiEnvelope<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        ApiEnvelope {
+            code: self.code,
+            result: self.result,
+            msg: self.msg,
+            data: self.data.map(f),
+        }
+    }
+}
+
+impl ApiEnvelope<IgnoredAny> {
+    /// 只读取原始响应中的 `code`/`result`/`msg`，不解析 `data`
+    ///
+    /// 适用于需要保留完整 JSON 响应、结果不一定位于 `data` 中的接口
+    pub fn peek(response: &Value) -> Result<Self, FishPiError> {
+        Self::deserialize(response).map_err(|e| FishPiError::Parse(format!("响应格式错误: {}", e)))
+    }
+}
+
+impl<T> From<ApiEnvelope<T>> for ApiResponse<T> {
+    fn from(envelope: ApiEnvelope<T>) -> Self {
+        Self {
+            code: envelope.status(),
+            msg: envelope.msg,
+            data: envelope.data,
+        }
+    }
+}
+
+/// 由于孤儿规则无法为 `T` 实现 `TryFrom<Response<T>>`，改为转换到 `Result`
+impl<T> From<Response<T>> for Result<T, FishPiError> {
+    fn from(response: Response<T>) -> Self {
+        response.into_typed_result()
+    }
+}
+
+impl<T> From<ApiEnvelope<T>> for Response<T> {
+    fn from(envelope: ApiEnvelope<T>) -> Self {
+        ApiResponse::from(envelope).into()
+    }
+}
+
 impl<T> From<ApiResponse<T>> for Response<T> {
     fn from(response: ApiResponse<T>) -> Self {
         if response.code == 0 {
@@ -296,15 +917,218 @@ impl<T> From<ApiResponse<T>> for Response<T> {
                     success: true,
                     message: None,
                     data: None,
+                    error: None,
                 }
             }
         } else {
-            Self::error(
-                response
-                    .msg
-                    .unwrap_or_else(|| "Unknown error".to_string())
-                    .as_str(),
-            )
+            let msg = response.msg.unwrap_or_else(|| "Unknown error".to_string());
+            Self::error(&msg).with_error(FishPiError::from_code(response.code, &msg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_response_reads_result_code() {
+        let failed: ApiResponse<()> =
+            serde_json::from_str(r#"{"result":-1,"msg":"失败"}"#).unwrap();
+        let ok: ApiResponse<i32> = serde_json::from_str(r#"{"result":0,"data":1}"#).unwrap();
+
+        assert_eq!(failed.code, -1);
+        assert_eq!(failed.msg.as_deref(), Some("失败"));
+        assert_eq!(ok.code, 0);
+        assert_eq!(ok.data, Some(1));
+    }
+
+    #[test]
+    fn envelope_peek_and_check_follow_both_conventions() {
+        let ok = serde_json::json!({"result": 0, "oId": "1"});
+        let failed = serde_json::json!({"code": 429, "msg": "发送太频繁"});
+        let bare = serde_json::json!({"msg": "缺少状态码"});
+
+        assert_eq!(ApiEnvelope::peek(&ok).unwrap().check("失败"), Ok(()));
+        assert_eq!(
+            ApiEnvelope::peek(&failed).unwrap().check("失败"),
+            Err(FishPiError::RateLimited)
+        );
+        assert!(ApiEnvelope::peek(&bare).unwrap().is_success());
+        assert!(matches!(
+            ApiEnvelope::peek(&serde_json::json!({"code": "x"})),
+            Err(FishPiError::Parse(_))
+        ));
+    }
+
+    fn login_response(json: &str) -> LoginResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn login_failure_returns_captcha_token() {
+        let response = login_response(r#"{"code":-1,"msg":"请输入验证码","needCaptcha":"abc"}"#);
+        assert_eq!(
+            response.failure(),
+            Some(LoginFailure::Challenge(LoginChallenge::Captcha {
+                token: "abc".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn login_failure_classifies_by_code() {
+        let response = login_response(r#"{"code":401,"msg":"密码不能包含空格"}"#);
+        assert_eq!(
+            response.failure(),
+            Some(LoginFailure::WrongPassword("密码不能包含空格".to_string()))
+        );
+
+        let response = login_response(r#"{"code":429,"msg":"密码尝试次数过多"}"#);
+        assert_eq!(
+            response.failure(),
+            Some(LoginFailure::Other("密码尝试次数过多".to_string()))
+        );
+    }
+
+    #[test]
+    fn login_failure_falls_back_to_message_for_generic_code() {
+        let response = login_response(r#"{"code":-1,"msg":"两步验证失败"}"#);
+        assert_eq!(
+            response.failure(),
+            Some(LoginFailure::Challenge(LoginChallenge::Mfa))
+        );
+
+        let response = login_response(r#"{"code":-1,"msg":"用户名或密码错误"}"#);
+        assert_eq!(
+            response.failure(),
+            Some(LoginFailure::WrongPassword("用户名或密码错误".to_string()))
+        );
+    }
+
+    #[test]
+    fn login_failure_is_none_on_success() {
+        let response = login_response(r#"{"code":0,"Key":"key"}"#);
+        assert_eq!(response.failure(), None);
+    }
+
+    #[test]
+    fn parse_liveness_accepts_numbers_and_percent_strings() {
+        assert_eq!(
+            parse_liveness(&serde_json::json!({"liveness": 12.5})).unwrap(),
+            12.5
+        );
+        assert_eq!(
+            parse_liveness(&serde_json::json!({"liveness": " 30% "})).unwrap(),
+            30.0
+        );
+    }
+
+    #[test]
+    fn parse_liveness_rejects_missing_or_invalid_values() {
+        for data in [
+            serde_json::json!({"code": -1, "msg": "未登录"}),
+            serde_json::json!({"liveness": "n/a"}),
+            serde_json::json!({"liveness": null}),
+        ] {
+            assert!(matches!(parse_liveness(&data), Err(FishPiError::Parse(_))));
+        }
+    }
+
+    #[test]
+    fn into_optional_result_accepts_success_without_data() {
+        let response: Response<()> = ApiResponse {
+            code: 0,
+            msg: None,
+            data: None,
         }
+        .into();
+        assert_eq!(response.clone().into_optional_result(), Ok(None));
+        assert!(matches!(
+            response.into_typed_result(),
+            Err(FishPiError::Parse(_))
+        ));
+
+        let response: Response<()> = Response::error("失败");
+        assert!(matches!(
+            response.into_optional_result(),
+            Err(FishPiError::ApiError { code: -1, .. })
+        ));
+    }
+
+    #[test]
+    fn validate_username_checks_length_and_charset() {
+        assert!(validate_username("alice_01").is_ok());
+        assert!(validate_username("a-b").is_ok());
+        assert!(validate_username(&"a".repeat(USERNAME_MAX_LEN)).is_ok());
+        let too_long = "a".repeat(USERNAME_MAX_LEN + 1);
+        for name in ["", "   ", " alice", "a b", "鱼排", "a@b", too_long.as_str()] {
+            assert!(matches!(
+                validate_username(name),
+                Err(FishPiError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn point_record_parses_signed_amounts_and_missing_fields() {
+        let income = PointRecord::from(&serde_json::json!({
+            "time": 1700000000000i64,
+            "sum": "+10",
+            "balance": 110,
+            "type": "checkin",
+            "description": "签到奖励",
+        }));
+        assert_eq!(income.time, 1700000000000);
+        assert_eq!(income.delta, 10);
+        assert_eq!(income.balance_after, 110);
+        assert_eq!(income.kind, "checkin");
+        assert_eq!(income.description, "签到奖励");
+        assert!(income.is_income());
+
+        let spend = PointRecord::from(&serde_json::json!({
+            "sum": " -5 ",
+            "balance": 95.0,
+            "type": 3,
+        }));
+        assert_eq!(spend.delta, -5);
+        assert_eq!(spend.balance_after, 95);
+        assert_eq!(spend.kind, "3");
+        assert!(!spend.is_income());
+
+        let empty = PointRecord::from(&serde_json::json!({ "sum": "abc", "type": null }));
+        assert_eq!(empty.time, 0);
+        assert_eq!(empty.delta, 0);
+        assert_eq!(empty.balance_after, 0);
+        assert_eq!(empty.kind, "");
+        assert_eq!(empty.description, "");
+    }
+
+    #[test]
+    fn reputation_add_articles_sums_counts_across_calls() {
+        let article = |thanks, goods, collects| ArticleDetail {
+            thank_cnt: thanks,
+            good_cnt: goods,
+            collect_cnt: collects,
+            ..Default::default()
+        };
+        let mut reputation = Reputation {
+            approximate: true,
+            ..Default::default()
+        };
+
+        reputation.add_articles(&[article(1, 2, 3), article(10, 0, 5)]);
+        reputation.add_articles(&[]);
+        reputation.add_articles(&[article(0, 7, 0)]);
+
+        assert_eq!(
+            reputation,
+            Reputation {
+                thanks: 11,
+                goods: 9,
+                collects: 8,
+                approximate: true,
+            }
+        );
     }
 }
@@ -1,22 +1,73 @@
 use anyhow::Result;
+use futures::channel::mpsc::UnboundedSender;
+use futures::{Stream, StreamExt};
 use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Mutex;
 
 use crate::api::ArticleApi;
+use crate::api::client::{
+    ConnectionState, ReconnectConfig, WEBSOCKET_CLOSE_TIMEOUT, WebSocketConnection,
+};
+use crate::error::{FishPiError, ensure_content};
 use crate::models::article::{
-    ArticleComment, ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticlePost,
-    CommentPost, ResponseResult,
+    ArticleComment, ArticleDetail, ArticleList, ArticleListParams, ArticleListType, ArticleNotify,
+    ArticlePost, CommentPost, HotRange, ResponseResult, VoteStatus,
 };
+use crate::models::user::Response;
+use crate::services::UserService;
+use crate::utils::strip_html_tags;
+
+/// 按时间范围筛选热门帖子时最多扫描的页数
+const HOT_RANGE_MAX_PAGES: i32 = 10;
+
+/// 帖子频道连接
+#[derive(Clone, Debug, Default)]
+struct LiveConnection {
+    connection: Arc<Mutex<Option<WebSocketConnection>>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl LiveConnection {
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// 取消订阅并关闭当前连接
+    async fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let connection = self.connection.lock().await.take();
+        if let Some(connection) = connection {
+            connection.shutdown(WEBSOCKET_CLOSE_TIMEOUT).await;
+        }
+    }
+}
 
 /// 帖子服务
 #[derive(Clone, Debug)]
 pub struct ArticleService {
     article_api: ArticleApi,
+    live_connections: Arc<Mutex<HashMap<String, LiveConnection>>>,
+    /// 用于确定当前登录用户
+    user: Option<UserService>,
 }
 
 impl ArticleService {
     /// 创建新的帖子服务实例
     pub fn new(article_api: ArticleApi) -> Self {
-        Self { article_api }
+        Self {
+            article_api,
+            live_connections: Arc::new(Mutex::new(HashMap::new())),
+            user: None,
+        }
+    }
+
+    /// 关联用户服务，用于查询当前登录用户的帖子
+    pub fn with_user(mut self, user: UserService) -> Self {
+        self.user = Some(user);
+        self
     }
 
     /// 发布帖子
@@ -38,6 +89,15 @@ impl ArticleService {
         self.article_api.update_article(id, data).await
     }
 
+    /// 删除帖子
+    ///
+    /// - `id` 帖子 Id
+    ///
+    /// 返回删除的帖子 Id，只能删除自己的帖子
+    pub async fn remove(&self, id: &str) -> Result<String> {
+        self.article_api.remove_article(id).await
+    }
+
     /// 查询帖子列表，使用完整参数
     ///
     /// - `params` 帖子列表查询参数
@@ -47,6 +107,43 @@ impl ArticleService {
         self.article_api.get_article_list_with_params(params).await
     }
 
+    /// 以流的形式逐页获取帖子
+    ///
+    /// - `params` 帖子列表查询参数，从 `params.page` 开始翻页
+    ///
+    /// 按需请求下一页，直到超过 `Pagination.count` 或返回空页；
+    /// 请求失败时产出错误并结束
+    pub fn list_stream(
+        &self,
+        params: ArticleListParams,
+    ) -> impl Stream<Item = Result<ArticleDetail>> + Send + 'static {
+        let article_api = self.article_api.clone();
+        let state = (params, VecDeque::<ArticleDetail>::new(), false);
+
+        futures::stream::unfold(state, move |(mut params, mut buffer, mut done)| {
+            let article_api = article_api.clone();
+            async move {
+                loop {
+                    if let Some(article) = buffer.pop_front() {
+                        return Some((Ok(article), (params, buffer, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    match article_api.get_article_list_with_params(&params).await {
+                        Ok(list) => {
+                            done = list.list.is_empty() || params.page >= list.pagination.count;
+                            params.page += 1;
+                            buffer.extend(list.list);
+                        }
+                        Err(err) => return Some((Err(err), (params, buffer, true))),
+                    }
+                }
+            }
+        })
+    }
+
     /// 查询帖子列表 (兼容旧方法)
     ///
     /// - `type_` 查询类型，来自 ArticleListType
@@ -87,6 +184,36 @@ impl ArticleService {
         self.article_api.get_hot_articles(page, size).await
     }
 
+    /// 获取指定时间范围内的热门帖子列表
+    ///
+    /// 服务端热门列表不支持时间范围参数，按帖子创建时间在客户端过滤；
+    /// 从 `page` 起依次翻页，直到凑满 `size` 篇、没有更多页或已扫描 10 页
+    ///
+    /// - `range` 时间范围
+    /// - `page` 起始页码
+    /// - `size` 每页数量
+    ///
+    /// 返回帖子列表，分页信息取自最后一次请求
+    pub async fn hot_in_range(&self, range: HotRange, page: i32, size: i32) -> Result<ArticleList> {
+        let now = chrono::Local::now();
+        let wanted = size.max(0) as usize;
+        let mut result = ArticleList::new();
+
+        for current in page..page.saturating_add(HOT_RANGE_MAX_PAGES) {
+            let mut list = self.article_api.get_hot_articles(current, size).await?;
+            let exhausted = list.list.is_empty() || current >= list.pagination.count;
+            list.retain_in_range(range, now);
+            result.list.append(&mut list.list);
+            result.pagination = list.pagination;
+            if result.list.len() >= wanted || exhausted {
+                break;
+            }
+        }
+
+        result.list.truncate(wanted);
+        Ok(result)
+    }
+
     /// 获取点赞帖子列表
     ///
     /// - `page` 页码
@@ -212,6 +339,47 @@ impl ArticleService {
             .await
     }
 
+    /// 搜索帖子
+    ///
+    /// - `keyword` 关键词
+    /// - `page` 页码
+    /// - `size` 每页数量
+    ///
+    /// 返回帖子列表，关键词为空时返回空列表
+    pub async fn search(&self, keyword: &str, page: i32, size: i32) -> Result<ArticleList> {
+        self.article_api.search_articles(keyword, page, size).await
+    }
+
+    /// 查询当前登录用户的帖子列表
+    ///
+    /// - `page` 页码
+    /// - `size` 每页数量
+    ///
+    /// 返回帖子列表。当前用户名取自关联的用户服务缓存，未通过 `with_user` 关联时返回错误
+    pub async fn my_articles(&self, page: i32, size: i32) -> Result<ArticleList> {
+        let Some(user) = &self.user else {
+            return Err(
+                FishPiError::Validation("未关联用户服务，无法确定当前用户".to_string()).into(),
+            );
+        };
+        let user_name = match user.current_username() {
+            Some(name) => name,
+            None => {
+                let result = user.fetch_current_user().await;
+                match result.data {
+                    Some(info) => info.user_name,
+                    None => {
+                        return Err(result
+                            .error
+                            .unwrap_or_else(|| FishPiError::Parse("获取当前用户失败".to_string()))
+                            .into());
+                    }
+                }
+            }
+        };
+        self.list_by_user(&user_name, page, size).await
+    }
+
     /// 获取帖子详情
     ///
     /// - `id` 帖子id
@@ -222,6 +390,35 @@ impl ArticleService {
         self.article_api.get_article_detail(id, p).await
     }
 
+    /// 并发获取多篇帖子详情
+    ///
+    /// - `ids` 帖子id列表
+    /// - `concurrency` 最大并发请求数，为 0 时按 1 处理
+    ///
+    /// 返回结果与 `ids` 顺序一致，每篇帖子的成功或失败单独记录
+    pub async fn get_details(
+        &self,
+        ids: &[&str],
+        concurrency: usize,
+    ) -> Vec<Response<ArticleDetail>> {
+        let mut results: Vec<(usize, Response<ArticleDetail>)> =
+            futures::stream::iter(ids.iter().enumerate())
+                .map(|(index, id)| async move {
+                    let response = match self.article_api.get_article_detail(id, 1).await {
+                        Ok(detail) => Response::success(detail),
+                        Err(err) => Response::error(&format!("获取帖子 {} 详情失败: {}", id, err))
+                            .with_error(FishPiError::from_anyhow(&err)),
+                    };
+                    (index, response)
+                })
+                .buffer_unordered(concurrency.max(1))
+                .collect()
+                .await;
+
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, response)| response).collect()
+    }
+
     /// 点赞/取消点赞帖子
     ///
     /// - `id` 帖子id
@@ -232,6 +429,88 @@ impl ArticleService {
         self.article_api.vote_article(id, like).await
     }
 
+    /// 点赞帖子，已点赞时取消点赞
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回操作后的投票状态：[`VoteStatus::Up`] 为已点赞，[`VoteStatus::Normal`] 为已取消
+    pub async fn like(&self, id: &str) -> Response<VoteStatus> {
+        self.vote_status(id, true).await
+    }
+
+    /// 点踩帖子，已点踩时取消点踩
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回操作后的投票状态：[`VoteStatus::Down`] 为已点踩，[`VoteStatus::Normal`] 为已取消
+    pub async fn dislike(&self, id: &str) -> Response<VoteStatus> {
+        self.vote_status(id, false).await
+    }
+
+    async fn vote_status(&self, id: &str, like: bool) -> Response<VoteStatus> {
+        if id.trim().is_empty() {
+            return Response::from_error(FishPiError::Validation("帖子id不能为空".to_string()));
+        }
+        match self.article_api.vote_article_status(id, like).await {
+            Ok(status) => Response::success(status),
+            Err(err) => Response::error(&format!("投票失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
+    /// 收藏或取消收藏帖子
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回操作后的收藏状态，true 为已收藏
+    pub async fn collect_toggle(&self, id: &str) -> Response<bool> {
+        self.toggle_state(id, true).await
+    }
+
+    /// 关注或取消关注帖子
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回操作后的关注状态，true 为已关注
+    pub async fn watch_toggle(&self, id: &str) -> Response<bool> {
+        self.toggle_state(id, false).await
+    }
+
+    /// 切换收藏（`collect` 为 true）或关注，再读取帖子详情得到切换后的状态
+    async fn toggle_state(&self, id: &str, collect: bool) -> Response<bool> {
+        if id.trim().is_empty() {
+            return Response::from_error(FishPiError::Validation("帖子id不能为空".to_string()));
+        }
+
+        let (action, result) = if collect {
+            ("收藏", self.article_api.follow_article(id).await)
+        } else {
+            ("关注", self.article_api.watch_article(id).await)
+        };
+        match result {
+            Ok(result) if result.code == 0 => {}
+            Ok(result) => {
+                let msg = if result.msg.is_empty() {
+                    format!("{}失败", action)
+                } else {
+                    result.msg
+                };
+                return Response::from_error(FishPiError::from_code(result.code, &msg));
+            }
+            Err(err) => {
+                return Response::error(&format!("{}失败: {}", action, err))
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        }
+
+        match self.article_api.get_article_detail(id, 1).await {
+            Ok(detail) if collect => Response::success(detail.is_following),
+            Ok(detail) => Response::success(detail.is_watching),
+            Err(err) => Response::error(&format!("获取{}状态失败: {}", action, err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
     /// 感谢帖子
     ///
     /// - `id` 帖子id
@@ -268,6 +547,69 @@ impl ArticleService {
         self.article_api.reward_article(id).await
     }
 
+    /// 打赏帖子并获取打赏内容
+    ///
+    /// 已打赏或自己的帖子不会重复打赏
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回去除 HTML 标签后的打赏内容
+    pub async fn reward_and_get_content(&self, id: &str) -> Response<String> {
+        let mut detail = match self.article_api.get_article_detail(id, 1).await {
+            Ok(detail) => detail,
+            Err(err) => {
+                return Response::error(&format!("获取帖子详情失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        };
+
+        if detail.reward_point <= 0 {
+            return Response::error("该帖子没有打赏内容")
+                .with_error(FishPiError::Validation("该帖子没有打赏内容".to_string()));
+        }
+
+        if !detail.rewarded && !detail.is_my_article {
+            match self.article_api.reward_article(id).await {
+                Ok(result) if result.code == 0 => {}
+                Ok(result) => {
+                    return Response::error(&format!("打赏失败: {}", result.msg))
+                        .with_error(FishPiError::from_code(result.code, &result.msg));
+                }
+                Err(err) => {
+                    return Response::error(&format!("打赏失败: {}", err))
+                        .with_error(FishPiError::from_anyhow(&err));
+                }
+            }
+
+            detail = match self.article_api.get_article_detail(id, 1).await {
+                Ok(detail) => detail,
+                Err(err) => {
+                    return Response::error(&format!("获取打赏内容失败: {}", err))
+                        .with_error(FishPiError::from_anyhow(&err));
+                }
+            };
+        }
+
+        if detail.reward_content.trim().is_empty() {
+            return Response::error("打赏内容为空");
+        }
+
+        Response::success(strip_html_tags(&detail.reward_content))
+    }
+
+    /// 获取帖子评论数与最后评论者
+    ///
+    /// - `id` 帖子id
+    ///
+    /// 返回 `(评论数, 最后评论者)`，无评论时为 `(0, "")`
+    pub async fn comment_stats(&self, id: &str) -> Response<(i32, String)> {
+        match self.article_api.get_article_detail(id, 1).await {
+            Ok(detail) => Response::success(detail.comment_stats()),
+            Err(err) => Response::error(&format!("获取帖子评论统计失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
     /// 获取帖子在线人数
     ///
     /// - `id` 帖子id
@@ -277,25 +619,136 @@ impl ArticleService {
         self.article_api.get_article_heat(id).await
     }
 
-    // /// 添加帖子监听器（WebSocket）
-    // ///
-    // /// - `id` 帖子id
-    // /// - `article_type` 帖子类型
-    // /// - `on_message` 消息回调函数
-    // /// - `on_error` 错误回调函数
-    // /// - `on_close` 关闭回调函数
-    // ///
-    // /// 返回连接结果
-    // pub async fn add_listener(
-    //     &self,
-    //     id: &str,
-    //     article_type: i32,
-    //     on_message: impl Fn(Value) + Send + 'static,
-    //     on_error: Option<impl Fn(String) + Send + 'static>,
-    //     on_close: Option<impl Fn() + Send + 'static>,
-    // ) -> Result<()> {
-    //     self.article_api.add_article_listener(id, article_type, on_message, on_error, on_close).await
-    // }
+    /// 订阅帖子实时事件（新评论、浏览人数变化、新关注者）
+    ///
+    /// - `id` 帖子id
+    /// - `article_type` 帖子类型
+    /// - `callback` 事件回调
+    ///
+    /// 同一帖子重复订阅会替换之前的连接；连接意外断开后按默认重连策略自动重连，
+    /// 调用 [`Self::disconnect`] 后停止
+    pub async fn watch_live<F>(&self, id: &str, article_type: i32, callback: F) -> Response<()>
+    where
+        F: Fn(ArticleNotify) + Send + Sync + 'static,
+    {
+        let _ = self.disconnect(id).await;
+
+        let live = LiveConnection::default();
+        let callback = Arc::new(callback);
+        let (closed_sender, mut closed_receiver) =
+            futures::channel::mpsc::unbounded::<ConnectionState>();
+        let open = {
+            let article_api = self.article_api.clone();
+            let live = live.clone();
+            let id = id.to_string();
+            move || {
+                Self::open_live(
+                    article_api.clone(),
+                    live.clone(),
+                    id.clone(),
+                    article_type,
+                    callback.clone(),
+                    closed_sender.clone(),
+                )
+            }
+        };
+
+        if let Err(e) = open().await {
+            return Response::error(&format!("连接帖子频道失败: {}", e));
+        }
+        self.live_connections
+            .lock()
+            .await
+            .insert(id.to_string(), live.clone());
+
+        let metrics = self.article_api.client().metrics().clone();
+        tokio::spawn(async move {
+            let config = ReconnectConfig::default();
+            'watch: while let Some(state) = closed_receiver.next().await {
+                if let ConnectionState::Banned { reason } = state {
+                    log::warn!("帖子频道连接被服务端关闭: {}", reason);
+                    break;
+                }
+                let mut retry_times = 0;
+                loop {
+                    if live.is_closed() || !config.can_retry(retry_times) {
+                        break 'watch;
+                    }
+                    tokio::time::sleep(config.backoff_delay(retry_times)).await;
+                    if live.is_closed() {
+                        break 'watch;
+                    }
+                    metrics.record_reconnect();
+                    match open().await {
+                        Ok(()) => break,
+                        Err(e) => {
+                            log::debug!("帖子频道重连失败: {}", e);
+                            retry_times += 1;
+                        }
+                    }
+                }
+            }
+        });
+
+        Response::success(())
+    }
+
+    /// 建立一次帖子频道连接
+    ///
+    /// 每次连接都按当前令牌重新生成地址，重新登录后的重连不会使用失效的令牌；
+    /// 连接期间订阅已被取消时立即关闭新连接
+    async fn open_live<F>(
+        article_api: ArticleApi,
+        live: LiveConnection,
+        id: String,
+        article_type: i32,
+        callback: Arc<F>,
+        closed_sender: UnboundedSender<ConnectionState>,
+    ) -> Result<()>
+    where
+        F: Fn(ArticleNotify) + Send + Sync + 'static,
+    {
+        let url = article_api.article_channel_url(&id, article_type).await?;
+        let connection = article_api
+            .client()
+            .connect_websocket(
+                url.as_str(),
+                None,
+                move |value: Value| callback(ArticleNotify::from(&value)),
+                None::<fn(String)>,
+                Some(move |state: ConnectionState| {
+                    let _ = closed_sender.unbounded_send(state);
+                }),
+            )
+            .await?;
+
+        // 持有锁时检查取消标记，与 `LiveConnection::close` 先置标记后取连接的顺序配合，
+        // 保证取消后建立的连接一定会被关闭
+        let mut slot = live.connection.lock().await;
+        if live.is_closed() {
+            drop(slot);
+            connection.shutdown(WEBSOCKET_CLOSE_TIMEOUT).await;
+            return Ok(());
+        }
+        *slot = Some(connection);
+        Ok(())
+    }
+
+    /// 是否存在帖子实时事件订阅
+    pub async fn has_live_connections(&self) -> bool {
+        !self.live_connections.lock().await.is_empty()
+    }
+
+    /// 取消订阅帖子实时事件
+    ///
+    /// - `id` 帖子id
+    pub async fn disconnect(&self, id: &str) -> Response<()> {
+        let live = self.live_connections.lock().await.remove(id);
+        if let Some(live) = live {
+            live.close().await;
+        }
+        Response::success(())
+    }
 
     /// 发布评论
     ///
@@ -303,6 +756,7 @@ impl ArticleService {
     ///
     /// 返回评论ID
     pub async fn post_comment(&self, comment: &CommentPost) -> Result<String> {
+        ensure_content(&comment.content)?;
         self.article_api.post_comment(comment).await
     }
 
@@ -325,6 +779,15 @@ impl ArticleService {
         self.article_api.thank_comment(id).await
     }
 
+    /// 采纳问答帖的回答
+    ///
+    /// - `comment_id` 评论id
+    ///
+    /// 返回执行结果，仅帖子作者可调用
+    pub async fn accept(&self, comment_id: &str) -> Result<ResponseResult> {
+        self.article_api.accept_answer(comment_id).await
+    }
+
     /// 更新评论
     ///
     /// - `id` 评论 Id
@@ -332,6 +795,7 @@ impl ArticleService {
     ///
     /// 返回评论内容HTML
     pub async fn update_comment(&self, id: &str, comment: &CommentPost) -> Result<String> {
+        ensure_content(&comment.content)?;
         self.article_api.update_comment(id, comment).await
     }
 
@@ -376,3 +840,175 @@ impl ArticleService {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::UserApi;
+    use crate::test_util::{MockServer, MockWebSocketServer, client_for, mock_client};
+    use std::time::Duration;
+
+    /// 轮询直到条件成立，超时后测试失败
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(tokio::time::Instant::now() < deadline, "等待超时");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    fn live_service(server: &MockWebSocketServer) -> ArticleService {
+        let base_url = server.url.replace("ws://", "http://");
+        let client = client_for(&base_url).with_token(Some("old".into()));
+        ArticleService::new(ArticleApi::new(client))
+    }
+
+    #[tokio::test]
+    async fn hot_in_range_pages_until_enough_matches() {
+        let now = chrono::Local::now();
+        let recent = (now - chrono::Duration::days(2)).timestamp_millis();
+        let old = (now - chrono::Duration::days(20)).timestamp_millis();
+        let page = |ids: &[i64]| {
+            let articles: Vec<_> = ids
+                .iter()
+                .map(|id| serde_json::json!({"oId": id.to_string()}))
+                .collect();
+            serde_json::json!({
+                "code": 0,
+                "data": {"articles": articles, "pagination": {"paginationPageCount": 3}},
+            })
+            .to_string()
+        };
+        let first = page(&[recent, old]);
+        let second = page(&[old, recent + 1, recent + 2]);
+        let server = MockServer::start(vec![(200, first.as_str()), (200, second.as_str())]).await;
+        let service = ArticleService::new(ArticleApi::new(mock_client(&server)));
+
+        let list = service.hot_in_range(HotRange::Week, 1, 2).await.unwrap();
+
+        let ids: Vec<_> = list.list.iter().map(|a| a.o_id.clone()).collect();
+        assert_eq!(ids, vec![recent.to_string(), (recent + 1).to_string()]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("p=2"));
+    }
+
+    #[tokio::test]
+    async fn my_articles_uses_cached_current_user() {
+        let articles = r#"{"code":0,"data":{"articles":[]}}"#;
+        let server = MockServer::start(vec![
+            (
+                200,
+                r#"{"code":0,"data":{"userName":"me","sysMetal":null}}"#,
+            ),
+            (200, articles),
+            (200, articles),
+        ])
+        .await;
+        let client = mock_client(&server);
+        let user = UserService::new(UserApi::new(client.clone()));
+        let service = ArticleService::new(ArticleApi::new(client)).with_user(user);
+
+        service.my_articles(1, 20).await.unwrap();
+        service.my_articles(2, 20).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].starts_with("GET /api/user/me/articles/"));
+        assert!(requests[2].starts_with("GET /api/user/me/articles/"));
+    }
+
+    #[tokio::test]
+    async fn my_articles_requires_user_service() {
+        let server = MockServer::start(vec![]).await;
+        let service = ArticleService::new(ArticleApi::new(mock_client(&server)));
+
+        let err = service.my_articles(1, 20).await.unwrap_err();
+        assert!(matches!(
+            FishPiError::from_anyhow(&err),
+            FishPiError::Validation(_)
+        ));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn watch_live_reconnects_with_current_token() {
+        let server = MockWebSocketServer::start().await;
+        let service = live_service(&server);
+
+        assert!(service.watch_live("a1", 0, |_| {}).await.success);
+        wait_until(|| server.paths().len() == 1).await;
+        assert!(server.paths()[0].contains("apiKey=old"));
+
+        service
+            .article_api
+            .client()
+            .set_token(Some("new".into()))
+            .await;
+        server.close_connections();
+        wait_until(|| server.paths().len() == 2).await;
+        assert!(server.paths()[1].contains("apiKey=new"));
+
+        service.disconnect("a1").await;
+        assert!(!service.has_live_connections().await);
+    }
+
+    #[tokio::test]
+    async fn disconnect_stops_reconnecting() {
+        let server = MockWebSocketServer::start().await;
+        let service = live_service(&server);
+
+        assert!(service.watch_live("a1", 0, |_| {}).await.success);
+        wait_until(|| server.paths().len() == 1).await;
+        service.disconnect("a1").await;
+        server.close_connections();
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(server.paths().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn post_comment_rejects_empty_content_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = ArticleService::new(ArticleApi::new(mock_client(&server)));
+
+        for content in ["", "  \n"] {
+            let comment = CommentPost {
+                article_id: "1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            };
+            let err = service.post_comment(&comment).await.unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<FishPiError>(),
+                Some(&FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reward_and_get_content_skips_reward_when_already_rewarded() {
+        for flag in ["rewarded", "isMyArticle"] {
+            let detail = serde_json::json!({
+                "code": 0,
+                "data": {"article": {
+                    "oId": "1",
+                    "articleRewardPoint": 10,
+                    "articleRewardContent": "<p>secret</p>",
+                    flag: true,
+                }},
+            })
+            .to_string();
+            let server = MockServer::start(vec![(200, &detail)]).await;
+            let service = ArticleService::new(ArticleApi::new(mock_client(&server)));
+
+            let response = service.reward_and_get_content("1").await;
+
+            assert_eq!(response.data.as_deref(), Some("secret"), "{}", flag);
+            let requests = server.requests();
+            assert_eq!(requests.len(), 1, "{}", flag);
+            assert!(requests[0].starts_with("GET /api/article/1?"));
+        }
+    }
+}
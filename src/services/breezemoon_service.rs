@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use crate::api::BreezemoonApi;
+use crate::error::ensure_content;
 use crate::models::breezemoon::{BreezemoonList, BreezemoonPost};
 
 /// 清风明月服务
@@ -47,8 +48,9 @@ impl BreezemoonService {
     ///
     /// - `content` 清风明月内容
     ///
-    /// 返回清风明月ID
+    /// 返回清风明月ID；内容过短等服务端校验失败时，错误信息为服务端返回的原因
     pub async fn post(&self, content: &str) -> Result<String> {
+        ensure_content(content)?;
         let data = BreezemoonPost {
             content: content.to_string(),
         };
@@ -62,6 +64,7 @@ impl BreezemoonService {
     ///
     /// 返回清风明月ID
     pub async fn update(&self, id: &str, content: &str) -> Result<String> {
+        ensure_content(content)?;
         let data = BreezemoonPost {
             content: content.to_string(),
         };
@@ -77,3 +80,25 @@ impl BreezemoonService {
         self.breezemoon_api.delete_breezemoon(id).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FishPiError;
+    use crate::test_util::{MockServer, mock_client};
+
+    #[tokio::test]
+    async fn post_rejects_empty_content_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = BreezemoonService::new(BreezemoonApi::new(mock_client(&server)));
+
+        for content in ["", "  \n"] {
+            let err = service.post(content).await.unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<FishPiError>(),
+                Some(&FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
+}
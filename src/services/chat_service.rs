@@ -1,19 +1,21 @@
 use crate::api::chat_api::ChatApi;
-use crate::api::client::ApiClient;
+use crate::api::client::{ConnectionState, ReconnectConfig, WEBSOCKET_CLOSE_TIMEOUT};
+use crate::error::{FishPiError, ensure_content};
 use crate::models::chat::{
-    ChatData, ChatDataContent, ChatMessage, ChatMessageType, ChatNotice, ChatRevoke, WebsocketInfo,
+    ChatData, ChatDataContent, ChatMessage, ChatMessageType, ChatNotice, ChatRevoke, ChatUnread,
+    WebsocketInfo, format_chat_reply,
 };
-use crate::models::user::Response;
-use crate::services::ApiCaller;
+use crate::models::user::{ApiEnvelope, Response};
+use crate::services::{ApiCaller, ConnectionWatch, UnknownHandlerSlot, notify_unknown};
 use anyhow::Result as AnyhowResult;
 use futures::SinkExt;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use serde_json::Value;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio_tungstenite::connect_async;
+use tokio::task::JoinHandle;
 use tokio_tungstenite::tungstenite::protocol::Message;
 use url::Url;
 
@@ -28,6 +30,15 @@ pub struct ChatService {
     message_listeners: Arc<Mutex<HashMap<String, Vec<ChatListener>>>>,
     websocket_senders:
         Arc<Mutex<HashMap<String, futures::channel::mpsc::UnboundedSender<Message>>>>,
+    /// 各连接的发送任务，断开时等待其发出关闭帧
+    websocket_writers: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+    reconnect_config: Arc<Mutex<ReconnectConfig>>,
+    unknown_handler: UnknownHandlerSlot,
+    unread_counts: Arc<Mutex<HashMap<String, u32>>>,
+    /// 未读消息总数，随 `unread` 与私聊通知更新
+    unread_count: Arc<Mutex<u32>>,
+    /// 连接状态变化回调
+    connection_watch: ConnectionWatch,
 }
 
 impl std::fmt::Debug for ChatService {
@@ -37,6 +48,12 @@ impl std::fmt::Debug for ChatService {
             .field("websocket_info", &self.websocket_info)
             .field("message_listeners", &"<function pointers>")
             .field("websocket_senders", &self.websocket_senders)
+            .field("websocket_writers", &self.websocket_writers)
+            .field("reconnect_config", &self.reconnect_config)
+            .field("unknown_handler", &"<function callback>")
+            .field("unread_counts", &self.unread_counts)
+            .field("unread_count", &self.unread_count)
+            .field("connection_watch", &self.connection_watch)
             .finish()
     }
 }
@@ -53,7 +70,8 @@ impl ApiCaller for ChatService {
     {
         match f().await {
             Ok(data) => Response::success(data),
-            Err(err) => Response::error(&format!("API调用失败: {}", err)),
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
         }
     }
 
@@ -66,22 +84,24 @@ impl ApiCaller for ChatService {
     {
         match f().await {
             Ok(response) => {
-                if let Some(0) = response.get("result").and_then(|v| v.as_i64()) {
-                    if let Some(data) = response.get("data") {
-                        if let Some(parsed_data) = parser(data) {
-                            return Response::success(parsed_data);
-                        }
+                let envelope = match serde_json::from_value::<ApiEnvelope<Value>>(response) {
+                    Ok(envelope) => envelope,
+                    Err(err) => {
+                        return Response::from_error(FishPiError::Parse(err.to_string()));
                     }
-                }
-
-                let error_msg = response
-                    .get("msg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("解析API响应数据失败")
-                    .to_string();
-                Response::error(&error_msg)
+                };
+                let msg = envelope.msg.as_deref().unwrap_or("解析API响应数据失败");
+                let error = match envelope.check(msg) {
+                    Ok(()) => match envelope.data.as_ref().and_then(parser) {
+                        Some(data) => return Response::success(data),
+                        None => FishPiError::Parse(msg.to_string()),
+                    },
+                    Err(err) => err,
+                };
+                Response::error(msg).with_error(error)
             }
-            Err(err) => Response::error(&format!("API调用失败: {}", err)),
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
         }
     }
 }
@@ -94,9 +114,47 @@ impl ChatService {
             websocket_info: Arc::new(Mutex::new(HashMap::new())),
             message_listeners: Arc::new(Mutex::new(HashMap::new())),
             websocket_senders: Arc::new(Mutex::new(HashMap::new())),
+            websocket_writers: Arc::new(Mutex::new(HashMap::new())),
+            reconnect_config: Arc::new(Mutex::new(ReconnectConfig::default())),
+            unknown_handler: Arc::new(Mutex::new(None)),
+            unread_counts: Arc::new(Mutex::new(HashMap::new())),
+            unread_count: Arc::new(Mutex::new(0)),
+            connection_watch: ConnectionWatch::default(),
         }
     }
 
+    /// 使用共享的未识别消息回调槽位
+    pub fn with_unknown_handler(mut self, slot: UnknownHandlerSlot) -> Self {
+        self.unknown_handler = slot;
+        self
+    }
+
+    /// 设置连接状态变化回调，参数为是否已连接
+    ///
+    /// 新消息通知频道与各私聊连接在首次连接、每次断开与每次重连成功时调用，
+    /// 同一连接连续相同的状态只通知一次，从未连上的连接不会通知断开
+    pub async fn on_connection_change<F>(&self, callback: F)
+    where
+        F: Fn(bool) + Send + Sync + 'static,
+    {
+        self.connection_watch
+            .set(Arc::new(move |_, connected| callback(connected)))
+            .await;
+    }
+
+    /// 设置重连配置
+    ///
+    /// * `config` - 重连配置
+    pub async fn set_reconnect_config(&self, config: ReconnectConfig) {
+        let mut current = self.reconnect_config.lock().await;
+        *current = config;
+    }
+
+    /// 获取当前重连配置
+    pub async fn reconnect_config(&self) -> ReconnectConfig {
+        self.reconnect_config.lock().await.clone()
+    }
+
     /// 获取私聊用户列表第一条消息
     pub async fn list(&self) -> Response<Vec<ChatData>> {
         self.call_json_api(
@@ -142,38 +200,222 @@ impl ChatService {
         result
     }
 
+    /// 以流的形式逐页获取与指定用户的私聊消息
+    ///
+    /// * `user` - 用户名
+    /// * `page_size` - 每页数量
+    ///
+    /// 从第一页开始按从新到旧的顺序产出消息，按需请求下一页，直到返回空页；
+    /// 不会标记消息为已读，请求失败时产出错误并结束
+    pub fn messages_stream(
+        &self,
+        user: &str,
+        page_size: i32,
+    ) -> impl Stream<Item = AnyhowResult<ChatData>> + Send + 'static {
+        let chat_api = self.chat_api.clone();
+        let user = user.to_string();
+        let state = (1, VecDeque::<ChatData>::new(), false);
+
+        futures::stream::unfold(state, move |(mut page, mut buffer, mut done)| {
+            let chat_api = chat_api.clone();
+            let user = user.clone();
+            async move {
+                loop {
+                    if let Some(message) = buffer.pop_front() {
+                        return Some((Ok(message), (page, buffer, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    match chat_api.get_messages(&user, page, page_size).await {
+                        Ok(response) => match Self::parse_messages_page(&response) {
+                            Ok(messages) => {
+                                done = messages.is_empty();
+                                page += 1;
+                                buffer.extend(messages);
+                            }
+                            Err(err) => return Some((Err(err.into()), (page, buffer, true))),
+                        },
+                        Err(err) => return Some((Err(err), (page, buffer, true))),
+                    }
+                }
+            }
+        })
+    }
+
+    /// 解析一页私聊消息，`data` 缺失时视为空页
+    fn parse_messages_page(response: &Value) -> Result<Vec<ChatData>, FishPiError> {
+        ApiEnvelope::peek(response)?.check("获取私聊消息失败")?;
+
+        Ok(response
+            .get("data")
+            .and_then(|data| data.as_array())
+            .map(|arr| arr.iter().filter_map(ChatData::from_json).collect())
+            .unwrap_or_default())
+    }
+
     /// 标记用户消息为已读
     ///
     /// * `user` - 用户名
     pub async fn mark_read(&self, user: &str) -> Response<()> {
-        self.call_json_api(
-            &format!("标记用户 {} 的消息为已读", user),
-            || self.chat_api.mark_as_read(user),
-            |_| Some(()),
-        )
-        .await
+        // 成功响应只有状态码而没有 data，不能经过要求 data 的 call_json_api
+        let result = match self.chat_api.mark_as_read(user).await {
+            Ok(response) => {
+                match ApiEnvelope::peek(&response)
+                    .and_then(|envelope| envelope.check("标记已读失败"))
+                {
+                    Ok(()) => Response::success(()),
+                    Err(err) => Response::from_error(err),
+                }
+            }
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        };
+
+        if result.success
+            && let Some(count) = self.unread_counts.lock().await.remove(user)
+        {
+            let mut total = self.unread_count.lock().await;
+            *total = total.saturating_sub(count);
+        }
+        result
+    }
+
+    /// 并发标记多个用户的消息为已读
+    ///
+    /// * `users` - 用户名列表
+    ///
+    /// 部分失败时返回错误，错误信息汇总所有失败的用户，`error` 取第一个失败原因
+    pub async fn mark_read_all(&self, users: &[&str]) -> Response<()> {
+        let results =
+            futures::future::join_all(users.iter().map(|user| self.mark_read(user))).await;
+
+        let mut errors = Vec::new();
+        let mut first_error = None;
+        for (user, result) in users
+            .iter()
+            .zip(results)
+            .filter(|(_, result)| !result.success)
+        {
+            errors.push(format!(
+                "{}: {}",
+                user,
+                result.message.as_deref().unwrap_or("未知错误")
+            ));
+            if first_error.is_none() {
+                first_error = result.error;
+            }
+        }
+
+        if errors.is_empty() {
+            return Response::success(());
+        }
+        let message = format!("部分用户标记已读失败: {}", errors.join("; "));
+        let error = first_error.unwrap_or_else(|| FishPiError::Network(message.clone()));
+        Response::error(&message).with_error(error)
+    }
+
+    /// 标记所有联系人的消息为已读
+    ///
+    /// 联系人来自私聊列表与未读计数缓存
+    pub async fn mark_all_read(&self) -> Response<()> {
+        let mut users: Vec<String> = self.unread_counts.lock().await.keys().cloned().collect();
+
+        let list = self.list().await;
+        if !list.success && users.is_empty() {
+            return Response::error(list.message.as_deref().unwrap_or("获取私聊列表失败"));
+        }
+        for contact in list.data.unwrap_or_default() {
+            if !contact.receiver_user_name.is_empty()
+                && !users.contains(&contact.receiver_user_name)
+            {
+                users.push(contact.receiver_user_name);
+            }
+        }
+
+        let users: Vec<&str> = users.iter().map(String::as_str).collect();
+        let result = self.mark_read_all(&users).await;
+        if result.success {
+            *self.unread_count.lock().await = 0;
+        }
+        result
+    }
+
+    /// 获取缓存的未读消息总数
+    pub async fn unread_count(&self) -> u32 {
+        *self.unread_count.lock().await
+    }
+
+    /// 获取缓存的各用户未读消息数
+    pub async fn unread_counts(&self) -> HashMap<String, u32> {
+        self.unread_counts.lock().await.clone()
+    }
+
+    /// 从服务端刷新各用户未读消息数缓存
+    pub async fn refresh_unread_counts(&self) -> Response<HashMap<String, u32>> {
+        let result = self.unread().await;
+        match result.data {
+            Some(unread) if result.success => Response::success(unread.counts_by_sender()),
+            _ => Response {
+                success: false,
+                message: result.message,
+                data: None,
+                error: result.error,
+            },
+        }
     }
 
     /// 获取未读私聊消息
-    pub async fn unread(&self) -> Response<ChatData> {
-        self.call_json_api(
-            "获取未读私聊消息",
-            || self.chat_api.has_unread(),
-            ChatData::from_json,
-        )
-        .await
+    ///
+    /// 返回未读总数与消息预览，并同步更新未读计数缓存
+    pub async fn unread(&self) -> Response<ChatUnread> {
+        let response = match self.chat_api.has_unread().await {
+            Ok(response) => response,
+            Err(err) => {
+                return Response::error(&format!("API调用失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        };
+
+        if let Err(err) =
+            ApiEnvelope::peek(&response).and_then(|envelope| envelope.check("获取未读私聊消息失败"))
+        {
+            return Response::from_error(err);
+        }
+
+        let unread = ChatUnread::from(&response);
+        *self.unread_counts.lock().await = unread.counts_by_sender();
+        *self.unread_count.lock().await = unread.count;
+        Response::success(unread)
     }
 
     /// 撤回私聊消息
     ///
-    /// * `msg_id` - 消息ID
+    /// * `msg_id` - 消息ID，不能为空
+    ///
+    /// 超过撤回时限或无权撤回时，`message` 中为服务端返回的原因
     pub async fn revoke(&self, msg_id: &str) -> Response<()> {
-        self.call_json_api(
-            &format!("撤回私聊消息 {}", msg_id),
-            || self.chat_api.revoke(msg_id),
-            |_| Some(()),
-        )
-        .await
+        if msg_id.trim().is_empty() {
+            return Response::from_error(FishPiError::Validation("消息ID不能为空".to_string()));
+        }
+
+        match self.chat_api.revoke(msg_id).await {
+            Ok(response) => {
+                // 撤回成功时响应中没有 data，只检查状态码
+                let envelope = match ApiEnvelope::peek(&response) {
+                    Ok(envelope) => envelope,
+                    Err(err) => return Response::from_error(err),
+                };
+                let msg = envelope.msg.as_deref().unwrap_or("撤回失败");
+                match envelope.check(msg) {
+                    Ok(()) => Response::success(()),
+                    Err(err) => Response::error(msg).with_error(err),
+                }
+            }
+            Err(err) => Response::error(&format!("撤回失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
     }
 
     /// 发送私聊消息
@@ -185,6 +427,10 @@ impl ChatService {
         user: &'a str,
         content: Cow<'a, str>,
     ) -> Response<WebsocketInfo> {
+        if let Err(err) = ensure_content(&content) {
+            return Response::from_error(err);
+        }
+
         // 确保WebSocket已连接
         if !self.is_connected(Some(user)).await {
             let connect_result = self.connect(Some(user)).await;
@@ -226,6 +472,52 @@ impl ChatService {
         Response::success(ws_info)
     }
 
+    /// 回复私聊消息
+    ///
+    /// 以 Markdown 引用约定附加原消息ID，接收方可通过 `ChatData::reply_to` 取得
+    ///
+    /// * `user` - 用户名
+    /// * `reply_to` - 被回复的消息ID
+    /// * `content` - 回复内容
+    pub async fn send_reply(
+        &self,
+        user: &str,
+        reply_to: &str,
+        content: &str,
+    ) -> Response<WebsocketInfo> {
+        if let Err(err) = ensure_content(content) {
+            return Response::from_error(err);
+        }
+        let content = format_chat_reply(reply_to, content);
+        self.send(user, Cow::Owned(content)).await
+    }
+
+    /// 直接发送原始 WebSocket 文本帧
+    ///
+    /// **不稳定接口**：内容不做任何校验，原样推送给服务器，仅用于调试或调用尚未封装的服务端指令，
+    /// 后续版本可能调整或移除。与 `send` 不同，不会自动建立连接。
+    ///
+    /// * `user` - 指定用户名，为空则使用新消息通知频道
+    /// * `text` - 原始帧内容
+    pub async fn send_raw(&self, user: Option<&str>, text: String) -> Response<()> {
+        let user_key = user.unwrap_or("_user-channel_");
+
+        if !self.is_connected(user).await {
+            let msg = format!("频道 {} 未连接，无法发送原始消息", user_key);
+            return Response::error(&msg).with_error(FishPiError::Network(msg.clone()));
+        }
+
+        let senders = self.websocket_senders.lock().await;
+        let msg = match senders.get(user_key) {
+            Some(sender) => match sender.unbounded_send(Message::Text(text)) {
+                Ok(_) => return Response::success(()),
+                Err(err) => format!("发送原始消息失败: {}", err),
+            },
+            None => format!("频道 {} 的WebSocket发送器不存在", user_key),
+        };
+        Response::error(&msg).with_error(FishPiError::Network(msg.clone()))
+    }
+
     /// 获取完整的WebSocket URL
     async fn get_full_websocket_url(&self, user: Option<&str>) -> Result<Url, String> {
         let ws_url = match self.chat_api.get_websocket_url(user).await {
@@ -233,8 +525,7 @@ impl ChatService {
             Err(err) => return Err(format!("获取私聊WebSocket URL失败: {}", err)),
         };
 
-        // 创建API客户端
-        let client = ApiClient::new();
+        let client = self.chat_api.client();
 
         // 构建完整的WebSocket URL
         let full_url = if ws_url.starts_with("ws") || ws_url.starts_with("wss") {
@@ -271,10 +562,10 @@ impl ChatService {
         // 检查是否已连接
         {
             let info = self.websocket_info.lock().await;
-            if let Some(ws_info) = info.get(&user_key) {
-                if ws_info.connected {
-                    return Response::success(());
-                }
+            if let Some(ws_info) = info.get(&user_key)
+                && ws_info.connected
+            {
+                return Response::success(());
             }
         }
 
@@ -290,8 +581,8 @@ impl ChatService {
         let user_key_clone = user_key.clone();
 
         // 建立WebSocket连接
-        let ws_stream = match connect_async(url).await {
-            Ok((stream, _)) => stream,
+        let ws_stream = match self.chat_api.client().open_websocket(url).await {
+            Ok(stream) => stream,
             Err(err) => return Response::error(&format!("连接WebSocket失败: {}", err)),
         };
 
@@ -301,7 +592,7 @@ impl ChatService {
         let (sender, receiver) = futures::channel::mpsc::unbounded();
         {
             let mut senders = websocket_senders.lock().await;
-            senders.insert(user_key.clone(), sender);
+            senders.insert(user_key.clone(), sender.clone());
         }
 
         // 更新连接状态
@@ -319,7 +610,15 @@ impl ChatService {
         }
 
         // 启动消息发送处理
-        self.start_websocket_sender(write, receiver, user_key.clone());
+        let writer = self.start_websocket_sender(write, receiver, user_key.clone());
+        if let Some(old) = self
+            .websocket_writers
+            .lock()
+            .await
+            .insert(user_key.clone(), writer)
+        {
+            old.abort();
+        }
 
         // 启动消息接收处理
         self.start_websocket_receiver(
@@ -327,13 +626,15 @@ impl ChatService {
             message_listeners,
             websocket_info,
             websocket_senders,
+            sender,
             user_key_clone,
         );
 
+        self.connection_watch.notify(user, true).await;
         Response::success(())
     }
 
-    /// 启动WebSocket消息发送处理
+    /// 启动WebSocket消息发送处理，发出关闭帧后结束
     fn start_websocket_sender(
         &self,
         mut write: impl futures::sink::Sink<Message, Error = tokio_tungstenite::tungstenite::Error>
@@ -342,14 +643,15 @@ impl ChatService {
         + 'static,
         mut receiver: futures::channel::mpsc::UnboundedReceiver<Message>,
         _user_key: String,
-    ) {
+    ) -> JoinHandle<()> {
         tokio::spawn(async move {
             while let Some(message) = receiver.next().await {
-                if write.send(message).await.is_err() {
+                let closing = matches!(message, Message::Close(_));
+                if write.send(message).await.is_err() || closing {
                     break;
                 }
             }
-        });
+        })
     }
 
     /// 启动WebSocket消息接收处理
@@ -365,6 +667,7 @@ impl ChatService {
         websocket_senders: Arc<
             Mutex<HashMap<String, futures::channel::mpsc::UnboundedSender<Message>>>,
         >,
+        own_sender: futures::channel::mpsc::UnboundedSender<Message>,
         user_key: String,
     ) {
         let chat_service = self.clone();
@@ -373,23 +676,52 @@ impl ChatService {
                 match msg_result {
                     Ok(msg) => match msg {
                         Message::Text(text) => {
+                            chat_service.chat_api.client().metrics().record_message();
                             if let Ok(value) = serde_json::from_str::<Value>(&text) {
                                 let message_listeners = message_listeners.clone();
                                 let websocket_info = websocket_info.clone();
+                                let unknown_handler = chat_service.unknown_handler.clone();
+                                let unread_counts = chat_service.unread_counts.clone();
+                                let unread_count = chat_service.unread_count.clone();
                                 let user_key = user_key.clone();
                                 tokio::spawn(async move {
                                     ChatService::handle_ws_message(
                                         value,
                                         message_listeners,
                                         websocket_info,
+                                        unknown_handler,
+                                        unread_counts,
+                                        unread_count,
                                         &user_key,
                                     )
                                     .await;
                                 });
                             }
                         }
-                        Message::Close(_) => {
+                        Message::Close(frame) => {
+                            // 连接已被主动断开或替换，不再重连
+                            let current = websocket_senders
+                                .lock()
+                                .await
+                                .get(&user_key)
+                                .is_some_and(|sender| sender.same_receiver(&own_sender));
+                            if !current {
+                                break;
+                            }
+
                             Self::update_connection_status(&websocket_info, &user_key, false).await;
+                            chat_service
+                                .connection_watch
+                                .notify(Self::user_of(&user_key), false)
+                                .await;
+
+                            // 被封禁/踢出时不再重连
+                            if let ConnectionState::Banned { reason } =
+                                ConnectionState::from_close_frame(frame.as_ref())
+                            {
+                                log::warn!("私聊连接被服务端关闭: {}", reason);
+                                break;
+                            }
 
                             // 获取重试次数
                             let retry_times = {
@@ -400,21 +732,18 @@ impl ChatService {
                             };
 
                             // 如果重试次数超过限制，则不再重连
-                            if retry_times >= 10 {
+                            let config = chat_service.reconnect_config().await;
+                            if !config.can_retry(retry_times) {
                                 break;
                             }
 
                             // 等待一段时间后重连
-                            tokio::time::sleep(std::time::Duration::from_millis(5000)).await;
+                            tokio::time::sleep(config.backoff_delay(retry_times)).await;
 
                             // 重新连接
-                            let user = if user_key == "_user-channel_" {
-                                None
-                            } else {
-                                Some(user_key.as_str())
-                            };
-
-                            let connect_result = chat_service.connect(user).await;
+                            chat_service.chat_api.client().metrics().record_reconnect();
+                            let connect_result =
+                                chat_service.connect(Self::user_of(&user_key)).await;
                             if !connect_result.success {
                                 // 更新重试次数
                                 Self::update_connection_error(&websocket_info, &user_key).await;
@@ -424,16 +753,34 @@ impl ChatService {
                     },
                     Err(_) => {
                         Self::update_connection_error(&websocket_info, &user_key).await;
+                        chat_service
+                            .connection_watch
+                            .notify(Self::user_of(&user_key), false)
+                            .await;
                         break;
                     }
                 }
             }
 
             let mut senders = websocket_senders.lock().await;
-            senders.remove(&user_key);
+            if senders
+                .get(&user_key)
+                .is_some_and(|sender| sender.same_receiver(&own_sender))
+            {
+                senders.remove(&user_key);
+            }
         });
     }
 
+    /// 连接标识对应的用户名，新消息通知频道为 `None`
+    fn user_of(user_key: &str) -> Option<&str> {
+        if user_key == "_user-channel_" {
+            None
+        } else {
+            Some(user_key)
+        }
+    }
+
     /// 更新连接状态
     async fn update_connection_status(
         websocket_info: &Arc<Mutex<HashMap<String, WebsocketInfo>>>,
@@ -465,11 +812,22 @@ impl ChatService {
         let user_key = user.unwrap_or("_user-channel_").to_string();
 
         Self::update_connection_status(&self.websocket_info, &user_key, false).await;
+        self.connection_watch.notify(user, false).await;
 
-        // 清理发送器
+        // 清理发送器并发出关闭帧
+        let sender = self.websocket_senders.lock().await.remove(&user_key);
+        if let Some(sender) = sender {
+            let _ = sender.unbounded_send(Message::Close(None));
+        }
+
+        // 等待关闭帧发出，超时后强制结束发送任务
+        let writer = self.websocket_writers.lock().await.remove(&user_key);
+        if let Some(mut writer) = writer
+            && tokio::time::timeout(WEBSOCKET_CLOSE_TIMEOUT, &mut writer)
+                .await
+                .is_err()
         {
-            let mut senders = self.websocket_senders.lock().await;
-            senders.remove(&user_key);
+            writer.abort();
         }
 
         Response::success(())
@@ -523,6 +881,15 @@ impl ChatService {
         Response::success(())
     }
 
+    /// 是否存在任一在线的私聊连接
+    pub async fn has_live_connections(&self) -> bool {
+        self.websocket_info
+            .lock()
+            .await
+            .values()
+            .any(|info| info.connected)
+    }
+
     /// 检查是否已连接
     ///
     /// * `user` - 指定用户名，为空则检查新消息通知频道
@@ -548,18 +915,21 @@ impl ChatService {
     /// 重新连接
     ///
     /// * `user` - 指定用户名，为空则重连新消息通知频道
-    /// * `max_retries` - 最大重试次数
+    /// * `max_retries` - 最大重试次数，为空则使用重连配置
     pub async fn reconnect(&self, user: Option<&str>, max_retries: Option<i32>) -> Response<()> {
         let user_key = user.unwrap_or("_user-channel_").to_string();
-        let max_retry_times = max_retries.unwrap_or(10);
+        let max_retry_times = match max_retries {
+            Some(max) => max,
+            None => self.reconnect_config().await.max_attempts,
+        };
 
         // 先检查重试次数
         {
             let info = self.websocket_info.lock().await;
-            if let Some(ws_info) = info.get(&user_key) {
-                if ws_info.retry_times >= max_retry_times {
-                    return Response::error(&format!("重连次数超过最大限制({})", max_retry_times));
-                }
+            if let Some(ws_info) = info.get(&user_key)
+                && ws_info.retry_times >= max_retry_times
+            {
+                return Response::error(&format!("重连次数超过最大限制({})", max_retry_times));
             }
         }
 
@@ -572,14 +942,38 @@ impl ChatService {
 
     /// 清除所有连接和监听器
     pub async fn clear_all_connections(&self) -> Response<()> {
-        {
+        let keys: Vec<String> = {
             let mut info = self.websocket_info.lock().await;
-            info.clear();
+            info.drain().map(|(key, _)| key).collect()
+        };
+        for key in &keys {
+            self.connection_watch
+                .notify(Self::user_of(key), false)
+                .await;
         }
 
         {
             let mut senders = self.websocket_senders.lock().await;
-            senders.clear();
+            for (_, sender) in senders.drain() {
+                let _ = sender.unbounded_send(Message::Close(None));
+            }
+        }
+
+        let mut writers: Vec<JoinHandle<()>> = self
+            .websocket_writers
+            .lock()
+            .await
+            .drain()
+            .map(|(_, writer)| writer)
+            .collect();
+        let all_done = futures::future::join_all(writers.iter_mut());
+        if tokio::time::timeout(WEBSOCKET_CLOSE_TIMEOUT, all_done)
+            .await
+            .is_err()
+        {
+            for writer in &writers {
+                writer.abort();
+            }
         }
 
         {
@@ -595,21 +989,28 @@ impl ChatService {
         value: Value,
         message_listeners: Arc<Mutex<HashMap<String, Vec<ChatListener>>>>,
         websocket_info: Arc<Mutex<HashMap<String, WebsocketInfo>>>,
+        unknown_handler: UnknownHandlerSlot,
+        unread_counts: Arc<Mutex<HashMap<String, u32>>>,
+        unread_count: Arc<Mutex<u32>>,
         user_key: &str,
     ) {
         let mut message_type = String::from(ChatMessageType::DATA);
 
-        if let Some(command) = value.get("command").and_then(|v| v.as_str()) {
-            if ["chatUnreadCountRefresh", "newIdleChatMessage"].contains(&command) {
-                message_type = String::from(ChatMessageType::NOTICE);
-            }
+        if let Some(command) = value.get("command").and_then(|v| v.as_str())
+            && ["chatUnreadCountRefresh", "newIdleChatMessage"].contains(&command)
+        {
+            message_type = String::from(ChatMessageType::NOTICE);
         }
 
         if value.get("type").and_then(|v| v.as_str()) == Some("revoke") {
             message_type = String::from(ChatMessageType::REVOKE);
         }
 
-        if message_type != ChatMessageType::NOTICE && value.get("command").is_some() {
+        if message_type != ChatMessageType::NOTICE
+            && let Some(command) = value.get("command").and_then(|v| v.as_str())
+        {
+            let command = command.to_string();
+            notify_unknown(&unknown_handler, &command, value).await;
             return;
         }
 
@@ -619,6 +1020,7 @@ impl ChatService {
                 data: ChatDataContent::Data(ChatData::from(&value)),
             },
             ChatMessageType::NOTICE => {
+                Self::update_unread(&value, &unread_counts, &unread_count).await;
                 let notice = ChatNotice {
                     command: value
                         .get("command")
@@ -689,6 +1091,41 @@ impl ChatService {
         Self::dispatch_to_listeners(chat_message, &message_listeners, user_key, &message_id).await;
     }
 
+    /// 根据私聊通知更新未读计数
+    ///
+    /// `chatUnreadCountRefresh` 携带最新总数，`newIdleChatMessage` 表示新增一条未读消息
+    async fn update_unread(
+        value: &Value,
+        unread_counts: &Arc<Mutex<HashMap<String, u32>>>,
+        unread_count: &Arc<Mutex<u32>>,
+    ) {
+        match value.get("command").and_then(|v| v.as_str()) {
+            Some("chatUnreadCountRefresh") => {
+                if let Some(count) = value.get("count").and_then(|v| v.as_u64()) {
+                    *unread_count.lock().await = count as u32;
+                    if count == 0 {
+                        unread_counts.lock().await.clear();
+                    }
+                }
+            }
+            Some("newIdleChatMessage") => {
+                *unread_count.lock().await += 1;
+                if let Some(sender) = value
+                    .get("senderUserName")
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                {
+                    *unread_counts
+                        .lock()
+                        .await
+                        .entry(sender.to_string())
+                        .or_insert(0) += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// 分发消息到监听器
     async fn dispatch_to_listeners(
         chat_message: ChatMessage,
@@ -709,3 +1146,106 @@ impl ChatService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockServer, MockWebSocketServer, client_for, mock_client};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn send_rejects_empty_content_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = ChatService::new(ChatApi::new(mock_client(&server)));
+
+        for content in ["", "  \n"] {
+            let response = service.send("someone", Cow::Borrowed(content)).await;
+            assert_eq!(
+                response.error,
+                Some(FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn send_raw_reaches_websocket() {
+        let ws_server = MockWebSocketServer::start().await;
+        let client = client_for(&ws_server.url.replace("ws://", "http://"));
+        let service = ChatService::new(ChatApi::new(client));
+        assert!(service.connect(None).await.success);
+
+        assert!(
+            service
+                .send_raw(None, "raw-frame".to_string())
+                .await
+                .success
+        );
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !ws_server.frames().contains(&"raw-frame".to_string()) {
+            assert!(tokio::time::Instant::now() < deadline, "原始帧未送达");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            ws_server.paths(),
+            vec!["/user-channel?apiKey=key".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn send_raw_requires_connection() {
+        let server = MockServer::start(vec![]).await;
+        let service = ChatService::new(ChatApi::new(mock_client(&server)));
+
+        let response = service
+            .send_raw(Some("someone"), "raw-frame".to_string())
+            .await;
+
+        assert!(!response.success);
+        assert!(matches!(response.error, Some(FishPiError::Network(_))));
+    }
+
+    #[tokio::test]
+    async fn mark_read_all_keeps_typed_error_of_failed_users() {
+        let server = MockServer::start(vec![
+            (200, r#"{"result":429,"msg":"操作过于频繁"}"#),
+            (200, r#"{"result":429,"msg":"操作过于频繁"}"#),
+        ])
+        .await;
+        let service = ChatService::new(ChatApi::new(mock_client(&server)));
+
+        let response = service.mark_read_all(&["a", "b"]).await;
+
+        assert!(!response.success);
+        assert_eq!(response.error, Some(FishPiError::RateLimited));
+        let message = response.message.unwrap();
+        assert!(message.contains("a: ") && message.contains("b: "));
+    }
+
+    #[tokio::test]
+    async fn mark_read_all_clears_unread_cache_of_marked_users() {
+        let server =
+            MockServer::start(vec![(200, r#"{"result":0}"#), (200, r#"{"result":0}"#)]).await;
+        let service = ChatService::new(ChatApi::new(mock_client(&server)));
+        *service.unread_counts.lock().await = HashMap::from([
+            ("a".to_string(), 2),
+            ("b".to_string(), 3),
+            ("c".to_string(), 1),
+        ]);
+        *service.unread_count.lock().await = 6;
+
+        let response = service.mark_read_all(&["a", "b"]).await;
+
+        assert!(response.success, "{:?}", response.message);
+        assert_eq!(
+            service.unread_counts().await,
+            HashMap::from([("c".to_string(), 1)])
+        );
+        assert_eq!(service.unread_count().await, 1);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().any(|r| r.contains("fromUser=a")));
+        assert!(requests.iter().any(|r| r.contains("fromUser=b")));
+    }
+}
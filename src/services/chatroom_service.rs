@@ -1,21 +1,67 @@
 use crate::AutoCompleteUsername;
 use crate::api::ChatroomApi;
-use crate::api::client::ApiClient;
+use crate::api::client::{
+    ConnectionState, ReconnectConfig, WEBSOCKET_CLOSE_TIMEOUT, WebSocketConnection,
+};
+use crate::error::{FishPiError, ensure_content};
 use crate::models::chatroom::{
-    BarrageCost, BarragerMsg, ChatRoomData, ChatRoomDataContent, ChatRoomMessage,
-    ChatRoomMessageType, ChatRoomUser, ChatSource, MuteItem, WebSocketMessage,
+    BarrageColor, BarrageCost, BarragerMsg, Capabilities, ChatContentType, ChatMessageKind,
+    ChatRoomData, ChatRoomDataContent, ChatRoomMessage, ChatRoomMessageType, ChatRoomNode,
+    ChatRoomNodeInfo, ChatRoomQueryMode, ChatRoomUser, ChatSource, LazyChatRoomMessage, LogFormat,
+    MusicMsg, MuteItem, SentMessage, WebSocketMessage, diff_online_users, parse_room_notice,
+    parse_slow_mode,
+};
+use crate::models::filter::MessageFilter;
+use crate::models::redpacket::{RedPacketMessage, RedPacketStatusMsg};
+use crate::models::upload::MAX_UPLOAD_SIZE;
+use crate::models::user::{ApiEnvelope, ApiResponse, Response};
+use crate::services::{
+    ApiCaller, ConnectionWatch, UnknownHandlerSlot, UserService, notify_unknown,
 };
-use crate::models::redpacket::RedPacketStatusMsg;
-use crate::models::user::{ApiResponse, Response};
-use crate::services::ApiCaller;
+use crate::utils::truncate_utf8;
+use futures::Stream;
 use serde_json::Value;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{Mutex, broadcast};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::protocol::Message;
 
 pub type ChatroomListener = Box<dyn Fn(ChatRoomData) + Send + Sync>;
 
+/// 按用户筛选历史消息时最多扫描的页数
+const USER_HISTORY_MAX_PAGES: i32 = 10;
+/// 内存中保留的最近消息数量
+const RECENT_MESSAGES_CAPACITY: usize = 200;
+/// 最近消息中单条消息内容的默认字节上限
+const DEFAULT_MESSAGE_CONTENT_LIMIT: usize = 16 * 1024;
+/// 内容被截断时追加的标记
+const TRUNCATED_MARK: &str = "…[已截断]";
+/// `subscribe` 广播通道容量，订阅者落后超过该数量的消息时会丢弃最旧的消息
+const SUBSCRIBE_CHANNEL_CAPACITY: usize = 256;
+/// 聊天室日志的刷新间隔
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// 连接空闲多久后发送一次心跳
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// 心跳帧内容
+const HEARTBEAT_FRAME: &str = "-hb-";
+/// 按消息向前翻页时每次获取的消息数量
+const HISTORY_PAGE_SIZE: i32 = 25;
+/// 消息去重窗口保留的 oid 数量
+const DEDUP_WINDOW: usize = 256;
+/// 发送接口未返回消息 oid 时，等待自己消息回显的最长时间，见 [`ChatroomService::set_echo_wait`]
+const SEND_ECHO_TIMEOUT: Duration = Duration::from_secs(3);
+/// 默认发送间隔，与服务端的发送冷却保持一致，避免连续发送被拒绝
+const DEFAULT_SEND_INTERVAL: Duration = Duration::from_secs(2);
+/// 普通用户可撤回消息的时限（秒）
+const REVOKE_TIME_WINDOW_SECS: i64 = 120;
+
 #[derive(Clone)]
 pub struct ChatroomService {
     pub chatroom_api: ChatroomApi,
@@ -24,6 +70,47 @@ pub struct ChatroomService {
     pub online_users: Arc<Mutex<Vec<ChatRoomUser>>>,
     pub discussing: Arc<Mutex<Option<String>>>,
     pub retry_times: Arc<Mutex<i32>>,
+    pub reconnect_config: Arc<Mutex<ReconnectConfig>>,
+    pub ws_connection: Arc<Mutex<Option<WebSocketConnection>>>,
+    /// 连接编号，替换或关闭连接时递增，旧连接的错误与关闭回调据此失效
+    connection_epoch: Arc<AtomicU64>,
+    pub capabilities: Arc<Mutex<Capabilities>>,
+    pub unknown_handler: UnknownHandlerSlot,
+    pub slow_mode: Arc<Mutex<Option<u64>>>,
+    pub recent_messages: Arc<Mutex<VecDeque<ChatRoomMessage>>>,
+    pub message_content_limit: Arc<Mutex<usize>>,
+    pub buffer_memory_limit: Arc<Mutex<Option<usize>>>,
+    /// 用于识别自己发送的消息，当前用户信息以其缓存为准
+    user: Option<UserService>,
+    pub connection_state: Arc<Mutex<ConnectionState>>,
+    /// 是否由用户主动断开，主动断开后不再自动重连
+    pub user_closed: Arc<Mutex<bool>>,
+    /// `subscribe` 使用的广播通道
+    pub broadcaster: broadcast::Sender<ChatRoomData>,
+    /// 通过 `connect_to` 指定的节点，重连时沿用
+    pub preferred_node: Arc<Mutex<Option<String>>>,
+    /// 最近一次收到服务端消息的时间
+    pub last_activity: Arc<std::sync::Mutex<Instant>>,
+    /// 心跳任务
+    pub keepalive: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// 最近见过的消息 oid，为 `None` 时不去重
+    pub dedup: Arc<Mutex<Option<VecDeque<String>>>>,
+    /// 连接状态变化回调
+    connection_watch: ConnectionWatch,
+    /// 两次发送之间的最小间隔
+    pub send_interval: Arc<Mutex<Duration>>,
+    /// 下一次允许发送的时间
+    next_send_at: Arc<std::sync::Mutex<Option<Instant>>>,
+    /// 接口未返回 oid 时是否等待自己消息的回显
+    echo_wait: Arc<Mutex<bool>>,
+    /// 是否在在线列表更新时额外推送加入/离开事件
+    online_diff: Arc<Mutex<bool>>,
+    /// 是否延迟解析特殊消息内容
+    lazy_special: Arc<AtomicBool>,
+    /// 发送消息时默认使用的客户端来源
+    source: Arc<Mutex<ChatSource>>,
+    /// 消息过滤规则，命中的消息不推送给监听器
+    filter: Arc<Mutex<Option<MessageFilter>>>,
 }
 
 impl std::fmt::Debug for ChatroomService {
@@ -41,6 +128,29 @@ impl std::fmt::Debug for ChatroomService {
             .field("online_users", &self.online_users)
             .field("discussing", &self.discussing)
             .field("retry_times", &self.retry_times)
+            .field("reconnect_config", &self.reconnect_config)
+            .field("ws_connection", &self.ws_connection)
+            .field("connection_epoch", &self.connection_epoch)
+            .field("capabilities", &self.capabilities)
+            .field("unknown_handler", &"<function callback>")
+            .field("slow_mode", &self.slow_mode)
+            .field("recent_messages", &"<message buffer>")
+            .field("message_content_limit", &self.message_content_limit)
+            .field("buffer_memory_limit", &self.buffer_memory_limit)
+            .field("user", &self.user)
+            .field("connection_state", &self.connection_state)
+            .field("user_closed", &self.user_closed)
+            .field("subscribers", &self.broadcaster.receiver_count())
+            .field("preferred_node", &self.preferred_node)
+            .field("last_activity", &self.last_activity)
+            .field("keepalive", &self.keepalive)
+            .field("dedup", &self.dedup)
+            .field("connection_watch", &self.connection_watch)
+            .field("send_interval", &self.send_interval)
+            .field("next_send_at", &self.next_send_at)
+            .field("online_diff", &self.online_diff)
+            .field("source", &self.source)
+            .field("filter", &self.filter)
             .finish()
     }
 }
@@ -57,7 +167,8 @@ impl ApiCaller for ChatroomService {
     {
         match f().await {
             Ok(data) => Response::success(data),
-            Err(err) => Response::error(&format!("API调用失败: {}", err)),
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
         }
     }
 
@@ -70,22 +181,55 @@ impl ApiCaller for ChatroomService {
     {
         match f().await {
             Ok(response) => {
-                if let Some(0) = response.get("result").and_then(|v| v.as_i64()) {
-                    if let Some(data) = response.get("data") {
-                        if let Some(parsed_data) = parser(data) {
-                            return Response::success(parsed_data);
-                        }
+                let envelope = match serde_json::from_value::<ApiEnvelope<Value>>(response) {
+                    Ok(envelope) => envelope,
+                    Err(err) => {
+                        return Response::from_error(FishPiError::Parse(err.to_string()));
                     }
-                }
-
-                let error_msg = response
-                    .get("msg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("解析API响应数据失败")
-                    .to_string();
-                Response::error(&error_msg)
+                };
+                let msg = envelope.msg.as_deref().unwrap_or("解析API响应数据失败");
+                let error = match envelope.check(msg) {
+                    Ok(()) => match envelope.data.as_ref().and_then(parser) {
+                        Some(data) => return Response::success(data),
+                        None => FishPiError::Parse(msg.to_string()),
+                    },
+                    Err(err) => err,
+                };
+                Response::error(msg).with_error(error)
             }
-            Err(err) => Response::error(&format!("API调用失败: {}", err)),
+            Err(err) => Response::error(&format!("API调用失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+}
+
+/// 预留的发送时间
+///
+/// 在等待期间被取消（如外层 `select!`/`timeout` 丢弃了发送 future）时归还预留，
+/// 后续发送无需再为未发出的消息等待；其后已有新的预留时保持不变
+struct SendSlot {
+    next_send_at: Arc<std::sync::Mutex<Option<Instant>>>,
+    /// 预留前的下一次允许发送时间
+    previous: Option<Instant>,
+    /// 预留后的下一次允许发送时间
+    reserved: Instant,
+    /// 本次可发送的时间
+    at: Instant,
+    /// 是否已开始发送
+    used: bool,
+}
+
+impl Drop for SendSlot {
+    fn drop(&mut self) {
+        if self.used {
+            return;
+        }
+        let mut next_send_at = self
+            .next_send_at
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *next_send_at == Some(self.reserved) {
+            *next_send_at = self.previous;
         }
     }
 }
@@ -99,11 +243,86 @@ impl ChatroomService {
             online_users: Arc::new(Mutex::new(Vec::new())),
             discussing: Arc::new(Mutex::new(None)),
             retry_times: Arc::new(Mutex::new(0)),
+            reconnect_config: Arc::new(Mutex::new(ReconnectConfig::default())),
+            ws_connection: Arc::new(Mutex::new(None)),
+            connection_epoch: Arc::new(AtomicU64::new(0)),
+            capabilities: Arc::new(Mutex::new(Capabilities::default())),
+            unknown_handler: Arc::new(Mutex::new(None)),
+            slow_mode: Arc::new(Mutex::new(None)),
+            recent_messages: Arc::new(Mutex::new(VecDeque::new())),
+            message_content_limit: Arc::new(Mutex::new(DEFAULT_MESSAGE_CONTENT_LIMIT)),
+            buffer_memory_limit: Arc::new(Mutex::new(None)),
+            user: None,
+            connection_state: Arc::new(Mutex::new(ConnectionState::default())),
+            user_closed: Arc::new(Mutex::new(false)),
+            broadcaster: broadcast::channel(SUBSCRIBE_CHANNEL_CAPACITY).0,
+            preferred_node: Arc::new(Mutex::new(None)),
+            last_activity: Arc::new(std::sync::Mutex::new(Instant::now())),
+            keepalive: Arc::new(Mutex::new(None)),
+            dedup: Arc::new(Mutex::new(None)),
+            connection_watch: ConnectionWatch::default(),
+            send_interval: Arc::new(Mutex::new(DEFAULT_SEND_INTERVAL)),
+            next_send_at: Arc::new(std::sync::Mutex::new(None)),
+            echo_wait: Arc::new(Mutex::new(false)),
+            online_diff: Arc::new(Mutex::new(false)),
+            lazy_special: Arc::new(AtomicBool::new(false)),
+            source: Arc::new(Mutex::new(ChatSource::default())),
+            filter: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// 使用共享的未识别消息回调槽位
+    pub fn with_unknown_handler(mut self, slot: UnknownHandlerSlot) -> Self {
+        self.unknown_handler = slot;
+        self
+    }
+
+    /// 关联用户服务，用于识别自己发送的消息
+    ///
+    /// 当前用户名取自用户服务的缓存，令牌变更后随之失效
+    pub fn with_user(mut self, user: UserService) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// 设置重连配置
+    pub async fn set_reconnect_config(&self, config: ReconnectConfig) {
+        let mut current = self.reconnect_config.lock().await;
+        *current = config;
+    }
+
+    /// 获取当前重连配置
+    pub async fn reconnect_config(&self) -> ReconnectConfig {
+        self.reconnect_config.lock().await.clone()
+    }
+
+    /// 设置断线后自动重连的最大次数
+    ///
+    /// - `max` 最大重连次数，`None` 表示无限重试，适合长期运行的机器人
+    pub async fn set_max_retries(&self, max: Option<u32>) {
+        let mut config = self.reconnect_config.lock().await;
+        config.max_attempts = max.map_or(i32::MAX, |n| n.min(i32::MAX as u32) as i32);
+    }
+
+    /// 获取服务端支持的聊天室能力
+    ///
+    /// 服务端暂未提供能力查询接口，默认返回官方服务器支持的全部指令，
+    /// 自定义服务器可通过 `set_capabilities` 覆盖
+    pub async fn server_capabilities(&self) -> Response<Capabilities> {
+        Response::success(self.capabilities.lock().await.clone())
+    }
+
+    /// 覆盖聊天室能力配置
+    pub async fn set_capabilities(&self, capabilities: Capabilities) {
+        let mut current = self.capabilities.lock().await;
+        *current = capabilities;
+    }
+
     /// 清理所有资源
     async fn clean_all_resources(&self) {
+        if let Some(handle) = self.keepalive.lock().await.take() {
+            handle.abort();
+        }
         {
             let mut listeners = self.message_listeners.lock().await;
             let count = listeners.len();
@@ -125,38 +344,629 @@ impl ChatroomService {
             let mut retries = self.retry_times.lock().await;
             *retries = 0;
         }
+
+        self.close_connection().await;
+    }
+
+    /// 关闭本服务的连接，不影响其他服务的连接
+    ///
+    /// 先使旧连接的回调失效，再发送关闭帧并等待连接结束，让服务端及时将本次会话下线
+    async fn close_connection(&self) {
+        self.connection_epoch.fetch_add(1, Ordering::SeqCst);
+        let connection = self.ws_connection.lock().await.take();
+        if let Some(connection) = connection {
+            connection.shutdown(WEBSOCKET_CLOSE_TIMEOUT).await;
+        }
     }
 
     /// 用户名补全
+    ///
+    /// 优先在当前在线用户中按前缀匹配，无匹配时再请求接口（带缓存）
     pub async fn autocomplete(&self, prefix: &str) -> Response<Vec<AutoCompleteUsername>> {
+        let lower = prefix.to_lowercase();
+        let local: Vec<AutoCompleteUsername> = self
+            .online_users
+            .lock()
+            .await
+            .iter()
+            .filter(|user| user.user_name.to_lowercase().starts_with(&lower))
+            .map(AutoCompleteUsername::from)
+            .collect();
+        if !local.is_empty() {
+            return Response::success(local);
+        }
+
         self.call_api("用户名补全", || {
-            self.chatroom_api.autocomplete_username(prefix)
+            self.chatroom_api.autocomplete_username_cached(prefix)
         })
         .await
     }
 
+    /// 上传图片并发送到聊天室
+    ///
+    /// - `path` 图片路径
+    ///
+    /// 以 `![文件名](地址)` 的形式发送，返回图片地址
+    pub async fn send_image(&self, path: &Path) -> Response<String> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.len() > MAX_UPLOAD_SIZE as u64 => {
+                return Response::from_error(FishPiError::Validation(format!(
+                    "文件 {} 大小 {} 字节，超过上限 {} 字节",
+                    name,
+                    metadata.len(),
+                    MAX_UPLOAD_SIZE
+                )));
+            }
+            Ok(_) => {}
+            Err(err) => {
+                return Response::from_error(FishPiError::Validation(format!(
+                    "读取文件失败: {}",
+                    err
+                )));
+            }
+        }
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(err) => {
+                return Response::from_error(FishPiError::Validation(format!(
+                    "读取文件失败: {}",
+                    err
+                )));
+            }
+        };
+
+        let url = match self
+            .chatroom_api
+            .client()
+            .upload(vec![(name.clone(), data)])
+            .await
+        {
+            Ok(urls) => match urls.into_iter().next() {
+                Some(url) => url,
+                None => {
+                    return Response::from_error(FishPiError::Parse(
+                        "上传失败: 未返回图片地址".to_string(),
+                    ));
+                }
+            },
+            Err(err) => {
+                return Response::error(&err.to_string())
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        };
+
+        let content = format!("![{}]({})", name, url);
+        let result = self.send(Cow::Owned(content), None).await;
+        if result.success {
+            Response::success(url)
+        } else {
+            Response {
+                success: false,
+                message: result.message,
+                data: None,
+                error: result.error,
+            }
+        }
+    }
+
     /// 发送消息
+    ///
+    /// - `content` 消息内容
+    /// - `client` 客户端来源，为 `None` 时使用 [`ChatroomService::set_source`] 设置的来源
+    ///
+    /// 成功时返回发送结果，其中的 oid 可用于回复或撤回，见 [`ChatroomService::send_with`]
     pub async fn send<'a>(
         &self,
         content: Cow<'a, str>,
         client: Option<&ChatSource>,
-    ) -> Response<ApiResponse<()>> {
-        self.call_api("发送聊天室消息", || {
-            self.chatroom_api
-                .send_message(content.as_ref(), client.cloned())
+    ) -> Response<SentMessage> {
+        self.send_with(content, ChatContentType::MARKDOWN, client)
+            .await
+    }
+
+    /// 以指定内容类型发送消息
+    ///
+    /// - `content` 消息内容
+    /// - `content_type` 内容类型，仅支持 [`ChatContentType::MARKDOWN`] 与 [`ChatContentType::HTML`]
+    /// - `client` 客户端来源，为 `None` 时使用 [`ChatroomService::set_source`] 设置的来源
+    ///
+    /// 距上次发送不足发送间隔时等待后再发送，见 [`ChatroomService::set_send_interval`]
+    ///
+    /// 成功时返回发送时间与新消息的 oid：oid 取自接口响应；接口未返回且已通过
+    /// [`ChatroomService::set_echo_wait`] 开启回显等待时，在已连接的情况下等待自己消息的回显，
+    /// 都无法获得时 oid 为 `None`
+    pub async fn send_with<'a>(
+        &self,
+        content: Cow<'a, str>,
+        content_type: &str,
+        client: Option<&ChatSource>,
+    ) -> Response<SentMessage> {
+        self.send_paced(content, content_type, client, true).await
+    }
+
+    /// 立即发送消息，不等待发送间隔
+    ///
+    /// - `content` 消息内容
+    /// - `client` 客户端来源
+    ///
+    /// 距上次发送不足发送间隔时直接返回 [`FishPiError::RateLimited`]，适合宁可丢弃也不愿延迟的场景
+    pub async fn try_send<'a>(
+        &self,
+        content: Cow<'a, str>,
+        client: Option<&ChatSource>,
+    ) -> Response<SentMessage> {
+        self.send_paced(content, ChatContentType::MARKDOWN, client, false)
+            .await
+    }
+
+    /// 发送消息，因发送过于频繁失败时退避重试
+    ///
+    /// - `content` 消息内容
+    /// - `attempts` 最多尝试次数，含首次发送，为 0 时按 1 处理
+    /// - `backoff` 首次重试前的等待时间，之后每次翻倍
+    ///
+    /// 仅在 [`FishPiError::is_rate_limited`] 判定为限流时重试，未登录、内容校验失败等
+    /// 重试也无法成功的错误直接返回。返回最后一次发送的结果
+    pub async fn send_retrying<'a>(
+        &self,
+        content: Cow<'a, str>,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Response<SentMessage> {
+        let attempts = attempts.max(1);
+        let mut delay = backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self.send(Cow::Borrowed(content.as_ref()), None).await;
+            let rate_limited = result
+                .error
+                .as_ref()
+                .is_some_and(FishPiError::is_rate_limited);
+            if result.success || !rate_limited || attempt >= attempts {
+                return result;
+            }
+            log::debug!("发送过于频繁，{:?} 后进行第 {} 次重试", delay, attempt);
+            tokio::time::sleep(delay).await;
+            delay = delay.saturating_mul(2);
+            attempt += 1;
+        }
+    }
+
+    /// 设置发送消息时默认使用的客户端来源
+    ///
+    /// - `source` 客户端来源，如 `ChatSource::new("MyBot", "1.2")`
+    ///
+    /// 发送时传入的 `client` 优先于该设置
+    pub async fn set_source(&self, source: ChatSource) {
+        *self.source.lock().await = source;
+    }
+
+    /// 获取发送消息时默认使用的客户端来源
+    pub async fn source(&self) -> ChatSource {
+        self.source.lock().await.clone()
+    }
+
+    /// 设置两次发送之间的最小间隔
+    ///
+    /// 默认与服务端发送冷却一致（2 秒），实际间隔取该值与服务端慢速模式间隔中的较大者，
+    /// 设为零时仅遵守慢速模式
+    pub async fn set_send_interval(&self, interval: Duration) {
+        *self.send_interval.lock().await = interval;
+    }
+
+    /// 设置接口未返回 oid 时是否等待自己消息的回显，默认不等待
+    ///
+    /// 开启后发送会在已连接且已知当前用户名时，最多额外等待数秒以从回显中获取 oid
+    pub async fn set_echo_wait(&self, enabled: bool) {
+        *self.echo_wait.lock().await = enabled;
+    }
+
+    /// 预留发送时间
+    ///
+    /// 返回本次预留的发送时间，`wait` 为 `false` 且需要等待时返回 [`FishPiError::RateLimited`]。
+    /// 预留在发送前被取消时归还，见 [`SendSlot`]
+    async fn reserve_send_slot(&self, wait: bool) -> Result<SendSlot, FishPiError> {
+        let interval = {
+            let configured = *self.send_interval.lock().await;
+            let slow_mode = self
+                .current_slow_mode()
+                .await
+                .map(Duration::from_secs)
+                .unwrap_or_default();
+            configured.max(slow_mode)
+        };
+
+        let mut next_send_at = self
+            .next_send_at
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let at = next_send_at.map_or(now, |next| next.max(now));
+        if !wait && at > now {
+            return Err(FishPiError::RateLimited);
+        }
+        let slot = SendSlot {
+            next_send_at: self.next_send_at.clone(),
+            previous: *next_send_at,
+            reserved: at + interval,
+            at,
+            used: false,
+        };
+        *next_send_at = Some(slot.reserved);
+        Ok(slot)
+    }
+
+    /// 按发送间隔发送消息
+    async fn send_paced(
+        &self,
+        content: Cow<'_, str>,
+        content_type: &str,
+        client: Option<&ChatSource>,
+        wait: bool,
+    ) -> Response<SentMessage> {
+        if !ChatContentType::is_valid(content_type) {
+            return Response::from_error(FishPiError::Validation(format!(
+                "不支持的内容类型: {}",
+                content_type
+            )));
+        }
+        if let Err(err) = ensure_content(&content) {
+            return Response::from_error(err);
+        }
+
+        let expected = content.trim().to_string();
+        self.send_matched(&content, content_type, client, wait, move |message| {
+            message.md_text().trim() == expected || message.content_text().trim() == expected
         })
         .await
     }
 
-    /// 获取历史消息
-    pub async fn get_history(&self, page: i32) -> Response<ApiResponse<Vec<ChatRoomMessage>>> {
-        self.call_api(
-            &format!("获取聊天室历史消息，页码: {}", page),
-            || self.chatroom_api.get_history(page, "html"),
+    /// 发送红包消息
+    ///
+    /// - `redpacket` 红包内容，调用方应已校验
+    ///
+    /// 与普通消息共用发送间隔；oid 的获取方式同 [`ChatroomService::send_with`]，
+    /// 回显按发送者、祝福语、类型与个数匹配
+    pub(crate) async fn send_redpacket(
+        &self,
+        redpacket: &RedPacketMessage,
+    ) -> Response<SentMessage> {
+        let content = match serde_json::to_string(redpacket) {
+            Ok(json) => format!("[redpacket]{}[/redpacket]", json),
+            Err(err) => return Response::error(&format!("序列化红包失败: {}", err)),
+        };
+
+        let expected = redpacket.clone();
+        self.send_matched(
+            &content,
+            ChatContentType::MARKDOWN,
+            None,
+            true,
+            move |message| {
+                message.redpacket().is_some_and(|got| {
+                    got.msg == expected.msg
+                        && got.type_ == expected.type_
+                        && got.count == expected.count
+                })
+            },
         )
         .await
     }
 
+    /// 发送已校验的内容并解析新消息的 oid
+    ///
+    /// `is_echo` 用于在自己发出的回显消息中识别本条消息
+    async fn send_matched(
+        &self,
+        content: &str,
+        content_type: &str,
+        client: Option<&ChatSource>,
+        wait: bool,
+        is_echo: impl Fn(&ChatRoomMessage) -> bool + Send,
+    ) -> Response<SentMessage> {
+        let mut slot = match self.reserve_send_slot(wait).await {
+            Ok(slot) => slot,
+            Err(err) => return Response::from_error(err),
+        };
+        tokio::time::sleep_until(slot.at).await;
+        slot.used = true;
+
+        // 在发送前订阅，避免错过自己消息的回显
+        let echo = if *self.echo_wait.lock().await {
+            match self.user.as_ref().and_then(UserService::current_username) {
+                Some(me) if self.is_connected().await => Some((me, self.broadcaster.subscribe())),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        let sent_at = SystemTime::now();
+
+        let source = match client {
+            Some(client) => client.clone(),
+            None => self.source().await,
+        };
+        let result = self
+            .call_api("发送聊天室消息", || async {
+                let response = self
+                    .chatroom_api
+                    .send_message_value_with(content, content_type, Some(source))
+                    .await?;
+
+                if let Some(seconds) = parse_slow_mode(&response) {
+                    self.update_slow_mode(Some(seconds)).await;
+                }
+
+                ApiEnvelope::peek(&response)?.check("发送消息失败")?;
+                Ok(response)
+            })
+            .await;
+        let Response {
+            success,
+            message,
+            data,
+            error,
+        } = result;
+        let response = match data {
+            Some(response) if success => response,
+            _ => {
+                return Response {
+                    success: false,
+                    message,
+                    data: None,
+                    error,
+                };
+            }
+        };
+
+        let oid = match (Self::parse_sent_oid(&response), echo) {
+            (Some(oid), _) => Some(oid),
+            (None, Some((me, receiver))) => Self::wait_for_echo(receiver, &me, is_echo).await,
+            (None, None) => None,
+        };
+        Response::success(SentMessage { oid, sent_at })
+    }
+
+    /// 从发送接口的响应中读取新消息的 oid
+    fn parse_sent_oid(response: &Value) -> Option<String> {
+        let oid = response
+            .get("oId")
+            .or_else(|| response.get("data").and_then(|data| data.get("oId")))
+            .or_else(|| response.get("data").filter(|data| !data.is_object()))?;
+        match oid {
+            Value::String(oid) if !oid.is_empty() => Some(oid.clone()),
+            Value::Number(oid) => Some(oid.to_string()),
+            _ => None,
+        }
+    }
+
+    /// 等待自己刚发送的消息通过 WebSocket 回显，返回其 oid
+    async fn wait_for_echo(
+        mut receiver: broadcast::Receiver<ChatRoomData>,
+        me: &str,
+        is_echo: impl Fn(&ChatRoomMessage) -> bool,
+    ) -> Option<String> {
+        let wait = async {
+            loop {
+                match receiver.recv().await {
+                    Ok(ChatRoomData {
+                        data: ChatRoomDataContent::Message(message),
+                        ..
+                    }) if message.user_name == me && is_echo(&message) => {
+                        return Some(message.oid.clone());
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+        tokio::time::timeout(SEND_ECHO_TIMEOUT, wait)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// 发送音乐消息
+    ///
+    /// - `music` 音乐消息，歌曲名与音频地址不能为空
+    ///
+    /// 成功时返回实际发送的消息内容
+    pub async fn send_music(&self, music: MusicMsg) -> Response<String> {
+        if let Err(err) = music.validate() {
+            return Response::from_error(err);
+        }
+
+        let content = music.to_content();
+        let result = self.send(Cow::Borrowed(content.as_str()), None).await;
+        if result.success {
+            Response::success(content)
+        } else {
+            Response {
+                success: false,
+                message: result.message,
+                data: None,
+                error: result.error,
+            }
+        }
+    }
+
+    /// 获取当前聊天室慢速模式间隔（秒）
+    ///
+    /// 从发送消息的响应中获取，尚未获知时返回 None
+    pub async fn current_slow_mode(&self) -> Option<u64> {
+        *self.slow_mode.lock().await
+    }
+
+    /// 更新慢速模式间隔
+    async fn update_slow_mode(&self, seconds: Option<u64>) {
+        let mut slow_mode = self.slow_mode.lock().await;
+        if *slow_mode != seconds {
+            log::debug!("聊天室慢速模式间隔变更: {:?} -> {:?}", *slow_mode, seconds);
+            *slow_mode = seconds;
+        }
+    }
+
+    /// 获取历史消息
+    ///
+    /// - `page` 页码，指定 `before_oid` 时忽略
+    /// - `before_oid` 从该消息开始向前获取，用于从已知消息继续翻页
+    ///
+    /// 返回按时间从新到旧排列的消息。开启去重时，返回的消息会记入去重窗口，
+    /// 之后推送的相同消息不再通知监听函数
+    pub async fn get_history(
+        &self,
+        page: i32,
+        before_oid: Option<&str>,
+    ) -> Response<Vec<ChatRoomMessage>> {
+        let result = match before_oid {
+            Some(oid) => {
+                self.call_api(
+                    &format!("获取聊天室历史消息，起始消息: {}", oid),
+                    || self.fetch_messages_before(oid),
+                )
+                .await
+            }
+            None => {
+                self.call_api(
+                    &format!("获取聊天室历史消息，页码: {}", page),
+                    || self.fetch_history(page),
+                )
+                .await
+            }
+        };
+        if !result.success {
+            let message = result
+                .message
+                .as_deref()
+                .unwrap_or("获取聊天室历史消息失败");
+            let response = Response::error(message);
+            return match result.error {
+                Some(err) => response.with_error(err),
+                None => response,
+            };
+        }
+
+        let response = result
+            .data
+            .unwrap_or_else(|| ApiResponse::success(Vec::new()));
+        if response.code != 0 {
+            let msg = response.msg.as_deref().unwrap_or("获取聊天室历史消息失败");
+            return Response::from_error(FishPiError::from_code(response.code, msg));
+        }
+
+        let messages = response.data.unwrap_or_default();
+        for message in messages.iter().rev() {
+            self.mark_seen(&message.oid).await;
+        }
+        Response::success(messages)
+    }
+
+    /// 设置是否按 oid 对消息去重
+    ///
+    /// 开启后，最近 `DEDUP_WINDOW` 条内出现过的消息（如重连后重新下发的消息）不再通知监听函数，
+    /// 关闭时清空已记录的 oid
+    pub async fn set_dedup(&self, enabled: bool) {
+        let mut dedup = self.dedup.lock().await;
+        match (enabled, dedup.is_some()) {
+            (true, false) => *dedup = Some(VecDeque::with_capacity(DEDUP_WINDOW)),
+            (false, true) => *dedup = None,
+            _ => {}
+        }
+    }
+
+    /// 记录消息 oid，返回该 oid 是否首次出现
+    ///
+    /// 未开启去重时总是返回 `true`
+    async fn mark_seen(&self, oid: &str) -> bool {
+        let mut dedup = self.dedup.lock().await;
+        let Some(seen) = dedup.as_mut() else {
+            return true;
+        };
+        if oid.is_empty() {
+            return true;
+        }
+        if seen.iter().any(|o| o == oid) {
+            return false;
+        }
+        if seen.len() >= DEDUP_WINDOW {
+            seen.pop_front();
+        }
+        seen.push_back(oid.to_string());
+        true
+    }
+
+    /// 从去重窗口中移除 oid
+    async fn forget_seen(&self, oid: &str) {
+        if let Some(seen) = self.dedup.lock().await.as_mut() {
+            seen.retain(|o| o != oid);
+        }
+    }
+
+    /// 获取指定用户的历史消息
+    ///
+    /// 服务端不支持按用户查询，从 `page` 开始逐页拉取历史消息并在本地筛选，
+    /// 最多扫描 `USER_HISTORY_MAX_PAGES` 页
+    ///
+    /// - `user` 用户名，不区分大小写，可带 `@` 前缀
+    /// - `page` 起始页码
+    ///
+    /// 返回按时间从新到旧排列的消息
+    pub async fn user_history(&self, user: &str, page: i32) -> Response<Vec<ChatRoomMessage>> {
+        let user = user.trim().trim_start_matches('@');
+        if user.is_empty() {
+            return Response::from_error(FishPiError::Validation("用户名不能为空".to_string()));
+        }
+
+        let mut messages = Vec::new();
+        for p in page.max(1)..page.max(1) + USER_HISTORY_MAX_PAGES {
+            let batch = match self.fetch_history(p).await {
+                Ok(ApiResponse {
+                    code: 0,
+                    data: Some(batch),
+                    ..
+                }) => batch,
+                Ok(response) => {
+                    if messages.is_empty() {
+                        let msg = response.msg.as_deref().unwrap_or("获取聊天室历史消息失败");
+                        return Response::error(msg)
+                            .with_error(FishPiError::from_code(response.code, msg));
+                    }
+                    break;
+                }
+                Err(err) => {
+                    if messages.is_empty() {
+                        return Response::error(&format!("获取聊天室历史消息失败: {}", err))
+                            .with_error(FishPiError::from_anyhow(&err));
+                    }
+                    break;
+                }
+            };
+
+            if batch.is_empty() {
+                break;
+            }
+
+            messages.extend(
+                batch
+                    .into_iter()
+                    .filter(|msg| msg.user_name.eq_ignore_ascii_case(user)),
+            );
+        }
+
+        messages.sort_by(|a, b| {
+            let a_oid = a.oid.parse::<i64>().unwrap_or(0);
+            let b_oid = b.oid.parse::<i64>().unwrap_or(0);
+            b_oid.cmp(&a_oid)
+        });
+        messages.dedup_by(|a, b| a.oid == b.oid);
+
+        Response::success(messages)
+    }
+
     /// 处理在线用户消息
     async fn handle_online_users(
         &self,
@@ -164,21 +974,78 @@ impl ChatroomService {
         online_chat_count: Option<i32>,
         disc: Option<String>,
     ) {
-        {
+        let previous = {
             let mut online_users_guard = self.online_users.lock().await;
-            *online_users_guard = users.clone();
-        }
+            std::mem::replace(&mut *online_users_guard, users.clone())
+        };
 
         {
             let mut discussing_value = self.discussing.lock().await;
             *discussing_value = disc.clone();
         }
 
+        // 连接后的首个在线列表没有可比较的上一次列表，不推送变化
+        let change = if *self.online_diff.lock().await && !previous.is_empty() {
+            Some(diff_online_users(&previous, &users))
+        } else {
+            None
+        };
+
         self.notify_listeners(ChatRoomData {
             type_: ChatRoomMessageType::ONLINE.to_string(),
             data: ChatRoomDataContent::OnlineUsers(users, online_chat_count, disc),
         })
         .await;
+
+        if let Some((joined, left)) = change
+            && (!joined.is_empty() || !left.is_empty())
+        {
+            self.notify_listeners(ChatRoomData {
+                type_: ChatRoomMessageType::ONLINE_CHANGE.to_string(),
+                data: ChatRoomDataContent::OnlineChange { joined, left },
+            })
+            .await;
+        }
+    }
+
+    /// 设置消息过滤规则
+    ///
+    /// - `filter` 过滤规则，命中的聊天消息不会推送给监听器，但仍保留在最近消息缓存中
+    ///
+    /// 规则中存在无效的正则表达式时返回 [`FishPiError::Validation`]，原有规则保持不变
+    pub async fn set_filter(&self, mut filter: MessageFilter) -> Response<()> {
+        if let Err(err) = filter.compile() {
+            return Response::from_error(err);
+        }
+        *self.filter.lock().await = Some(filter);
+        Response::success(())
+    }
+
+    /// 清除消息过滤规则
+    pub async fn clear_filter(&self) {
+        *self.filter.lock().await = None;
+    }
+
+    /// 获取当前的消息过滤规则
+    pub async fn filter(&self) -> Option<MessageFilter> {
+        self.filter.lock().await.clone()
+    }
+
+    /// 消息是否命中过滤规则
+    async fn is_filtered(&self, message: &ChatRoomMessage) -> bool {
+        self.filter
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|filter| filter.should_block(message))
+    }
+
+    /// 设置是否推送在线用户变化事件
+    ///
+    /// 开启后，每次在线列表更新时在完整列表之外，额外推送
+    /// [`ChatRoomDataContent::OnlineChange`]，包含相比上一次列表新加入与离开的用户
+    pub async fn set_online_diff(&self, enabled: bool) {
+        *self.online_diff.lock().await = enabled;
     }
 
     /// 处理讨论主题变更消息
@@ -195,12 +1062,16 @@ impl ChatroomService {
         .await;
     }
 
-    /// 通知所有消息监听器
+    /// 通知所有消息监听器与 `subscribe` 订阅者
     async fn notify_listeners(&self, chat_room_data: ChatRoomData) {
-        let listeners = self.message_listeners.lock().await;
-        for listener in listeners.iter() {
-            listener(chat_room_data.clone());
+        {
+            let listeners = self.message_listeners.lock().await;
+            for listener in listeners.iter() {
+                listener(chat_room_data.clone());
+            }
         }
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.broadcaster.send(chat_room_data);
     }
 
     /// 创建WebSocket消息处理器
@@ -214,46 +1085,81 @@ impl ChatroomService {
 
         move |value: Value| {
             let service = service.clone();
+            service.touch_activity();
 
             tokio::spawn(async move {
-                if let Ok(ws_message) = serde_json::from_value::<WebSocketMessage>(value.clone()) {
-                    match ws_message {
-                        WebSocketMessage::OnlineUsers {
-                            users,
-                            online_chat_count,
-                            discussing: disc,
-                        } => {
-                            service
-                                .handle_online_users(users, online_chat_count, disc)
-                                .await;
+                let is_chat_message = value.get("type").and_then(|v| v.as_str()) == Some("msg");
+                let parsed = if is_chat_message && service.is_lazy_special_content() {
+                    serde_json::from_value::<LazyChatRoomMessage>(value.clone()).map(|message| {
+                        WebSocketMessage::ChatMessage {
+                            message: Box::new(message.0),
+                        }
+                    })
+                } else {
+                    serde_json::from_value::<WebSocketMessage>(value.clone())
+                };
+                let ws_message = match parsed {
+                    Ok(ws_message) => ws_message,
+                    Err(_) => {
+                        let type_ = value
+                            .get("type")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string();
+                        notify_unknown(&service.unknown_handler, &type_, value).await;
+                        return;
+                    }
+                };
+
+                match ws_message {
+                    WebSocketMessage::OnlineUsers {
+                        users,
+                        online_chat_count,
+                        discussing: disc,
+                    } => {
+                        service
+                            .handle_online_users(users, online_chat_count, disc)
+                            .await;
+                    }
+                    WebSocketMessage::DiscussChanged { new_discuss } => {
+                        service.handle_discuss_changed(new_discuss).await;
+                    }
+                    WebSocketMessage::ChatMessage { mut message } => {
+                        if !service.mark_seen(&message.oid).await {
+                            return;
                         }
-                        WebSocketMessage::DiscussChanged { new_discuss } => {
-                            service.handle_discuss_changed(new_discuss).await;
+                        message.mark_received();
+                        service.push_recent_message(&message).await;
+                        if service.is_filtered(&message).await {
+                            return;
                         }
-                        WebSocketMessage::ChatMessage { message } => {
-                            let message_type = message
+
+                        let actual_type = match message.kind() {
+                            ChatMessageKind::Normal => message
                                 .message_type
                                 .clone()
-                                .unwrap_or_else(|| ChatRoomMessageType::MSG.to_string());
-
-                            let actual_type = if message.is_redpacket() {
-                                ChatRoomMessageType::RED_PACKET.to_string()
-                            } else if message.is_weather() {
-                                ChatRoomMessageType::WEATHER.to_string()
-                            } else if message.is_music() {
-                                ChatRoomMessageType::MUSIC.to_string()
-                            } else {
-                                message_type
-                            };
-
-                            service
-                                .notify_listeners(ChatRoomData {
-                                    type_: actual_type,
-                                    data: ChatRoomDataContent::Message(message),
-                                })
-                                .await;
-                        }
-                        WebSocketMessage::Barrager {
+                                .unwrap_or_else(|| ChatRoomMessageType::MSG.to_string()),
+                            kind => kind.as_str().to_string(),
+                        };
+
+                        service
+                            .notify_listeners(ChatRoomData {
+                                type_: actual_type,
+                                data: ChatRoomDataContent::Message(message),
+                            })
+                            .await;
+                    }
+                    WebSocketMessage::Barrager {
+                        user_name,
+                        user_nickname,
+                        barrager_content,
+                        barrager_color,
+                        user_avatar_url,
+                        user_avatar_url_20,
+                        user_avatar_url_48,
+                        user_avatar_url_210,
+                    } => {
+                        let barrager = BarragerMsg {
                             user_name,
                             user_nickname,
                             barrager_content,
@@ -262,102 +1168,239 @@ impl ChatroomService {
                             user_avatar_url_20,
                             user_avatar_url_48,
                             user_avatar_url_210,
-                        } => {
-                            let barrager = BarragerMsg {
-                                user_name,
-                                user_nickname,
-                                barrager_content,
-                                barrager_color,
-                                user_avatar_url,
-                                user_avatar_url_20,
-                                user_avatar_url_48,
-                                user_avatar_url_210,
-                            };
-
-                            service
-                                .notify_listeners(ChatRoomData {
-                                    type_: ChatRoomMessageType::BARRAGER.to_string(),
-                                    data: ChatRoomDataContent::Barrager(barrager),
-                                })
-                                .await;
-                        }
-                        WebSocketMessage::RedPacketStatus {
-                            oid,
+                        };
+
+                        service
+                            .notify_listeners(ChatRoomData {
+                                type_: ChatRoomMessageType::BARRAGER.to_string(),
+                                data: ChatRoomDataContent::Barrager(barrager),
+                            })
+                            .await;
+                    }
+                    WebSocketMessage::RedPacketStatus {
+                        oid,
+                        count,
+                        got,
+                        who_give,
+                        who_got,
+                        avatar_url_20,
+                        avatar_url_48,
+                        avatar_url_210,
+                    } => {
+                        let status = RedPacketStatusMsg {
+                            oid: oid.clone(),
                             count,
                             got,
-                            who_give,
-                            who_got,
-                            avatar_url_20,
-                            avatar_url_48,
-                            avatar_url_210,
-                        } => {
-                            let status = RedPacketStatusMsg {
-                                oid: oid.clone(),
-                                count,
-                                got,
-                                who_give: who_give.clone(),
-                                who_got: who_got.clone(),
-                                avatar_url_20: avatar_url_20.clone(),
-                                avatar_url_48: avatar_url_48.clone(),
-                                avatar_url_210: avatar_url_210.clone(),
-                            };
-
-                            service
-                                .notify_listeners(ChatRoomData {
-                                    type_: ChatRoomMessageType::RED_PACKET_STATUS.to_string(),
-                                    data: ChatRoomDataContent::RedPacketStatus(status),
-                                })
-                                .await;
-                        }
-                        WebSocketMessage::Custom { message } => {
-                            service
-                                .notify_listeners(ChatRoomData {
-                                    type_: ChatRoomMessageType::CUSTOM.to_string(),
-                                    data: ChatRoomDataContent::Custom(message),
-                                })
-                                .await;
-                        }
-                        _ => {}
+                            who_give: who_give.clone(),
+                            who_got: who_got.clone(),
+                            avatar_url_20: avatar_url_20.filter(|url| !url.is_empty()),
+                            avatar_url_48: avatar_url_48.filter(|url| !url.is_empty()),
+                            avatar_url_210: avatar_url_210.filter(|url| !url.is_empty()),
+                        };
+
+                        service
+                            .notify_listeners(ChatRoomData {
+                                type_: ChatRoomMessageType::RED_PACKET_STATUS.to_string(),
+                                data: ChatRoomDataContent::RedPacketStatus(status),
+                            })
+                            .await;
+                    }
+                    WebSocketMessage::Custom { message } => {
+                        service
+                            .notify_listeners(ChatRoomData {
+                                type_: ChatRoomMessageType::CUSTOM.to_string(),
+                                data: ChatRoomDataContent::Custom(message),
+                            })
+                            .await;
+                    }
+                    WebSocketMessage::Revoke { oid } => {
+                        service.remove_recent_message(&oid).await;
+                        service.forget_seen(&oid).await;
+
+                        service
+                            .notify_listeners(ChatRoomData {
+                                type_: ChatRoomMessageType::REVOKE.to_string(),
+                                data: ChatRoomDataContent::Revoke(oid),
+                            })
+                            .await;
+                    }
+                    WebSocketMessage::SimpleHeartbeat => {
+                        let type_ = value
+                            .get("type")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string();
+                        notify_unknown(&service.unknown_handler, &type_, value).await;
                     }
+                    _ => {}
                 }
             });
         }
     }
 
     /// 创建WebSocket错误处理器
+    ///
+    /// - `epoch` 所属连接的编号，连接已被替换或关闭时忽略
     fn create_error_handler(
         &self,
         retry_times: Arc<Mutex<i32>>,
         connected: Arc<Mutex<bool>>,
+        epoch: u64,
     ) -> impl Fn(String) + Send + Sync + Clone + 'static {
+        let connection_watch = self.connection_watch.clone();
+        let connection_epoch = self.connection_epoch.clone();
         move |_error: String| {
             let retry_times = retry_times.clone();
             let connected = connected.clone();
+            let connection_watch = connection_watch.clone();
+            if connection_epoch.load(Ordering::SeqCst) != epoch {
+                return;
+            }
 
             tokio::spawn(async move {
-                let mut connected = connected.lock().await;
-                *connected = false;
+                *connected.lock().await = false;
+                connection_watch.notify(None, false).await;
 
-                let mut retry_count = retry_times.lock().await;
-                *retry_count += 1;
+                *retry_times.lock().await += 1;
             });
         }
     }
 
     /// 创建WebSocket关闭处理器
+    ///
+    /// 被封禁/踢出时记录原因并通知监听器，之后 `reconnect` 不再重试；
+    /// 其余情况下按重连配置自动重连，监听器保持不变
+    ///
+    /// - `epoch` 所属连接的编号，连接已被替换或主动关闭时忽略
     fn create_close_handler(
         &self,
         connected: Arc<Mutex<bool>>,
-    ) -> impl Fn() + Send + Sync + Clone + 'static {
-        move || {
+        epoch: u64,
+    ) -> impl Fn(ConnectionState) + Send + Sync + Clone + 'static {
+        let connection_state = self.connection_state.clone();
+        let service = self.clone();
+        move |state: ConnectionState| {
+            if service.connection_epoch.load(Ordering::SeqCst) != epoch {
+                return;
+            }
             let connected = connected.clone();
+            let connection_state = connection_state.clone();
+            let service = service.clone();
             tokio::spawn(async move {
-                let mut connected_lock = connected.lock().await;
-                *connected_lock = false;
+                {
+                    let mut connected_lock = connected.lock().await;
+                    *connected_lock = false;
+                }
+                service.connection_watch.notify(None, false).await;
+
+                if let ConnectionState::Banned { reason } = &state {
+                    log::warn!("聊天室连接被服务端关闭: {}", reason);
+                }
+                *connection_state.lock().await = state.clone();
+
+                if state.is_banned() {
+                    service
+                        .notify_listeners(ChatRoomData {
+                            type_: "connectionState".to_string(),
+                            data: ChatRoomDataContent::ConnectionState(state),
+                        })
+                        .await;
+                    return;
+                }
+
+                service.auto_reconnect().await;
             });
         }
     }
 
+    /// 断线后自动重连，直到成功、超过最大重连次数或用户主动断开
+    async fn auto_reconnect(&self) {
+        loop {
+            if *self.user_closed.lock().await {
+                return;
+            }
+
+            let config = self.reconnect_config().await;
+            let retry_count = *self.retry_times.lock().await;
+            if !config.can_retry(retry_count) {
+                log::warn!(
+                    "聊天室重连次数超过最大限制({})，停止重连",
+                    config.max_attempts
+                );
+                return;
+            }
+
+            log::debug!("聊天室连接断开，第 {} 次重连", retry_count + 1);
+            self.chatroom_api.client().metrics().record_reconnect();
+            if self.reconnect_once().await.success {
+                *self.retry_times.lock().await = 0;
+                return;
+            }
+        }
+    }
+
+    /// 设置特殊消息内容是否延迟解析
+    ///
+    /// 大量拉取历史消息且不关心红包/天气/音乐内容时可开启以减少解析开销，
+    /// 仅对本服务获取与推送的消息生效，见 [`LazyChatRoomMessage`]
+    pub fn set_lazy_special_content(&self, lazy: bool) {
+        self.lazy_special.store(lazy, Ordering::Relaxed);
+    }
+
+    /// 是否延迟解析特殊消息内容
+    pub fn is_lazy_special_content(&self) -> bool {
+        self.lazy_special.load(Ordering::Relaxed)
+    }
+
+    /// 按解析模式获取历史消息
+    async fn fetch_history(&self, page: i32) -> anyhow::Result<ApiResponse<Vec<ChatRoomMessage>>> {
+        if !self.is_lazy_special_content() {
+            return self.chatroom_api.get_history(page, "html").await;
+        }
+        let response = self
+            .chatroom_api
+            .get_history_as::<LazyChatRoomMessage>(page, "html")
+            .await?;
+        Ok(response.map_data(|messages| messages.into_iter().map(|m| m.0).collect()))
+    }
+
+    /// 按解析模式获取指定消息之前的消息
+    async fn fetch_messages_before(
+        &self,
+        oid: &str,
+    ) -> anyhow::Result<ApiResponse<Vec<ChatRoomMessage>>> {
+        let mode = ChatRoomQueryMode::Before;
+        if !self.is_lazy_special_content() {
+            return self
+                .chatroom_api
+                .get_messages(oid, mode, HISTORY_PAGE_SIZE, "html")
+                .await;
+        }
+        let response = self
+            .chatroom_api
+            .get_messages_as::<LazyChatRoomMessage>(oid, mode, HISTORY_PAGE_SIZE, "html")
+            .await?;
+        Ok(response.map_data(|messages| messages.into_iter().map(|m| m.0).collect()))
+    }
+
+    /// 设置连接状态变化回调，参数为是否已连接
+    ///
+    /// 首次连接、每次断开与每次重连成功时调用，连续相同的状态只通知一次，
+    /// 从未连上时不会通知断开
+    pub async fn on_connection_change<F>(&self, callback: F)
+    where
+        F: Fn(bool) + Send + Sync + 'static,
+    {
+        self.connection_watch
+            .set(Arc::new(move |_, connected| callback(connected)))
+            .await;
+    }
+
+    /// 获取当前连接状态
+    pub async fn connection_state(&self) -> ConnectionState {
+        self.connection_state.lock().await.clone()
+    }
+
     /// 连接到聊天室
     pub async fn connect(&self) -> Response<()> {
         if self.is_connected().await {
@@ -366,17 +1409,30 @@ impl ChatroomService {
 
         {
             let listeners = self.message_listeners.lock().await;
-            if listeners.is_empty() {
+            if listeners.is_empty() && self.broadcaster.receiver_count() == 0 {
                 return Response::error("没有监听器，无法建立有效连接");
             }
         }
+        // 仅用户主动连接时清除断开标记，自动重连不会覆盖用户的断开操作
+        *self.user_closed.lock().await = false;
+        self.open_connection().await
+    }
 
-        let ws_url = match self.chatroom_api.get_websocket_url().await {
-            Ok(url) => url,
-            Err(err) => return Response::error(&format!("获取WebSocket地址失败: {}", err)),
+    /// 建立WebSocket连接，不改变用户断开标记
+    async fn open_connection(&self) -> Response<()> {
+        let preferred_node = self.preferred_node.lock().await.clone();
+        let ws_url = match preferred_node {
+            Some(node) => match self.resolve_node(&node).await {
+                Ok(node) => node.node,
+                Err(err) => return Response::error(&format!("获取聊天室节点失败: {}", err)),
+            },
+            None => match self.chatroom_api.get_websocket_url().await {
+                Ok(url) => url,
+                Err(err) => return Response::error(&format!("获取WebSocket地址失败: {}", err)),
+            },
         };
 
-        let client = ApiClient::new();
+        let client = self.chatroom_api.client().clone();
         let base_url = client.base_url();
 
         let full_url = if ws_url.starts_with("ws") || ws_url.starts_with("wss") {
@@ -395,6 +1451,10 @@ impl ChatroomService {
             )
         };
 
+        // 替换本服务的旧连接，其他服务的连接不受影响
+        self.close_connection().await;
+        let epoch = self.connection_epoch.load(Ordering::SeqCst);
+
         {
             let mut connected = self.connected.lock().await;
             *connected = true;
@@ -406,10 +1466,13 @@ impl ChatroomService {
             self.discussing.clone(),
         );
 
-        let error_handler =
-            Some(self.create_error_handler(self.retry_times.clone(), self.connected.clone()));
+        let error_handler = Some(self.create_error_handler(
+            self.retry_times.clone(),
+            self.connected.clone(),
+            epoch,
+        ));
 
-        let close_handler = Some(self.create_close_handler(self.connected.clone()));
+        let close_handler = Some(self.create_close_handler(self.connected.clone(), epoch));
 
         let mut params = HashMap::new();
         if let Some(token) = client.get_token().await {
@@ -426,12 +1489,27 @@ impl ChatroomService {
             )
             .await
         {
-            Ok(_) => {
-                let mut retry_count = self.retry_times.lock().await;
-                *retry_count = 0;
-                Response::success(())
-            }
-            Err(err) => {
+            Ok(connection) => {
+                // 建立连接期间用户已断开时关闭新连接
+                if *self.user_closed.lock().await {
+                    *self.connected.lock().await = false;
+                    connection.shutdown(WEBSOCKET_CLOSE_TIMEOUT).await;
+                    return Response::error("连接已被用户断开");
+                }
+                {
+                    let mut ws_connection = self.ws_connection.lock().await;
+                    *ws_connection = Some(connection);
+                }
+                *self.connection_state.lock().await = ConnectionState::Connected;
+                {
+                    let mut retry_count = self.retry_times.lock().await;
+                    *retry_count = 0;
+                }
+                self.start_keepalive().await;
+                self.connection_watch.notify(None, true).await;
+                Response::success(())
+            }
+            Err(err) => {
                 {
                     let mut connected = self.connected.lock().await;
                     *connected = false;
@@ -441,8 +1519,96 @@ impl ChatroomService {
         }
     }
 
+    /// 记录收到服务端消息
+    fn touch_activity(&self) {
+        if let Ok(mut last) = self.last_activity.lock() {
+            *last = Instant::now();
+        }
+    }
+
+    /// 启动心跳任务，替换已有的心跳任务
+    ///
+    /// 连接空闲超过 [`HEARTBEAT_INTERVAL`] 时发送心跳帧，避免服务端回收空闲连接；连接断开后自动退出
+    async fn start_keepalive(&self) {
+        self.touch_activity();
+        let service = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL / 2);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if !*service.connected.lock().await {
+                    break;
+                }
+                let idle = service
+                    .last_activity
+                    .lock()
+                    .map(|last| last.elapsed())
+                    .unwrap_or_default();
+                if idle < HEARTBEAT_INTERVAL {
+                    continue;
+                }
+                let sent = match service.ws_connection.lock().await.as_ref() {
+                    Some(connection) => connection
+                        .sender()
+                        .unbounded_send(Message::Text(HEARTBEAT_FRAME.to_string()))
+                        .is_ok(),
+                    None => false,
+                };
+                if !sent {
+                    break;
+                }
+                service.touch_activity();
+            }
+        });
+
+        if let Some(old) = self.keepalive.lock().await.replace(handle) {
+            old.abort();
+        }
+    }
+
+    /// 获取聊天室节点列表
+    pub async fn nodes(&self) -> Response<ChatRoomNodeInfo> {
+        self.call_api("获取聊天室节点", || self.chatroom_api.get_nodes())
+            .await
+    }
+
+    /// 连接到指定节点
+    ///
+    /// - `node` 节点名称或地址，需在可用节点列表中
+    ///
+    /// 节点不在可用列表中（已下线）时连接推荐节点；之后的自动重连沿用该选择。
+    /// 已连接时保留监听器并切换到新节点
+    pub async fn connect_to(&self, node: &str) -> Response<()> {
+        *self.preferred_node.lock().await = Some(node.to_string());
+        // 建立新连接时只关闭本服务的旧连接
+        *self.connected.lock().await = false;
+        self.connect().await
+    }
+
+    /// 在可用节点中查找指定节点，找不到时退回推荐节点
+    async fn resolve_node(&self, node: &str) -> anyhow::Result<ChatRoomNode> {
+        let info = self.chatroom_api.get_nodes().await?;
+        match info
+            .avaliable
+            .into_iter()
+            .find(|n| n.node == node || n.name == node)
+        {
+            Some(found) => Ok(found),
+            None => {
+                log::warn!(
+                    "聊天室节点 {} 不可用，改用推荐节点 {}",
+                    node,
+                    info.recommend.name
+                );
+                Ok(info.recommend)
+            }
+        }
+    }
+
     /// 断开与聊天室的连接
     pub async fn disconnect(&self) -> Response<()> {
+        *self.user_closed.lock().await = true;
         {
             let mut connected = self.connected.lock().await;
             if !*connected {
@@ -452,26 +1618,285 @@ impl ChatroomService {
         }
 
         self.clean_all_resources().await;
+        self.connection_watch.notify(None, false).await;
 
-        let client = ApiClient::new();
-        let _ = client.close_websocket_connections().await;
-
-        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
         Response::success(())
     }
 
+    /// 重新连接聊天室
+    ///
+    /// 按重连配置进行退避等待，超过最大重连次数或已被封禁时返回错误
+    pub async fn reconnect(&self) -> Response<()> {
+        *self.user_closed.lock().await = false;
+        self.reconnect_once().await
+    }
+
+    /// 退避等待后重连一次，等待期间用户主动断开时放弃重连
+    async fn reconnect_once(&self) -> Response<()> {
+        if let ConnectionState::Banned { reason } = self.connection_state().await {
+            return Response::error(&format!("已被封禁，不再重连: {}", reason));
+        }
+
+        let config = self.reconnect_config().await;
+        let retry_count = *self.retry_times.lock().await;
+        if !config.can_retry(retry_count) {
+            return Response::error(&format!("重连次数超过最大限制({})", config.max_attempts));
+        }
+
+        tokio::time::sleep(config.backoff_delay(retry_count)).await;
+        if *self.user_closed.lock().await {
+            return Response::error("连接已被用户断开");
+        }
+
+        {
+            let mut connected = self.connected.lock().await;
+            *connected = false;
+        }
+
+        let result = self.open_connection().await;
+        if !result.success {
+            let mut retry_count = self.retry_times.lock().await;
+            *retry_count += 1;
+        }
+        result
+    }
+
+    /// 直接发送原始 WebSocket 文本帧
+    ///
+    /// **不稳定接口**：内容不做任何校验，原样推送给服务器，仅用于调试或调用尚未封装的服务端指令，
+    /// 后续版本可能调整或移除。需先调用 `connect` 建立连接。
+    ///
+    /// - `text` 原始帧内容
+    pub async fn send_raw(&self, text: String) -> Response<()> {
+        if !self.is_connected().await {
+            let msg = "聊天室未连接，无法发送原始消息";
+            return Response::error(msg).with_error(FishPiError::Network(msg.to_string()));
+        }
+
+        let connection = self.ws_connection.lock().await;
+        let msg = match connection.as_ref() {
+            Some(connection) => match connection.sender().unbounded_send(Message::Text(text)) {
+                Ok(_) => return Response::success(()),
+                Err(err) => format!("发送原始消息失败: {}", err),
+            },
+            None => "聊天室WebSocket发送器不存在".to_string(),
+        };
+        Response::error(&msg).with_error(FishPiError::Network(msg.clone()))
+    }
+
     /// 撤回聊天室消息
-    pub async fn revoke(&self, oid: &str) -> Response<ApiResponse<()>> {
-        self.call_api(&format!("撤回聊天室消息: id={}", oid), || async {
-            self.chatroom_api.revoke_message(oid).await
+    ///
+    /// - `oid` 消息 oid，不能为空
+    ///
+    /// 超过撤回时限或无权撤回时，`message` 中为服务端返回的原因
+    pub async fn revoke(&self, oid: &str) -> Response<()> {
+        if oid.trim().is_empty() {
+            return Response::from_error(FishPiError::Validation("消息 oid 不能为空".to_string()));
+        }
+
+        log::debug!("撤回聊天室消息: id={}", oid);
+        match self.chatroom_api.revoke_message(oid).await {
+            Ok(response) if response.code == 0 => {
+                self.remove_recent_message(oid).await;
+                Response::success(())
+            }
+            Ok(response) => {
+                let msg = response.msg.unwrap_or_else(|| "撤回失败".to_string());
+                Response::error(&msg).with_error(FishPiError::from_code(response.code, &msg))
+            }
+            Err(err) => Response::error(&format!("撤回失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
+    /// 撤回当前用户最近发送的 n 条消息
+    ///
+    /// 从内存中的最近消息里查找当前用户发送的消息，超过撤回时限的消息会被跳过并记录错误
+    ///
+    /// - `n` 撤回数量
+    ///
+    /// 返回成功撤回的消息 id，失败信息汇总在 `message` 中
+    pub async fn revoke_last(&self, n: usize) -> Response<Vec<String>> {
+        let me = match self.current_user_name().await {
+            Ok(name) => name,
+            Err(err) => {
+                return Response::error(&format!("获取当前用户失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        };
+
+        let candidates: Vec<ChatRoomMessage> = {
+            let messages = self.recent_messages.lock().await;
+            messages
+                .iter()
+                .rev()
+                .filter(|msg| msg.user_name == me)
+                .take(n)
+                .cloned()
+                .collect()
+        };
+
+        if candidates.is_empty() {
+            return Response::from_error(FishPiError::Validation("没有可撤回的消息".to_string()));
+        }
+
+        let now = chrono::Local::now().naive_local();
+        let mut revoked = Vec::new();
+        let mut errors = Vec::new();
+        let mut first_error = None;
+
+        for msg in candidates {
+            if let Ok(time) = chrono::NaiveDateTime::parse_from_str(&msg.time, "%Y-%m-%d %H:%M:%S")
+                && (now - time).num_seconds() > REVOKE_TIME_WINDOW_SECS
+            {
+                errors.push(format!("{}: 超过撤回时限", msg.oid));
+                first_error.get_or_insert(FishPiError::Validation("超过撤回时限".to_string()));
+                continue;
+            }
+
+            let result = self.revoke(&msg.oid).await;
+            if result.success {
+                revoked.push(msg.oid);
+            } else {
+                errors.push(format!(
+                    "{}: {}",
+                    msg.oid,
+                    result.message.as_deref().unwrap_or("撤回失败")
+                ));
+                if first_error.is_none() {
+                    first_error = result.error;
+                }
+            }
+        }
+
+        if revoked.is_empty() {
+            let message = errors.join("; ");
+            let error = first_error.unwrap_or_else(|| FishPiError::Network(message.clone()));
+            return Response::error(&message).with_error(error);
+        }
+
+        Response {
+            success: true,
+            message: (!errors.is_empty()).then(|| errors.join("; ")),
+            data: Some(revoked),
+            error: None,
+        }
+    }
+
+    /// 获取当前用户名
+    ///
+    /// 已关联用户服务时使用其缓存，缓存失效时由用户服务重新获取；否则每次从服务端获取
+    async fn current_user_name(&self) -> anyhow::Result<String> {
+        let Some(user) = &self.user else {
+            return self.chatroom_api.get_current_user_name().await;
+        };
+        if let Some(name) = user.current_username() {
+            return Ok(name);
+        }
+
+        let result = user.fetch_current_user().await;
+        result.data.map(|info| info.user_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}",
+                result.message.as_deref().unwrap_or("获取当前用户失败")
+            )
         })
-        .await
+    }
+
+    /// 获取内存中的最近消息，按时间从旧到新排列
+    pub async fn recent_messages(&self) -> Vec<ChatRoomMessage> {
+        self.recent_messages.lock().await.iter().cloned().collect()
+    }
+
+    /// 记录最近消息
+    ///
+    /// 超出单条内容上限的消息截断后保存，超出总内存预算时丢弃最早的消息
+    async fn push_recent_message(&self, message: &ChatRoomMessage) {
+        let content_limit = *self.message_content_limit.lock().await;
+        let memory_limit = *self.buffer_memory_limit.lock().await;
+
+        let mut message = message.clone();
+        if message.content.len() > content_limit {
+            message.content = format!(
+                "{}{}",
+                truncate_utf8(&message.content, content_limit),
+                TRUNCATED_MARK
+            );
+        }
+        if let Some(md) = message.md.as_mut()
+            && md.len() > content_limit
+        {
+            *md = format!("{}{}", truncate_utf8(md, content_limit), TRUNCATED_MARK);
+        }
+
+        let mut messages = self.recent_messages.lock().await;
+        if messages.len() >= RECENT_MESSAGES_CAPACITY {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+
+        if let Some(limit) = memory_limit {
+            let mut total: usize = messages.iter().map(Self::message_bytes).sum();
+            while total > limit && messages.len() > 1 {
+                if let Some(dropped) = messages.pop_front() {
+                    total -= Self::message_bytes(&dropped);
+                }
+            }
+        }
+    }
+
+    /// 估算消息占用的字节数（内容与 Markdown 原文）
+    fn message_bytes(message: &ChatRoomMessage) -> usize {
+        message.content.len() + message.md.as_ref().map_or(0, |md| md.len())
+    }
+
+    /// 设置最近消息缓冲区的总内存预算（字节），`None` 表示仅按条数限制
+    pub async fn set_buffer_memory_limit(&self, bytes: Option<usize>) {
+        *self.buffer_memory_limit.lock().await = bytes;
+        if let Some(limit) = bytes {
+            let mut messages = self.recent_messages.lock().await;
+            let mut total: usize = messages.iter().map(Self::message_bytes).sum();
+            while total > limit {
+                match messages.pop_front() {
+                    Some(dropped) => total -= Self::message_bytes(&dropped),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// 设置最近消息中单条消息内容的字节上限，超出部分截断保存
+    pub async fn set_message_content_limit(&self, bytes: usize) {
+        *self.message_content_limit.lock().await = bytes;
+    }
+
+    /// 从最近消息中移除指定消息
+    async fn remove_recent_message(&self, oid: &str) {
+        let mut messages = self.recent_messages.lock().await;
+        messages.retain(|msg| msg.oid != oid);
     }
 
     /// 发送弹幕
-    pub async fn send_barrage(&self, content: &str, color: &str) -> Response<ApiResponse<()>> {
+    ///
+    /// - `content` 弹幕内容
+    /// - `color` 弹幕颜色，可传入 [`BarrageColor`] 或十六进制、`rgba(...)` 字符串
+    ///
+    /// 颜色字符串无法解析时返回 [`FishPiError::Validation`]，不发起请求
+    pub async fn send_barrage<C>(&self, content: &str, color: C) -> Response<ApiResponse<()>>
+    where
+        C: TryInto<BarrageColor>,
+        C::Error: Into<FishPiError>,
+    {
+        if let Err(err) = ensure_content(content) {
+            return Response::from_error(err);
+        }
+
+        let color = match color.try_into() {
+            Ok(color) => color.to_string(),
+            Err(err) => return Response::from_error(err.into()),
+        };
         self.call_api(&format!("发送弹幕: color={}", color), || async {
-            self.chatroom_api.send_barrage(content, color).await
+            self.chatroom_api.send_barrage(content, &color).await
         })
         .await
     }
@@ -492,6 +1917,18 @@ impl ChatroomService {
         .await
     }
 
+    /// 获取单条消息
+    ///
+    /// - `oid` 消息ID
+    ///
+    /// 返回包含作者、时间、勋章与特殊消息内容的完整消息，可用于渲染引用预览
+    pub async fn get_message(&self, oid: &str) -> Response<ChatRoomMessage> {
+        self.call_api(&format!("获取聊天室消息: {}", oid), || {
+            self.chatroom_api.get_message(oid)
+        })
+        .await
+    }
+
     /// 获取消息原文
     pub async fn get_raw_message(&self, oid: &str) -> Result<String, anyhow::Error> {
         self.chatroom_api.get_raw_message(oid).await
@@ -510,11 +1947,46 @@ impl ChatroomService {
     }
 
     /// 设置当前话题
-    pub async fn set_discussing(&self, topic: &str) -> Response<ApiResponse<()>> {
+    ///
+    /// 成功时返回设置话题消息的发送结果
+    pub async fn set_discussing(&self, topic: &str) -> Response<SentMessage> {
         let content = format!("[setdiscuss]{}[/setdiscuss]", topic);
         self.send(Cow::Owned(content), None).await
     }
 
+    /// 获取聊天室置顶公告
+    ///
+    /// 没有公告时返回 `None`
+    pub async fn room_notice(&self) -> Response<Option<String>> {
+        match self.chatroom_api.get_room_notice().await {
+            Ok(response) => {
+                match ApiEnvelope::peek(&response)
+                    .and_then(|envelope| envelope.check("获取聊天室公告失败"))
+                {
+                    Ok(()) => Response::success(parse_room_notice(&response)),
+                    Err(err) => Response::from_error(err),
+                }
+            }
+            Err(err) => Response::error(&format!("获取聊天室公告失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
+    /// 设置聊天室置顶公告（需管理员权限）
+    ///
+    /// - `text` 公告内容，为空则清除公告
+    pub async fn set_room_notice(&self, text: &str) -> Response<()> {
+        match self.chatroom_api.set_room_notice(text.trim()).await {
+            Ok(response) if response.code == 0 => Response::success(()),
+            Ok(response) => {
+                let msg = response.msg.as_deref().unwrap_or("设置聊天室公告失败");
+                Response::error(msg).with_error(FishPiError::from_code(response.code, msg))
+            }
+            Err(err) => Response::error(&format!("设置聊天室公告失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+
     /// 添加消息监听函数
     pub async fn add_listener<F>(&self, callback: F) -> Response<()>
     where
@@ -533,17 +2005,68 @@ impl ChatroomService {
         Response::success(())
     }
 
+    /// 将聊天室消息写入日志
+    ///
+    /// - `writer` 输出目标，如文件或标准输出
+    /// - `format` 输出格式
+    ///
+    /// 每条数据写为一行，写入在独立的阻塞线程中进行，不会阻塞消息分发；
+    /// 有未刷新的数据时至少每秒刷新一次，监听器移除后刷新并结束写入。
+    /// 写入失败时记录错误并停止写入。与 `add_listener` 不同，不会断开已有连接
+    pub async fn log_to<W>(&self, writer: W, format: LogFormat) -> Response<()>
+    where
+        W: Write + Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel::<String>();
+        tokio::task::spawn_blocking(move || write_log_lines(writer, receiver));
+        let listener = move |data: ChatRoomData| {
+            // 写入线程因错误退出后发送失败，忽略即可
+            let _ = sender.send(data.to_log_line(format));
+        };
+
+        self.message_listeners.lock().await.push(Box::new(listener));
+        Response::success(())
+    }
+
+    /// 以流的形式订阅聊天室数据
+    ///
+    /// 与 `add_listener` 收到相同的数据，可在循环中直接 await 其他异步方法：
+    ///
+    /// ```ignore
+    /// let mut stream = client.chatroom.subscribe();
+    /// client.chatroom.connect().await;
+    /// while let Some(data) = stream.next().await {
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// 订阅需在 `connect` 之前完成，否则没有监听器时无法连接。
+    /// 消费速度跟不上时，最多缓存 256 条，更早的数据会被丢弃并记录警告，流本身不会结束；
+    /// 服务实例全部释放后流结束
+    pub fn subscribe(&self) -> impl Stream<Item = ChatRoomData> + Send + 'static {
+        futures::stream::unfold(self.broadcaster.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(data) => return Some((data, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("聊天室订阅者处理过慢，丢弃了 {} 条数据", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
     /// 移除消息监听函数
     pub async fn remove_listener(&self) -> Response<()> {
+        *self.user_closed.lock().await = true;
         {
             let mut connected = self.connected.lock().await;
             *connected = false;
         }
 
         self.clean_all_resources().await;
-
-        let client = ApiClient::new();
-        let _ = client.close_websocket_connections().await;
+        self.connection_watch.notify(None, false).await;
 
         Response::success(())
     }
@@ -574,3 +2097,750 @@ impl ChatroomService {
         Response::success(())
     }
 }
+
+/// 聊天室日志写入线程
+///
+/// 逐行写入，有未刷新的数据时至少每 `LOG_FLUSH_INTERVAL` 刷新一次；
+/// 发送端全部释放后刷新并退出，写入失败时记录错误并退出
+fn write_log_lines<W: Write>(mut writer: W, receiver: std::sync::mpsc::Receiver<String>) {
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let mut dirty = false;
+    let mut last_flush = std::time::Instant::now();
+    loop {
+        let timeout = LOG_FLUSH_INTERVAL.saturating_sub(last_flush.elapsed());
+        let mut result = match receiver.recv_timeout(timeout) {
+            Ok(line) => {
+                dirty = true;
+                writeln!(writer, "{}", line)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(()),
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Err(err) = writer.flush() {
+                    log::error!("刷新聊天室日志失败: {}", err);
+                }
+                return;
+            }
+        };
+        if result.is_ok() && last_flush.elapsed() >= LOG_FLUSH_INTERVAL {
+            if dirty {
+                result = writer.flush();
+                dirty = false;
+            }
+            last_flush = std::time::Instant::now();
+        }
+        if let Err(err) = result {
+            log::error!("写入聊天室日志失败，停止记录: {}", err);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::UserApi;
+    use crate::test_util::{MockServer, MockWebSocketServer, mock_client};
+
+    fn mock_service(server: &MockServer) -> ChatroomService {
+        ChatroomService::new(ChatroomApi::new(mock_client(server)))
+    }
+
+    const ME: &str = r#"{"code":0,"data":{"userName":"me","sysMetal":null}}"#;
+
+    /// 关联已缓存当前用户 `me` 的用户服务，消耗服务端的一个响应
+    async fn with_cached_user(service: ChatroomService) -> ChatroomService {
+        let user = UserService::new(UserApi::new(service.chatroom_api.client().clone()));
+        assert!(user.fetch_current_user().await.success);
+        service.with_user(user)
+    }
+
+    fn message(oid: &str, user_name: &str) -> ChatRoomMessage {
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        serde_json::from_value(serde_json::json!({
+            "oId": oid,
+            "userOId": 1,
+            "userName": user_name,
+            "userAvatarURL": "",
+            "content": "hi",
+            "time": time.to_string(),
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn send_returns_oid_from_response() {
+        let server = MockServer::start(vec![(200, r#"{"code":0,"oId":"1700000000123"}"#)]).await;
+        let service = mock_service(&server);
+
+        let before = SystemTime::now();
+        let sent = service
+            .send(Cow::Borrowed("hello"), None)
+            .await
+            .data
+            .unwrap();
+        assert_eq!(sent.oid.as_deref(), Some("1700000000123"));
+        assert!(sent.sent_at >= before);
+    }
+
+    #[tokio::test]
+    async fn send_failure_keeps_error_and_slow_mode_from_response() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":429,"msg":"慢速模式中，请 30 秒后再试","slowMode":10}"#,
+        )])
+        .await;
+        let service = mock_service(&server);
+
+        let result = service.send(Cow::Borrowed("hello"), None).await;
+
+        assert!(!result.success);
+        assert_eq!(result.error, Some(FishPiError::RateLimited));
+        assert_eq!(service.current_slow_mode().await, Some(10));
+    }
+
+    #[tokio::test]
+    async fn send_without_oid_skips_echo_wait_by_default() {
+        let server = MockServer::start(vec![(200, ME), (200, r#"{"code":0}"#)]).await;
+        let service = with_cached_user(mock_service(&server)).await;
+        service.set_echo_wait(false).await;
+        *service.connected.lock().await = true;
+
+        let started = Instant::now();
+        let sent = service
+            .send(Cow::Borrowed("hello"), None)
+            .await
+            .data
+            .unwrap();
+        assert_eq!(sent.oid, None);
+        assert!(started.elapsed() < SEND_ECHO_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn revoke_last_revokes_own_recent_messages() {
+        let server = MockServer::start(vec![
+            (200, ME),
+            (200, r#"{"code":0}"#),
+            (200, r#"{"code":0}"#),
+        ])
+        .await;
+        let service = with_cached_user(mock_service(&server)).await;
+        for (oid, user_name) in [("1", "me"), ("2", "other"), ("3", "me"), ("4", "me")] {
+            service.push_recent_message(&message(oid, user_name)).await;
+        }
+
+        let result = service.revoke_last(2).await;
+
+        assert_eq!(result.data, Some(vec!["4".to_string(), "3".to_string()]));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].starts_with("DELETE /chat-room/revoke/4"));
+        assert!(requests[2].starts_with("DELETE /chat-room/revoke/3"));
+    }
+
+    #[tokio::test]
+    async fn current_user_follows_user_service_cache() {
+        let server = MockServer::start(vec![
+            (200, ME),
+            (
+                200,
+                r#"{"code":0,"data":{"userName":"someone-else","sysMetal":null}}"#,
+            ),
+        ])
+        .await;
+        let service = with_cached_user(mock_service(&server)).await;
+        assert_eq!(service.current_user_name().await.unwrap(), "me");
+
+        service
+            .chatroom_api
+            .client()
+            .set_token(Some("other".into()))
+            .await;
+        assert_eq!(service.current_user_name().await.unwrap(), "someone-else");
+    }
+
+    #[tokio::test]
+    async fn send_is_paced_by_default_interval() {
+        let server = MockServer::start(vec![(200, r#"{"code":0}"#)]).await;
+        let service = mock_service(&server);
+
+        let before = SystemTime::now();
+        let sent = service.send(Cow::Borrowed("hello"), None).await;
+        let sent_at = sent.data.unwrap().sent_at;
+        assert!(sent_at >= before && sent_at <= SystemTime::now());
+
+        let second = service.try_send(Cow::Borrowed("again"), None).await;
+        assert_eq!(second.error, Some(FishPiError::RateLimited));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancelled_send_releases_its_slot() {
+        let server = MockServer::start(vec![(200, r#"{"code":0}"#)]).await;
+        let service = mock_service(&server);
+        service.set_send_interval(Duration::from_secs(10)).await;
+
+        assert!(service.send(Cow::Borrowed("hello"), None).await.success);
+        let after_first = *service.next_send_at.lock().unwrap();
+
+        let waiting = service.send(Cow::Borrowed("again"), None);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), waiting)
+                .await
+                .is_err()
+        );
+
+        assert_eq!(*service.next_send_at.lock().unwrap(), after_first);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn auto_reconnect_stops_at_max_attempts() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        service
+            .set_reconnect_config(ReconnectConfig {
+                max_attempts: 3,
+                base_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+                jitter: Duration::ZERO,
+            })
+            .await;
+
+        service.auto_reconnect().await;
+
+        assert_eq!(*service.retry_times.lock().await, 3);
+        assert_eq!(server.requests().len(), 3);
+        let metrics = service.chatroom_api.client().metrics().snapshot();
+        assert_eq!(metrics.ws_reconnects, 3);
+        assert!(!service.reconnect().await.success);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn disconnect_during_reconnect_backoff_cancels_reconnect() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        service
+            .set_reconnect_config(ReconnectConfig {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(200),
+                max_delay: Duration::from_millis(200),
+                jitter: Duration::ZERO,
+            })
+            .await;
+
+        let reconnecting = service.clone();
+        let attempt = tokio::spawn(async move { reconnecting.reconnect_once().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        service.disconnect().await;
+
+        assert!(!attempt.await.unwrap().success);
+        assert!(*service.user_closed.lock().await);
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn disconnect_closes_only_this_services_connection() {
+        let ws_server = MockWebSocketServer::start().await;
+        let server = MockServer::start(vec![]).await;
+        let ours = mock_service(&server);
+        let theirs = mock_service(&server);
+        let theirs_closed = Arc::new(AtomicBool::new(false));
+        for (service, closed) in [(&ours, None), (&theirs, Some(theirs_closed.clone()))] {
+            let connection = service
+                .chatroom_api
+                .client()
+                .connect_websocket(
+                    &ws_server.url,
+                    None,
+                    |_| {},
+                    None::<fn(String)>,
+                    Some(move |_| {
+                        if let Some(closed) = &closed {
+                            closed.store(true, Ordering::SeqCst);
+                        }
+                    }),
+                )
+                .await
+                .unwrap();
+            *service.ws_connection.lock().await = Some(connection);
+            *service.connected.lock().await = true;
+        }
+
+        ours.disconnect().await;
+        assert!(ours.ws_connection.lock().await.is_none());
+
+        let connection = theirs.ws_connection.lock().await.clone().unwrap();
+        connection
+            .sender()
+            .unbounded_send(Message::Text("still-here".to_string()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(ws_server.frames().contains(&"still-here".to_string()));
+        assert!(!theirs_closed.load(Ordering::SeqCst));
+        assert!(theirs.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn reopening_replaces_own_connection_without_reconnecting() {
+        let ws_server = MockWebSocketServer::start().await;
+        let node = format!(r#"{{"code":0,"data":"{}"}}"#, ws_server.url);
+        let server = MockServer::start(vec![(200, &node), (200, &node)]).await;
+        let service = mock_service(&server);
+
+        assert!(service.open_connection().await.success);
+        let first = service.ws_connection.lock().await.clone().unwrap();
+        assert!(service.open_connection().await.success);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(first.sender().is_closed());
+        assert!(service.is_connected().await);
+        assert_eq!(*service.retry_times.lock().await, 0);
+        assert_eq!(server.requests().len(), 2);
+        service.disconnect().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keepalive_sends_heartbeat_when_idle_and_stops_after_cleanup() {
+        let ws_server = MockWebSocketServer::start().await;
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        let connection = service
+            .chatroom_api
+            .client()
+            .connect_websocket(
+                &ws_server.url,
+                None,
+                |_| {},
+                None::<fn(String)>,
+                None::<fn(ConnectionState)>,
+            )
+            .await
+            .unwrap();
+        *service.ws_connection.lock().await = Some(connection);
+        *service.connected.lock().await = true;
+
+        service.start_keepalive().await;
+        tokio::time::sleep(HEARTBEAT_INTERVAL * 2).await;
+        let sent = ws_server
+            .frames()
+            .iter()
+            .filter(|frame| *frame == HEARTBEAT_FRAME)
+            .count();
+        assert!(sent >= 1);
+
+        service.clean_all_resources().await;
+        tokio::time::sleep(HEARTBEAT_INTERVAL * 4).await;
+        let after = ws_server
+            .frames()
+            .iter()
+            .filter(|frame| *frame == HEARTBEAT_FRAME)
+            .count();
+        assert_eq!(after, sent);
+        assert!(service.keepalive.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn lazy_special_content_is_scoped_to_the_service() {
+        let body = r#"{"code":0,"data":[{"oId":"1","userOId":2,"userName":"alice","userAvatarURL":"","content":"{\"msgType\":\"music\",\"source\":\"1234\"}"}]}"#;
+        let lazy_server = MockServer::start(vec![(200, body)]).await;
+        let eager_server = MockServer::start(vec![(200, body)]).await;
+        let lazy = mock_service(&lazy_server);
+        let eager = mock_service(&eager_server);
+        lazy.set_lazy_special_content(true);
+
+        let lazy_messages = lazy.get_history(1, None).await.data.unwrap();
+        let eager_messages = eager.get_history(1, None).await.data.unwrap();
+        assert!(!lazy_messages[0].is_special_parsed());
+        assert!(eager_messages[0].is_special_parsed());
+        assert_eq!(lazy_messages[0].kind(), ChatMessageKind::Music);
+    }
+
+    #[tokio::test]
+    async fn set_filter_rejects_invalid_regexes() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        let mut filter = MessageFilter::new();
+        filter.regexes.push("(".to_string());
+
+        let response = service.set_filter(filter).await;
+        assert!(matches!(response.error, Some(FishPiError::Validation(_))));
+        assert!(service.filter().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn send_image_rejects_oversized_file_before_reading() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        let path = std::env::temp_dir().join(format!("fishpi-big-{}.png", rand::random::<u64>()));
+        std::fs::File::create(&path)
+            .unwrap()
+            .set_len(MAX_UPLOAD_SIZE as u64 + 1)
+            .unwrap();
+
+        let response = service.send_image(&path).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(response.error, Some(FishPiError::Validation(_))));
+        assert!(server.requests().is_empty());
+    }
+
+    /// 只在刷新时才把数据交给共享缓冲区的写入目标，用于验证定时刷新
+    struct FlushOnly {
+        pending: Vec<u8>,
+        flushed: Arc<std::sync::Mutex<Vec<u8>>>,
+    }
+
+    impl Write for FlushOnly {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed.lock().unwrap().append(&mut self.pending);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn log_to_writes_one_line_per_message_in_each_format() {
+        for format in [LogFormat::JsonLines, LogFormat::Plain] {
+            let server = MockServer::start(vec![]).await;
+            let service = mock_service(&server);
+            let flushed = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let writer = FlushOnly {
+                pending: Vec::new(),
+                flushed: flushed.clone(),
+            };
+            assert!(service.log_to(writer, format).await.success);
+
+            for (oid, user) in [("1", "alice"), ("2", "bob")] {
+                service
+                    .notify_listeners(ChatRoomData {
+                        type_: ChatRoomMessageType::MSG.to_string(),
+                        data: ChatRoomDataContent::Message(Box::new(message(oid, user))),
+                    })
+                    .await;
+            }
+
+            // 写入目标只在刷新时交出数据，两行都出现说明空闲时也按间隔刷新
+            let deadline = std::time::Instant::now() + LOG_FLUSH_INTERVAL * 3;
+            let lines = || {
+                let text = String::from_utf8(flushed.lock().unwrap().clone()).unwrap();
+                text.split_inclusive('\n')
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            };
+            while lines().len() < 2 && std::time::Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            let lines = lines();
+            assert_eq!(lines.len(), 2, "{:?}", format);
+            assert!(lines.iter().all(|line| line.ends_with('\n')));
+            assert!(lines[0].contains("alice") && lines[1].contains("bob"));
+            if format == LogFormat::JsonLines {
+                for line in &lines {
+                    serde_json::from_str::<Value>(line).unwrap();
+                }
+            } else {
+                assert!(lines[0].trim_end().ends_with("alice: hi"));
+            }
+        }
+    }
+
+    /// 每次写入都失败并记录调用次数的写入目标
+    struct FailingWriter {
+        writes: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            self.writes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn log_to_detaches_failing_writer() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        let writes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let writer = FailingWriter {
+            writes: writes.clone(),
+        };
+        assert!(service.log_to(writer, LogFormat::Plain).await.success);
+
+        let data = ChatRoomData {
+            type_: ChatRoomMessageType::DISCUSS_CHANGED.to_string(),
+            data: ChatRoomDataContent::Discuss("topic".to_string()),
+        };
+        service.notify_listeners(data.clone()).await;
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while writes.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+            assert!(std::time::Instant::now() < deadline, "日志未写入");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        // 写入线程已退出，后续数据不再交给写入目标，分发也不受影响
+        service.notify_listeners(data.clone()).await;
+        service.notify_listeners(data).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(writes.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn send_barrage_rejects_invalid_color() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+
+        let response = service.send_barrage("hi", "not-a-color").await;
+        assert!(matches!(response.error, Some(FishPiError::Validation(_))));
+
+        let response = service
+            .send_barrage("hi", BarrageColor::from_rgba(1, 2, 3, 1.0))
+            .await;
+        assert!(!matches!(response.error, Some(FishPiError::Validation(_))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn send_rejects_empty_content_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+
+        for content in ["", "  \n"] {
+            let response = service.send(Cow::Borrowed(content), None).await;
+            assert_eq!(
+                response.error,
+                Some(FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn set_room_notice_fails_on_result_code() {
+        let server = MockServer::start(vec![(200, r#"{"result":-1,"msg":"无权限"}"#)]).await;
+        let service = mock_service(&server);
+
+        let response = service.set_room_notice("公告").await;
+
+        assert!(!response.success);
+        assert_eq!(response.message.as_deref(), Some("无权限"));
+        assert_eq!(
+            response.error,
+            Some(FishPiError::ApiError {
+                code: -1,
+                msg: "无权限".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn room_notice_reads_present_empty_and_absent_notice() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":0,"data":{"content":" 今晚停服 "}}"#),
+            (200, r#"{"code":0,"data":"  "}"#),
+            (200, r#"{"code":0}"#),
+            (200, r#"{"code":401,"msg":"未登录"}"#),
+        ])
+        .await;
+        let service = mock_service(&server);
+
+        assert_eq!(
+            service.room_notice().await.data,
+            Some(Some("今晚停服".to_string()))
+        );
+        assert_eq!(service.room_notice().await.data, Some(None));
+        assert_eq!(service.room_notice().await.data, Some(None));
+        assert_eq!(
+            service.room_notice().await.error,
+            Some(FishPiError::NotAuthenticated)
+        );
+    }
+
+    #[tokio::test]
+    async fn send_raw_reaches_websocket() {
+        let ws_server = MockWebSocketServer::start().await;
+        let node = format!(r#"{{"code":0,"data":"{}"}}"#, ws_server.url);
+        let server = MockServer::start(vec![(200, &node)]).await;
+        let service = mock_service(&server);
+        assert!(service.open_connection().await.success);
+
+        assert!(service.send_raw("raw-frame".to_string()).await.success);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ws_server.frames().contains(&"raw-frame".to_string()) {
+            assert!(Instant::now() < deadline, "原始帧未送达");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        service.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn send_raw_requires_connection() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+
+        let response = service.send_raw("raw-frame".to_string()).await;
+
+        assert!(!response.success);
+        assert!(matches!(response.error, Some(FishPiError::Network(_))));
+    }
+
+    #[tokio::test]
+    async fn server_capabilities_defaults_to_all_commands_and_can_be_overridden() {
+        use crate::models::chatroom::ChatRoomCommand;
+
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+
+        let capabilities = service.server_capabilities().await.data.unwrap();
+        for command in ChatRoomCommand::values() {
+            assert!(capabilities.supports(command));
+        }
+
+        service
+            .set_capabilities(Capabilities {
+                commands: vec!["Barrager".to_string()],
+            })
+            .await;
+        let capabilities = service.server_capabilities().await.data.unwrap();
+        assert!(capabilities.supports(ChatRoomCommand::BARRAGER));
+        assert!(!capabilities.supports(ChatRoomCommand::RED_PACKET));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn user_history_filters_mixed_authors_case_insensitively() {
+        let page = |messages: &[(&str, &str)]| {
+            let data: Vec<_> = messages
+                .iter()
+                .map(|(oid, user)| {
+                    serde_json::json!({
+                        "oId": oid,
+                        "userOId": 1,
+                        "userName": user,
+                        "userAvatarURL": "",
+                        "content": "hi",
+                        "time": "2024-01-01 12:00:00",
+                    })
+                })
+                .collect();
+            serde_json::json!({ "code": 0, "data": data }).to_string()
+        };
+        let first = page(&[
+            ("3", "alice"),
+            ("4", "bob"),
+            ("1", "Alice"),
+            ("2", "malice"),
+        ]);
+        let second = page(&[("3", "alice"), ("5", "ALICE"), ("6", "carol")]);
+        let server = MockServer::start(vec![
+            (200, &first),
+            (200, &second),
+            (200, r#"{"code":0,"data":[]}"#),
+        ])
+        .await;
+        let service = mock_service(&server);
+
+        let response = service.user_history(" @Alice", 1).await;
+
+        let oids: Vec<String> = response.data.unwrap().into_iter().map(|m| m.oid).collect();
+        assert_eq!(oids, ["5", "3", "1"]);
+        assert_eq!(server.requests().len(), 3);
+
+        let response = service.user_history(" @ ", 1).await;
+        assert!(matches!(response.error, Some(FishPiError::Validation(_))));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn unknown_frame_reaches_fishpi_on_unknown() {
+        let server = MockServer::start(vec![]).await;
+        let fishpi = crate::FishPi::from_api_client(mock_client(&server));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        fishpi
+            .on_unknown(move |type_, value| {
+                recorded.lock().unwrap().push((type_.to_string(), value));
+            })
+            .await;
+
+        let service = &fishpi.chatroom;
+        let handler = service.create_message_handler(
+            service.message_listeners.clone(),
+            service.online_users.clone(),
+            service.discussing.clone(),
+        );
+        handler(serde_json::json!({ "type": "brandNew", "payload": 1 }));
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while seen.lock().unwrap().is_empty() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "未调用未识别消息回调"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen[0].0, "brandNew");
+        assert_eq!(seen[0].1["payload"], 1);
+    }
+
+    #[tokio::test]
+    async fn push_recent_message_truncates_oversized_content_on_char_boundary() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        service.set_message_content_limit(5).await;
+
+        let mut long = message("1", "alice");
+        long.content = "鱼鱼鱼".to_string();
+        long.md = Some("abcdefg".to_string());
+        service.push_recent_message(&long).await;
+        let mut short = message("2", "alice");
+        short.content = "hello".to_string();
+        service.push_recent_message(&short).await;
+
+        let messages = service.recent_messages.lock().await;
+        assert_eq!(messages[0].content, format!("鱼{}", TRUNCATED_MARK));
+        assert_eq!(
+            messages[0].md.as_deref(),
+            Some(&*format!("abcde{}", TRUNCATED_MARK))
+        );
+        assert_eq!(messages[1].content, "hello");
+    }
+
+    #[tokio::test]
+    async fn set_buffer_memory_limit_evicts_oldest_messages() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        for oid in ["1", "2", "3"] {
+            let mut msg = message(oid, "alice");
+            msg.content = "0123456789".to_string();
+            service.push_recent_message(&msg).await;
+        }
+
+        service.set_buffer_memory_limit(Some(25)).await;
+        let oids = |messages: &VecDeque<ChatRoomMessage>| {
+            messages.iter().map(|m| m.oid.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(oids(&*service.recent_messages.lock().await), ["2", "3"]);
+
+        let mut msg = message("4", "alice");
+        msg.content = "0123456789".to_string();
+        service.push_recent_message(&msg).await;
+        assert_eq!(oids(&*service.recent_messages.lock().await), ["3", "4"]);
+
+        service.set_buffer_memory_limit(None).await;
+        service.push_recent_message(&message("5", "alice")).await;
+        assert_eq!(service.recent_messages.lock().await.len(), 3);
+    }
+}
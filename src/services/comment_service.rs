@@ -2,8 +2,9 @@ use anyhow::Result;
 use serde_json::Value;
 
 use crate::api::CommentApi;
-use crate::models::article::{ArticleComment, CommentPost, ResponseResult};
-use crate::models::comment;
+use crate::error::ensure_content;
+use crate::models::article::{ArticleComment, CommentAuthor, CommentPost, ResponseResult};
+use crate::models::comment::{self, CommentNode};
 
 #[derive(Clone, Debug)]
 pub struct CommentService {
@@ -21,6 +22,7 @@ impl CommentService {
     ///
     /// 返回评论 ID
     pub async fn post(&self, comment: &CommentPost) -> Result<String> {
+        ensure_content(&comment.content)?;
         self.comment_api.send(comment).await.map(|r| r.msg)
     }
 
@@ -31,6 +33,7 @@ impl CommentService {
     ///
     /// 返回评论内容 HTML
     pub async fn update(&self, id: &str, comment: &CommentPost) -> Result<String> {
+        ensure_content(&comment.content)?;
         self.comment_api.update(id, comment).await
     }
 
@@ -53,6 +56,24 @@ impl CommentService {
         self.comment_api.thank(id).await
     }
 
+    /// 评论打赏
+    ///
+    /// - `id` 评论 ID
+    ///
+    /// 返回执行结果
+    pub async fn reward(&self, id: &str) -> Result<ResponseResult> {
+        self.comment_api.reward(id).await
+    }
+
+    /// 获取感谢过评论的用户
+    ///
+    /// - `id` 评论 ID
+    ///
+    /// 返回感谢者列表，感谢数见 `ArticleComment::thank_cnt`
+    pub async fn thankers(&self, id: &str) -> Result<Vec<CommentAuthor>> {
+        self.comment_api.thankers(id).await
+    }
+
     /// 删除评论
     ///
     /// - `id` 评论 ID
@@ -73,4 +94,79 @@ impl CommentService {
     ) -> (Vec<ArticleComment>, Vec<ArticleComment>) {
         comment::parse_comment_data(comments_data)
     }
+
+    /// 将评论按回复关系组织为树
+    ///
+    /// - `comments` 评论列表
+    ///
+    /// 返回顶层评论节点，回复挂在被回复评论的 `children` 下
+    pub fn build_thread(comments: Vec<ArticleComment>) -> Vec<CommentNode> {
+        comment::build_thread(comments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FishPiError;
+    use crate::test_util::{MockServer, mock_client};
+
+    fn service(server: &MockServer) -> CommentService {
+        CommentService::new(CommentApi::new(mock_client(server)))
+    }
+
+    #[tokio::test]
+    async fn reward_reports_rejected_reward() {
+        let server = MockServer::start(vec![(200, r#"{"code":-1,"msg":"积分不足"}"#)]).await;
+
+        let err = service(&server).reward("c1").await.unwrap_err();
+        match FishPiError::from_anyhow(&err) {
+            FishPiError::ApiError { code, msg } => {
+                assert_eq!(code, -1);
+                assert_eq!(msg, "积分不足");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(server.requests()[0].starts_with("POST /comment/reward"));
+    }
+
+    #[tokio::test]
+    async fn thankers_skips_unparsable_entries() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":0,"data":[{"userName":"a"},5,{"userName":"b"}]}"#,
+        )])
+        .await;
+
+        let thankers = service(&server).thankers("c1").await.unwrap();
+        let names: Vec<_> = thankers.iter().map(|u| u.user_name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn thankers_reports_error_code() {
+        let server = MockServer::start(vec![(200, r#"{"code":-1,"msg":"评论不存在"}"#)]).await;
+
+        assert!(service(&server).thankers("c1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn post_rejects_empty_content_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = service(&server);
+
+        for content in ["", "  \n"] {
+            let comment = CommentPost {
+                article_id: "1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            };
+            let err = service.post(&comment).await.unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<FishPiError>(),
+                Some(&FishPiError::Validation("content empty".to_string()))
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
 }
@@ -1,13 +1,40 @@
 use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
 
 use crate::api::EmojiApi;
+use crate::error::FishPiError;
 use crate::models::emoji::EmojiList;
-use crate::models::user::Response;
+use crate::models::user::{ApiEnvelope, Response};
 use crate::services::ApiCaller;
 
+/// 匹配 `:code:` 形式的表情短码
+static SHORTCODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r":([A-Za-z0-9_+\-]+):").unwrap());
+
+/// 获取表情短码映射失败后，在此时间内不再重试
+const SHORTCODE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 表情短码缓存
+#[derive(Debug)]
+enum ShortcodeCache {
+    /// 已获取的短码到图片地址的映射
+    Loaded(HashMap<String, String>),
+    /// 上次获取失败的时间
+    Failed(Instant),
+}
+
 #[derive(Clone, Debug)]
 pub struct EmojiService {
     emoji_api: EmojiApi,
+    /// 当前用户的表情短码映射，首次解析时获取
+    shortcodes: Arc<Mutex<Option<ShortcodeCache>>>,
+    /// 串行化自定义表情的读取-修改-同步，避免并发修改互相覆盖
+    mutation: Arc<Mutex<()>>,
 }
 
 impl ApiCaller for EmojiService {
@@ -22,6 +49,7 @@ impl ApiCaller for EmojiService {
             Err(err) => {
                 log::error!("API调用失败: {}", err);
                 Response::error(&format!("API调用失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
             }
         }
     }
@@ -36,24 +64,27 @@ impl ApiCaller for EmojiService {
         log::debug!("{}", log_msg);
         match f().await {
             Ok(response) => {
-                if let Some(0) = response.get("result").and_then(|v| v.as_i64()) {
-                    if let Some(data) = response.get("data") {
-                        if let Some(parsed_data) = parser(data) {
-                            return Response::success(parsed_data);
+                let envelope =
+                    match serde_json::from_value::<ApiEnvelope<serde_json::Value>>(response) {
+                        Ok(envelope) => envelope,
+                        Err(err) => {
+                            return Response::from_error(FishPiError::Parse(err.to_string()));
                         }
-                    }
-                }
-
-                let error_msg = response
-                    .get("msg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("解析API响应数据失败")
-                    .to_string();
-                Response::error(&error_msg)
+                    };
+                let msg = envelope.msg.as_deref().unwrap_or("解析API响应数据失败");
+                let error = match envelope.check(msg) {
+                    Ok(()) => match envelope.data.as_ref().and_then(parser) {
+                        Some(data) => return Response::success(data),
+                        None => FishPiError::Parse(msg.to_string()),
+                    },
+                    Err(err) => err,
+                };
+                Response::error(msg).with_error(error)
             }
             Err(err) => {
                 log::error!("API调用失败: {}", err);
                 Response::error(&format!("API调用失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
             }
         }
     }
@@ -61,7 +92,11 @@ impl ApiCaller for EmojiService {
 
 impl EmojiService {
     pub fn new(emoji_api: EmojiApi) -> Self {
-        Self { emoji_api }
+        Self {
+            emoji_api,
+            shortcodes: Arc::new(Mutex::new(None)),
+            mutation: Arc::new(Mutex::new(())),
+        }
     }
 
     /// 获取表情列表
@@ -70,4 +105,246 @@ impl EmojiService {
     pub async fn list(&self) -> Result<EmojiList> {
         self.emoji_api.get_emoji_list().await
     }
+
+    /// 获取内置表情，按分类分组
+    pub async fn default_emojis(&self) -> Response<EmojiList> {
+        self.call_api("获取内置表情列表", || {
+            self.emoji_api.get_emoji_list()
+        })
+        .await
+    }
+
+    /// 将表情短码解析为图片地址
+    ///
+    /// - `shortcode` 表情短码，可带或不带两侧的 `:`
+    ///
+    /// 使用当前用户的表情映射，首次调用时获取并缓存；未知短码或获取失败时返回 `None`，
+    /// 获取失败后 `SHORTCODE_RETRY_INTERVAL` 内不再重新请求
+    pub async fn resolve(&self, shortcode: &str) -> Option<String> {
+        let code = shortcode.trim().trim_matches(':');
+        if code.is_empty() {
+            return None;
+        }
+        self.with_shortcodes(|map| map.get(code).cloned())
+            .await
+            .flatten()
+    }
+
+    /// 将文本中所有 `:code:` 替换为对应的表情图片地址，未知短码保持原样
+    pub async fn expand_shortcodes(&self, text: &str) -> String {
+        if !SHORTCODE_RE.is_match(text) {
+            return text.to_string();
+        }
+        self.with_shortcodes(|map| {
+            SHORTCODE_RE
+                .replace_all(text, |caps: &Captures| match map.get(&caps[1]) {
+                    Some(url) => url.clone(),
+                    None => caps[0].to_string(),
+                })
+                .into_owned()
+        })
+        .await
+        .unwrap_or_else(|| text.to_string())
+    }
+
+    /// 清空表情短码缓存，下次解析时重新获取
+    pub async fn clear_shortcode_cache(&self) {
+        *self.shortcodes.lock().await = None;
+    }
+
+    /// 使用表情短码映射，未缓存或上次失败已超过重试间隔时先获取
+    async fn with_shortcodes<T>(&self, f: impl FnOnce(&HashMap<String, String>) -> T) -> Option<T> {
+        let mut cache = self.shortcodes.lock().await;
+        match &*cache {
+            Some(ShortcodeCache::Loaded(map)) => return Some(f(map)),
+            Some(ShortcodeCache::Failed(at)) if at.elapsed() < SHORTCODE_RETRY_INTERVAL => {
+                return None;
+            }
+            _ => {}
+        }
+
+        match self.emoji_api.get_user_emotions().await {
+            Ok(map) => {
+                let result = f(&map);
+                *cache = Some(ShortcodeCache::Loaded(map));
+                Some(result)
+            }
+            Err(err) => {
+                log::warn!("获取表情短码失败: {}", err);
+                *cache = Some(ShortcodeCache::Failed(Instant::now()));
+                None
+            }
+        }
+    }
+
+    /// 获取用户自定义表情
+    ///
+    /// 返回表情URL列表
+    pub async fn user_emojis(&self) -> Response<Vec<String>> {
+        self.call_api("获取自定义表情", || self.emoji_api.get_user_emojis())
+            .await
+    }
+
+    /// 添加自定义表情
+    ///
+    /// - `url` 表情图片地址，仅支持 http(s)
+    ///
+    /// 已存在的表情不会重复添加，返回更新后的表情列表。
+    /// 同一服务内的添加与删除依次执行，不会互相覆盖
+    pub async fn add(&self, url: &str) -> Response<Vec<String>> {
+        let url = match validate_emoji_url(url) {
+            Ok(url) => url,
+            Err(err) => return Response::from_error(err),
+        };
+        let _guard = self.mutation.lock().await;
+        self.call_api(&format!("添加自定义表情: {}", url), || async {
+            let mut emojis = self.emoji_api.get_user_emojis().await?;
+            if !emojis.iter().any(|e| e == url) {
+                emojis.push(url.to_string());
+                self.emoji_api.sync_user_emojis(&emojis).await?;
+            }
+            Ok(emojis)
+        })
+        .await
+    }
+
+    /// 删除自定义表情
+    ///
+    /// - `url` 表情图片地址
+    ///
+    /// 返回更新后的表情列表，与 [`Self::add`] 依次执行
+    pub async fn remove(&self, url: &str) -> Response<Vec<String>> {
+        let url = match validate_emoji_url(url) {
+            Ok(url) => url,
+            Err(err) => return Response::from_error(err),
+        };
+        let _guard = self.mutation.lock().await;
+        self.call_api(&format!("删除自定义表情: {}", url), || async {
+            let mut emojis = self.emoji_api.get_user_emojis().await?;
+            let before = emojis.len();
+            emojis.retain(|e| e != url);
+            if emojis.len() != before {
+                self.emoji_api.sync_user_emojis(&emojis).await?;
+            }
+            Ok(emojis)
+        })
+        .await
+    }
+}
+
+/// 校验表情地址，仅允许 http(s)
+fn validate_emoji_url(url: &str) -> Result<&str, FishPiError> {
+    let url = url.trim();
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(url),
+        Ok(parsed) => Err(FishPiError::Validation(format!(
+            "表情地址仅支持 http(s)，当前为 {}",
+            parsed.scheme()
+        ))),
+        Err(err) => Err(FishPiError::Validation(format!("表情地址无效: {}", err))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockServer, mock_client};
+
+    fn service(server: &MockServer) -> EmojiService {
+        EmojiService::new(EmojiApi::new(mock_client(server)))
+    }
+
+    #[tokio::test]
+    async fn resolve_uses_user_emotions() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":0,"data":[{"smile":"https://e/smile.png"},{"cry":"https://e/cry.png"}]}"#,
+        )])
+        .await;
+        let service = service(&server);
+
+        assert_eq!(
+            service.resolve(":smile:").await.as_deref(),
+            Some("https://e/smile.png")
+        );
+        assert_eq!(
+            service.expand_shortcodes("a :cry: :nope:").await,
+            "a https://e/cry.png :nope:"
+        );
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /users/emotions"));
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_is_cached_until_retry_interval() {
+        let server = MockServer::start(vec![
+            (500, "error"),
+            (200, r#"{"code":0,"data":{"smile":"https://e/smile.png"}}"#),
+        ])
+        .await;
+        let service = service(&server);
+
+        assert_eq!(service.resolve("smile").await, None);
+        assert_eq!(service.expand_shortcodes(":smile:").await, ":smile:");
+        assert_eq!(server.requests().len(), 1);
+
+        *service.shortcodes.lock().await = Some(ShortcodeCache::Failed(
+            Instant::now() - SHORTCODE_RETRY_INTERVAL,
+        ));
+        assert_eq!(
+            service.resolve("smile").await.as_deref(),
+            Some("https://e/smile.png")
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_adds_are_serialized() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":0,"data":"[]"}"#),
+            (200, r#"{"code":0}"#),
+            (200, r#"{"code":0,"data":"[\"https://e/a.png\"]"}"#),
+            (200, r#"{"code":0}"#),
+        ])
+        .await;
+        let service = service(&server);
+
+        let (a, b) = tokio::join!(
+            service.add("https://e/a.png"),
+            service.add("https://e/b.png")
+        );
+        assert!(a.success && b.success);
+
+        let requests = server.requests();
+        let paths: Vec<_> = requests
+            .iter()
+            .map(|r| r.split_whitespace().nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "/api/cloud/get",
+                "/api/cloud/sync",
+                "/api/cloud/get",
+                "/api/cloud/sync"
+            ]
+        );
+        assert!(requests[3].contains("https://e/a.png") && requests[3].contains("https://e/b.png"));
+    }
+
+    #[tokio::test]
+    async fn user_emotions_accept_result_envelope() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"result":0,"data":{"smile":"https://e/smile.png"}}"#,
+        )])
+        .await;
+        let service = service(&server);
+
+        assert_eq!(
+            service.resolve(":smile:").await.as_deref(),
+            Some("https://e/smile.png")
+        );
+    }
 }
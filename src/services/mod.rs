@@ -16,10 +16,72 @@ pub use comment_service::CommentService;
 pub use emoji_service::EmojiService;
 pub use notice_service::NoticeService;
 pub use redpacket_service::RedpacketService;
-pub use user_service::UserService;
+pub use user_service::{CheckinSchedule, UserService};
 
 use crate::models::user::Response;
 use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// 未识别消息回调，参数为消息类型与原始 JSON
+pub type UnknownMessageHandler = Arc<dyn Fn(&str, Value) + Send + Sync>;
+
+/// 未识别消息回调槽位，由聊天室、私聊、通知服务共享
+pub type UnknownHandlerSlot = Arc<Mutex<Option<UnknownMessageHandler>>>;
+
+/// 调用未识别消息回调
+pub(crate) async fn notify_unknown(slot: &UnknownHandlerSlot, type_: &str, value: Value) {
+    log::debug!("收到未识别的WebSocket消息: type={}", type_);
+    let handler = slot.lock().await.clone();
+    if let Some(handler) = handler {
+        handler(type_, value);
+    }
+}
+
+/// 连接状态变化回调，参数为连接标识（私聊用户名，新消息通知频道或聊天室为 `None`）与是否已连接
+pub type ConnectionChangeHandler = Arc<dyn Fn(Option<&str>, bool) + Send + Sync>;
+
+/// 连接状态回调槽位，按连接记录上次通知的状态，相同状态不重复通知；
+/// 连接初始视为未连接，从未连上时不会通知断开
+#[derive(Clone, Default)]
+pub(crate) struct ConnectionWatch {
+    handler: Arc<Mutex<Option<ConnectionChangeHandler>>>,
+    last: Arc<Mutex<HashMap<Option<String>, bool>>>,
+}
+
+impl std::fmt::Debug for ConnectionWatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionWatch")
+            .field("handler", &"<function callback>")
+            .field("last", &self.last)
+            .finish()
+    }
+}
+
+impl ConnectionWatch {
+    /// 设置回调，替换已有回调
+    pub(crate) async fn set(&self, handler: ConnectionChangeHandler) {
+        *self.handler.lock().await = Some(handler);
+    }
+
+    /// 通知连接状态变化，与上次状态相同时忽略
+    pub(crate) async fn notify(&self, key: Option<&str>, connected: bool) {
+        let previous = self
+            .last
+            .lock()
+            .await
+            .insert(key.map(str::to_string), connected);
+        if previous.unwrap_or(false) == connected {
+            return;
+        }
+        let handler = self.handler.lock().await.clone();
+        if let Some(handler) = handler {
+            handler(key, connected);
+        }
+    }
+}
 
 /// 通用 API 调用 trait
 #[allow(async_fn_in_trait)]
@@ -38,3 +100,32 @@ pub trait ApiCaller {
         P: FnOnce(&serde_json::Value) -> Option<T>,
         T: Default;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn connection_watch_skips_initial_disconnect_and_duplicates() {
+        let watch = ConnectionWatch::default();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        watch
+            .set(Arc::new(move |key: Option<&str>, connected| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((key.map(str::to_string), connected));
+            }))
+            .await;
+
+        watch.notify(None, false).await;
+        watch.notify(None, true).await;
+        watch.notify(None, true).await;
+        watch.notify(Some("a"), false).await;
+        watch.notify(None, false).await;
+        watch.notify(None, false).await;
+
+        assert_eq!(*events.lock().unwrap(), [(None, true), (None, false)]);
+    }
+}
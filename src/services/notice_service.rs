@@ -1,15 +1,18 @@
 use crate::api::NoticeApi;
-use crate::api::client::ApiClient;
+use crate::api::client::ConnectionState;
+use crate::error::FishPiError;
 use crate::models::notice::{
     NoticeAt, NoticeComment, NoticeCount, NoticeFollow, NoticeItem, NoticeMsg, NoticeMsgType,
     NoticePoint, NoticeSystem, NoticeType, NoticeWebsocketInfo,
 };
-use crate::models::user::Response;
+use crate::models::user::{ApiResponse, Response};
+use crate::services::{UnknownHandlerSlot, notify_unknown};
+use futures::{SinkExt, StreamExt};
 use serde_json::Value;
-use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio_tungstenite::tungstenite::protocol::Message;
+use url::Url;
 
 /// 通知监听器类型
 pub type NoticeListener = Box<dyn Fn(NoticeMsg) + Send + Sync>;
@@ -29,6 +32,7 @@ pub struct NoticeService {
     websocket_sender: Arc<Mutex<Option<futures::channel::mpsc::UnboundedSender<Message>>>>,
     error_handlers: Arc<Mutex<Vec<ErrorHandler>>>,
     close_handlers: Arc<Mutex<Vec<CloseHandler>>>,
+    unknown_handler: UnknownHandlerSlot,
 }
 
 impl std::fmt::Debug for NoticeService {
@@ -40,6 +44,7 @@ impl std::fmt::Debug for NoticeService {
             .field("websocket_sender", &self.websocket_sender)
             .field("error_handlers", &"<function callbacks>")
             .field("close_handlers", &"<function callbacks>")
+            .field("unknown_handler", &"<function callback>")
             .finish()
     }
 }
@@ -54,9 +59,16 @@ impl NoticeService {
             websocket_sender: Arc::new(Mutex::new(None)),
             error_handlers: Arc::new(Mutex::new(Vec::new())),
             close_handlers: Arc::new(Mutex::new(Vec::new())),
+            unknown_handler: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// 使用共享的未识别消息回调槽位
+    pub fn with_unknown_handler(mut self, slot: UnknownHandlerSlot) -> Self {
+        self.unknown_handler = slot;
+        self
+    }
+
     /// 获取未读消息数
     pub async fn count(&self) -> Response<NoticeCount> {
         match self.notice_api.count().await {
@@ -111,11 +123,20 @@ impl NoticeService {
         self.get_notices::<NoticeSystem>(page).await
     }
 
-    /// 获取通知列表
+    /// 获取指定类型的通知列表
+    ///
+    /// 接口由 `T::notice_type()` 决定，例如 `list::<NoticeAt>(1)` 获取提及我的通知
+    ///
+    /// * `page` - 页码
+    pub async fn list<T: NoticeItem>(&self, page: i32) -> Response<Vec<T>> {
+        self.get_notices::<T>(Some(page)).await
+    }
+
+    /// 按类型名获取通知列表，返回原始 JSON
     ///
     /// * `notice_type` - 通知类型
     /// * `page` - 可选的页码，默认为1
-    pub async fn list(&self, notice_type: &str, page: Option<i32>) -> Response<Vec<Value>> {
+    pub async fn list_by_type(&self, notice_type: &str, page: Option<i32>) -> Response<Vec<Value>> {
         async fn convert_notices<T: NoticeItem>(
             service: &NoticeService,
             page: Option<i32>,
@@ -157,39 +178,193 @@ impl NoticeService {
     /// 标记指定类型的通知为已读
     ///
     /// * `notice_type` - 通知类型
-    pub async fn make_read(&self, notice_type: &str) -> Response<Value> {
-        match self.notice_api.make_read(notice_type).await {
+    pub async fn make_read(&self, notice_type: NoticeType) -> Response<Value> {
+        match self.notice_api.make_read(notice_type.as_str()).await {
             Ok(value) => Response::success(value),
-            Err(e) => Response::error(&format!("标记指定类型的通知为已读失败: {}", e)),
+            Err(e) => Response::error(&format!("标记指定类型的通知为已读失败: {}", e))
+                .with_error(FishPiError::from_anyhow(&e)),
+        }
+    }
+
+    /// 按 oId 标记指定通知为已读，其余通知保持未读
+    ///
+    /// * `ids` - 通知 oId 列表，为空时不发起请求
+    ///
+    /// 优先一次请求批量标记，服务端不支持批量时逐条标记；部分失败时错误信息中列出失败的 oId
+    pub async fn mark_read(&self, ids: &[&str]) -> Response<()> {
+        let ids: Vec<&str> = ids
+            .iter()
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+            .collect();
+        if ids.is_empty() {
+            return Response::success(());
+        }
+
+        let error = match self.notice_api.make_read_ids(&ids).await {
+            Ok(ApiResponse { code: 0, .. }) => return Response::success(()),
+            Ok(ApiResponse { code, msg, .. }) => {
+                FishPiError::from_code(code, msg.as_deref().unwrap_or("批量标记通知为已读失败"))
+            }
+            Err(e) => FishPiError::from_anyhow(&e),
+        };
+        if error == FishPiError::NotAuthenticated {
+            return Response::from_error(error);
+        }
+        log::debug!("批量标记通知为已读失败（{}），改为逐条标记", error);
+
+        let mut failed = Vec::new();
+        let mut first_error = None;
+        for id in ids {
+            let error = match self.notice_api.make_read_id(id).await {
+                Ok(ApiResponse { code: 0, .. }) => continue,
+                Ok(ApiResponse { code, msg, .. }) => {
+                    let msg = msg.unwrap_or_default();
+                    log::debug!("标记通知 {} 为已读失败: {}", id, msg);
+                    FishPiError::from_code(code, &msg)
+                }
+                Err(e) => {
+                    log::debug!("标记通知 {} 为已读失败: {}", id, e);
+                    FishPiError::from_anyhow(&e)
+                }
+            };
+            failed.push(id);
+            first_error.get_or_insert(error);
+        }
+
+        match first_error {
+            None => Response::success(()),
+            Some(error) => Response::error(&format!("以下通知标记已读失败: {}", failed.join(", ")))
+                .with_error(error),
         }
     }
 
     /// 标记所有通知为已读
-    pub async fn read_all(&self) -> Response<Value> {
+    pub async fn mark_all_read(&self) -> Response<Value> {
         match self.notice_api.read_all().await {
             Ok(value) => Response::success(value),
             Err(e) => Response::error(&format!("标记所有通知为已读失败: {}", e)),
         }
     }
 
+    /// 连接通知频道 (`user-channel`)
+    ///
+    /// 收到的消息解析为 [`NoticeMsg`] 后分发给监听器；
+    /// `refreshNotification` 未携带数量时会查询一次未读数并填入 `count`
     pub async fn connect(&self, _timeout: Option<u64>) -> Response<()> {
         // 如果已连接，先断开
         if self.is_connected().await {
             let _ = self.disconnect().await;
         }
 
-        let client = ApiClient::new();
-        let base_url = client.base_url();
-        if base_url.is_empty() {
-            return Response::error("未设置API基础URL");
+        let url = match self.get_full_websocket_url().await {
+            Ok(url) => url,
+            Err(err) => return Response::error(&err),
+        };
+
+        let ws_stream = match self.notice_api.client().open_websocket(url).await {
+            Ok(stream) => stream,
+            Err(e) => return Response::error(&format!("连接WebSocket失败: {}", e)),
+        };
+        let (mut write, mut read) = ws_stream.split();
+
+        // 创建消息发送通道
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded::<Message>();
+        {
+            let mut websocket_sender = self.websocket_sender.lock().await;
+            *websocket_sender = Some(sender.clone());
+        }
+
+        // 更新连接状态
+        {
+            let mut info = self.websocket_info.lock().await;
+            let retry_times = info.as_ref().map(|i| i.retry_times).unwrap_or(0);
+            *info = Some(NoticeWebsocketInfo {
+                connected: true,
+                retry_times,
+                connection_id: None,
+            });
         }
 
+        tokio::spawn(async move {
+            while let Some(message) = receiver.next().await {
+                let is_close = matches!(message, Message::Close(_));
+                if write.send(message).await.is_err() || is_close {
+                    break;
+                }
+            }
+        });
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut state = ConnectionState::Disconnected;
+            while let Some(msg_result) = read.next().await {
+                match msg_result {
+                    Ok(Message::Text(text)) => {
+                        service.notice_api.client().metrics().record_message();
+                        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+                            let service = service.clone();
+                            tokio::spawn(async move {
+                                service.handle_ws_message(value).await;
+                            });
+                        }
+                    }
+                    Ok(Message::Close(frame)) => {
+                        state = ConnectionState::from_close_frame(frame.as_ref());
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        let handlers = service.error_handlers.lock().await;
+                        for handler in handlers.iter() {
+                            handler(format!("WebSocket错误: {}", e));
+                        }
+                        break;
+                    }
+                }
+            }
+
+            if let ConnectionState::Banned { reason } = &state {
+                log::warn!("通知连接被服务端关闭: {}", reason);
+            }
+
+            // 已被新连接替换时不改动当前状态
+            {
+                let mut websocket_sender = service.websocket_sender.lock().await;
+                if websocket_sender
+                    .as_ref()
+                    .is_some_and(|current| current.same_receiver(&sender))
+                {
+                    websocket_sender.take();
+                    let mut info = service.websocket_info.lock().await;
+                    if let Some(info) = &mut *info {
+                        info.connected = false;
+                    }
+                }
+            }
+
+            let handlers = service.close_handlers.lock().await;
+            for handler in handlers.iter() {
+                handler();
+            }
+        });
+
+        Response::success(())
+    }
+
+    /// 获取完整的通知频道 WebSocket URL
+    async fn get_full_websocket_url(&self) -> Result<Url, String> {
         let ws_path = match self.notice_api.get_websocket_url().await {
             Ok(path) => path,
-            Err(e) => return Response::error(&format!("获取WebSocket URL失败: {}", e)),
+            Err(e) => return Err(format!("获取WebSocket URL失败: {}", e)),
         };
 
-        let full_ws_url = if base_url.starts_with("https") {
+        let base_url = self.notice_api.client().base_url();
+        if base_url.is_empty() {
+            return Err("未设置API基础URL".to_string());
+        }
+
+        let full_url = if base_url.starts_with("https") {
             format!(
                 "wss://{}/{}",
                 base_url.trim_start_matches("https://"),
@@ -203,69 +378,33 @@ impl NoticeService {
             )
         };
 
-        let message_handler = {
-            let listeners = self.message_listeners.clone();
-            move |value: Value| {
-                let listeners = listeners.clone();
-                tokio::spawn(async move {
-                    if let Some(command) = value.get("command").and_then(|v| v.as_str()) {
-                        let msg_type = NoticeMsgType::from_str(command);
-                        if NoticeMsgType::values().contains(&msg_type) {
-                            let notice_msg = NoticeMsg::from(&value);
-                            let listeners = listeners.lock().await;
-                            for listener in listeners.iter() {
-                                listener(notice_msg.clone());
-                            }
-                        }
-                    }
-                });
-            }
-        };
-
-        let error_handler = {
-            let error_handlers = self.error_handlers.clone();
-            move |err: String| {
-                let error_handlers = error_handlers.clone();
-                tokio::spawn(async move {
-                    let handlers = error_handlers.lock().await;
-                    for handler in handlers.iter() {
-                        handler(err.clone());
-                    }
-                });
-            }
-        };
-
-        let close_handler = {
-            let close_handlers = self.close_handlers.clone();
-            move || {
-                let close_handlers = close_handlers.clone();
-                tokio::spawn(async move {
-                    let handlers = close_handlers.lock().await;
-                    for handler in handlers.iter() {
-                        handler();
-                    }
-                });
-            }
-        };
+        Url::parse(&full_url).map_err(|e| format!("解析WebSocket URL失败: {}", e))
+    }
 
-        let mut params = HashMap::new();
-        if let Some(token) = client.get_token().await {
-            params.insert("apiKey".to_string(), token);
+    /// 分发一条通知频道消息
+    async fn handle_ws_message(&self, value: Value) {
+        let command = value
+            .get("command")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let msg_type = NoticeMsgType::from_str(&command);
+        if !NoticeMsgType::values().contains(&msg_type) {
+            notify_unknown(&self.unknown_handler, &command, value).await;
+            return;
         }
 
-        let result = client
-            .connect_websocket(
-                &full_ws_url,
-                Some(params),
-                message_handler,
-                Some(error_handler),
-                Some(close_handler),
-            )
-            .await;
+        let mut notice_msg = NoticeMsg::from(&value);
+        if msg_type == NoticeMsgType::RefreshNotification
+            && notice_msg.count.is_none()
+            && let Ok(count) = self.notice_api.count().await
+        {
+            notice_msg.count = Some(count.count);
+        }
 
-        match result {
-            Ok(_) => Response::success(()),
-            Err(e) => Response::error(&format!("连接WebSocket失败: {}", e)),
+        let listeners = self.message_listeners.lock().await;
+        for listener in listeners.iter() {
+            listener(notice_msg.clone());
         }
     }
 
@@ -341,7 +480,7 @@ impl NoticeService {
         {
             let mut sender = self.websocket_sender.lock().await;
             if let Some(sender) = sender.take() {
-                drop(sender);
+                let _ = sender.unbounded_send(Message::Close(None));
             }
         }
 
@@ -362,10 +501,10 @@ impl NoticeService {
         // 检查重试次数
         {
             let info = self.websocket_info.lock().await;
-            if let Some(info) = &*info {
-                if info.retry_times >= max_retry_times {
-                    return Response::error(&format!("重连次数超过最大限制({})", max_retry_times));
-                }
+            if let Some(info) = &*info
+                && info.retry_times >= max_retry_times
+            {
+                return Response::error(&format!("重连次数超过最大限制({})", max_retry_times));
             }
         }
 
@@ -376,3 +515,51 @@ impl NoticeService {
         self.connect(None).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockServer, mock_client};
+
+    #[tokio::test]
+    async fn mark_read_partial_failure_keeps_typed_error() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":-1,"msg":"不支持批量标记"}"#),
+            (200, r#"{"code":0}"#),
+            (200, r#"{"code":429,"msg":"操作过于频繁"}"#),
+        ])
+        .await;
+        let service = NoticeService::new(NoticeApi::new(mock_client(&server)));
+
+        let response = service.mark_read(&["a", "b"]).await;
+
+        assert!(!response.success);
+        assert_eq!(response.error, Some(FishPiError::RateLimited));
+        assert!(response.message.unwrap().ends_with(": b"));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn unknown_command_reaches_fishpi_on_unknown() {
+        let server = MockServer::start(vec![]).await;
+        let fishpi = crate::FishPi::from_api_client(mock_client(&server));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        fishpi
+            .on_unknown(move |type_, value| {
+                recorded.lock().unwrap().push((type_.to_string(), value));
+            })
+            .await;
+
+        fishpi
+            .notice
+            .handle_ws_message(serde_json::json!({ "command": "brandNew", "payload": 1 }))
+            .await;
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].0, "brandNew");
+        assert_eq!(seen[0].1["payload"], 1);
+        assert!(server.requests().is_empty());
+    }
+}
@@ -1,17 +1,136 @@
 use crate::api::RedpacketApi;
-use crate::models::redpacket::{GestureType, RedPacketInfo, RedPacketMessage, RedPacketType};
-use crate::models::user::Response;
+use crate::error::FishPiError;
+use crate::models::redpacket::{
+    GestureStats, GestureType, RedPacketInfo, RedPacketMessage, RedPacketType,
+};
+use crate::models::user::{ApiResponse, Response};
+use crate::services::{ChatroomService, UserService};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 /// 红包服务
 #[derive(Clone, Debug)]
 pub struct RedpacketService {
     redpacket_api: RedpacketApi,
+    /// 用于确定猜拳结果中哪一条是自己的领取记录
+    user: Option<UserService>,
+    /// 用于发送红包并获取红包消息的 oid
+    chatroom: Option<ChatroomService>,
+    /// 猜拳红包统计
+    gesture_stats: Arc<Mutex<GestureStats>>,
+    /// 猜拳统计的持久化文件，为 None 时仅保存在内存中
+    stats_path: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl RedpacketService {
     /// 创建一个新的红包服务
     pub fn new(redpacket_api: RedpacketApi) -> Self {
-        Self { redpacket_api }
+        Self {
+            redpacket_api,
+            user: None,
+            chatroom: None,
+            gesture_stats: Arc::new(Mutex::new(GestureStats::default())),
+            stats_path: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 关联用户服务，用于统计猜拳红包的胜负
+    pub fn with_user(mut self, user: UserService) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// 关联聊天室服务，发送红包时经由聊天室发送，与普通消息共用发送间隔并可通过回显获取 oid
+    pub fn with_chatroom(mut self, chatroom: ChatroomService) -> Self {
+        self.chatroom = Some(chatroom);
+        self
+    }
+
+    /// 获取猜拳红包统计
+    pub fn gesture_stats(&self) -> GestureStats {
+        self.gesture_stats
+            .lock()
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+
+    /// 清空猜拳红包统计
+    pub fn reset_gesture_stats(&self) {
+        self.update_gesture_stats(|stats| *stats = GestureStats::default());
+    }
+
+    /// 开启猜拳统计持久化
+    ///
+    /// - `path` 统计文件路径
+    ///
+    /// 立即从文件加载统计（文件存在且有效时），此后每次打开猜拳红包都会同步写入该文件。
+    /// 返回是否加载到了统计
+    pub fn set_gesture_stats_path(&self, path: impl Into<PathBuf>) -> bool {
+        let path = path.into();
+        let loaded = Self::load_gesture_stats(&path);
+        if let Ok(mut stats_path) = self.stats_path.lock() {
+            *stats_path = Some(path);
+        }
+        match loaded {
+            Some(loaded) => {
+                if let Ok(mut stats) = self.gesture_stats.lock() {
+                    *stats = loaded;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 读取统计文件，文件不存在或内容无效时返回 `None`
+    fn load_gesture_stats(path: &Path) -> Option<GestureStats> {
+        let content = std::fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(stats) => Some(stats),
+            Err(err) => {
+                log::warn!("猜拳统计文件 {} 内容无效，已忽略: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// 修改猜拳统计，已开启持久化时写入文件
+    fn update_gesture_stats(&self, f: impl FnOnce(&mut GestureStats)) {
+        let Ok(mut stats) = self.gesture_stats.lock() else {
+            return;
+        };
+        f(&mut stats);
+
+        // 先取得路径锁再释放统计锁：写文件时不阻塞读取统计，且多次写入按修改顺序进行
+        let Ok(path) = self.stats_path.lock() else {
+            return;
+        };
+        let Some(path) = path.as_ref() else {
+            return;
+        };
+        let json = serde_json::to_string(&*stats);
+        drop(stats);
+
+        let result = json
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(err) = result {
+            log::warn!("保存猜拳统计到 {} 失败: {}", path.display(), err);
+        }
+    }
+
+    /// 记录一次猜拳红包的出拳与结果
+    async fn record_gesture(&self, gesture: GestureType, info: &RedPacketInfo) {
+        let me = match &self.user {
+            Some(user) => user
+                .fetch_current_user()
+                .await
+                .data
+                .map(|user| user.user_name),
+            None => None,
+        };
+        let outcome = me.and_then(|me| info.outcome_for(&me, false));
+        self.update_gesture_stats(|stats| stats.record(gesture, outcome));
     }
 
     /// 打开红包
@@ -27,13 +146,10 @@ impl RedpacketService {
             Err(err) => {
                 let err_msg = err.to_string();
                 if err_msg.contains("已被领完") || err_msg.contains("已领取") {
-                    Response {
-                        success: false,
-                        message: Some(err_msg),
-                        data: None,
-                    }
+                    Response::error(&err_msg).with_error(FishPiError::from_anyhow(&err))
                 } else {
                     Response::error(&format!("打开红包失败: {}", err))
+                        .with_error(FishPiError::from_anyhow(&err))
                 }
             }
         }
@@ -41,6 +157,8 @@ impl RedpacketService {
 
     /// 打开猜拳红包
     ///
+    /// 成功时计入猜拳统计，见 [`RedpacketService::gesture_stats`]
+    ///
     /// # 参数
     /// * `oid` - 红包消息ID
     /// * `gesture` - 猜拳类型
@@ -58,17 +176,17 @@ impl RedpacketService {
             .open_redpacket(oid, Some(gesture_value))
             .await
         {
-            Ok(info) => Response::success(info),
+            Ok(info) => {
+                self.record_gesture(gesture, &info).await;
+                Response::success(info)
+            }
             Err(err) => {
                 let err_msg = err.to_string();
                 if err_msg.contains("已被领完") || err_msg.contains("已领取") {
-                    Response {
-                        success: false,
-                        message: Some(err_msg),
-                        data: None,
-                    }
+                    Response::error(&err_msg).with_error(FishPiError::from_anyhow(&err))
                 } else {
                     Response::error(&format!("打开猜拳红包失败: {}", err))
+                        .with_error(FishPiError::from_anyhow(&err))
                 }
             }
         }
@@ -82,8 +200,8 @@ impl RedpacketService {
     /// * `msg` - 祝福语
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
-    pub async fn send_random(&self, count: i32, money: i32, msg: &str) -> Response<()> {
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
+    pub async fn send_random(&self, count: i32, money: i32, msg: &str) -> Response<Option<String>> {
         let redpacket = RedPacketMessage {
             type_: RedPacketType::RANDOM.to_string(),
             count,
@@ -103,8 +221,13 @@ impl RedpacketService {
     /// * `msg` - 祝福语
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
-    pub async fn send_average(&self, count: i32, money: i32, msg: &str) -> Response<()> {
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
+    pub async fn send_average(
+        &self,
+        count: i32,
+        money: i32,
+        msg: &str,
+    ) -> Response<Option<String>> {
         let redpacket = RedPacketMessage {
             type_: RedPacketType::AVERAGE.to_string(),
             count,
@@ -124,13 +247,18 @@ impl RedpacketService {
     /// * `msg` - 祝福语
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
     pub async fn send_specify(
         &self,
         receivers: Vec<String>,
         money: i32,
         msg: &str,
-    ) -> Response<()> {
+    ) -> Response<Option<String>> {
+        let receivers: Vec<String> = receivers
+            .into_iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
         let receivers_json = match serde_json::to_string(&receivers) {
             Ok(json) => json,
             Err(err) => {
@@ -158,8 +286,13 @@ impl RedpacketService {
     /// * `msg` - 祝福语
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
-    pub async fn send_heartbeat(&self, count: i32, money: i32, msg: &str) -> Response<()> {
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
+    pub async fn send_heartbeat(
+        &self,
+        count: i32,
+        money: i32,
+        msg: &str,
+    ) -> Response<Option<String>> {
         let redpacket = RedPacketMessage {
             type_: RedPacketType::HEARTBEAT.to_string(),
             count,
@@ -180,14 +313,14 @@ impl RedpacketService {
     /// * `gesture` - 猜拳类型
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
     pub async fn send_rock_paper_scissors(
         &self,
         count: i32,
         money: i32,
         msg: &str,
         gesture: GestureType,
-    ) -> Response<()> {
+    ) -> Response<Option<String>> {
         let redpacket = RedPacketMessage {
             type_: RedPacketType::ROCK_PAPER_SCISSORS.to_string(),
             count,
@@ -206,11 +339,96 @@ impl RedpacketService {
     /// * `redpacket` - 红包消息对象
     ///
     /// # 返回
-    /// * `Response<()>` - 响应结果
-    async fn send_redpacket(&self, redpacket: RedPacketMessage) -> Response<()> {
+    /// * `Response<Option<String>>` - 成功时为红包消息的 oid，未能获取时为 `None`
+    ///
+    /// 已关联聊天室服务时经由聊天室发送，oid 的获取方式同 [`ChatroomService::send_with`]；
+    /// 否则取接口返回的 oid
+    async fn send_redpacket(&self, redpacket: RedPacketMessage) -> Response<Option<String>> {
+        if let Err(err) = redpacket.base().validate(&redpacket.receiver_list()) {
+            return Response::from_error(err);
+        }
+        if let Some(chatroom) = &self.chatroom {
+            return chatroom
+                .send_redpacket(&redpacket)
+                .await
+                .map(|sent| sent.oid);
+        }
+
         match self.redpacket_api.send_redpacket(&redpacket).await {
-            Ok(response) => response.into(),
-            Err(err) => Response::error(&format!("发送红包失败: {}", err)),
+            Ok(ApiResponse { code: 0, data, .. }) => Response::success(data),
+            Ok(ApiResponse { code, msg, .. }) => Response::from_error(FishPiError::from_code(
+                code,
+                msg.as_deref().unwrap_or("发送红包失败"),
+            )),
+            Err(err) => Response::error(&format!("发送红包失败: {}", err))
+                .with_error(FishPiError::from_anyhow(&err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockServer, mock_client};
+
+    fn mock_service(server: &MockServer) -> RedpacketService {
+        RedpacketService::new(RedpacketApi::new(mock_client(server)))
+    }
+
+    #[tokio::test]
+    async fn send_returns_oid_from_response() {
+        let server = MockServer::start(vec![(200, r#"{"code":0,"oId":"1700000000123"}"#)]).await;
+        let response = mock_service(&server).send_random(2, 32, "恭喜发财").await;
+        assert_eq!(response.data, Some(Some("1700000000123".to_string())));
+    }
+
+    #[tokio::test]
+    async fn send_without_oid_returns_none() {
+        let server = MockServer::start(vec![(200, r#"{"code":0}"#)]).await;
+        let response = mock_service(&server).send_random(2, 32, "恭喜发财").await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(None));
+    }
+
+    #[tokio::test]
+    async fn legacy_gesture_stats_file_is_migrated() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+        let path =
+            std::env::temp_dir().join(format!("fishpi-gesture-stats-{}", std::process::id()));
+        std::fs::write(&path, "[3,1,2]").unwrap();
+
+        assert!(service.set_gesture_stats_path(&path));
+        let stats = service.gesture_stats();
+        assert_eq!(stats.rock.played, 3);
+        assert_eq!(stats.scissors.played, 1);
+        assert_eq!(stats.paper.played, 2);
+        assert_eq!(stats.net_points(), 0);
+
+        // 修改后以新格式写回，且可再次读取
+        service.reset_gesture_stats();
+        let saved: GestureStats =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, GestureStats::default());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_redpackets_are_rejected_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = mock_service(&server);
+
+        let responses = [
+            service.send_random(0, 32, "恭喜发财").await,
+            service.send_average(5, 4, "恭喜发财").await,
+            service
+                .send_specify(vec![" ".to_string()], 32, "恭喜发财")
+                .await,
+        ];
+
+        for response in responses {
+            assert!(matches!(response.error, Some(FishPiError::Validation(_))));
         }
+        assert!(server.requests().is_empty());
     }
 }
@@ -1,11 +1,80 @@
-use crate::api::UserApi;
-use crate::models::user::{ApiResponse, LoginResponse, Response, UserInfo};
+use crate::api::{ArticleApi, UserApi};
+use crate::error::FishPiError;
+use crate::models::user::{
+    Activity, ActivityClaimFailure, ApiEnvelope, ApiResponse, CheckinResult, LeaderboardEntry,
+    LeaderboardKind, LoginResponse, PointRecord, Relationship, Reputation, Response, UserInfo,
+    validate_username,
+};
 use crate::services::ApiCaller;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::{Stream, StreamExt};
 use std::borrow::Cow;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// 查询关注关系时每页数量
+const FOLLOWING_PAGE_SIZE: i32 = 50;
+/// 查询关注关系时最多翻页数
+const FOLLOWING_MAX_PAGES: i32 = 20;
+/// 统计认可数据时每页帖子数量
+const REPUTATION_PAGE_SIZE: i32 = 50;
+/// 统计认可数据时最多翻页数
+const REPUTATION_MAX_PAGES: i32 = 10;
+/// 定时签到检查时间的间隔，进程休眠错过签到时间后最迟在一个间隔内补签
+const CHECKIN_TICK: Duration = Duration::from_secs(60);
+
+/// 每日定时签到任务
+///
+/// 以流的形式产出每次签到的结果，丢弃后停止签到
+#[derive(Debug)]
+pub struct CheckinSchedule {
+    receiver: UnboundedReceiver<Response<CheckinResult>>,
+    task: JoinHandle<()>,
+}
+
+impl Stream for CheckinSchedule {
+    type Item = Response<CheckinResult>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().receiver.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for CheckinSchedule {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 计算下一次签到时间（本地时间），今天的签到时间已过时取明天
+fn next_checkin_time(now: DateTime<Local>, time: NaiveTime) -> DateTime<Local> {
+    let mut date = now.date_naive();
+    if now.time() >= time {
+        date = date.succ_opt().unwrap_or(date);
+    }
+    let target = date.and_time(time);
+    // 夏令时跳过的时间不存在，顺延一小时
+    Local
+        .from_local_datetime(&target)
+        .earliest()
+        .or_else(|| {
+            Local
+                .from_local_datetime(&(target + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or(now + chrono::Duration::days(1))
+}
 
 #[derive(Clone, Debug)]
 pub struct UserService {
     user_api: UserApi,
+    article_api: ArticleApi,
+    /// 当前用户信息及获取时的令牌版本
+    current_user: Arc<RwLock<Option<(u64, UserInfo)>>>,
 }
 
 unsafe impl Send for UserService {}
@@ -23,6 +92,7 @@ impl ApiCaller for UserService {
             Err(err) => {
                 log::error!("API调用失败: {}", err);
                 Response::error(&format!("API调用失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
             }
         }
     }
@@ -37,24 +107,27 @@ impl ApiCaller for UserService {
         log::debug!("{}", log_msg);
         match f().await {
             Ok(response) => {
-                if let Some(0) = response.get("result").and_then(|v| v.as_i64()) {
-                    if let Some(data) = response.get("data") {
-                        if let Some(parsed_data) = parser(data) {
-                            return Response::success(parsed_data);
+                let envelope =
+                    match serde_json::from_value::<ApiEnvelope<serde_json::Value>>(response) {
+                        Ok(envelope) => envelope,
+                        Err(err) => {
+                            return Response::from_error(FishPiError::Parse(err.to_string()));
                         }
-                    }
-                }
-
-                let error_msg = response
-                    .get("msg")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("解析API响应数据失败")
-                    .to_string();
-                Response::error(&error_msg)
+                    };
+                let msg = envelope.msg.as_deref().unwrap_or("解析API响应数据失败");
+                let error = match envelope.check(msg) {
+                    Ok(()) => match envelope.data.as_ref().and_then(parser) {
+                        Some(data) => return Response::success(data),
+                        None => FishPiError::Parse(msg.to_string()),
+                    },
+                    Err(err) => err,
+                };
+                Response::error(msg).with_error(error)
             }
             Err(err) => {
                 log::error!("API调用失败: {}", err);
                 Response::error(&format!("API调用失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
             }
         }
     }
@@ -62,25 +135,914 @@ impl ApiCaller for UserService {
 
 impl UserService {
     pub fn new(user_api: UserApi) -> Self {
-        Self { user_api }
+        let article_api = ArticleApi::new(user_api.client().clone());
+        Self {
+            user_api,
+            article_api,
+            current_user: Arc::new(RwLock::new(None)),
+        }
     }
 
     /// 用户登录
+    ///
+    /// 登录成功后获取并缓存当前用户信息，可通过 `current_user` 或 `current_username` 读取
+    ///
+    /// - `username` 用户名或邮箱
+    /// - `password` 密码的 MD5
+    /// - `mfa_code` 二次验证码，未开启时传空字符串
+    ///
+    /// 失败时 `data` 中的 [`LoginResponse::failure`] 可区分需要验证码、需要二次验证与密码错误
     pub async fn login<'a>(
         &'a self,
         username: &'a str,
         password: Cow<'a, str>,
         mfa_code: &'a str,
     ) -> Response<LoginResponse> {
-        self.call_api(&format!("用户登录: {}", username), || {
+        self.login_inner(username, || {
             self.user_api.login(username, password.as_ref(), mfa_code)
         })
         .await
     }
 
+    /// 使用二次验证码登录
+    ///
+    /// - `username` 用户名或邮箱
+    /// - `password` 密码的 MD5
+    /// - `mfa_code` 身份验证器中的一次性密码
+    pub async fn login_with_mfa<'a>(
+        &'a self,
+        username: &'a str,
+        password: Cow<'a, str>,
+        mfa_code: &'a str,
+    ) -> Response<LoginResponse> {
+        let mfa_code = mfa_code.trim();
+        if mfa_code.is_empty() {
+            return Response::from_error(FishPiError::Validation("二次验证码不能为空".to_string()));
+        }
+        self.login(username, password, mfa_code).await
+    }
+
+    /// 使用验证码登录
+    ///
+    /// - `username` 用户名或邮箱
+    /// - `password` 密码的 MD5
+    /// - `mfa_code` 二次验证码，未开启时传空字符串
+    /// - `captcha_token` `LoginChallenge::Captcha` 中的 `token`
+    /// - `captcha` 用户输入的验证码
+    pub async fn login_with_captcha<'a>(
+        &'a self,
+        username: &'a str,
+        password: Cow<'a, str>,
+        mfa_code: &'a str,
+        captcha_token: &'a str,
+        captcha: &'a str,
+    ) -> Response<LoginResponse> {
+        let captcha = captcha.trim();
+        if captcha.is_empty() {
+            return Response::from_error(FishPiError::Validation("验证码不能为空".to_string()));
+        }
+        self.login_inner(username, || {
+            self.user_api.login_with_captcha(
+                username,
+                password.as_ref(),
+                mfa_code,
+                captcha_token,
+                captcha,
+            )
+        })
+        .await
+    }
+
+    async fn login_inner<'a, F, Fut>(
+        &'a self,
+        username: &str,
+        request: F,
+    ) -> Response<LoginResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<LoginResponse>> + Send + Sync + 'a,
+    {
+        self.clear_current_user();
+        let result = self
+            .call_api(&format!("用户登录: {}", username), request)
+            .await;
+
+        let Some(response) = result.data.as_ref() else {
+            return result;
+        };
+        if let Some(failure) = response.failure() {
+            let error = FishPiError::ApiError {
+                code: response.code,
+                msg: failure.to_string(),
+            };
+            let mut failed = Response::from_error(error);
+            failed.data = result.data;
+            return failed;
+        }
+
+        let _ = self.get_info().await;
+        result
+    }
+
+    /// 退出登录，清除令牌与缓存的用户信息
+    pub async fn logout(&self) {
+        self.user_api.client().set_token(None).await;
+        self.clear_current_user();
+    }
+
     /// 获取用户信息
+    ///
+    /// 获取成功时同时刷新 `current_user` 缓存
     pub async fn get_info(&self) -> Response<ApiResponse<UserInfo>> {
-        self.call_api("获取用户信息", || self.user_api.get_user_info())
+        let generation = self.user_api.client().token_generation();
+        let result = self
+            .call_api("获取用户信息", || self.user_api.get_user_info())
+            .await;
+
+        if let Some(ApiResponse {
+            code: 0,
+            data: Some(user),
+            ..
+        }) = &result.data
+            && let Ok(mut current) = self.current_user.write()
+        {
+            *current = Some((generation, user.clone()));
+        }
+        result
+    }
+
+    /// 获取当前登录用户信息
+    ///
+    /// 优先使用缓存，令牌变更后缓存失效，重新获取并缓存。只读取缓存时使用 [`UserService::current_user`]
+    pub async fn fetch_current_user(&self) -> Response<UserInfo> {
+        if let Some(user) = self.current_user() {
+            return Response::success(user);
+        }
+
+        let result = self.get_info().await;
+        match result.data {
+            Some(ApiResponse {
+                code: 0,
+                data: Some(user),
+                ..
+            }) => Response::success(user),
+            Some(ApiResponse { code, msg, .. }) => {
+                let msg = msg.unwrap_or_else(|| "获取当前用户信息失败".to_string());
+                Response::from_error(FishPiError::from_code(code, &msg))
+            }
+            None => Response {
+                success: false,
+                message: result.message,
+                data: None,
+                error: result.error,
+            },
+        }
+    }
+
+    /// 获取缓存的当前用户信息，不发起网络请求
+    ///
+    /// 未登录、尚未获取或令牌已变更时为 None，可先调用 `fetch_current_user` 获取
+    pub fn current_user(&self) -> Option<UserInfo> {
+        let generation = self.user_api.client().token_generation();
+        self.current_user
+            .read()
+            .ok()
+            .and_then(|current| match current.as_ref() {
+                Some((cached, user)) if *cached == generation => Some(user.clone()),
+                _ => None,
+            })
+    }
+
+    /// 获取缓存的当前用户名，不发起网络请求
+    ///
+    /// 缓存不可用时为 None，可先调用 `fetch_current_user` 获取
+    pub fn current_username(&self) -> Option<String> {
+        self.current_user().map(|user| user.user_name)
+    }
+
+    /// 清除缓存的当前用户信息，令牌变更时调用
+    pub fn clear_current_user(&self) {
+        if let Ok(mut current) = self.current_user.write() {
+            *current = None;
+        }
+    }
+
+    /// 获取积分流水
+    ///
+    /// - `page` 页码
+    pub async fn points_log(&self, page: i32) -> Response<Vec<PointRecord>> {
+        match self.user_api.get_points_log(page).await {
+            Ok(response) => response.into(),
+            Err(err) => {
+                log::error!("获取积分流水失败: {}", err);
+                Response::error(&format!("获取积分流水失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 查询与指定用户的关注关系
+    ///
+    /// - `other` 对方用户名
+    ///
+    /// 当前用户取自缓存，见 [`UserService::fetch_current_user`]；
+    /// 对方隐藏关注列表等无法确定的情况下，对应字段为 None
+    pub async fn relationship(&self, other: &str) -> Response<Relationship> {
+        let me = match self.fetch_current_user().await.into_typed_result() {
+            Ok(user) => user.user_name,
+            Err(err) => return Response::from_error(err),
+        };
+
+        let i_follow = match self.user_api.get_user(other).await {
+            Ok(ApiResponse {
+                code: 0,
+                data: Some(user),
+                ..
+            }) => user.is_followed(),
+            Ok(response) => {
+                let msg = response.msg.as_deref().unwrap_or("获取用户信息失败");
+                return Response::error(msg).with_error(FishPiError::from_code(response.code, msg));
+            }
+            Err(err) => {
+                return Response::error(&format!("获取用户信息失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err));
+            }
+        };
+
+        let follows_me = self.is_following_user(other, |u| u.user_name == me).await;
+
+        Response::success(Relationship {
+            i_follow,
+            follows_me,
+        })
+    }
+
+    /// 查询用户获得的感谢、赞同、收藏总数
+    ///
+    /// - `user` 用户名
+    ///
+    /// 服务端未提供汇总接口，由用户帖子列表累加得出，结果标记为估算值
+    pub async fn reputation(&self, user: &str) -> Response<Reputation> {
+        let mut reputation = Reputation {
+            approximate: true,
+            ..Default::default()
+        };
+
+        for page in 1..=REPUTATION_MAX_PAGES {
+            let list = match self
+                .article_api
+                .get_user_article_list(user, page, REPUTATION_PAGE_SIZE)
+                .await
+            {
+                Ok(list) => list,
+                Err(err) if page == 1 => {
+                    return Response::error(&format!("获取用户帖子列表失败: {}", err));
+                }
+                Err(_) => break,
+            };
+
+            reputation.add_articles(&list.list);
+            if list.list.len() < REPUTATION_PAGE_SIZE as usize || page >= list.pagination.count {
+                break;
+            }
+        }
+
+        Response::success(reputation)
+    }
+
+    /// 遍历 `user` 的关注列表，判断其中是否有满足 `matches` 的用户
+    async fn is_following_user<F>(&self, user: &str, matches: F) -> Option<bool>
+    where
+        F: Fn(&UserInfo) -> bool,
+    {
+        for page in 1..=FOLLOWING_MAX_PAGES {
+            let users = match self
+                .user_api
+                .get_following(user, page, FOLLOWING_PAGE_SIZE)
+                .await
+            {
+                Ok(ApiResponse {
+                    code: 0,
+                    data: Some(users),
+                    ..
+                }) => users,
+                _ => return None,
+            };
+
+            if users.iter().any(&matches) {
+                return Some(true);
+            }
+            if (users.len() as i32) < FOLLOWING_PAGE_SIZE {
+                return Some(false);
+            }
+        }
+        None
+    }
+
+    /// 获取用户关注的人
+    ///
+    /// - `user` 用户名，为 None 时查询当前登录用户
+    /// - `page` 页码，从 1 开始
+    /// - `size` 每页数量
+    pub async fn following(
+        &self,
+        user: Option<&str>,
+        page: i32,
+        size: i32,
+    ) -> Response<Vec<UserInfo>> {
+        self.follow_list(user, page, size, true).await
+    }
+
+    /// 获取用户的粉丝
+    ///
+    /// - `user` 用户名，为 None 时查询当前登录用户
+    /// - `page` 页码，从 1 开始
+    /// - `size` 每页数量
+    pub async fn followers(
+        &self,
+        user: Option<&str>,
+        page: i32,
+        size: i32,
+    ) -> Response<Vec<UserInfo>> {
+        self.follow_list(user, page, size, false).await
+    }
+
+    async fn follow_list(
+        &self,
+        user: Option<&str>,
+        page: i32,
+        size: i32,
+        following: bool,
+    ) -> Response<Vec<UserInfo>> {
+        let user = match user {
+            Some(user) if !user.trim().is_empty() => user.trim().to_string(),
+            _ => match self.fetch_current_user().await {
+                Response {
+                    data: Some(user), ..
+                } => user.user_name,
+                failed => {
+                    return Response {
+                        success: false,
+                        message: failed.message,
+                        data: None,
+                        error: failed.error,
+                    };
+                }
+            },
+        };
+
+        let (action, result) = if following {
+            (
+                "获取关注列表",
+                self.user_api.get_following(&user, page, size).await,
+            )
+        } else {
+            (
+                "获取粉丝列表",
+                self.user_api.get_followers(&user, page, size).await,
+            )
+        };
+        match result {
+            Ok(response) => response.into(),
+            Err(err) => {
+                log::error!("{}失败: {}", action, err);
+                Response::error(&format!("{}失败: {}", action, err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 关注用户
+    ///
+    /// - `user_id` 对方用户的 oId（`UserInfo::oid`），注意不是用户名
+    pub async fn follow_user(&self, user_id: &str) -> Response<()> {
+        self.set_following(user_id, true).await
+    }
+
+    /// 取消关注用户
+    ///
+    /// - `user_id` 对方用户的 oId（`UserInfo::oid`），注意不是用户名
+    pub async fn unfollow_user(&self, user_id: &str) -> Response<()> {
+        self.set_following(user_id, false).await
+    }
+
+    async fn set_following(&self, user_id: &str, follow: bool) -> Response<()> {
+        let action = if follow { "关注" } else { "取消关注" };
+        match self.user_api.follow(user_id, follow).await {
+            Ok(ApiResponse { code: 0, .. }) => Response::success(()),
+            Ok(response) => {
+                let msg = response.msg.unwrap_or_else(|| format!("{}失败", action));
+                Response::error(&msg).with_error(FishPiError::from_code(response.code, &msg))
+            }
+            Err(err) => {
+                log::error!("{}用户 {} 失败: {}", action, user_id, err);
+                Response::error(&format!("{}失败: {}", action, err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 当前登录用户是否已关注对方
+    ///
+    /// - `user_id` 对方用户的 oId（`UserInfo::oid`），注意不是用户名
+    ///
+    /// 通过遍历自己的关注列表判断，关注数量超出扫描上限时返回错误
+    pub async fn is_following(&self, user_id: &str) -> Response<bool> {
+        let me = match self.fetch_current_user().await {
+            Response {
+                data: Some(user), ..
+            } => user.user_name,
+            failed => {
+                return Response::error(&format!(
+                    "获取当前用户失败: {}",
+                    failed.message.as_deref().unwrap_or("未知错误")
+                ));
+            }
+        };
+
+        let matches = |u: &UserInfo| {
+            u.oid.as_deref() == Some(user_id)
+                || u.user_oid.is_some_and(|oid| oid.to_string() == user_id)
+        };
+        match self.is_following_user(&me, matches).await {
+            Some(following) => Response::success(following),
+            None => Response::error("无法确定关注状态"),
+        }
+    }
+
+    /// 获取可参与的社区活动列表
+    pub async fn activities(&self) -> Response<Vec<Activity>> {
+        match self.user_api.get_activities().await {
+            Ok(response) => response.into(),
+            Err(err) => {
+                log::error!("获取活动列表失败: {}", err);
+                Response::error(&format!("获取活动列表失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 获取排行榜
+    ///
+    /// - `kind` 榜单类型
+    /// - `limit` 返回条目上限
+    ///
+    /// 榜单为空时返回空列表
+    pub async fn leaderboard(
+        &self,
+        kind: LeaderboardKind,
+        limit: usize,
+    ) -> Response<Vec<LeaderboardEntry>> {
+        match self.user_api.get_leaderboard(kind).await {
+            Ok(ApiResponse { code: 0, data, .. }) => {
+                let mut entries = data.unwrap_or_default();
+                entries.truncate(limit);
+                Response::success(entries)
+            }
+            Ok(response) => {
+                let msg = response
+                    .msg
+                    .unwrap_or_else(|| format!("获取{}失败", kind.display_name()));
+                Response::error(&msg).with_error(FishPiError::from_code(response.code, &msg))
+            }
+            Err(err) => {
+                log::error!("获取{}失败: {}", kind.display_name(), err);
+                Response::error(&format!("获取{}失败: {}", kind.display_name(), err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 每日签到
+    ///
+    /// 返回获得的积分与当前连续签到天数；今日已签到时返回错误，
+    /// 响应缺少状态码时返回 [`FishPiError::Parse`]
+    pub async fn checkin(&self) -> Response<CheckinResult> {
+        match self.user_api.checkin().await {
+            Ok(response) => {
+                let envelope = match ApiEnvelope::peek(&response) {
+                    Ok(envelope) => envelope,
+                    Err(err) => return Response::from_error(err),
+                };
+                if envelope.code.or(envelope.result).is_none() {
+                    log::error!("签到响应缺少状态码: {}", response);
+                    return Response::from_error(FishPiError::Parse(format!(
+                        "签到响应缺少状态码: {}",
+                        response
+                    )));
+                }
+                let msg = envelope.msg.as_deref().unwrap_or("签到失败");
+                match envelope.check(msg) {
+                    Ok(()) => Response::success(CheckinResult::from(&response)),
+                    Err(err) => Response::error(msg).with_error(err),
+                }
+            }
+            Err(err) => {
+                log::error!("签到失败: {}", err);
+                Response::error(&format!("签到失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 每天在指定的本地时间自动签到
+    ///
+    /// - `hour` 小时，0-23
+    /// - `minute` 分钟，0-59
+    ///
+    /// 返回的任务以流的形式产出每次签到结果，丢弃即取消。进程休眠错过签到时间时，
+    /// 唤醒后会立即补签。需要在 tokio 运行时中调用
+    pub fn schedule_checkin(&self, hour: u32, minute: u32) -> Result<CheckinSchedule, FishPiError> {
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(|| {
+            FishPiError::Validation(format!("无效的签到时间: {:02}:{:02}", hour, minute))
+        })?;
+
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let service = self.clone();
+        let task = tokio::spawn(async move {
+            let mut next = next_checkin_time(Local::now(), time);
+            loop {
+                let now = Local::now();
+                if now >= next {
+                    let result = service.checkin().await;
+                    if sender.unbounded_send(result).is_err() {
+                        break;
+                    }
+                    next = next_checkin_time(Local::now(), time);
+                    continue;
+                }
+                // 按墙上时间分段等待，休眠唤醒后能及时发现已错过签到时间
+                let wait = (next - now).to_std().unwrap_or_default().min(CHECKIN_TICK);
+                tokio::time::sleep(wait).await;
+            }
+        });
+
+        Ok(CheckinSchedule { receiver, task })
+    }
+
+    /// 获取当前活跃度（百分比）
+    pub async fn liveness(&self) -> Response<f64> {
+        self.call_api("获取活跃度", || self.user_api.get_liveness())
+            .await
+    }
+
+    /// 获取用户公开资料
+    ///
+    /// - `name` 用户名
+    ///
+    /// 包含积分、勋章（见 `UserInfo::metals`）、简介与在线状态
+    pub async fn get_user(&self, name: &str) -> Response<UserInfo> {
+        match self.user_api.get_user(name).await {
+            Ok(response) => response.into(),
+            Err(err) => {
+                log::error!("获取用户 {} 信息失败: {}", name, err);
+                Response::error(&format!("获取用户信息失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 查询用户名是否可注册
+    ///
+    /// - `name` 用户名
+    ///
+    /// 先在本地校验长度与字符集，不合法时直接返回 [`FishPiError::Validation`] 而不发起请求；
+    /// 服务端拒绝时返回其错误信息，响应无法解析时返回 [`FishPiError::Parse`]
+    pub async fn username_available(&self, name: &str) -> Response<bool> {
+        if let Err(err) = validate_username(name) {
+            return Response::from_error(err);
+        }
+
+        match self.user_api.check_username(name).await {
+            Ok(response) => response.into(),
+            Err(err) => {
+                log::error!("查询用户名失败: {}", err);
+                Response::error(&format!("查询用户名失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+
+    /// 领取活动奖励
+    ///
+    /// - `id` 活动 id
+    ///
+    /// 返回获得的积分；不满足条件与已领取会返回不同的错误信息，见 `ActivityClaimFailure`
+    pub async fn claim_activity(&self, id: &str) -> Response<i32> {
+        match self.user_api.claim_activity(id).await {
+            Ok(ApiResponse {
+                code: 0,
+                data: Some(sum),
+                ..
+            }) => Response::success(sum),
+            Ok(response) => {
+                let msg = response
+                    .msg
+                    .unwrap_or_else(|| "领取活动奖励失败".to_string());
+                Response::error(&ActivityClaimFailure::from_msg(&msg).to_string())
+                    .with_error(FishPiError::from_code(response.code, &msg))
+            }
+            Err(err) => {
+                log::error!("领取活动奖励失败: {}", err);
+                Response::error(&format!("领取活动奖励失败: {}", err))
+                    .with_error(FishPiError::from_anyhow(&err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockServer, mock_client};
+
+    fn service(server: &MockServer) -> UserService {
+        UserService::new(UserApi::new(mock_client(server).with_token(None)))
+    }
+
+    #[tokio::test]
+    async fn current_user_is_cached_after_login_and_cleared_on_token_change() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":0,"Key":"key"}"#),
+            (
+                200,
+                r#"{"code":0,"data":{"userName":"me","sysMetal":null}}"#,
+            ),
+        ])
+        .await;
+        let service = service(&server);
+
+        assert!(
+            service
+                .login("user", Cow::Borrowed("md5"), "")
+                .await
+                .success
+        );
+
+        assert_eq!(
+            service.current_user().map(|user| user.user_name).as_deref(),
+            Some("me")
+        );
+        assert_eq!(service.current_username().as_deref(), Some("me"));
+        assert_eq!(
+            service.fetch_current_user().await.data.unwrap().user_name,
+            "me"
+        );
+        assert_eq!(server.requests().len(), 2);
+
+        service
+            .user_api
+            .client()
+            .set_token(Some("other".into()))
+            .await;
+        assert!(service.current_user().is_none());
+    }
+
+    #[tokio::test]
+    async fn login_with_captcha_sends_token_back() {
+        let server = MockServer::start(vec![(200, r#"{"code":-1,"msg":"验证码错误"}"#)]).await;
+        let service = service(&server);
+
+        let response = service
+            .login_with_captcha("user", Cow::Borrowed("md5"), "", "token-1", " 1234 ")
+            .await;
+
+        assert!(!response.success);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains(r#""needCaptcha":"token-1""#));
+        assert!(requests[0].contains(r#""captcha":"1234""#));
+    }
+
+    #[tokio::test]
+    async fn login_reports_captcha_challenge() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":-1,"msg":"请输入验证码","needCaptcha":"token-1"}"#,
+        )])
+        .await;
+        let service = service(&server);
+
+        let response = service.login("user", Cow::Borrowed("md5"), "").await;
+
+        assert!(!response.success);
+        assert_eq!(
+            response.data.and_then(|data| data.failure()),
+            Some(crate::models::user::LoginFailure::Challenge(
+                crate::models::user::LoginChallenge::Captcha {
+                    token: "token-1".to_string()
+                }
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn checkin_without_status_code_is_a_parse_error() {
+        let server = MockServer::start(vec![
+            (200, r#"{"sum":10,"currentStreak":3}"#),
+            (200, r#"{"code":0,"sum":10,"currentStreak":3}"#),
+        ])
+        .await;
+        let service = UserService::new(UserApi::new(mock_client(&server)));
+
+        let response = service.checkin().await;
+        assert!(!response.success);
+        assert!(matches!(response.error, Some(FishPiError::Parse(_))));
+
+        let response = service.checkin().await;
+        assert_eq!(
+            response.data,
+            Some(CheckinResult {
+                points: 10,
+                streak: 3
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn username_available_reads_server_answer() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":0,"data":true}"#),
+            (200, r#"{"code":0,"data":{"available":false}}"#),
+            (200, r#"{"code":-1,"msg":"用户名不合法"}"#),
+            (200, r#"{"code":0,"data":"?"}"#),
+        ])
+        .await;
+        let service = service(&server);
+
+        assert_eq!(service.username_available("a").await.data, Some(true));
+        assert_eq!(service.username_available("b").await.data, Some(false));
+
+        let response = service.username_available("c").await;
+        assert!(!response.success);
+        assert_eq!(response.message.as_deref(), Some("用户名不合法"));
+
+        let response = service.username_available("d").await;
+        assert!(matches!(response.error, Some(FishPiError::Parse(_))));
+
+        assert!(matches!(
+            service.username_available("").await.error,
+            Some(FishPiError::Validation(_))
+        ));
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn username_available_rejects_bad_format_without_request() {
+        let server = MockServer::start(vec![]).await;
+        let service = service(&server);
+
+        for name in ["a b".to_string(), "a".repeat(40)] {
+            assert!(matches!(
+                service.username_available(&name).await.error,
+                Some(FishPiError::Validation(_))
+            ));
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn relationship_combines_follow_state_and_caches_current_user() {
+        const ME: &str = r#"{"code":0,"data":{"userName":"me","sysMetal":null}}"#;
+        let server = MockServer::start(vec![
+            (200, ME),
+            (
+                200,
+                r#"{"code":0,"data":{"userName":"a","canFollow":"no","sysMetal":null}}"#,
+            ),
+            (
+                200,
+                r#"{"code":0,"data":[{"userName":"me","sysMetal":null}]}"#,
+            ),
+            (
+                200,
+                r#"{"code":0,"data":{"userName":"b","canFollow":"yes","sysMetal":null}}"#,
+            ),
+            (200, r#"{"code":-1,"msg":"对方隐藏了关注列表"}"#),
+        ])
+        .await;
+        let service = UserService::new(UserApi::new(mock_client(&server)));
+
+        let mutual = service.relationship("a").await.data.unwrap();
+        assert_eq!(mutual.i_follow, Some(true));
+        assert_eq!(mutual.follows_me, Some(true));
+
+        let hidden = service.relationship("b").await.data.unwrap();
+        assert_eq!(hidden.i_follow, Some(false));
+        assert_eq!(hidden.follows_me, None);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests[0].starts_with("GET /api/user?"));
+        assert!(requests[3].starts_with("GET /user/b?"));
+    }
+
+    #[tokio::test]
+    async fn failed_calls_attach_typed_errors() {
+        let server = MockServer::start(vec![
+            (200, r#"{"code":-1,"msg":"榜单维护中"}"#),
+            (200, r#"{"code":429,"msg":"操作过于频繁"}"#),
+        ])
+        .await;
+        let service = UserService::new(UserApi::new(mock_client(&server)));
+
+        let leaderboard = service.leaderboard(LeaderboardKind::Points, 10).await;
+        assert_eq!(
+            leaderboard.error,
+            Some(FishPiError::ApiError {
+                code: -1,
+                msg: "榜单维护中".to_string()
+            })
+        );
+
+        let follow = service.follow_user("1").await;
+        assert_eq!(follow.error, Some(FishPiError::RateLimited));
+    }
+
+    #[tokio::test]
+    async fn activities_parse_both_field_spellings() {
+        let server = MockServer::start(vec![(
+            200,
+            r#"{"code":0,"data":[
+                {"oId":"1","activityTitle":"签到周","activityDescription":"连续签到","activityReward":"+50",
+                 "activityStartTime":1700000000000,"activityEndTime":"1700600000000","eligible":1,"claimed":0},
+                {"id":2,"title":"答题","reward":20,"eligible":true,"claimed":true},
+                {}
+            ]}"#,
+        )])
+        .await;
+        let service = UserService::new(UserApi::new(mock_client(&server)));
+
+        let activities = service.activities().await.data.unwrap();
+
+        assert_eq!(activities.len(), 3);
+        let first = &activities[0];
+        assert_eq!(
+            (
+                first.id.as_str(),
+                first.title.as_str(),
+                first.description.as_str()
+            ),
+            ("1", "签到周", "连续签到")
+        );
+        assert_eq!(first.reward, 50);
+        assert_eq!(
+            (first.start_time, first.end_time),
+            (1700000000000, 1700600000000)
+        );
+        assert!(first.claimable());
+
+        let second = &activities[1];
+        assert_eq!((second.id.as_str(), second.title.as_str()), ("2", "答题"));
+        assert_eq!(second.reward, 20);
+        assert!(second.eligible && second.claimed && !second.claimable());
+
+        let empty = &activities[2];
+        assert!(empty.id.is_empty() && empty.title.is_empty());
+        assert_eq!((empty.reward, empty.start_time), (0, 0));
+        assert!(!empty.claimable());
+        assert!(server.requests()[0].starts_with("GET /api/activities?apiKey=key"));
+    }
+
+    #[tokio::test]
+    async fn leaderboard_numbers_ranks_and_tolerates_missing_fields() {
+        let body = r#"{"code":0,"data":[
+            {"userName":"a","userNickname":"甲","userCurrentCheckinStreak":30,"userLongestCheckinStreak":40},
+            {"userName":"b","userLongestCheckinStreak":"25"},
+            {"rank":7,"userName":"c","value":3},
+            {}
+        ]}"#;
+        let server = MockServer::start(vec![(200, body), (200, body)]).await;
+        let service = UserService::new(UserApi::new(mock_client(&server)));
+
+        let entries = service
+            .leaderboard(LeaderboardKind::CheckinStreak, 10)
+            .await
+            .data
+            .unwrap();
+
+        let entry = |rank, user_name: &str, nickname: &str, value| LeaderboardEntry {
+            rank,
+            user_name: user_name.to_string(),
+            nickname: nickname.to_string(),
+            value,
+        };
+        assert_eq!(
+            entries,
+            [
+                entry(1, "a", "甲", 30),
+                entry(2, "b", "", 25),
+                entry(7, "c", "", 3),
+                entry(4, "", "", 0),
+            ]
+        );
+        assert!(server.requests()[0].starts_with("GET /api/top/checkin?"));
+
+        let entries = service
+            .leaderboard(LeaderboardKind::CheckinStreak, 2)
             .await
+            .data
+            .unwrap();
+        assert_eq!(entries.len(), 2);
     }
 }
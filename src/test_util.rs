@@ -0,0 +1,230 @@
+//! 测试辅助：按顺序返回预设响应的本地 HTTP 服务与记录收到帧的本地 WebSocket 服务
+
+use crate::api::ApiClient;
+use futures::{SinkExt, StreamExt};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// 预设响应，可附加响应头与延迟
+pub(crate) struct MockResponse {
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+    delay: Duration,
+}
+
+impl MockResponse {
+    /// 以状态码与 JSON 响应体创建
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+            headers: Vec::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// 附加响应头
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// 收到请求后等待 `delay` 再响应
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// 本地 HTTP 服务，依次返回预设响应，响应用完后返回 404
+pub(crate) struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// 启动服务
+    ///
+    /// - `responses` 依次返回的状态码与 JSON 响应体
+    pub async fn start(responses: Vec<(u16, &str)>) -> Self {
+        Self::start_with(
+            responses
+                .into_iter()
+                .map(|(status, body)| MockResponse::new(status, body))
+                .collect(),
+        )
+        .await
+    }
+
+    /// 启动服务，响应可附加响应头与延迟
+    ///
+    /// - `responses` 依次返回的响应
+    pub async fn start_with(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mut responses: Vec<MockResponse> = responses.into_iter().rev().collect();
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let Some(request) = read_request(&mut stream).await else {
+                    continue;
+                };
+                recorded.lock().unwrap().push(request);
+                let response = responses
+                    .pop()
+                    .unwrap_or_else(|| MockResponse::new(404, "{}"));
+                tokio::time::sleep(response.delay).await;
+                let headers: String = response
+                    .headers
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                    .collect();
+                let response = format!(
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    response.status,
+                    response.body.len(),
+                    headers,
+                    response.body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// 已收到的请求，含请求行、请求头与请求体
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// 连接到 `server` 的客户端，不经过系统代理，令牌为 `key`
+pub(crate) fn mock_client(server: &MockServer) -> ApiClient {
+    client_for(&server.url)
+}
+
+/// 连接到 `base_url` 的客户端，不经过系统代理，令牌为 `key`
+pub(crate) fn client_for(base_url: &str) -> ApiClient {
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+    ApiClient::from_reqwest(client, base_url).with_token(Some("key".into()))
+}
+
+/// 本地 WebSocket 服务，记录客户端的握手路径与发来的文本帧
+pub(crate) struct MockWebSocketServer {
+    pub url: String,
+    frames: Arc<Mutex<Vec<String>>>,
+    paths: Arc<Mutex<Vec<String>>>,
+    kick: tokio::sync::broadcast::Sender<()>,
+}
+
+impl MockWebSocketServer {
+    /// 启动服务
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let (kick, _) = tokio::sync::broadcast::channel(1);
+
+        let recorded = frames.clone();
+        let recorded_paths = paths.clone();
+        let kicker = kick.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let recorded_paths = recorded_paths.clone();
+                let mut kicked = kicker.subscribe();
+                tokio::spawn(async move {
+                    // 握手回调的错误类型由 tungstenite 规定
+                    #[allow(clippy::result_large_err)]
+                    let callback =
+                        |request: &tokio_tungstenite::tungstenite::handshake::server::Request,
+                         response| {
+                            recorded_paths
+                                .lock()
+                                .unwrap()
+                                .push(request.uri().to_string());
+                            Ok(response)
+                        };
+                    let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await
+                    else {
+                        return;
+                    };
+                    loop {
+                        tokio::select! {
+                            message = ws.next() => match message {
+                                Some(Ok(Message::Text(text))) => {
+                                    recorded.lock().unwrap().push(text);
+                                }
+                                Some(Ok(_)) => {}
+                                _ => return,
+                            },
+                            _ = kicked.recv() => {
+                                let _ = ws.send(Message::Close(None)).await;
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        Self {
+            url,
+            frames,
+            paths,
+            kick,
+        }
+    }
+
+    /// 已建立连接的握手路径（含查询参数），按连接顺序排列
+    pub fn paths(&self) -> Vec<String> {
+        self.paths.lock().unwrap().clone()
+    }
+
+    /// 以普通关闭帧断开当前所有连接
+    pub fn close_connections(&self) {
+        let _ = self.kick.send(());
+    }
+
+    /// 已收到的文本帧
+    pub fn frames(&self) -> Vec<String> {
+        self.frames.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buf);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text[..end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())
+                        .flatten()
+                })
+                .unwrap_or(0);
+            if buf.len() >= end + 4 + length {
+                break;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
@@ -0,0 +1,3 @@
+pub mod text;
+
+pub use text::{render_markdown_plain, strip_html_tags, truncate_utf8};
@@ -0,0 +1,155 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static MD_FENCE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(```|~~~)").unwrap());
+static MD_CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`[^`\n]+`").unwrap());
+static MD_IMAGE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[[^\]\n]*\]\([^)\n]*\)").unwrap());
+static MD_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\[((?:[^\[\]\n]|\[[^\[\]\n]*\])*)\]\([^)\s]*(?:\s+"[^"]*")?\)"#).unwrap()
+});
+static MD_HEADING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\s*)#{1,6}\s+").unwrap());
+static MD_BOLD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*(.+?)\*\*|\b__(.+?)__\b").unwrap());
+static MD_ITALIC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\*([^*\s](?:[^*\n]*[^*\s])?)\*|\b_([^_\s](?:[^_\n]*[^_\s])?)_\b").unwrap()
+});
+static MD_STRIKE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"~~(.+?)~~").unwrap());
+static BARE_URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s<>()\[\]]+").unwrap());
+
+/// 去除 HTML 标签，保留文本内容
+///
+/// - `html` HTML 文本
+pub fn strip_html_tags(html: &str) -> String {
+    HTML_TAG_RE.replace_all(html, "").trim().to_string()
+}
+
+/// 按字节数截断字符串，保证不切断 UTF-8 字符
+///
+/// - `text` 原始文本
+/// - `max_bytes` 最大字节数
+pub fn truncate_utf8(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// 将 Markdown 转换为纯文本，适合在终端等不支持富文本的环境中显示
+///
+/// - `md` Markdown 文本
+///
+/// 链接保留文字部分，图片替换为 `[图片]`，去除标题与粗体、斜体、删除线标记；
+/// 代码块、行内代码与裸链接原样保留，`@用户名` 不受影响
+pub fn render_markdown_plain(md: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in md.lines() {
+        if MD_FENCE_RE.is_match(line) {
+            in_fence = !in_fence;
+            lines.push(line.to_string());
+        } else if in_fence {
+            lines.push(line.to_string());
+        } else {
+            lines.push(render_plain_line(line));
+        }
+    }
+    lines.join("\n")
+}
+
+/// 转换单行 Markdown，行内代码原样保留
+fn render_plain_line(line: &str) -> String {
+    let line = MD_HEADING_RE.replace(line, "$1");
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+    for code in MD_CODE_RE.find_iter(&line) {
+        result.push_str(&render_plain_inline(&line[last..code.start()]));
+        result.push_str(code.as_str());
+        last = code.end();
+    }
+    result.push_str(&render_plain_inline(&line[last..]));
+    result
+}
+
+/// 转换行内 Markdown，裸链接中的 `__`、`*` 等字符不视为强调标记
+fn render_plain_inline(text: &str) -> String {
+    let text = MD_IMAGE_RE.replace_all(text, "[图片]");
+    let text = MD_LINK_RE.replace_all(&text, "$1");
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for url in BARE_URL_RE.find_iter(&text) {
+        result.push_str(&strip_emphasis(&text[last..url.start()]));
+        result.push_str(url.as_str());
+        last = url.end();
+    }
+    result.push_str(&strip_emphasis(&text[last..]));
+    result
+}
+
+/// 去除粗体、斜体与删除线标记
+fn strip_emphasis(text: &str) -> String {
+    let text = MD_BOLD_RE.replace_all(text, |caps: &regex::Captures| {
+        caps.get(1)
+            .or_else(|| caps.get(2))
+            .unwrap()
+            .as_str()
+            .to_string()
+    });
+    let text = MD_ITALIC_RE.replace_all(&text, |caps: &regex::Captures| {
+        caps.get(1)
+            .or_else(|| caps.get(2))
+            .unwrap()
+            .as_str()
+            .to_string()
+    });
+    MD_STRIKE_RE.replace_all(&text, "$1").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_keep_their_text() {
+        assert_eq!(
+            render_markdown_plain(r#"看 [这里](https://fishpi.cn "鱼排") 和 [a [b]](x)"#),
+            "看 这里 和 a [b]"
+        );
+    }
+
+    #[test]
+    fn images_become_placeholders() {
+        assert_eq!(
+            render_markdown_plain("![表情](https://x/1.png) @alice 你好"),
+            "[图片] @alice 你好"
+        );
+    }
+
+    #[test]
+    fn nested_emphasis_is_stripped() {
+        assert_eq!(
+            render_markdown_plain("## **粗体里有 *斜体*** 和 ~~删除~~"),
+            "粗体里有 斜体 和 删除"
+        );
+    }
+
+    #[test]
+    fn fenced_and_inline_code_are_preserved() {
+        let md = "说明 `**x**`\n```rust\nlet __a__ = *b;\n```\n**完**";
+        assert_eq!(
+            render_markdown_plain(md),
+            "说明 `**x**`\n```rust\nlet __a__ = *b;\n```\n完"
+        );
+    }
+
+    #[test]
+    fn bare_urls_keep_underscores() {
+        assert_eq!(
+            render_markdown_plain("见 https://example.com/__init__/a_b_c.py 的 __说明__"),
+            "见 https://example.com/__init__/a_b_c.py 的 说明"
+        );
+    }
+}